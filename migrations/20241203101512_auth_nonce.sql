@@ -0,0 +1,11 @@
+-- This PostgreSQL table records the nonces of signed requests that have already been
+-- accepted so a captured signature cannot be replayed before it expires.
+create table auth_nonce(
+    wallet_address varchar(66) not null check (wallet_address ~ '^0x[a-fA-F0-9]{64}$'),
+    nonce varchar(66) not null,
+    expires_at timestamptz not null,
+    created_at timestamptz not null default now(),
+    primary key (wallet_address, nonce)
+);
+
+create index on auth_nonce(expires_at);
@@ -0,0 +1,26 @@
+-- This PostgreSQL table stores how far each on-chain indexer has scanned. The hash of
+-- the last scanned block is kept to detect chain reorganisations.
+create table indexer_cursor(
+    name varchar(64) primary key not null,
+    block_number bigint not null check (block_number >= 0),
+    block_hash varchar(66) not null,
+    created_at timestamptz not null default now(),
+    updated_at timestamptz
+);
+
+SELECT trigger_updated_at('"indexer_cursor"');
+
+-- This PostgreSQL table records every ERC20 Transfer to a subscribed wallet that has
+-- been handed to the swap pipeline, so re-scanned blocks never trigger a swap twice.
+create table indexed_transfer(
+    transaction_hash varchar(66) not null,
+    token_address varchar(66) not null check (token_address ~ '^0x[a-fA-F0-9]{64}$'),
+    event_index integer not null,
+    wallet_address varchar(66) not null check (wallet_address ~ '^0x[a-fA-F0-9]{64}$'),
+    amount numeric(78, 0) not null,
+    block_number bigint not null,
+    created_at timestamptz not null default now(),
+    primary key (transaction_hash, token_address, event_index)
+);
+
+create index on indexed_transfer(wallet_address, created_at);
@@ -0,0 +1,31 @@
+-- Lifecycle of a queued swap:
+--   pending   -> waiting for its first attempt
+--   submitted -> swap transaction sent to the network
+--   confirmed -> swap transaction accepted on chain
+--   failed    -> last attempt failed, retried once next_attempt_at is reached
+--   dead      -> gave up after max_attempts
+create type swap_job_status as enum ('pending', 'submitted', 'confirmed', 'failed', 'dead');
+
+-- This PostgreSQL table is a durable queue of swaps waiting to be executed by the swap
+-- workers. Workers claim due jobs with `FOR UPDATE SKIP LOCKED`.
+create table swap_jobs(
+    id uuid primary key default uuid_generate_v1mc(),
+    wallet_address varchar(66) not null check (wallet_address ~ '^0x[a-fA-F0-9]{64}$'),
+    from_token varchar(66) not null check (from_token ~ '^0x[a-fA-F0-9]{64}$'),
+    to_token varchar(66) not null check (to_token ~ '^0x[a-fA-F0-9]{64}$'),
+    percentage smallint not null check (percentage between 1 and 100),
+    amount numeric(78, 0) not null check (amount > 0),
+    status swap_job_status not null default 'pending',
+    attempts integer not null default 0,
+    max_attempts integer not null check (max_attempts > 0),
+    next_attempt_at timestamptz not null default now(),
+    last_error text,
+    transaction_hash varchar(66),
+    created_at timestamptz not null default now(),
+    updated_at timestamptz
+);
+
+SELECT trigger_updated_at('"swap_jobs"');
+
+create index on swap_jobs(next_attempt_at) where status in ('pending', 'failed');
+create index on swap_jobs(wallet_address, created_at);
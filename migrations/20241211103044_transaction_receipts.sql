@@ -0,0 +1,23 @@
+-- Outcome of a swap transaction once its receipt is available:
+--   accepted_on_l2 -> executed successfully and included in an L2 block
+--   accepted_on_l1 -> executed successfully and proven on L1
+--   reverted       -> included in a block but the execution reverted
+create type transaction_status as enum ('accepted_on_l2', 'accepted_on_l1', 'reverted');
+
+-- On-chain details of a logged swap. Rows written through /log_transaction have no
+-- transaction attached and leave these columns empty.
+alter table transactions_log
+    add column tx_hash varchar(66) unique,
+    add column status transaction_status,
+    add column block_number bigint check (block_number >= 0),
+    add column revert_reason text,
+    add column actual_fee numeric(78, 0),
+    add column fee_unit varchar(4);
+
+-- A reverted swap moves no tokens.
+alter table transactions_log drop constraint transactions_log_amount_from_check;
+alter table transactions_log add constraint transactions_log_amount_from_check check (amount_from >= 0);
+alter table transactions_log drop constraint transactions_log_amount_to_check;
+alter table transactions_log add constraint transactions_log_amount_to_check check (amount_to >= 0);
+
+create index on transactions_log(status) where status = 'accepted_on_l2';
@@ -0,0 +1,5 @@
+-- Tokens are disabled instead of deleted so that existing subscriptions and logs
+-- keep pointing at a known token.
+alter table token add column is_active boolean not null default true;
+
+create index idx_token_active on token(lower(contract_address)) where is_active = true;
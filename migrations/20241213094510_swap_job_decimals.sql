@@ -0,0 +1,3 @@
+-- Decimals of the from_token, so a job's raw amount can be read without the token registry.
+alter table swap_jobs add column from_decimals smallint not null default 18 check (from_decimals >= 0);
+alter table swap_jobs alter column from_decimals drop default;
@@ -0,0 +1,4 @@
+-- Token amounts are u256 values and do not fit in a bigint.
+alter table transactions_log
+    alter column amount_from type numeric(78, 0),
+    alter column amount_to type numeric(78, 0);
@@ -0,0 +1,11 @@
+-- Largest price movement, in basis points, a subscription accepts on its swaps.
+alter table swap_subscription
+    add column max_slippage_bps smallint not null default 50 check (max_slippage_bps between 0 and 10000);
+
+-- Jobs keep the slippage setting of the subscription they were queued for.
+alter table swap_jobs
+    add column max_slippage_bps smallint not null default 50 check (max_slippage_bps between 0 and 10000);
+alter table swap_jobs alter column max_slippage_bps drop default;
+
+--   aborted -> not sent because the quoted price moved more than the allowed slippage
+alter type swap_job_status add value 'aborted';
@@ -0,0 +1,16 @@
+-- Ekubo pools that swaps between two tokens are quoted in. Pairs without a
+-- registered pool are quoted in the configured fee tiers instead.
+-- Tokens are stored sorted, as in Ekubo's pool keys.
+create table ekubo_pool(
+    id uuid primary key default uuid_generate_v1mc(),
+    token0 varchar(66) not null check (token0 ~ '^0x[a-f0-9]{64}$'),
+    token1 varchar(66) not null check (token1 ~ '^0x[a-f0-9]{64}$'),
+    fee numeric(39, 0) not null check (fee >= 0),
+    tick_spacing bigint not null check (tick_spacing > 0),
+    extension varchar(66) not null check (extension ~ '^0x[a-f0-9]{64}$'),
+    created_at timestamptz not null default now(),
+    updated_at timestamptz,
+    unique (token0, token1, fee, tick_spacing, extension)
+);
+
+SELECT trigger_updated_at('"ekubo_pool"');
@@ -0,0 +1,8 @@
+-- Venue a subscription's swaps go through: 'best_price' or the name of a venue.
+alter table swap_subscription
+    add column venue varchar(32) not null default 'best_price';
+
+-- Jobs keep the venue of the subscription they were queued for.
+alter table swap_jobs
+    add column venue varchar(32) not null default 'best_price';
+alter table swap_jobs alter column venue drop default;
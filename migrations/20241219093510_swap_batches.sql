@@ -0,0 +1,15 @@
+-- Swaps of several jobs can be sent in one transaction, which is then logged once
+-- per job. Rows written for a job are keyed by the job and the transaction.
+alter table transactions_log
+    add column swap_job_id uuid references swap_jobs(id) on delete set null;
+
+update transactions_log
+set swap_job_id = swap_jobs.id
+from swap_jobs
+where swap_jobs.transaction_hash = transactions_log.tx_hash;
+
+alter table transactions_log drop constraint transactions_log_tx_hash_key;
+alter table transactions_log add constraint transactions_log_swap_job_tx_hash_key unique (swap_job_id, tx_hash);
+
+create index on transactions_log(tx_hash);
+create index on swap_jobs(transaction_hash) where status = 'submitted';
@@ -0,0 +1,19 @@
+-- Tokens are priced in USD through a feed: `eth` and `strk` are read from the
+-- router contract, `pragma:<pair id>` from a Pragma oracle. Swaps keep their
+-- USD value at the time they executed.
+alter table token add column price_feed text;
+
+update token set price_feed = 'eth'
+where lower(contract_address) in (
+    '0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7',
+    '0x49d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7'
+);
+update token set price_feed = 'strk'
+where lower(contract_address) in (
+    '0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d',
+    '0x4718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d'
+);
+
+alter table transactions_log
+    add column amount_from_usd numeric(38, 8),
+    add column amount_to_usd numeric(38, 8);
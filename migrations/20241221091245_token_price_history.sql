@@ -0,0 +1,11 @@
+-- USD prices of the registered tokens, snapshotted from the oracles every few
+-- minutes. Token addresses are stored lowercase.
+create table token_price_history(
+    id uuid primary key default uuid_generate_v1mc(),
+    token_address varchar(66) not null check (token_address ~ '^0x[a-f0-9]{1,64}$'),
+    price_usd numeric(38, 18) not null check (price_usd > 0),
+    recorded_at timestamptz not null default now(),
+    unique (token_address, recorded_at)
+);
+
+create index on token_price_history(recorded_at);
@@ -0,0 +1,3 @@
+-- Wallet statistics aggregate the swaps of one wallet per token pair.
+create index on transactions_log(lower(wallet_address), lower(from_token), lower(to_token))
+    where status is distinct from 'reverted';
@@ -0,0 +1,43 @@
+-- Lifecycle of a dollar-cost averaging schedule:
+--   active -> queues a swap every time it is due
+--   paused -> skipped until resumed
+--   ended  -> past its end date, never runs again
+create type dca_schedule_status as enum ('active', 'paused', 'ended');
+
+-- Swaps of a fixed amount of from_token into to_token on a cron schedule. The
+-- scheduler claims due schedules with `FOR UPDATE SKIP LOCKED`, and queues the
+-- swap and moves next_run_at forward in the same transaction.
+create table dca_schedule(
+    id uuid primary key default uuid_generate_v1mc(),
+    wallet_address varchar(66) not null check (wallet_address ~ '^0x[a-fA-F0-9]{64}$'),
+    from_token varchar(66) not null check (from_token ~ '^0x[a-fA-F0-9]{64}$'),
+    to_token varchar(66) not null check (to_token ~ '^0x[a-fA-F0-9]{64}$'),
+    amount numeric(78, 0) not null check (amount > 0),
+    from_decimals smallint not null check (from_decimals >= 0),
+    schedule text not null,
+    max_slippage_bps smallint not null check (max_slippage_bps between 0 and 10000),
+    venue varchar(32) not null,
+    status dca_schedule_status not null default 'active',
+    starts_at timestamptz not null,
+    ends_at timestamptz,
+    -- Null once the schedule has ended.
+    next_run_at timestamptz,
+    last_run_at timestamptz,
+    created_at timestamptz not null default now(),
+    updated_at timestamptz
+);
+
+SELECT trigger_updated_at('"dca_schedule"');
+
+create index on dca_schedule(next_run_at) where status = 'active';
+create index on dca_schedule(wallet_address, created_at);
+
+-- Every run of a schedule, at most once per scheduled time. swap_job_id is null
+-- for runs that were skipped.
+create table dca_run(
+    schedule_id uuid not null references dca_schedule(id) on delete cascade,
+    run_at timestamptz not null,
+    swap_job_id uuid references swap_jobs(id) on delete set null,
+    created_at timestamptz not null default now(),
+    primary key (schedule_id, run_at)
+);
@@ -0,0 +1,59 @@
+-- A subscribed token can be swapped only once its USD price is above or below a
+-- threshold, until an optional expiry.
+create type trigger_condition as enum ('above', 'below');
+
+alter table swap_subscription_from_token
+    add column trigger_condition trigger_condition,
+    add column trigger_price_usd numeric(38, 18) check (trigger_price_usd > 0),
+    add column trigger_expires_at timestamptz,
+    add constraint swap_subscription_from_token_trigger_check
+        check ((trigger_condition is null) = (trigger_price_usd is null));
+
+-- Lifecycle of a swap held until its price trigger is met:
+--   waiting -> the price has not met the trigger yet
+--   fired   -> the swap was queued as swap_job_id
+--   expired -> the trigger expired before it was met
+create type triggered_swap_status as enum ('waiting', 'fired', 'expired');
+
+-- Swaps of incoming transfers held for a price trigger.
+create table triggered_swap(
+    id uuid primary key default uuid_generate_v1mc(),
+    wallet_address varchar(66) not null check (wallet_address ~ '^0x[a-fA-F0-9]{64}$'),
+    from_token varchar(66) not null check (from_token ~ '^0x[a-fA-F0-9]{64}$'),
+    to_token varchar(66) not null check (to_token ~ '^0x[a-fA-F0-9]{64}$'),
+    percentage smallint not null check (percentage between 1 and 100),
+    amount numeric(78, 0) not null check (amount > 0),
+    from_decimals smallint not null check (from_decimals >= 0),
+    max_slippage_bps smallint not null check (max_slippage_bps between 0 and 10000),
+    venue varchar(32) not null,
+    condition trigger_condition not null,
+    price_usd numeric(38, 18) not null check (price_usd > 0),
+    expires_at timestamptz,
+    status triggered_swap_status not null default 'waiting',
+    swap_job_id uuid references swap_jobs(id) on delete set null,
+    created_at timestamptz not null default now(),
+    updated_at timestamptz
+);
+
+SELECT trigger_updated_at('"triggered_swap"');
+
+create index on triggered_swap(created_at) where status = 'waiting';
+create index on triggered_swap(wallet_address, created_at);
+
+-- Outcome of checking a held swap against the price of its token:
+--   waiting  -> the price has not met the trigger
+--   fired    -> the price met the trigger and the swap was queued
+--   expired  -> the trigger had expired
+--   unpriced -> the token could not be priced
+create type trigger_outcome as enum ('waiting', 'fired', 'expired', 'unpriced');
+
+-- Every evaluation of a held swap.
+create table trigger_evaluation(
+    id uuid primary key default uuid_generate_v1mc(),
+    triggered_swap_id uuid not null references triggered_swap(id) on delete cascade,
+    price_usd numeric(38, 18),
+    outcome trigger_outcome not null,
+    evaluated_at timestamptz not null
+);
+
+create index on trigger_evaluation(triggered_swap_id, evaluated_at);
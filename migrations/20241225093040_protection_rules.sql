@@ -0,0 +1,42 @@
+-- Kind of protection a rule gives a held token:
+--   stop_loss   -> swap the whole balance once the price falls to the rule's price
+--   take_profit -> swap the whole balance once the price rises to the rule's price
+create type protection_kind as enum ('stop_loss', 'take_profit');
+
+-- Lifecycle of a protection rule:
+--   active -> the price of the token is watched
+--   fired  -> the balance was queued for a swap as swap_job_id
+create type protection_rule_status as enum ('active', 'fired');
+
+-- Rules of subscribed wallets that swap their balance of a token into a stable
+-- token once its USD price crosses a level.
+create table protection_rule(
+    id uuid primary key default uuid_generate_v1mc(),
+    wallet_address varchar(66) not null references swap_subscription(wallet_address) on delete cascade,
+    token varchar(66) not null check (token ~ '^0x[a-fA-F0-9]{64}$'),
+    to_token varchar(66) not null check (to_token ~ '^0x[a-fA-F0-9]{64}$'),
+    kind protection_kind not null,
+    price_usd numeric(38, 18) not null check (price_usd > 0),
+    status protection_rule_status not null default 'active',
+    swap_job_id uuid references swap_jobs(id) on delete set null,
+    fired_price_usd numeric(38, 18),
+    fired_at timestamptz,
+    -- Why the last time the rule was met did not fire it, such as a missing allowance.
+    last_error text,
+    created_at timestamptz not null default now(),
+    updated_at timestamptz
+);
+
+SELECT trigger_updated_at('"protection_rule"');
+
+-- A token has at most one active rule of each kind per wallet.
+create unique index on protection_rule(lower(wallet_address), lower(token), kind)
+    where status = 'active';
+create index on protection_rule(created_at) where status = 'active';
+
+-- Firings are logged when they queue their swap, without a transaction until the
+-- swap's outcome is recorded.
+alter table transactions_log
+    add column protection_rule_id uuid references protection_rule(id) on delete set null;
+
+create index on transactions_log(swap_job_id) where tx_hash is null;
@@ -0,0 +1,22 @@
+-- A subscribed token can be swapped into several target tokens, its swapped
+-- share being split between them in proportion to their weights. Subscribed
+-- tokens without targets swap into the subscription's to_token.
+create table swap_subscription_target(
+    wallet_address varchar(66) not null,
+    from_token varchar(66) not null,
+    to_token varchar(66) not null check (to_token ~ '^0x[a-fA-F0-9]{64}$'),
+    weight smallint not null check (weight between 1 and 10000),
+    created_at timestamptz not null default now(),
+    updated_at timestamptz,
+    primary key (wallet_address, from_token, to_token),
+    foreign key (wallet_address, from_token)
+        references swap_subscription_from_token(wallet_address, from_token) on delete cascade
+);
+
+SELECT trigger_updated_at('"swap_subscription_target"');
+
+-- Existing subscribed tokens keep swapping into the subscription's to_token.
+insert into swap_subscription_target (wallet_address, from_token, to_token, weight)
+select sf.wallet_address, sf.from_token, s.to_token, 1
+from swap_subscription_from_token sf
+inner join swap_subscription s on s.wallet_address = sf.wallet_address;
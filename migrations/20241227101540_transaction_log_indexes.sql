@@ -0,0 +1,12 @@
+-- Indexes for the transaction log listing, which filters by wallets, tokens or
+-- status and pages by creation time or amount, ties broken by transaction id.
+-- They replace the index on exact wallet, amount and token matches.
+drop index if exists transactions_log_wallet_address_amount_to_from_token_to_tok_idx;
+
+create index on transactions_log(created_at, transaction_id);
+create index on transactions_log(wallet_address, created_at, transaction_id);
+create index on transactions_log(wallet_address, amount_from, transaction_id);
+create index on transactions_log(wallet_address, amount_to, transaction_id);
+create index on transactions_log(from_token, created_at, transaction_id);
+create index on transactions_log(to_token, created_at, transaction_id);
+create index on transactions_log(status, created_at, transaction_id);
@@ -0,0 +1,7 @@
+-- Workers commit their claim on a job before sending its swap, so that a job
+-- whose result could not be recorded is never sent twice:
+--   sending -> claimed by a worker, its swap about to be or already sent
+alter type swap_job_status add value 'sending';
+
+-- When the job was last claimed for sending.
+alter table swap_jobs add column sent_at timestamptz;
@@ -0,0 +1,3 @@
+-- Swaps of protection rules sell the wallet's own tokens, pulled from it with
+-- the allowance it gave the swap account, rather than the swap account's.
+alter table swap_jobs add column pull_from_wallet boolean not null default false;
@@ -0,0 +1,5 @@
+-- When the transaction of a submitted job was sent. Jobs whose transaction the
+-- node still does not know long after are failed rather than tracked forever.
+alter table swap_jobs add column submitted_at timestamptz;
+
+update swap_jobs set submitted_at = coalesce(updated_at, created_at) where status = 'submitted';
@@ -0,0 +1,4 @@
+-- Jobs left sending whose swap may have gone out without its transaction being
+-- recorded. They are not sent again and wait for an operator to check the chain:
+--   unknown -> claimed for sending, but the outcome of its send is not known
+alter type swap_job_status add value 'unknown';
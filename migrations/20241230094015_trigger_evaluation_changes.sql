@@ -0,0 +1,9 @@
+-- Evaluations of a held swap are only logged when their outcome changes.
+-- Drop the repeated ones logged so far.
+delete from trigger_evaluation e
+using (
+    select id, outcome,
+        lag(outcome) over (partition by triggered_swap_id order by evaluated_at, id) as previous
+    from trigger_evaluation
+) logged
+where e.id = logged.id and logged.outcome = logged.previous;
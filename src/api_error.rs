@@ -8,6 +8,8 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::error;
 
+use crate::utils::pagination::PaginationError;
+
 // Error Variants.
 #[derive(Error, Debug)]
 pub enum ApiError {
@@ -15,12 +17,22 @@ pub enum ApiError {
     InvalidJsonBody(#[from] JsonRejection),
     #[error("Invalid request: {0}")]
     InvalidRequest(String),
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+    #[error("{0} not found.")]
+    NotFound(String),
     #[error("A database error has occured.")]
     DatabaseError(#[from] sqlx::Error),
     #[error("An internal server error has occured.")]
     InternalError(#[from] anyhow::Error),
 }
 
+impl From<PaginationError> for ApiError {
+    fn from(err: PaginationError) -> Self {
+        ApiError::InvalidRequest(err.to_string())
+    }
+}
+
 // Error Message.
 #[derive(Serialize, Deserialize)]
 pub struct ApiErrorResp {
@@ -41,7 +53,9 @@ impl IntoResponse for ApiError {
                 JsonRejection::BytesRejection(_) => "Failed to buffer request body".to_string(),
                 _ => "Unknown error".to_string(),
             },
-            ApiError::InvalidRequest(_) => format!("{}", self),
+            ApiError::InvalidRequest(_) | ApiError::Unauthorized(_) | ApiError::NotFound(_) => {
+                format!("{}", self)
+            }
             ApiError::DatabaseError(ref err) => format!("{}", err),
             ApiError::InternalError(ref err) => format!("{}", err),
         };
@@ -55,6 +69,8 @@ impl IntoResponse for ApiError {
         // Status Code for error variants.
         let status = match self {
             ApiError::InvalidJsonBody(_) | ApiError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
             ApiError::DatabaseError(_) | ApiError::InternalError(_) => {
                 StatusCode::INTERNAL_SERVER_ERROR
             }
@@ -0,0 +1,39 @@
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+
+use super::ADMIN_KEY_HEADER;
+use crate::{api_error::ApiError, AppState};
+
+// Guard for admin endpoints. Only succeeds if the request carries the configured
+// admin API key; admin endpoints are disabled when no key is configured.
+pub struct Admin;
+
+#[async_trait]
+impl FromRequestParts<AppState> for Admin {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let expected = match &state.config.admin_api_key {
+            Some(key) => key,
+            None => return Err(ApiError::Unauthorized("Admin API is disabled".to_string())),
+        };
+        let provided = parts
+            .headers
+            .get(ADMIN_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| {
+                ApiError::Unauthorized(format!("Missing {} header", ADMIN_KEY_HEADER))
+            })?;
+
+        match constant_time_eq(provided.as_bytes(), expected.as_bytes()) {
+            true => Ok(Admin),
+            false => Err(ApiError::Unauthorized("Invalid admin key".to_string())),
+        }
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
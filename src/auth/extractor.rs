@@ -0,0 +1,118 @@
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::HeaderMap,
+    Json,
+};
+use serde::de::DeserializeOwned;
+use starknet::core::types::Felt;
+use time::OffsetDateTime;
+
+use super::{
+    nonce::consume_nonce,
+    signature::is_valid_signature,
+    typed_data::{body_hash, method_felt, path_hash, SignedRequest},
+    SignedPayload, EXPIRY_HEADER, MAX_SIGNATURE_TTL, NONCE_HEADER, SIGNATURE_HEADER,
+};
+use crate::{api_error::ApiError, http::is_valid_address, AppState};
+
+// JSON body extractor that only succeeds if the request was signed by the
+// wallet named in the payload.
+pub struct SignedJson<T>(pub T);
+
+#[async_trait]
+impl<T> FromRequest<AppState> for SignedJson<T>
+where
+    T: DeserializeOwned + SignedPayload + Send,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &AppState) -> Result<Self, Self::Rejection> {
+        let headers = req.headers().clone();
+        // The signature covers the route, so it can't be replayed on another one.
+        let method = method_felt(req.method().as_str());
+        let path = path_hash(req.uri().path());
+        let body = Bytes::from_request(req, state)
+            .await
+            .map_err(|_| ApiError::InvalidRequest("Failed to buffer request body".to_string()))?;
+        let Json(payload) = Json::<T>::from_bytes(&body)?;
+
+        let wallet_address = payload.signer();
+        if !is_valid_address(wallet_address) {
+            return Err(ApiError::InvalidRequest(
+                "Invalid wallet address format".to_string(),
+            ));
+        }
+
+        let signature = header(&headers, SIGNATURE_HEADER)?
+            .split(',')
+            .map(|part| Felt::from_hex(part.trim()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| ApiError::Unauthorized("Malformed signature".to_string()))?;
+        let nonce = Felt::from_hex(header(&headers, NONCE_HEADER)?)
+            .map_err(|_| ApiError::Unauthorized("Malformed nonce".to_string()))?;
+        let expiry = header(&headers, EXPIRY_HEADER)?
+            .parse::<u64>()
+            .map_err(|_| ApiError::Unauthorized("Malformed expiry".to_string()))?;
+
+        // Reject stale signatures and ones that stay valid for too long.
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let expiry_ts = i64::try_from(expiry).unwrap_or(i64::MAX);
+        if expiry_ts <= now {
+            return Err(ApiError::Unauthorized("Signature has expired".to_string()));
+        }
+        if expiry_ts > now + MAX_SIGNATURE_TTL {
+            return Err(ApiError::Unauthorized(
+                "Signature expiry is too far in the future".to_string(),
+            ));
+        }
+
+        let request = SignedRequest {
+            wallet_address: Felt::from_hex(wallet_address).map_err(|_| {
+                ApiError::InvalidRequest("Invalid wallet address format".to_string())
+            })?,
+            method,
+            path_hash: path,
+            body_hash: body_hash(&body),
+            nonce,
+            expiry,
+        };
+        let message_hash = request.message_hash(state.config.chain.chain_id());
+
+        let valid = is_valid_signature(
+            &*state.provider,
+            request.wallet_address,
+            message_hash,
+            &signature,
+        )
+        .await
+        .map_err(anyhow::Error::from)?;
+        if !valid {
+            return Err(ApiError::Unauthorized("Invalid signature".to_string()));
+        }
+
+        let fresh = consume_nonce(
+            &state.db.pool,
+            wallet_address,
+            &format!("{:#x}", nonce),
+            expiry,
+        )
+        .await?;
+        if !fresh {
+            return Err(ApiError::Unauthorized(
+                "Nonce has already been used".to_string(),
+            ));
+        }
+
+        Ok(SignedJson(payload))
+    }
+}
+
+// Read a required authentication header.
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str, ApiError> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized(format!("Missing {} header", name)))
+}
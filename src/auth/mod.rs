@@ -0,0 +1,33 @@
+// Wallet authentication for state-changing endpoints.
+//
+// Clients sign a SNIP-12 typed data message binding the request body, a
+// nonce and an expiry, and send the signature in request headers. The
+// signature is checked against the wallet's account contract through
+// `is_valid_signature`, so any account implementation is supported.
+mod admin;
+mod extractor;
+mod nonce;
+mod signature;
+pub mod typed_data;
+
+pub use admin::Admin;
+pub use extractor::SignedJson;
+pub use signature::is_valid_signature;
+
+// Comma separated hex felts of the account signature.
+pub const SIGNATURE_HEADER: &str = "x-signature";
+// Hex felt, single use per wallet.
+pub const NONCE_HEADER: &str = "x-signature-nonce";
+// Unix timestamp in seconds after which the signature is rejected.
+pub const EXPIRY_HEADER: &str = "x-signature-expiry";
+
+// Shared secret required by the admin endpoints.
+pub const ADMIN_KEY_HEADER: &str = "x-admin-key";
+
+// Longest time a signature may stay valid, in seconds.
+pub const MAX_SIGNATURE_TTL: i64 = 15 * 60;
+
+// Payloads that name the wallet which has to sign the request.
+pub trait SignedPayload {
+    fn signer(&self) -> &str;
+}
@@ -0,0 +1,29 @@
+use sqlx::PgPool;
+
+// Record a request nonce as used. Returns false if it was already consumed.
+pub async fn consume_nonce(
+    db: &PgPool,
+    wallet_address: &str,
+    nonce: &str,
+    expiry: u64,
+) -> Result<bool, sqlx::Error> {
+    // Expired nonces can never be replayed, so there is no need to keep them around.
+    sqlx::query("DELETE FROM auth_nonce WHERE expires_at < NOW()")
+        .execute(db)
+        .await?;
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO auth_nonce (wallet_address, nonce, expires_at)
+        VALUES ($1, $2, TO_TIMESTAMP($3))
+        ON CONFLICT (wallet_address, nonce) DO NOTHING
+        "#,
+    )
+    .bind(wallet_address)
+    .bind(nonce)
+    .bind(expiry as f64)
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
@@ -0,0 +1,42 @@
+use starknet::{
+    core::types::{BlockId, BlockTag, Felt, FunctionCall, StarknetError},
+    macros::{selector, short_string},
+    providers::{Provider, ProviderError},
+};
+
+// Magic value returned by SNIP-6 accounts for a valid signature.
+const VALID: Felt = short_string!("VALID");
+
+// Ask the account contract whether `signature` is valid for `message_hash`.
+// Older account implementations return `1` instead of the SNIP-6 magic value.
+pub async fn is_valid_signature<P: Provider>(
+    provider: &P,
+    account: Felt,
+    message_hash: Felt,
+    signature: &[Felt],
+) -> Result<bool, ProviderError> {
+    let calldata = [message_hash, Felt::from(signature.len())]
+        .into_iter()
+        .chain(signature.iter().copied())
+        .collect();
+
+    let result = provider
+        .call(
+            FunctionCall {
+                contract_address: account,
+                entry_point_selector: selector!("is_valid_signature"),
+                calldata,
+            },
+            BlockId::Tag(BlockTag::Pending),
+        )
+        .await;
+
+    match result {
+        Ok(values) => Ok(matches!(values.first(), Some(v) if *v == VALID || *v == Felt::ONE)),
+        // Accounts revert on bad signatures and undeployed accounts have no code to call.
+        Err(ProviderError::StarknetError(
+            StarknetError::ContractError(_) | StarknetError::ContractNotFound,
+        )) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
@@ -0,0 +1,75 @@
+use starknet::core::{
+    types::Felt,
+    utils::{cairo_short_string_to_felt, starknet_keccak},
+};
+use starknet_crypto::poseidon_hash_many;
+
+// SNIP-12 (revision 1) domain and message type encodings.
+const DOMAIN_TYPE: &str = r#""StarknetDomain"("name":"shortstring","version":"shortstring","chainId":"shortstring","revision":"shortstring")"#;
+const REQUEST_TYPE: &str = r#""AutoswapprRequest"("wallet_address":"ContractAddress","method":"shortstring","path_hash":"felt","body_hash":"felt","nonce":"felt","expiry":"timestamp")"#;
+
+pub const DOMAIN_NAME: &str = "Autoswappr";
+pub const DOMAIN_VERSION: Felt = Felt::ONE;
+pub const DOMAIN_REVISION: Felt = Felt::ONE;
+
+// Typed data message a wallet signs to authorize a single API request.
+#[derive(Debug, Clone, Copy)]
+pub struct SignedRequest {
+    pub wallet_address: Felt,
+    pub method: Felt,
+    pub path_hash: Felt,
+    pub body_hash: Felt,
+    pub nonce: Felt,
+    pub expiry: u64,
+}
+
+impl SignedRequest {
+    // Hash of the request struct as defined by SNIP-12.
+    pub fn struct_hash(&self) -> Felt {
+        poseidon_hash_many(&[
+            starknet_keccak(REQUEST_TYPE.as_bytes()),
+            self.wallet_address,
+            self.method,
+            self.path_hash,
+            self.body_hash,
+            self.nonce,
+            Felt::from(self.expiry),
+        ])
+    }
+
+    // Final message hash that is checked against the account's `is_valid_signature`.
+    pub fn message_hash(&self, chain_id: Felt) -> Felt {
+        poseidon_hash_many(&[
+            cairo_short_string_to_felt("StarkNet Message").unwrap(),
+            domain_hash(chain_id),
+            self.wallet_address,
+            self.struct_hash(),
+        ])
+    }
+}
+
+// Hash of the Autoswappr signing domain on the given chain.
+pub fn domain_hash(chain_id: Felt) -> Felt {
+    poseidon_hash_many(&[
+        starknet_keccak(DOMAIN_TYPE.as_bytes()),
+        cairo_short_string_to_felt(DOMAIN_NAME).unwrap(),
+        DOMAIN_VERSION,
+        chain_id,
+        DOMAIN_REVISION,
+    ])
+}
+
+// HTTP method as the short string that ends up in the signed message.
+pub fn method_felt(method: &str) -> Felt {
+    cairo_short_string_to_felt(method).unwrap_or(Felt::ZERO)
+}
+
+// Hash of the request path, resource ids included, that ends up in the signed message.
+pub fn path_hash(path: &str) -> Felt {
+    starknet_keccak(path.as_bytes())
+}
+
+// Hash of the raw request body that ends up in the signed message.
+pub fn body_hash(body: &[u8]) -> Felt {
+    starknet_keccak(body)
+}
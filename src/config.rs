@@ -1,21 +1,54 @@
 use serde::Deserialize;
+use starknet::{
+    core::{chain_id, types::Felt},
+    providers::Url,
+};
 use std::{
     net::{Ipv6Addr, SocketAddr},
     str::FromStr,
     sync::Arc,
 };
 
+use crate::utils::ekubo::{PoolTier, DEFAULT_POOL_TIERS};
+
 // Type alias for thread safe app configuration.
 pub type Config = Arc<Configuration>;
 
 // App Configuration Struct.
-#[derive(Deserialize)]
 pub struct Configuration {
     pub env: Environment,
     pub listen_address: SocketAddr,
     pub app_port: u16,
     pub db_str: String,
     pub db_pool_max_size: u32,
+    pub rpc_url: Url,
+    pub chain: Chain,
+    // Account that sends the swaps, and its key.
+    pub account_address: Felt,
+    pub private_key: Felt,
+    // Router contract the swaps go through.
+    pub contract_address: Felt,
+    // More accounts swaps are spread across.
+    pub executor_accounts: Vec<ExecutorAccount>,
+    pub indexer_start_block: Option<u64>,
+    pub indexer_poll_interval_secs: u64,
+    pub indexer_reorg_depth: u64,
+    pub swap_workers: usize,
+    pub swap_batch_size: i64,
+    pub swap_batch_window_secs: u64,
+    pub swap_job_max_attempts: i32,
+    pub receipt_poll_interval_secs: u64,
+    pub receipt_submission_timeout_secs: u64,
+    pub admin_api_key: Option<String>,
+    pub avnu_api_url: Option<String>,
+    pub ekubo_pool_tiers: Vec<PoolTier>,
+    // Pragma oracle that `pragma:` price feeds are read from.
+    pub pragma_address: Option<Felt>,
+    pub price_cache_ttl_secs: u64,
+    pub price_snapshot_interval_mins: u64,
+    pub dca_poll_interval_secs: u64,
+    pub trigger_poll_interval_secs: u64,
+    pub protection_poll_interval_secs: u64,
 }
 
 // Environment application is running in.
@@ -25,6 +58,31 @@ pub enum Environment {
     Production,
 }
 
+// An account that sends swaps, and its key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutorAccount {
+    pub address: Felt,
+    pub private_key: Felt,
+}
+
+// Starknet network the app sends transactions to.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chain {
+    Mainnet,
+    Sepolia,
+    Devnet,
+}
+
+impl Chain {
+    pub fn chain_id(&self) -> Felt {
+        match self {
+            Chain::Mainnet => chain_id::MAINNET,
+            // starknet-devnet uses the Sepolia chain id unless started with another.
+            Chain::Sepolia | Chain::Devnet => chain_id::SEPOLIA,
+        }
+    }
+}
+
 impl Configuration {
     pub fn new() -> Config {
         // Environment application is running in.
@@ -43,6 +101,130 @@ impl Configuration {
             .parse::<u32>()
             .expect("Unable to parse the value of the DATABASE_POOL_MAX_SIZE environment variable. Please make sure it is a valid unsigned 32-bit integer.");
 
+        // Starknet JSON-RPC endpoint and network.
+        let rpc_url = Url::parse(&env_var("RPC_URL"))
+            .expect("Unable to parse the value of the RPC_URL environment variable. Please make sure it is a valid URL.");
+        let chain = env_var_or("STARKNET_CHAIN", "mainnet")
+            .parse::<Chain>()
+            .expect("Unable to parse the value of the STARKNET_CHAIN environment variable. Please make sure it is either \"mainnet\", \"sepolia\" or \"devnet\".");
+
+        // Account that sends the swaps, and the router contract they go through.
+        let account_address = Felt::from_hex(&env_var("PUBLIC_KEY"))
+            .expect("Unable to parse the value of the PUBLIC_KEY environment variable. Please make sure it is the hex address of the account.");
+        let private_key = Felt::from_hex(&env_var("PRIVATE_KEY"))
+            .expect("Unable to parse the value of the PRIVATE_KEY environment variable. Please make sure it is a hex private key.");
+        let contract_address = Felt::from_hex(&env_var("CONTRACT_ADDRESS"))
+            .expect("Unable to parse the value of the CONTRACT_ADDRESS environment variable. Please make sure it is the hex address of the router contract.");
+        let executor_accounts = env_var_or("EXECUTOR_ACCOUNTS", "")
+            .split(',')
+            .filter(|account| !account.trim().is_empty())
+            .map(|account| account.parse::<ExecutorAccount>())
+            .collect::<Result<Vec<_>, _>>()
+            .expect("Unable to parse the value of the EXECUTOR_ACCOUNTS environment variable. Please make sure it is a comma separated list of address:private_key pairs.");
+
+        // Transfer indexer parameters.
+        let indexer_start_block = std::env::var("INDEXER_START_BLOCK")
+            .ok()
+            .filter(|block| !block.is_empty())
+            .map(|block| {
+            block
+                .parse::<u64>()
+                .expect("Unable to parse the value of the INDEXER_START_BLOCK environment variable. Please make sure it is a valid block number.")
+        });
+        let indexer_poll_interval_secs = env_var_or("INDEXER_POLL_INTERVAL_SECS", "10")
+            .parse::<u64>()
+            .expect("Unable to parse the value of the INDEXER_POLL_INTERVAL_SECS environment variable. Please make sure it is a valid unsigned 64-bit integer.");
+        let indexer_reorg_depth = env_var_or("INDEXER_REORG_DEPTH", "10")
+            .parse::<u64>()
+            .expect("Unable to parse the value of the INDEXER_REORG_DEPTH environment variable. Please make sure it is a valid unsigned 64-bit integer.");
+
+        // Swap job queue parameters.
+        let swap_workers = env_var_or("SWAP_WORKERS", "4")
+            .parse::<usize>()
+            .expect("Unable to parse the value of the SWAP_WORKERS environment variable. Please make sure it is a valid unsigned integer.");
+        // Swaps of the same pair are sent together, up to SWAP_BATCH_SIZE in one
+        // transaction, once the oldest has waited SWAP_BATCH_WINDOW_SECS.
+        let swap_batch_size = env_var_or("SWAP_BATCH_SIZE", "10")
+            .parse::<i64>()
+            .ok()
+            .filter(|size| *size > 0)
+            .expect("Unable to parse the value of the SWAP_BATCH_SIZE environment variable. Please make sure it is a positive integer.");
+        let swap_batch_window_secs = env_var_or("SWAP_BATCH_WINDOW_SECS", "2")
+            .parse::<u64>()
+            .expect("Unable to parse the value of the SWAP_BATCH_WINDOW_SECS environment variable. Please make sure it is a valid unsigned 64-bit integer.");
+        let swap_job_max_attempts = env_var_or("SWAP_JOB_MAX_ATTEMPTS", "5")
+            .parse::<i32>()
+            .ok()
+            .filter(|attempts| *attempts > 0)
+            .expect("Unable to parse the value of the SWAP_JOB_MAX_ATTEMPTS environment variable. Please make sure it is a positive 32-bit integer.");
+
+        // Receipt tracker parameters.
+        let receipt_poll_interval_secs = env_var_or("RECEIPT_POLL_INTERVAL_SECS", "5")
+            .parse::<u64>()
+            .expect("Unable to parse the value of the RECEIPT_POLL_INTERVAL_SECS environment variable. Please make sure it is a valid unsigned 64-bit integer.");
+        let receipt_submission_timeout_secs = env_var_or("RECEIPT_SUBMISSION_TIMEOUT_SECS", "600")
+            .parse::<u64>()
+            .expect("Unable to parse the value of the RECEIPT_SUBMISSION_TIMEOUT_SECS environment variable. Please make sure it is a valid unsigned 64-bit integer.");
+
+        // Key required by the admin endpoints, which are disabled when it is empty.
+        let admin_api_key = std::env::var("ADMIN_API_KEY")
+            .ok()
+            .filter(|key| !key.is_empty());
+
+        // AVNU swap API that routes are fetched from. AVNU is not used when it is empty.
+        let avnu_api_url = std::env::var("AVNU_API_URL")
+            .ok()
+            .filter(|url| !url.is_empty());
+
+        // Ekubo pools quoted for pairs without a registered pool, as comma separated
+        // `fee:tick_spacing[:extension]` tiers.
+        let ekubo_pool_tiers = env_var_or("EKUBO_POOL_TIERS", DEFAULT_POOL_TIERS)
+            .split(',')
+            .filter(|tier| !tier.trim().is_empty())
+            .map(|tier| tier.parse::<PoolTier>())
+            .collect::<Result<Vec<_>, _>>()
+            .expect("Unable to parse the value of the EKUBO_POOL_TIERS environment variable. Please make sure it is a comma separated list of fee:tick_spacing pairs.");
+
+        // Oracle prices. Tokens with a `pragma:` price feed are not priced when
+        // PRAGMA_ADDRESS is empty.
+        let pragma_address = std::env::var("PRAGMA_ADDRESS")
+            .ok()
+            .filter(|address| !address.is_empty())
+            .map(|address| {
+            Felt::from_hex(&address)
+                .expect("Unable to parse the value of the PRAGMA_ADDRESS environment variable. Please make sure it is the hex address of the Pragma oracle.")
+        });
+        let price_cache_ttl_secs = env_var_or("PRICE_CACHE_TTL_SECS", "60")
+            .parse::<u64>()
+            .expect("Unable to parse the value of the PRICE_CACHE_TTL_SECS environment variable. Please make sure it is a valid unsigned 64-bit integer.");
+        // Token prices are stored for the price history every PRICE_SNAPSHOT_INTERVAL_MINS.
+        let price_snapshot_interval_mins = env_var_or("PRICE_SNAPSHOT_INTERVAL_MINS", "5")
+            .parse::<u64>()
+            .ok()
+            .filter(|mins| *mins > 0)
+            .expect("Unable to parse the value of the PRICE_SNAPSHOT_INTERVAL_MINS environment variable. Please make sure it is a positive integer.");
+
+        // DCA schedules are checked for due runs every DCA_POLL_INTERVAL_SECS.
+        let dca_poll_interval_secs = env_var_or("DCA_POLL_INTERVAL_SECS", "30")
+            .parse::<u64>()
+            .ok()
+            .filter(|secs| *secs > 0)
+            .expect("Unable to parse the value of the DCA_POLL_INTERVAL_SECS environment variable. Please make sure it is a positive integer.");
+
+        // Swaps held for a price trigger are checked every TRIGGER_POLL_INTERVAL_SECS.
+        let trigger_poll_interval_secs = env_var_or("TRIGGER_POLL_INTERVAL_SECS", "30")
+            .parse::<u64>()
+            .ok()
+            .filter(|secs| *secs > 0)
+            .expect("Unable to parse the value of the TRIGGER_POLL_INTERVAL_SECS environment variable. Please make sure it is a positive integer.");
+
+        // Protection rules are checked against token prices every PROTECTION_POLL_INTERVAL_SECS.
+        let protection_poll_interval_secs = env_var_or("PROTECTION_POLL_INTERVAL_SECS", "30")
+            .parse::<u64>()
+            .ok()
+            .filter(|secs| *secs > 0)
+            .expect("Unable to parse the value of the PROTECTION_POLL_INTERVAL_SECS environment variable. Please make sure it is a positive integer.");
+
         // 0.0.0.0 + Port to support containerisation.
         let listen_address = SocketAddr::from((Ipv6Addr::UNSPECIFIED, app_port));
 
@@ -53,6 +235,30 @@ impl Configuration {
             app_port,
             db_str,
             db_pool_max_size,
+            rpc_url,
+            chain,
+            account_address,
+            private_key,
+            contract_address,
+            executor_accounts,
+            indexer_start_block,
+            indexer_poll_interval_secs,
+            indexer_reorg_depth,
+            swap_workers,
+            swap_batch_size,
+            swap_batch_window_secs,
+            swap_job_max_attempts,
+            receipt_poll_interval_secs,
+            receipt_submission_timeout_secs,
+            admin_api_key,
+            avnu_api_url,
+            ekubo_pool_tiers,
+            pragma_address,
+            price_cache_ttl_secs,
+            price_snapshot_interval_mins,
+            dca_poll_interval_secs,
+            trigger_poll_interval_secs,
+            protection_poll_interval_secs,
         })
     }
 
@@ -60,6 +266,36 @@ impl Configuration {
     pub fn set_db_str(&mut self, db_str: String) {
         self.db_str = db_str
     }
+
+    // Helper function to point the app at a mock RPC node in test environment
+    pub fn set_rpc_url(&mut self, rpc_url: String) {
+        self.rpc_url = Url::parse(&rpc_url).expect("Invalid RPC URL")
+    }
+
+    // Helper function to spread swaps across more accounts in test environment
+    pub fn set_executor_accounts(&mut self, executor_accounts: Vec<ExecutorAccount>) {
+        self.executor_accounts = executor_accounts
+    }
+
+    // Helper function to point the app at a stub AVNU API in test environment
+    pub fn set_avnu_api_url(&mut self, avnu_api_url: Option<String>) {
+        self.avnu_api_url = avnu_api_url
+    }
+
+    // Helper function to set the Ekubo pool tiers in test environment
+    pub fn set_ekubo_pool_tiers(&mut self, ekubo_pool_tiers: Vec<PoolTier>) {
+        self.ekubo_pool_tiers = ekubo_pool_tiers
+    }
+
+    // Helper function to read `pragma:` price feeds in test environment
+    pub fn set_pragma_address(&mut self, pragma_address: Option<Felt>) {
+        self.pragma_address = pragma_address
+    }
+
+    // Helper function to enable the admin endpoints in test environment
+    pub fn set_admin_api_key(&mut self, admin_api_key: String) {
+        self.admin_api_key = Some(admin_api_key)
+    }
 }
 
 impl FromStr for Environment {
@@ -78,9 +314,48 @@ impl FromStr for Environment {
     }
 }
 
+// Parse an `address:private_key` pair.
+impl FromStr for ExecutorAccount {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (address, private_key) = s
+            .trim()
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid executor account: {}", s))?;
+        Ok(ExecutorAccount {
+            address: Felt::from_hex(address)
+                .map_err(|_| format!("Invalid executor account address: {}", address))?,
+            private_key: Felt::from_hex(private_key)
+                .map_err(|_| "Invalid executor account private key".to_string())?,
+        })
+    }
+}
+
+impl FromStr for Chain {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mainnet" => Ok(Chain::Mainnet),
+            "sepolia" => Ok(Chain::Sepolia),
+            "devnet" => Ok(Chain::Devnet),
+            _ => Err(format!(
+                "Invalid chain: {}. \"mainnet\", \"sepolia\" or \"devnet\" currently supported",
+                s
+            )),
+        }
+    }
+}
+
 // Helper function to read environment variables
 pub fn env_var(name: &str) -> String {
     std::env::var(name)
         .map_err(|e| format!("{}: {}", name, e))
         .expect("Missing environment variable")
 }
+
+// Helper function to read optional environment variables
+pub fn env_var_or(name: &str, default: &str) -> String {
+    std::env::var(name).unwrap_or_else(|_| default.to_string())
+}
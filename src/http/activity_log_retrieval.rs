@@ -1,110 +1,124 @@
 use crate::AppState;
 use axum::extract::{Query, State};
 use axum::Json;
-use serde_json::{json, Value};
-
-use super::types::{ActivityLogData, ActivityLogGetRequest, ActivityLogGetResponse};
-use crate::api_error::ApiError;
 use time::format_description::well_known::Rfc3339;
 use time::OffsetDateTime;
 
-const LIMIT: i32 = 10;
+use super::types::{ActivityLogData, ActivityLogGetRequest, ActivityLogSort, SortOrder};
+use crate::api_error::ApiError;
+use crate::utils::filters::{Column, Filters};
+use crate::utils::pagination::{Keyset, Page, Pagination};
+
+const CREATED_AT: Column = Column::cast("created_at", "TIMESTAMPTZ");
+
+// Most values one list filter can take.
+const MAX_FILTER_VALUES: usize = 50;
 
 pub async fn log_retrieval(
     State(app_state): State<AppState>,
     Query(query_params): Query<ActivityLogGetRequest>,
-) -> Result<Json<Value>, ApiError> {
-    // Add default date if no cursor is provided
-    let cursor: String = match query_params.cursor {
-        Some(cursor1) => match OffsetDateTime::parse(&cursor1, &Rfc3339) {
-            Ok(cur) => cur.format(&Rfc3339).unwrap(),
-            Err(_) => return Err(ApiError::InvalidRequest("Invalid cursor".to_string())),
-        },
+) -> Result<Json<Page<ActivityLogData>>, ApiError> {
+    for (name, values) in [
+        ("wallet_address", &query_params.wallet_address),
+        ("from_token", &query_params.from_token),
+        ("to_token", &query_params.to_token),
+    ] {
+        if values.as_ref().is_some_and(|v| v.len() > MAX_FILTER_VALUES) {
+            return Err(ApiError::InvalidRequest(format!(
+                "{} takes at most {} values",
+                name, MAX_FILTER_VALUES
+            )));
+        }
+    }
+    let from = query_params
+        .from
+        .as_deref()
+        .map(|from| parse_time(from, "from"))
+        .transpose()?;
+    let to = query_params
+        .to
+        .as_deref()
+        .map(|to| parse_time(to, "to"))
+        .transpose()?;
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            return Err(ApiError::InvalidRequest(
+                "from must not be after to".to_string(),
+            ));
+        }
+    }
 
-        None => {
-            let now = OffsetDateTime::now_utc();
-            now.format(&Rfc3339).unwrap()
+    // Ties are broken by transaction id, in the same direction.
+    let (key, sort_key) = match query_params.sort_by {
+        ActivityLogSort::CreatedAt => (
+            CREATED_AT,
+            r#"TO_CHAR(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"')"#,
+        ),
+        ActivityLogSort::AmountFrom => {
+            (Column::cast("amount_from", "NUMERIC"), "amount_from::TEXT")
         }
+        ActivityLogSort::AmountTo => (Column::cast("amount_to", "NUMERIC"), "amount_to::TEXT"),
     };
-    let initial_query = r#"
+    let keyset = Keyset {
+        key,
+        id: Column::cast("transaction_id", "UUID"),
+        descending: query_params.order == SortOrder::Desc,
+    };
+    let pagination = Pagination::new(keyset, query_params.cursor.as_deref(), query_params.limit)?;
+
+    let mut filters = Filters::new(&format!(
+        r#"
         SELECT
+            transaction_id,
             wallet_address,
             from_token,
             to_token,
             amount_from,
             percentage,
             amount_to,
-            TO_CHAR(created_at, 'YYYY-MM-DD"T"HH24:MI:SSZ') AS created_at
+            TRIM_SCALE(amount_from_usd)::TEXT AS amount_from_usd,
+            TRIM_SCALE(amount_to_usd)::TEXT AS amount_to_usd,
+            tx_hash,
+            status::TEXT AS status,
+            TO_CHAR(created_at, 'YYYY-MM-DD"T"HH24:MI:SSZ') AS created_at,
+            {} AS sort_key
         FROM transactions_log
-        WHERE created_at < $1::TIMESTAMPTZ
-    "#;
-    let mut conditions = vec![];
-    if let Some(wallet_address) = query_params.wallet_address {
-        conditions.push(format!("AND wallet_address = '{}'", wallet_address));
-    }
-
-    if let Some(from_token) = query_params.from_token {
-        conditions.push(format!("AND from_token = '{}'", from_token));
-    }
-
-    if let Some(to_token) = query_params.to_token {
-        conditions.push(format!("AND to_token = '{}'", to_token));
-    }
-
-    if let Some(amount_to) = query_params.amount_to {
-        conditions.push(format!("AND amount_to = {}", amount_to));
-    }
-
-    let query_build = format!(
-        r#"
-        {}
-        {}
-        ORDER BY created_at DESC
-        LIMIT $2
         "#,
-        initial_query,
-        conditions.join(" ")
-    );
-    let rows: Vec<ActivityLogData> = sqlx::query_as::<_, ActivityLogData>(&query_build)
-        .bind(cursor)
-        .bind(LIMIT)
+        sort_key
+    ));
+    filters
+        .any_of("wallet_address", query_params.wallet_address)
+        .any_of("from_token", query_params.from_token)
+        .any_of("to_token", query_params.to_token)
+        .any_of("status", query_params.status)
+        .eq("amount_to", query_params.amount_to)
+        .range(
+            "amount_from",
+            query_params.min_amount_from,
+            query_params.max_amount_from,
+        )
+        .range(
+            "amount_to",
+            query_params.min_amount_to,
+            query_params.max_amount_to,
+        )
+        .range(
+            CREATED_AT,
+            from.map(|from| from.format(&Rfc3339).unwrap()),
+            to.map(|to| to.format(&Rfc3339).unwrap()),
+        );
+
+    let rows: Vec<ActivityLogData> = pagination
+        .apply(filters)
+        .build_query_as::<ActivityLogData>()
         .fetch_all(&app_state.db.pool)
         .await
         .map_err(ApiError::DatabaseError)?;
 
-    // Map results to the response data structure
-    let mut response_data: ActivityLogGetResponse = ActivityLogGetResponse {
-        transactions: rows
-            .into_iter()
-            .map(|row| ActivityLogData {
-                wallet_address: row.wallet_address,
-                from_token: row.from_token,
-                to_token: row.to_token,
-                percentage: row.percentage,
-                amount_from: row.amount_from,
-                amount_to: row.amount_to,
-                created_at: row.created_at,
-            })
-            .collect(),
-        next_cursor: None,
-    };
-
-    // Check if there are more transactions
-    match response_data.transactions.len() == LIMIT as usize {
-        true => {
-            response_data.next_cursor = Some(
-                response_data
-                    .transactions
-                    .last()
-                    .unwrap()
-                    .created_at
-                    .clone(),
-            );
-        }
-        false => {
-            response_data.next_cursor = None;
-        }
-    };
+    Ok(Json(pagination.page(rows)))
+}
 
-    Ok(Json(json!(response_data)))
+fn parse_time(value: &str, name: &str) -> Result<OffsetDateTime, ApiError> {
+    OffsetDateTime::parse(value, &Rfc3339)
+        .map_err(|_| ApiError::InvalidRequest(format!("Invalid {}, use an RFC 3339 time", name)))
 }
@@ -0,0 +1,310 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use sqlx::PgConnection;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use uuid::Uuid;
+
+use super::types::{
+    is_valid_address, CreateDcaRequest, DcaOwnerRequest, ListDcaRequest, SuccessResponse,
+    UpdateDcaRequest,
+};
+use crate::{
+    api_error::ApiError,
+    auth::SignedJson,
+    service::{
+        dca::{self, first_run, next_run, DcaSchedule, DcaStatus, DcaUpdate, NewDcaSchedule},
+        slippage::{DEFAULT_MAX_SLIPPAGE_BPS, MAX_SLIPPAGE_BPS},
+        tokens,
+        venues::BEST_PRICE,
+    },
+    utils::{
+        amount::Amount,
+        cron::CronSchedule,
+        pagination::{Page, Pagination},
+    },
+    AppState,
+};
+
+pub async fn create_dca(
+    State(state): State<AppState>,
+    SignedJson(payload): SignedJson<CreateDcaRequest>,
+) -> Result<Json<DcaSchedule>, ApiError> {
+    if !is_valid_address(&payload.from_token) || !is_valid_address(&payload.to_token) {
+        return Err(ApiError::InvalidRequest(
+            "Invalid token address format".to_string(),
+        ));
+    }
+    if payload.from_token.eq_ignore_ascii_case(&payload.to_token) {
+        return Err(ApiError::InvalidRequest(
+            "from_token and to_token must differ".to_string(),
+        ));
+    }
+
+    // Schedules belong to an active subscription.
+    if !dca::is_subscribed(&state.db.pool, &payload.wallet_address).await? {
+        return Err(ApiError::InvalidRequest(
+            "Wallet has no active subscription".to_string(),
+        ));
+    }
+
+    // Only registered tokens can be swapped.
+    let from_token = match tokens::get(&state.db.pool, &payload.from_token).await? {
+        Some(token) if token.is_active => token,
+        _ => return Err(ApiError::InvalidRequest("Unknown from_token".to_string())),
+    };
+    if !tokens::unregistered(&state.db.pool, &[payload.to_token.as_str()])
+        .await?
+        .is_empty()
+    {
+        return Err(ApiError::InvalidRequest("Unknown to_token".to_string()));
+    }
+
+    let amount = parse_amount(&payload.amount, from_token.token_decimals)?;
+    let schedule = parse_schedule(&payload.schedule)?;
+    let max_slippage_bps =
+        check_slippage(payload.max_slippage_bps.unwrap_or(DEFAULT_MAX_SLIPPAGE_BPS))?;
+    let venue = check_venue(
+        &state,
+        payload.venue.unwrap_or_else(|| BEST_PRICE.to_string()),
+    )?;
+
+    let starts_at = match payload.starts_at {
+        Some(starts_at) => parse_time(&starts_at, "starts_at")?,
+        None => OffsetDateTime::now_utc(),
+    };
+    let ends_at = match payload.ends_at {
+        Some(ends_at) => Some(parse_time(&ends_at, "ends_at")?),
+        None => None,
+    };
+    if first_run(&schedule, starts_at, ends_at).is_none() {
+        return Err(never_runs());
+    }
+
+    let dca = NewDcaSchedule {
+        wallet_address: payload.wallet_address,
+        from_token: payload.from_token,
+        to_token: payload.to_token,
+        amount,
+        schedule,
+        max_slippage_bps,
+        venue,
+        starts_at,
+        ends_at,
+    };
+    Ok(Json(dca::create(&state.db.pool, &dca).await?))
+}
+
+pub async fn list_dca(
+    State(state): State<AppState>,
+    Query(params): Query<ListDcaRequest>,
+) -> Result<Json<Page<DcaSchedule>>, ApiError> {
+    if !is_valid_address(&params.wallet_address) {
+        return Err(ApiError::InvalidRequest(
+            "Invalid wallet address format".to_string(),
+        ));
+    }
+    let pagination = Pagination::new(dca::KEYSET, params.cursor.as_deref(), params.limit)?;
+    Ok(Json(
+        dca::list(&state.db.pool, &params.wallet_address, &pagination).await?,
+    ))
+}
+
+pub async fn get_dca(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DcaSchedule>, ApiError> {
+    let mut conn = state.db.pool.acquire().await?;
+    match dca::find(&mut conn, id).await? {
+        Some(dca) => Ok(Json(dca)),
+        None => Err(ApiError::NotFound("DCA schedule".to_string())),
+    }
+}
+
+// Change the settings of a schedule that has not ended. A new schedule or end
+// date moves the next run to the first one from now.
+pub async fn update_dca(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    SignedJson(payload): SignedJson<UpdateDcaRequest>,
+) -> Result<Json<DcaSchedule>, ApiError> {
+    let mut tx = state.db.pool.begin().await?;
+    let current = owned(&mut tx, id, &payload.wallet_address).await?;
+    if current.status == DcaStatus::Ended {
+        return Err(ApiError::InvalidRequest("Schedule has ended".to_string()));
+    }
+
+    let mut update = settings(&current)?;
+    if let Some(amount) = payload.amount {
+        update.amount = parse_amount(&amount, current.from_decimals)?;
+    }
+    if let Some(max_slippage_bps) = payload.max_slippage_bps {
+        update.max_slippage_bps = check_slippage(max_slippage_bps)?;
+    }
+    if let Some(venue) = payload.venue {
+        update.venue = check_venue(&state, venue)?;
+    }
+    if payload.schedule.is_some() || payload.ends_at.is_some() {
+        let schedule = match payload.schedule {
+            Some(schedule) => parse_schedule(&schedule)?,
+            None => parse_schedule(&current.schedule)?,
+        };
+        if let Some(ends_at) = payload.ends_at {
+            update.ends_at = Some(parse_time(&ends_at, "ends_at")?);
+        }
+        update.schedule = schedule.to_string();
+        update.next_run_at = upcoming_run(&current, &schedule, update.ends_at);
+        if update.next_run_at.is_none() {
+            return Err(never_runs());
+        }
+    }
+
+    let dca = dca::update(&mut tx, id, &update).await?;
+    tx.commit().await?;
+    Ok(Json(dca))
+}
+
+// Stop queueing swaps until the schedule is resumed.
+pub async fn pause_dca(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    SignedJson(payload): SignedJson<DcaOwnerRequest>,
+) -> Result<Json<DcaSchedule>, ApiError> {
+    let mut tx = state.db.pool.begin().await?;
+    let current = owned(&mut tx, id, &payload.wallet_address).await?;
+    if current.status == DcaStatus::Ended {
+        return Err(ApiError::InvalidRequest("Schedule has ended".to_string()));
+    }
+
+    let update = DcaUpdate {
+        status: DcaStatus::Paused,
+        ..settings(&current)?
+    };
+    let dca = dca::update(&mut tx, id, &update).await?;
+    tx.commit().await?;
+    Ok(Json(dca))
+}
+
+// Queue swaps again from the first run after now. Runs due while the schedule
+// was paused are skipped, and a schedule past its end date ends.
+pub async fn resume_dca(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    SignedJson(payload): SignedJson<DcaOwnerRequest>,
+) -> Result<Json<DcaSchedule>, ApiError> {
+    let mut tx = state.db.pool.begin().await?;
+    let current = owned(&mut tx, id, &payload.wallet_address).await?;
+    if current.status == DcaStatus::Ended {
+        return Err(ApiError::InvalidRequest("Schedule has ended".to_string()));
+    }
+    if current.status == DcaStatus::Active {
+        return Ok(Json(current));
+    }
+
+    let mut update = settings(&current)?;
+    update.next_run_at = upcoming_run(
+        &current,
+        &parse_schedule(&current.schedule)?,
+        update.ends_at,
+    );
+    update.status = match update.next_run_at {
+        Some(_) => DcaStatus::Active,
+        None => DcaStatus::Ended,
+    };
+    let dca = dca::update(&mut tx, id, &update).await?;
+    tx.commit().await?;
+    Ok(Json(dca))
+}
+
+pub async fn delete_dca(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    SignedJson(payload): SignedJson<DcaOwnerRequest>,
+) -> Result<Json<SuccessResponse>, ApiError> {
+    let mut tx = state.db.pool.begin().await?;
+    owned(&mut tx, id, &payload.wallet_address).await?;
+    dca::delete(&mut tx, id).await?;
+    tx.commit().await?;
+    Ok(Json(SuccessResponse { success: true }))
+}
+
+// Lock a schedule of `wallet_address`. Schedules of other wallets are not found.
+async fn owned(
+    conn: &mut PgConnection,
+    id: Uuid,
+    wallet_address: &str,
+) -> Result<DcaSchedule, ApiError> {
+    match dca::find_for_update(conn, id).await? {
+        Some(dca) if dca.wallet_address.eq_ignore_ascii_case(wallet_address) => Ok(dca),
+        _ => Err(ApiError::NotFound("DCA schedule".to_string())),
+    }
+}
+
+// The current settings of a schedule, to be changed and written back.
+fn settings(dca: &DcaSchedule) -> Result<DcaUpdate, ApiError> {
+    let invalid = || anyhow::anyhow!("DCA schedule {} is invalid", dca.id);
+    Ok(DcaUpdate {
+        amount: dca.amount().ok_or_else(invalid)?,
+        schedule: dca.schedule.clone(),
+        max_slippage_bps: dca.max_slippage_bps,
+        venue: dca.venue.clone(),
+        status: dca.status,
+        ends_at: dca.ends_at(),
+        next_run_at: match &dca.next_run_at {
+            Some(next_run_at) => {
+                Some(OffsetDateTime::parse(next_run_at, &Rfc3339).map_err(|_| invalid())?)
+            }
+            None => None,
+        },
+    })
+}
+
+// The first run of a schedule from now, or from its start if it has not started.
+fn upcoming_run(
+    dca: &DcaSchedule,
+    schedule: &CronSchedule,
+    ends_at: Option<OffsetDateTime>,
+) -> Option<OffsetDateTime> {
+    let now = OffsetDateTime::now_utc();
+    match dca.starts_at() {
+        Some(starts_at) if starts_at > now => first_run(schedule, starts_at, ends_at),
+        _ => next_run(schedule, now, ends_at),
+    }
+}
+
+fn parse_amount(value: &str, decimals: i16) -> Result<Amount, ApiError> {
+    match Amount::parse(value, decimals as u8) {
+        Ok(amount) if !amount.is_zero() => Ok(amount),
+        _ => Err(ApiError::InvalidRequest("Invalid amount".to_string())),
+    }
+}
+
+fn parse_schedule(value: &str) -> Result<CronSchedule, ApiError> {
+    value.parse().map_err(ApiError::InvalidRequest)
+}
+
+fn parse_time(value: &str, name: &str) -> Result<OffsetDateTime, ApiError> {
+    OffsetDateTime::parse(value, &Rfc3339)
+        .map_err(|_| ApiError::InvalidRequest(format!("Invalid {}, use an RFC 3339 time", name)))
+}
+
+fn check_slippage(max_slippage_bps: i16) -> Result<i16, ApiError> {
+    match (0..=MAX_SLIPPAGE_BPS).contains(&max_slippage_bps) {
+        true => Ok(max_slippage_bps),
+        false => Err(ApiError::InvalidRequest(
+            "Invalid max_slippage_bps".to_string(),
+        )),
+    }
+}
+
+fn check_venue(state: &AppState, venue: String) -> Result<String, ApiError> {
+    match state.venues.accepts(&venue) {
+        true => Ok(venue),
+        false => Err(ApiError::InvalidRequest("Unknown venue".to_string())),
+    }
+}
+
+fn never_runs() -> ApiError {
+    ApiError::InvalidRequest("The schedule never runs before ends_at".to_string())
+}
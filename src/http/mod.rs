@@ -1,16 +1,24 @@
 use axum::{
-    routing::{get, patch, post},
+    routing::{delete, get, patch, post},
     Router,
 };
 mod activity_log_retrieval;
-mod auto_swap_service;
+mod dca;
 mod health_check;
 mod percentage_update;
+mod pools;
+mod prices;
+mod protection;
+mod quote;
 mod subscription;
+mod swap_jobs;
+mod tokens;
 mod transaction_logs;
+mod triggers;
 mod types;
 pub use types::is_valid_address;
 mod unsubscription;
+mod wallets;
 use crate::AppState;
 
 // Application router.
@@ -32,5 +40,43 @@ pub fn router() -> Router<AppState> {
             "/update_percentage",
             patch(percentage_update::update_percentage),
         )
-        .route("/auto_swap", post(auto_swap_service::handle_auto_swap))
+        .route("/quote", get(quote::get_quote))
+        .route("/pools", get(pools::get_pools))
+        .route("/prices/:token", get(prices::get_price_history))
+        .route("/swap_jobs/:id", get(swap_jobs::get_swap_job))
+        .route("/wallets/:address/stats", get(wallets::get_wallet_stats))
+        .route("/dca", get(dca::list_dca).post(dca::create_dca))
+        .route(
+            "/dca/:id",
+            get(dca::get_dca)
+                .patch(dca::update_dca)
+                .delete(dca::delete_dca),
+        )
+        .route("/dca/:id/pause", post(dca::pause_dca))
+        .route("/dca/:id/resume", post(dca::resume_dca))
+        .route("/triggered_swaps", get(triggers::list_triggered_swaps))
+        .route(
+            "/triggered_swaps/:id/evaluations",
+            get(triggers::get_trigger_evaluations),
+        )
+        .route(
+            "/protection_rules",
+            get(protection::list_protection_rules).post(protection::create_protection_rule),
+        )
+        .route(
+            "/protection_rules/:id",
+            delete(protection::delete_protection_rule),
+        )
+        .route(
+            "/admin/tokens",
+            get(tokens::list_tokens).post(tokens::add_token),
+        )
+        .route(
+            "/admin/tokens/:address",
+            get(tokens::get_token)
+                .patch(tokens::update_token)
+                .delete(tokens::disable_token),
+        )
+        .route("/admin/pools", get(pools::list_pools).post(pools::add_pool))
+        .route("/admin/pools/:id", delete(pools::delete_pool))
 }
@@ -1,10 +1,11 @@
 use super::types::{is_valid_address, SuccessResponse, UpdatePercentageRequest};
+use crate::auth::SignedJson;
 use crate::AppState;
 use axum::{extract::State, http::StatusCode, Json};
 
 pub async fn update_percentage(
     State(state): State<AppState>,
-    Json(payload): Json<UpdatePercentageRequest>,
+    SignedJson(payload): SignedJson<UpdatePercentageRequest>,
 ) -> Result<Json<SuccessResponse>, StatusCode> {
     let UpdatePercentageRequest {
         wallet_address,
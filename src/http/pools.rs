@@ -0,0 +1,105 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use starknet::core::types::Felt;
+use uuid::Uuid;
+
+use super::types::{is_valid_address, AddPoolRequest, GetPoolsRequest};
+use crate::{
+    api_error::ApiError,
+    auth::Admin,
+    service::pools::{self, Pool},
+    utils::ekubo::{PoolKey, PoolTier},
+    AppState,
+};
+
+// Pools a swap between two tokens is quoted in.
+pub async fn get_pools(
+    State(state): State<AppState>,
+    Query(params): Query<GetPoolsRequest>,
+) -> Result<Json<Vec<PoolKey>>, ApiError> {
+    let (token_a, token_b) = parse_pair(&params.token_a, &params.token_b)?;
+    let pools = pools::candidates(
+        &state.db.pool,
+        token_a,
+        token_b,
+        &state.config.ekubo_pool_tiers,
+    )
+    .await?;
+    Ok(Json(pools))
+}
+
+pub async fn list_pools(
+    _: Admin,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<Pool>>, ApiError> {
+    Ok(Json(pools::list(&state.db.pool).await?))
+}
+
+pub async fn add_pool(
+    _: Admin,
+    State(state): State<AppState>,
+    Json(payload): Json<AddPoolRequest>,
+) -> Result<Json<Pool>, ApiError> {
+    let (token_a, token_b) = parse_pair(&payload.token_a, &payload.token_b)?;
+    if payload.fee.0.high() != 0 {
+        return Err(ApiError::InvalidRequest(
+            "Fee must fit in a u128".to_string(),
+        ));
+    }
+    if payload.tick_spacing == 0 || i64::try_from(payload.tick_spacing).is_err() {
+        return Err(ApiError::InvalidRequest("Invalid tick spacing".to_string()));
+    }
+    let extension = match payload.extension {
+        Some(extension) if is_valid_address(&extension) => Felt::from_hex(&extension).unwrap(),
+        Some(_) => {
+            return Err(ApiError::InvalidRequest(
+                "Invalid extension address format".to_string(),
+            ))
+        }
+        None => Felt::ZERO,
+    };
+
+    let tier = PoolTier {
+        fee: payload.fee.0.low(),
+        tick_spacing: payload.tick_spacing,
+        extension,
+    };
+    match pools::create(&state.db.pool, token_a, token_b, &tier).await? {
+        Some(pool) => Ok(Json(pool)),
+        None => Err(ApiError::InvalidRequest(
+            "Pool is already registered".to_string(),
+        )),
+    }
+}
+
+pub async fn delete_pool(
+    _: Admin,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<(), ApiError> {
+    match pools::delete(&state.db.pool, id).await? {
+        true => Ok(()),
+        false => Err(ApiError::NotFound("Pool".to_string())),
+    }
+}
+
+fn parse_pair(token_a: &str, token_b: &str) -> Result<(Felt, Felt), ApiError> {
+    if !is_valid_address(token_a) || !is_valid_address(token_b) {
+        return Err(ApiError::InvalidRequest(
+            "Invalid token address format".to_string(),
+        ));
+    }
+    // Addresses were validated above.
+    let (token_a, token_b) = (
+        Felt::from_hex(token_a).unwrap(),
+        Felt::from_hex(token_b).unwrap(),
+    );
+    if token_a == token_b {
+        return Err(ApiError::InvalidRequest(
+            "A pool needs two different tokens".to_string(),
+        ));
+    }
+    Ok((token_a, token_b))
+}
@@ -0,0 +1,77 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime};
+
+use super::types::{is_valid_address, GetPricesRequest, PriceHistoryResponse};
+use crate::{
+    api_error::ApiError,
+    service::{
+        price_history::{candles, parse_interval},
+        tokens,
+    },
+    AppState,
+};
+
+// Most buckets one request can return.
+const MAX_CANDLES: i64 = 1000;
+
+const DEFAULT_INTERVAL: &str = "1h";
+
+pub async fn get_price_history(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+    Query(params): Query<GetPricesRequest>,
+) -> Result<Json<PriceHistoryResponse>, ApiError> {
+    if !is_valid_address(&address) {
+        return Err(ApiError::InvalidRequest(
+            "Invalid token address format".to_string(),
+        ));
+    }
+    if tokens::get(&state.db.pool, &address).await?.is_none() {
+        return Err(ApiError::NotFound("Token".to_string()));
+    }
+
+    // The last day by default.
+    let to = match params.to {
+        Some(to) => parse_time(&to, "to")?,
+        None => OffsetDateTime::now_utc(),
+    };
+    let from = match params.from {
+        Some(from) => parse_time(&from, "from")?,
+        None => to - Duration::days(1),
+    };
+    if from >= to {
+        return Err(ApiError::InvalidRequest(
+            "from must be before to".to_string(),
+        ));
+    }
+
+    let interval = params
+        .interval
+        .unwrap_or_else(|| DEFAULT_INTERVAL.to_string());
+    let interval_secs = parse_interval(&interval).ok_or_else(|| {
+        ApiError::InvalidRequest(
+            "Invalid interval, use minutes, hours or days such as 15m, 1h or 1d".to_string(),
+        )
+    })?;
+    if (to - from).whole_seconds() / interval_secs >= MAX_CANDLES {
+        return Err(ApiError::InvalidRequest(format!(
+            "The range holds more than {} intervals",
+            MAX_CANDLES
+        )));
+    }
+
+    let candles = candles(&state.db.pool, &address, from, to, interval_secs).await?;
+    Ok(Json(PriceHistoryResponse {
+        token_address: address.to_lowercase(),
+        interval,
+        candles,
+    }))
+}
+
+fn parse_time(value: &str, name: &str) -> Result<OffsetDateTime, ApiError> {
+    OffsetDateTime::parse(value, &Rfc3339)
+        .map_err(|_| ApiError::InvalidRequest(format!("Invalid {}, use an RFC 3339 time", name)))
+}
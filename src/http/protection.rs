@@ -0,0 +1,120 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use uuid::Uuid;
+
+use super::types::{
+    is_valid_address, CreateProtectionRuleRequest, ListProtectionRulesRequest,
+    ProtectionRuleOwnerRequest, SuccessResponse,
+};
+use crate::{
+    api_error::ApiError,
+    auth::SignedJson,
+    service::{
+        pricing::UsdPrice,
+        protection::{self, NewProtectionRule, ProtectionRule},
+        tokens,
+    },
+    utils::pagination::{Page, Pagination},
+    AppState,
+};
+
+pub async fn create_protection_rule(
+    State(state): State<AppState>,
+    SignedJson(payload): SignedJson<CreateProtectionRuleRequest>,
+) -> Result<Json<ProtectionRule>, ApiError> {
+    if !is_valid_address(&payload.token) {
+        return Err(ApiError::InvalidRequest(
+            "Invalid token address format".to_string(),
+        ));
+    }
+    if !UsdPrice::parse(&payload.price_usd).is_some_and(|price| price.price > 0) {
+        return Err(ApiError::InvalidRequest("Invalid price_usd".to_string()));
+    }
+
+    // Rules belong to a subscription and swap into its target by default.
+    let target =
+        match protection::subscription_target(&state.db.pool, &payload.wallet_address).await? {
+            Some(target) => target,
+            None => {
+                return Err(ApiError::InvalidRequest(
+                    "Wallet has no subscription".to_string(),
+                ))
+            }
+        };
+    let to_token = payload.to_token.unwrap_or(target);
+    if !is_valid_address(&to_token) {
+        return Err(ApiError::InvalidRequest(
+            "Invalid token address format".to_string(),
+        ));
+    }
+    if payload.token.eq_ignore_ascii_case(&to_token) {
+        return Err(ApiError::InvalidRequest(
+            "token and to_token must differ".to_string(),
+        ));
+    }
+
+    match tokens::get(&state.db.pool, &payload.token).await? {
+        Some(token) if token.is_active => {}
+        _ => return Err(ApiError::InvalidRequest("Unknown token".to_string())),
+    }
+    match tokens::get(&state.db.pool, &to_token).await? {
+        Some(token) if token.is_active && token.is_stable => {}
+        _ => {
+            return Err(ApiError::InvalidRequest(
+                "to_token must be a stable token".to_string(),
+            ))
+        }
+    }
+
+    let rule = NewProtectionRule {
+        wallet_address: payload.wallet_address,
+        token: payload.token,
+        to_token,
+        kind: payload.kind,
+        price_usd: payload.price_usd,
+    };
+    match protection::create(&state.db.pool, &rule).await? {
+        Some(rule) => Ok(Json(rule)),
+        None => Err(ApiError::InvalidRequest(
+            "The token already has an active rule of this kind".to_string(),
+        )),
+    }
+}
+
+pub async fn list_protection_rules(
+    State(state): State<AppState>,
+    Query(params): Query<ListProtectionRulesRequest>,
+) -> Result<Json<Page<ProtectionRule>>, ApiError> {
+    if !is_valid_address(&params.wallet_address) {
+        return Err(ApiError::InvalidRequest(
+            "Invalid wallet address format".to_string(),
+        ));
+    }
+    let pagination = Pagination::new(protection::KEYSET, params.cursor.as_deref(), params.limit)?;
+    Ok(Json(
+        protection::list(&state.db.pool, &params.wallet_address, &pagination).await?,
+    ))
+}
+
+// Rules of other wallets are not found.
+pub async fn delete_protection_rule(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    SignedJson(payload): SignedJson<ProtectionRuleOwnerRequest>,
+) -> Result<Json<SuccessResponse>, ApiError> {
+    let mut tx = state.db.pool.begin().await?;
+    let owned = protection::find_for_update(&mut tx, id)
+        .await?
+        .is_some_and(|rule| {
+            rule.wallet_address
+                .eq_ignore_ascii_case(&payload.wallet_address)
+        });
+    if !owned {
+        return Err(ApiError::NotFound("Protection rule".to_string()));
+    }
+    protection::delete(&mut tx, id).await?;
+    tx.commit().await?;
+    Ok(Json(SuccessResponse { success: true }))
+}
@@ -0,0 +1,96 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use starknet::core::types::Felt;
+
+use super::types::{is_valid_address, GetQuoteRequest, QuoteResponse};
+use crate::{
+    api_error::ApiError,
+    service::{
+        quote::{quote, QuoteParams},
+        tokens::{self, Token},
+    },
+    utils::amount::Amount,
+    AppState,
+};
+
+pub async fn get_quote(
+    State(state): State<AppState>,
+    Query(params): Query<GetQuoteRequest>,
+) -> Result<Json<QuoteResponse>, ApiError> {
+    let GetQuoteRequest {
+        from_token,
+        to_token,
+        amount,
+    } = params;
+
+    if !is_valid_address(&from_token) || !is_valid_address(&to_token) {
+        return Err(ApiError::InvalidRequest(
+            "Invalid token address format".to_string(),
+        ));
+    }
+    if from_token.eq_ignore_ascii_case(&to_token) {
+        return Err(ApiError::InvalidRequest(
+            "Cannot swap a token for itself".to_string(),
+        ));
+    }
+    let from = registered_token(&state, &from_token).await?;
+    let to = registered_token(&state, &to_token).await?;
+
+    let amount = Amount::parse(&amount, from.token_decimals as u8)
+        .map_err(|err| ApiError::InvalidRequest(err.to_string()))?;
+    if amount.is_zero() {
+        return Err(ApiError::InvalidRequest(
+            "Amount must be greater than zero".to_string(),
+        ));
+    }
+
+    // Addresses were validated above.
+    let from_token = Felt::from_hex(&from_token).unwrap();
+    let to_token = Felt::from_hex(&to_token).unwrap();
+    let params = QuoteParams {
+        from_token,
+        to_token,
+        amount,
+        contract_address: state.config.contract_address,
+        funded_by: None,
+    };
+
+    let quote = quote(&state.account, state.venues.all(), &params).await?;
+
+    let best_amount_out = quote
+        .quotes
+        .iter()
+        .find(|venue| Some(venue.venue.as_str()) == quote.best_venue.as_deref())
+        .map(|venue| Amount::from_raw(venue.amount_out.into(), to.token_decimals as u8));
+    let amount_in_usd = usd_value(&state, &from, Some(amount)).await;
+    let best_amount_out_usd = usd_value(&state, &to, best_amount_out).await;
+    Ok(Json(QuoteResponse {
+        quote,
+        amount_in_usd,
+        best_amount_out_usd,
+    }))
+}
+
+// USD value of `amount` of `token`, or `None` if it cannot be priced. A quote
+// is still returned when the oracles are unavailable.
+async fn usd_value(state: &AppState, token: &Token, amount: Option<Amount>) -> Option<String> {
+    match state.pricing.usd_value(token, &amount?).await {
+        Ok(value) => value.map(|value| value.to_string()),
+        Err(err) => {
+            tracing::warn!("Unable to price {}: {:#}", token.contract_address, err);
+            None
+        }
+    }
+}
+
+async fn registered_token(state: &AppState, address: &str) -> Result<Token, ApiError> {
+    match tokens::get(&state.db.pool, address).await? {
+        Some(token) if token.is_active => Ok(token),
+        _ => Err(ApiError::InvalidRequest(format!(
+            "Unregistered token: {}",
+            address
+        ))),
+    }
+}
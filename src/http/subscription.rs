@@ -1,37 +1,134 @@
 use axum::{extract::Query, extract::State, http::StatusCode, Json};
 
 use super::types::{
-    is_valid_address, CreateSubscriptionRequest, GetSubscriptionRequest, GetSubscriptionResponse,
-    SubscriptionData, SuccessResponse,
+    is_valid_address, CreateSubscriptionRequest, GetSubscriptionRequest, SubscriptionData,
+    SubscriptionTarget, SubscriptionTargetRequest, SuccessResponse,
 };
 use crate::api_error::ApiError;
+use crate::auth::SignedJson;
+use crate::service::pricing::UsdPrice;
+use crate::service::slippage::{DEFAULT_MAX_SLIPPAGE_BPS, MAX_SLIPPAGE_BPS};
+use crate::service::tokens::unregistered;
+use crate::service::venues::BEST_PRICE;
+use crate::utils::filters::{Column, Filters};
+use crate::utils::pagination::{Keyset, Page, Pagination};
 use crate::AppState;
 use time::format_description::well_known::Rfc3339;
 use time::OffsetDateTime;
 
-use serde_json::{json, Value};
+// Newest first.
+const KEYSET: Keyset = Keyset {
+    key: Column::cast("swap_subscription_from_token.created_at", "TIMESTAMPTZ"),
+    id: Column::new("swap_subscription_from_token.from_token"),
+    descending: true,
+};
 
-const LIMIT: i32 = 10;
+// Largest weight of a subscription target.
+const MAX_TARGET_WEIGHT: i16 = 10_000;
 
 pub async fn create_subscription(
     State(state): State<AppState>,
-    Json(payload): Json<CreateSubscriptionRequest>,
+    SignedJson(payload): SignedJson<CreateSubscriptionRequest>,
 ) -> Result<Json<SuccessResponse>, StatusCode> {
     let CreateSubscriptionRequest {
         wallet_address,
         to_token,
         from_token,
         percentage,
+        targets,
+        max_slippage_bps,
+        venue,
+        triggers,
     } = payload;
 
     if from_token.len() != percentage.len() {
         return Err(StatusCode::BAD_REQUEST);
     }
 
+    // Without `targets`, every token swaps into `to_token`.
+    let targets = match (targets, &to_token) {
+        (Some(targets), _) if targets.len() != from_token.len() => {
+            return Err(StatusCode::BAD_REQUEST)
+        }
+        (Some(targets), _) => targets,
+        (None, Some(to_token)) => from_token
+            .iter()
+            .map(|_| {
+                vec![SubscriptionTargetRequest {
+                    to_token: to_token.clone(),
+                    weight: 1,
+                }]
+            })
+            .collect(),
+        (None, None) => return Err(StatusCode::BAD_REQUEST),
+    };
+    // Every token has distinct targets other than itself, with positive weights.
+    for (token, targets) in from_token.iter().zip(&targets) {
+        if targets.is_empty() {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        for (i, target) in targets.iter().enumerate() {
+            if !(1..=MAX_TARGET_WEIGHT).contains(&target.weight)
+                || !is_valid_address(&target.to_token)
+                || target.to_token.eq_ignore_ascii_case(token)
+                || targets[..i]
+                    .iter()
+                    .any(|other| other.to_token.eq_ignore_ascii_case(&target.to_token))
+            {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+        }
+    }
+    let to_token = match to_token.or_else(|| Some(targets.first()?.first()?.to_token.clone())) {
+        Some(to_token) => to_token,
+        None => return Err(StatusCode::BAD_REQUEST),
+    };
+
+    let triggers = match triggers {
+        Some(triggers) if triggers.len() != from_token.len() => {
+            return Err(StatusCode::BAD_REQUEST)
+        }
+        Some(triggers) => triggers,
+        None => from_token.iter().map(|_| None).collect(),
+    };
+    // Trigger prices are positive dollar amounts and expiries RFC 3339 times.
+    for trigger in triggers.iter().flatten() {
+        if !UsdPrice::parse(&trigger.price_usd).is_some_and(|price| price.price > 0) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        if let Some(expires_at) = &trigger.expires_at {
+            OffsetDateTime::parse(expires_at, &Rfc3339).map_err(|_| StatusCode::BAD_REQUEST)?;
+        }
+    }
+
+    let max_slippage_bps = max_slippage_bps.unwrap_or(DEFAULT_MAX_SLIPPAGE_BPS);
+    if !(0..=MAX_SLIPPAGE_BPS).contains(&max_slippage_bps) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let venue = venue.unwrap_or_else(|| BEST_PRICE.to_string());
+    if !state.venues.accepts(&venue) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
     if !is_valid_address(&to_token) || !is_valid_address(&wallet_address) {
         return Err(StatusCode::BAD_REQUEST);
     }
 
+    // Only registered tokens can be swapped.
+    let tokens: Vec<&str> = std::iter::once(&to_token)
+        .chain(&from_token)
+        .chain(targets.iter().flatten().map(|target| &target.to_token))
+        .map(String::as_str)
+        .collect();
+    let unknown = unregistered(&state.db.pool, &tokens)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !unknown.is_empty() {
+        tracing::debug!("Subscription rejected, unregistered tokens: {:?}", unknown);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
     let mut tx = state
         .db
         .pool
@@ -39,34 +136,76 @@ pub async fn create_subscription(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    sqlx::query!(
+    sqlx::query(
         r#"
-        INSERT INTO swap_subscription (wallet_address, to_token, is_active)
-        VALUES ($1, $2, true)
+        INSERT INTO swap_subscription (wallet_address, to_token, is_active, max_slippage_bps, venue)
+        VALUES ($1, $2, true, $3, $4)
         ON CONFLICT (wallet_address)
-        DO UPDATE SET to_token = $2, is_active = true, updated_at = NOW()
+        DO UPDATE SET to_token = $2, is_active = true, max_slippage_bps = $3, venue = $4,
+            updated_at = NOW()
         "#,
-        wallet_address,
-        to_token,
     )
+    .bind(&wallet_address)
+    .bind(&to_token)
+    .bind(max_slippage_bps)
+    .bind(&venue)
     .execute(&mut *tx)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    for (token, percentage) in from_token.iter().zip(percentage.iter()) {
-        sqlx::query!(
+    for (((token, percentage), trigger), targets) in from_token
+        .iter()
+        .zip(&percentage)
+        .zip(&triggers)
+        .zip(&targets)
+    {
+        // Subscribing to a token again replaces its settings and targets.
+        let trigger = trigger.as_ref();
+        sqlx::query(
             r#"
             INSERT INTO swap_subscription_from_token
-            (wallet_address, from_token, percentage)
-            VALUES ($1, $2, $3)
+            (wallet_address, from_token, percentage, trigger_condition, trigger_price_usd,
+             trigger_expires_at)
+            VALUES ($1, $2, $3, $4, $5::NUMERIC, $6::TIMESTAMPTZ)
+            ON CONFLICT (wallet_address, from_token)
+            DO UPDATE SET percentage = $3, trigger_condition = $4, trigger_price_usd = $5::NUMERIC,
+                trigger_expires_at = $6::TIMESTAMPTZ, updated_at = NOW()
             "#,
-            wallet_address,
-            token,
-            percentage,
         )
+        .bind(&wallet_address)
+        .bind(token)
+        .bind(percentage)
+        .bind(trigger.map(|trigger| trigger.condition))
+        .bind(trigger.map(|trigger| trigger.price_usd.as_str()))
+        .bind(trigger.and_then(|trigger| trigger.expires_at.as_deref()))
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        sqlx::query(
+            "DELETE FROM swap_subscription_target WHERE wallet_address = $1 AND from_token = $2",
+        )
+        .bind(&wallet_address)
+        .bind(token)
         .execute(&mut *tx)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        for target in targets {
+            sqlx::query(
+                r#"
+                INSERT INTO swap_subscription_target (wallet_address, from_token, to_token, weight)
+                VALUES ($1, $2, $3, $4)
+                "#,
+            )
+            .bind(&wallet_address)
+            .bind(token)
+            .bind(&target.to_token)
+            .bind(target.weight)
+            .execute(&mut *tx)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        }
     }
 
     tx.commit()
@@ -79,61 +218,67 @@ pub async fn create_subscription(
 pub async fn get_subscription(
     State(state): State<AppState>,
     Query(params): Query<GetSubscriptionRequest>,
-) -> Result<Json<Value>, ApiError> {
-    let cursor: String = match params.cursor {
-        Some(cursor1) => match OffsetDateTime::parse(&cursor1, &Rfc3339) {
-            Ok(cur) => cur.format(&Rfc3339).unwrap(),
-            Err(_) => return Err(ApiError::InvalidRequest("Invalid cursor".to_string())),
-        },
-
-        None => {
-            let now = OffsetDateTime::now_utc();
-            now.format(&Rfc3339).unwrap()
-        }
-    };
-
-    let rows: Vec<SubscriptionData> = sqlx::query_as::<_, SubscriptionData>(
-    r#"
+) -> Result<Json<Page<SubscriptionData>>, ApiError> {
+    let pagination = Pagination::new(KEYSET, params.cursor.as_deref(), params.limit)?;
+    let mut filters = Filters::new(
+        r#"
         SELECT
             swap_subscription_from_token.from_token AS from_token,
             swap_subscription.to_token AS to_token,
             swap_subscription_from_token.percentage AS percentage,
+            swap_subscription.max_slippage_bps AS max_slippage_bps,
+            swap_subscription.venue AS venue,
             swap_subscription.is_active AS is_active,
-            TO_CHAR(swap_subscription_from_token.created_at, 'YYYY-MM-DD"T"HH24:MI:SSZ') AS created_at
+            swap_subscription_from_token.trigger_condition AS trigger_condition,
+            TRIM_SCALE(swap_subscription_from_token.trigger_price_usd)::TEXT AS trigger_price_usd,
+            TO_CHAR(swap_subscription_from_token.trigger_expires_at, 'YYYY-MM-DD"T"HH24:MI:SSZ') AS trigger_expires_at,
+            TO_CHAR(swap_subscription_from_token.created_at, 'YYYY-MM-DD"T"HH24:MI:SSZ') AS created_at,
+            TO_CHAR(swap_subscription_from_token.created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"') AS sort_key
         FROM swap_subscription_from_token
         INNER JOIN swap_subscription ON swap_subscription_from_token.wallet_address = swap_subscription.wallet_address
-        WHERE swap_subscription_from_token.created_at < $1::TIMESTAMPTZ
-        AND swap_subscription_from_token.wallet_address = $2;
-        "#
-    )
-        .bind(cursor)
-        .bind(&params.wallet_address)
+        "#,
+    );
+    filters.eq(
+        "swap_subscription_from_token.wallet_address",
+        Some(&params.wallet_address),
+    );
+    let mut rows: Vec<SubscriptionData> = pagination
+        .apply(filters)
+        .build_query_as::<SubscriptionData>()
         .fetch_all(&state.db.pool)
         .await
         .map_err(ApiError::DatabaseError)?;
 
-    let mut response_data: GetSubscriptionResponse = GetSubscriptionResponse {
-        data: rows
-            .into_iter()
-            .map(|row| SubscriptionData {
-                from_token: row.from_token,
-                to_token: row.to_token,
-                percentage: row.percentage,
-                is_active: row.is_active,
-                created_at: row.created_at,
-            })
-            .collect(),
-        next_cursor: None,
-    };
+    let targets: Vec<(String, String, i16)> = sqlx::query_as(
+        r#"
+        SELECT from_token, to_token, weight
+        FROM swap_subscription_target
+        WHERE wallet_address = $1
+        ORDER BY created_at, to_token
+        "#,
+    )
+    .bind(&params.wallet_address)
+    .fetch_all(&state.db.pool)
+    .await
+    .map_err(ApiError::DatabaseError)?;
 
-    match response_data.data.len() == LIMIT as usize {
-        true => {
-            response_data.next_cursor = Some(response_data.data.last().unwrap().created_at.clone());
-        }
-        false => {
-            response_data.next_cursor = None;
+    for row in &mut rows {
+        row.targets = targets
+            .iter()
+            .filter(|(from_token, _, _)| *from_token == row.from_token)
+            .map(|(_, to_token, weight)| SubscriptionTarget {
+                to_token: to_token.clone(),
+                weight: *weight,
+            })
+            .collect();
+        // Tokens without targets swap into the subscription's `to_token`.
+        if row.targets.is_empty() {
+            row.targets.push(SubscriptionTarget {
+                to_token: row.to_token.clone(),
+                weight: 1,
+            });
         }
-    };
+    }
 
-    Ok(Json(json!(response_data)))
+    Ok(Json(pagination.page(rows)))
 }
@@ -0,0 +1,22 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    api_error::ApiError,
+    service::swap_jobs::{find, SwapJob},
+    AppState,
+};
+
+pub async fn get_swap_job(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<SwapJob>, ApiError> {
+    let mut conn = state.db.pool.acquire().await?;
+    match find(&mut conn, id).await? {
+        Some(job) => Ok(Json(job)),
+        None => Err(ApiError::NotFound("Swap job".to_string())),
+    }
+}
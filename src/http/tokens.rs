@@ -0,0 +1,177 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use starknet::core::types::Felt;
+
+use super::types::{is_valid_address, AddTokenRequest, ListTokensRequest, UpdateTokenRequest};
+use crate::{
+    api_error::ApiError,
+    auth::Admin,
+    service::{
+        pricing::PriceFeed,
+        tokens::{self, fetch_metadata, NewToken, Token, TokenMetadata, TokenUpdate},
+    },
+    AppState,
+};
+
+// Longest symbol the `token` table accepts.
+const MAX_SYMBOL_LENGTH: usize = 10;
+
+// Most decimals the `token` table accepts.
+const MAX_DECIMALS: i16 = 18;
+
+pub async fn list_tokens(
+    _: Admin,
+    State(state): State<AppState>,
+    Query(params): Query<ListTokensRequest>,
+) -> Result<Json<Vec<Token>>, ApiError> {
+    let tokens = tokens::list(&state.db.pool, params.include_disabled).await?;
+    Ok(Json(tokens))
+}
+
+pub async fn get_token(
+    _: Admin,
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+) -> Result<Json<Token>, ApiError> {
+    match tokens::get(&state.db.pool, &address).await? {
+        Some(token) => Ok(Json(token)),
+        None => Err(ApiError::NotFound("Token".to_string())),
+    }
+}
+
+pub async fn add_token(
+    _: Admin,
+    State(state): State<AppState>,
+    Json(payload): Json<AddTokenRequest>,
+) -> Result<Json<Token>, ApiError> {
+    let AddTokenRequest {
+        contract_address,
+        token_name,
+        token_symbol,
+        token_decimals,
+        is_stable,
+        price_feed,
+    } = payload;
+
+    if !is_valid_address(&contract_address) {
+        return Err(ApiError::InvalidRequest(
+            "Invalid token address format".to_string(),
+        ));
+    }
+    if tokens::get(&state.db.pool, &contract_address)
+        .await?
+        .is_some()
+    {
+        return Err(ApiError::InvalidRequest(
+            "Token is already registered".to_string(),
+        ));
+    }
+
+    // Fill in whatever metadata the request left out from the contract.
+    let (token_name, token_symbol, token_decimals) =
+        match (token_name, token_symbol, token_decimals) {
+            (Some(name), Some(symbol), Some(decimals)) => (name, symbol, decimals),
+            (name, symbol, decimals) => {
+                let metadata = read_metadata(&state, &contract_address).await?;
+                (
+                    name.unwrap_or(metadata.name),
+                    symbol.unwrap_or(metadata.symbol),
+                    decimals.unwrap_or(metadata.decimals.into()),
+                )
+            }
+        };
+    validate_metadata(Some(&token_symbol), Some(token_decimals))?;
+    validate_price_feed(price_feed.as_deref())?;
+
+    let token = NewToken {
+        contract_address,
+        token_name,
+        token_symbol,
+        token_decimals,
+        is_stable,
+        price_feed,
+    };
+    // Another request may have registered it since the check above.
+    match tokens::create(&state.db.pool, &token).await? {
+        Some(token) => Ok(Json(token)),
+        None => Err(ApiError::InvalidRequest(
+            "Token is already registered".to_string(),
+        )),
+    }
+}
+
+pub async fn update_token(
+    _: Admin,
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+    Json(payload): Json<UpdateTokenRequest>,
+) -> Result<Json<Token>, ApiError> {
+    validate_metadata(payload.token_symbol.as_deref(), payload.token_decimals)?;
+    validate_price_feed(payload.price_feed.as_deref())?;
+
+    let update = TokenUpdate {
+        token_name: payload.token_name,
+        token_symbol: payload.token_symbol,
+        token_decimals: payload.token_decimals,
+        is_stable: payload.is_stable,
+        is_active: payload.is_active,
+        price_feed: payload.price_feed,
+    };
+    match tokens::update(&state.db.pool, &address, &update).await? {
+        Some(token) => Ok(Json(token)),
+        None => Err(ApiError::NotFound("Token".to_string())),
+    }
+}
+
+pub async fn disable_token(
+    _: Admin,
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+) -> Result<Json<Token>, ApiError> {
+    match tokens::disable(&state.db.pool, &address).await? {
+        Some(token) => Ok(Json(token)),
+        None => Err(ApiError::NotFound("Token".to_string())),
+    }
+}
+
+async fn read_metadata(
+    state: &AppState,
+    contract_address: &str,
+) -> Result<TokenMetadata, ApiError> {
+    let address = Felt::from_hex(contract_address)
+        .map_err(|_| ApiError::InvalidRequest("Invalid token address format".to_string()))?;
+    fetch_metadata(&*state.provider, address)
+        .await
+        .map_err(|err| {
+            ApiError::InvalidRequest(format!("Unable to read token metadata: {:#}", err))
+        })
+}
+
+fn validate_metadata(symbol: Option<&str>, decimals: Option<i16>) -> Result<(), ApiError> {
+    if let Some(symbol) = symbol {
+        if symbol.is_empty() || symbol.chars().count() > MAX_SYMBOL_LENGTH {
+            return Err(ApiError::InvalidRequest(format!(
+                "Token symbol must be 1 to {} characters long",
+                MAX_SYMBOL_LENGTH
+            )));
+        }
+    }
+    if let Some(decimals) = decimals {
+        if !(0..=MAX_DECIMALS).contains(&decimals) {
+            return Err(ApiError::InvalidRequest(format!(
+                "Token decimals must be between 0 and {}",
+                MAX_DECIMALS
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn validate_price_feed(price_feed: Option<&str>) -> Result<(), ApiError> {
+    match price_feed.map(str::parse::<PriceFeed>) {
+        Some(Err(err)) => Err(ApiError::InvalidRequest(err)),
+        _ => Ok(()),
+    }
+}
@@ -3,6 +3,7 @@ use serde::Deserialize;
 
 use super::types::SuccessResponse;
 use crate::service::transaction_logs::log_transaction;
+use crate::utils::uint256::Uint256;
 use crate::{api_error::ApiError, AppState};
 
 #[derive(Debug, Deserialize)]
@@ -11,8 +12,8 @@ pub struct TransactionLogPayload {
     pub from_token: String,
     pub to_token: String,
     pub percentage: u16,
-    pub amount_from: u64,
-    pub amount_to: u64,
+    pub amount_from: Uint256,
+    pub amount_to: Uint256,
 }
 
 pub async fn log_transaction_to_db(
@@ -24,8 +25,8 @@ pub async fn log_transaction_to_db(
         &payload.from_token,
         &payload.to_token,
         payload.percentage,
-        payload.amount_from,
-        payload.amount_to,
+        payload.amount_from.into(),
+        payload.amount_to.into(),
         &state.db.pool,
     )
     .await;
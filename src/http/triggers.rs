@@ -0,0 +1,47 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use uuid::Uuid;
+
+use super::types::{is_valid_address, ListTriggeredSwapsRequest, PageRequest};
+use crate::{
+    api_error::ApiError,
+    service::triggers::{self, TriggerEvaluation, TriggeredSwap},
+    utils::pagination::{Page, Pagination},
+    AppState,
+};
+
+pub async fn list_triggered_swaps(
+    State(state): State<AppState>,
+    Query(params): Query<ListTriggeredSwapsRequest>,
+) -> Result<Json<Page<TriggeredSwap>>, ApiError> {
+    if !is_valid_address(&params.wallet_address) {
+        return Err(ApiError::InvalidRequest(
+            "Invalid wallet address format".to_string(),
+        ));
+    }
+    let pagination = Pagination::new(triggers::KEYSET, params.cursor.as_deref(), params.limit)?;
+    Ok(Json(
+        triggers::list(&state.db.pool, &params.wallet_address, &pagination).await?,
+    ))
+}
+
+// Every check of a held swap against the price of its token.
+pub async fn get_trigger_evaluations(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<PageRequest>,
+) -> Result<Json<Page<TriggerEvaluation>>, ApiError> {
+    let pagination = Pagination::new(
+        triggers::EVALUATION_KEYSET,
+        params.cursor.as_deref(),
+        params.limit,
+    )?;
+    if triggers::find(&state.db.pool, id).await?.is_none() {
+        return Err(ApiError::NotFound("Triggered swap".to_string()));
+    }
+    Ok(Json(
+        triggers::evaluations(&state.db.pool, id, &pagination).await?,
+    ))
+}
@@ -1,57 +1,156 @@
-use serde::de::Visitor;
+use crate::auth::SignedPayload;
+use crate::service::{
+    price_history::PriceCandle, protection::ProtectionKind, quote::Quote,
+    transaction_logs::TransactionStatus, triggers::TriggerCondition, wallet_stats::PairStats,
+};
+use crate::utils::{pagination::Paged, uint256::Uint256};
+use serde::de::{DeserializeOwned, IntoDeserializer, Visitor};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use sqlx::FromRow;
 use std::fmt::Formatter;
 use time::format_description::well_known::Rfc3339;
 use time::OffsetDateTime;
+use uuid::Uuid;
 
 pub const ADDRESS_PREFIX: &str = "0x";
 pub const ADDRESS_LENGTH: usize = 66;
 
+// Addresses and statuses take comma separated lists, matching any of them.
+// Times are RFC 3339 and ranges include both ends.
 #[derive(Debug, Deserialize)]
 pub struct ActivityLogGetRequest {
-    pub wallet_address: Option<String>,
-    pub from_token: Option<String>,
-    pub to_token: Option<String>,
-    pub amount_to: Option<i64>,
+    #[serde(default, deserialize_with = "comma_separated")]
+    pub wallet_address: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "comma_separated")]
+    pub from_token: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "comma_separated")]
+    pub to_token: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "comma_separated")]
+    pub status: Option<Vec<TransactionStatus>>,
+    pub amount_to: Option<Uint256>,
+    pub min_amount_from: Option<Uint256>,
+    pub max_amount_from: Option<Uint256>,
+    pub min_amount_to: Option<Uint256>,
+    pub max_amount_to: Option<Uint256>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    #[serde(default)]
+    pub sort_by: ActivityLogSort,
+    #[serde(default)]
+    pub order: SortOrder,
     pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+// Column the transaction log is listed by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityLogSort {
+    #[default]
+    CreatedAt,
+    AmountFrom,
+    AmountTo,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+// Deserialize `a,b,c` into its values, skipping empty ones.
+fn comma_separated<'de, D, T>(deserializer: D) -> Result<Option<Vec<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    let list = match Option::<String>::deserialize(deserializer)? {
+        Some(list) => list,
+        None => return Ok(None),
+    };
+    list.split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| T::deserialize(value.into_deserializer()))
+        .collect::<Result<Vec<_>, _>>()
+        .map(Some)
 }
 
 #[derive(FromRow, Debug, Serialize)]
 pub struct ActivityLogData {
+    #[serde(skip)]
+    pub transaction_id: Uuid,
     pub wallet_address: String,
     pub from_token: String,
     pub to_token: String,
     pub percentage: i16,
-    pub amount_from: i64,
-    pub amount_to: i64,
+    pub amount_from: Uint256,
+    pub amount_to: Uint256,
+    // USD values when the swap executed, in dollars.
+    pub amount_from_usd: Option<String>,
+    pub amount_to_usd: Option<String>,
+    pub tx_hash: Option<String>,
+    pub status: Option<String>,
     pub created_at: String,
+    // Creation time with microseconds, the position of the row in the listing.
+    #[serde(skip)]
+    pub sort_key: String,
 }
 
-#[derive(Debug, Serialize)]
-pub struct ActivityLogGetResponse {
-    pub transactions: Vec<ActivityLogData>,
-    pub next_cursor: Option<String>,
+impl Paged for ActivityLogData {
+    fn position(&self) -> (String, String) {
+        (self.sort_key.clone(), self.transaction_id.to_string())
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreateSubscriptionRequest {
     pub wallet_address: String,
-    pub to_token: String,
+    // Target of the `from_token`s without `targets`. Defaults to the first
+    // target when `targets` are given.
+    pub to_token: Option<String>,
     pub from_token: Vec<String>,
     pub percentage: Vec<i16>,
+    // Targets of the `from_token`s, in the same order. The subscribed
+    // percentage of a token is split between its targets by weight, so
+    // `percentage` 80 with weights 5 and 3 swaps 50% and 30%.
+    pub targets: Option<Vec<Vec<SubscriptionTargetRequest>>>,
+    // Basis points, defaults to `DEFAULT_MAX_SLIPPAGE_BPS`.
+    pub max_slippage_bps: Option<i16>,
+    // Name of a venue, defaults to `BEST_PRICE`.
+    pub venue: Option<String>,
+    // Price triggers of the `from_token`s, in the same order. Tokens without
+    // one are swapped as soon as they are received.
+    pub triggers: Option<Vec<Option<PriceTriggerRequest>>>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct CreateSubscriptionResponse {
-    pub wallet_address: String,
+#[derive(Debug, Deserialize)]
+pub struct SubscriptionTargetRequest {
+    pub to_token: String,
+    pub weight: i16,
 }
 
+// Hold the swaps of a token until its USD price is `condition` `price_usd`,
+// e.g. above "0.55", or until `expires_at`, an RFC 3339 time.
 #[derive(Debug, Deserialize)]
-pub struct AutoSwapRequest {
-    pub token_from: String,
-    pub swap_recipient: String,
-    pub value_received: i64,
+pub struct PriceTriggerRequest {
+    pub condition: TriggerCondition,
+    #[serde(deserialize_with = "decimal_string")]
+    pub price_usd: String,
+    pub expires_at: Option<String>,
+}
+
+impl SignedPayload for CreateSubscriptionRequest {
+    fn signer(&self) -> &str {
+        &self.wallet_address
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateSubscriptionResponse {
+    pub wallet_address: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -65,19 +164,43 @@ pub struct SubscriptionData {
     pub is_active: bool,
     pub from_token: String,
     pub percentage: i16,
+    #[sqlx(skip)]
+    pub targets: Vec<SubscriptionTarget>,
+    pub max_slippage_bps: i16,
+    pub venue: String,
+    pub trigger_condition: Option<TriggerCondition>,
+    pub trigger_price_usd: Option<String>,
+    pub trigger_expires_at: Option<String>,
     pub created_at: String,
+    // Creation time with microseconds, the position of the row in the listing.
+    #[serde(skip)]
+    pub sort_key: String,
+}
+
+impl Paged for SubscriptionData {
+    fn position(&self) -> (String, String) {
+        (self.sort_key.clone(), self.from_token.clone())
+    }
+}
+
+#[derive(FromRow, Debug, Serialize)]
+pub struct SubscriptionTarget {
+    pub to_token: String,
+    pub weight: i16,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct GetSubscriptionRequest {
     pub wallet_address: String,
     pub cursor: Option<String>,
+    pub limit: Option<i64>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct GetSubscriptionResponse {
-    pub data: Vec<SubscriptionData>,
-    pub next_cursor: Option<String>,
+// Page of a listing without filters.
+#[derive(Debug, Deserialize)]
+pub struct PageRequest {
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
 }
 
 #[derive(sqlx::Type)]
@@ -127,11 +250,235 @@ pub struct UpdatePercentageRequest {
     pub percentage: i16,
 }
 
+impl SignedPayload for UpdatePercentageRequest {
+    fn signer(&self) -> &str {
+        &self.wallet_address
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct UpdatePercentageResponse {
     pub message: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ListTokensRequest {
+    #[serde(default)]
+    pub include_disabled: bool,
+}
+
+// Metadata left out is read from the token contract.
+#[derive(Debug, Deserialize)]
+pub struct AddTokenRequest {
+    pub contract_address: String,
+    pub token_name: Option<String>,
+    pub token_symbol: Option<String>,
+    pub token_decimals: Option<i16>,
+    #[serde(default)]
+    pub is_stable: bool,
+    // `eth`, `strk` or `pragma:<pair id>`.
+    pub price_feed: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTokenRequest {
+    pub token_name: Option<String>,
+    pub token_symbol: Option<String>,
+    pub token_decimals: Option<i16>,
+    pub is_stable: Option<bool>,
+    pub is_active: Option<bool>,
+    pub price_feed: Option<String>,
+}
+
+// `amount` is in whole `from_token` tokens, e.g. "1.5".
+#[derive(Debug, Deserialize)]
+pub struct GetQuoteRequest {
+    pub from_token: String,
+    pub to_token: String,
+    pub amount: String,
+}
+
+// `from` and `to` are RFC 3339 times, `interval` a bucket length such as "15m",
+// "1h" or "1d".
+#[derive(Debug, Deserialize)]
+pub struct GetPricesRequest {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub interval: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PriceHistoryResponse {
+    pub token_address: String,
+    pub interval: String,
+    pub candles: Vec<PriceCandle>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WalletStatsResponse {
+    pub wallet_address: String,
+    pub pairs: Vec<PairStats>,
+}
+
+// A quote with the USD values of its input and best output, in dollars such as
+// "2500.5". Values are left out when a token cannot be priced.
+#[derive(Debug, Serialize)]
+pub struct QuoteResponse {
+    #[serde(flatten)]
+    pub quote: Quote,
+    pub amount_in_usd: Option<String>,
+    pub best_amount_out_usd: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetPoolsRequest {
+    pub token_a: String,
+    pub token_b: String,
+}
+
+// `fee` is Ekubo's 0.128 fixed point fee, e.g. "170141183460469235273462165868118016"
+// for 0.05%. Tokens may be given in either order.
+#[derive(Debug, Deserialize)]
+pub struct AddPoolRequest {
+    pub token_a: String,
+    pub token_b: String,
+    pub fee: Uint256,
+    pub tick_spacing: u128,
+    pub extension: Option<String>,
+}
+
+// `amount` is in whole `from_token` tokens, e.g. "1.5", swapped every time the
+// cron `schedule` is due. `starts_at` and `ends_at` are RFC 3339 times.
+#[derive(Debug, Deserialize)]
+pub struct CreateDcaRequest {
+    pub wallet_address: String,
+    pub from_token: String,
+    pub to_token: String,
+    #[serde(deserialize_with = "decimal_string")]
+    pub amount: String,
+    pub schedule: String,
+    // Basis points, defaults to `DEFAULT_MAX_SLIPPAGE_BPS`.
+    pub max_slippage_bps: Option<i16>,
+    // Name of a venue, defaults to `BEST_PRICE`.
+    pub venue: Option<String>,
+    // Defaults to now.
+    pub starts_at: Option<String>,
+    pub ends_at: Option<String>,
+}
+
+impl SignedPayload for CreateDcaRequest {
+    fn signer(&self) -> &str {
+        &self.wallet_address
+    }
+}
+
+// Settings left out are kept.
+#[derive(Debug, Deserialize)]
+pub struct UpdateDcaRequest {
+    pub wallet_address: String,
+    #[serde(default, deserialize_with = "optional_decimal_string")]
+    pub amount: Option<String>,
+    pub schedule: Option<String>,
+    pub max_slippage_bps: Option<i16>,
+    pub venue: Option<String>,
+    pub ends_at: Option<String>,
+}
+
+impl SignedPayload for UpdateDcaRequest {
+    fn signer(&self) -> &str {
+        &self.wallet_address
+    }
+}
+
+// Pauses, resumes or deletes a DCA schedule of `wallet_address`.
+#[derive(Debug, Deserialize)]
+pub struct DcaOwnerRequest {
+    pub wallet_address: String,
+}
+
+impl SignedPayload for DcaOwnerRequest {
+    fn signer(&self) -> &str {
+        &self.wallet_address
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListDcaRequest {
+    pub wallet_address: String,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListTriggeredSwapsRequest {
+    pub wallet_address: String,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+// Swap the wallet's balance of `token` into `to_token` once its USD price falls
+// to (`stop_loss`) or rises to (`take_profit`) `price_usd`.
+#[derive(Debug, Deserialize)]
+pub struct CreateProtectionRuleRequest {
+    pub wallet_address: String,
+    pub token: String,
+    pub kind: ProtectionKind,
+    #[serde(deserialize_with = "decimal_string")]
+    pub price_usd: String,
+    // A stable token, defaults to the target of the wallet's subscription.
+    pub to_token: Option<String>,
+}
+
+impl SignedPayload for CreateProtectionRuleRequest {
+    fn signer(&self) -> &str {
+        &self.wallet_address
+    }
+}
+
+// Deletes a protection rule of `wallet_address`.
+#[derive(Debug, Deserialize)]
+pub struct ProtectionRuleOwnerRequest {
+    pub wallet_address: String,
+}
+
+impl SignedPayload for ProtectionRuleOwnerRequest {
+    fn signer(&self) -> &str {
+        &self.wallet_address
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListProtectionRulesRequest {
+    pub wallet_address: String,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+// Accept a decimal amount either as a JSON string or as a JSON number.
+pub fn decimal_string<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Decimal {
+        String(String),
+        Number(serde_json::Number),
+    }
+
+    match Decimal::deserialize(deserializer)? {
+        Decimal::String(value) => Ok(value),
+        Decimal::Number(value) => Ok(value.to_string()),
+    }
+}
+
+fn optional_decimal_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    decimal_string(deserializer).map(Some)
+}
+
 /// Returns true if the wallet address is valid.
 pub fn is_valid_address(address: &str) -> bool {
     address.starts_with(ADDRESS_PREFIX)
@@ -2,7 +2,11 @@ use axum::{extract::State, Json};
 use serde::Deserialize;
 
 use super::types::{is_valid_address, SuccessResponse};
-use crate::{api_error::ApiError, AppState};
+use crate::{
+    api_error::ApiError,
+    auth::{SignedJson, SignedPayload},
+    AppState,
+};
 
 #[derive(Debug, Deserialize)]
 pub struct UnsubscriptionPayload {
@@ -10,9 +14,15 @@ pub struct UnsubscriptionPayload {
     pub from_token: String,
 }
 
+impl SignedPayload for UnsubscriptionPayload {
+    fn signer(&self) -> &str {
+        &self.wallet_address
+    }
+}
+
 pub async fn handle_unsubscribe(
     State(state): State<AppState>,
-    Json(payload): Json<UnsubscriptionPayload>,
+    SignedJson(payload): SignedJson<UnsubscriptionPayload>,
 ) -> Result<Json<SuccessResponse>, ApiError> {
     let UnsubscriptionPayload {
         wallet_address,
@@ -0,0 +1,24 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+
+use super::types::{is_valid_address, WalletStatsResponse};
+use crate::{api_error::ApiError, service::wallet_stats::pair_stats, AppState};
+
+pub async fn get_wallet_stats(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+) -> Result<Json<WalletStatsResponse>, ApiError> {
+    if !is_valid_address(&address) {
+        return Err(ApiError::InvalidRequest(
+            "Invalid wallet address format".to_string(),
+        ));
+    }
+
+    let pairs = pair_stats(&state.db.pool, &address).await?;
+    Ok(Json(WalletStatsResponse {
+        wallet_address: address,
+        pairs,
+    }))
+}
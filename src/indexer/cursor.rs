@@ -0,0 +1,43 @@
+use sqlx::PgPool;
+use starknet::core::types::Felt;
+
+// Last block scanned by an indexer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub block_number: u64,
+    pub block_hash: Felt,
+}
+
+impl Cursor {
+    pub async fn load(db: &PgPool, name: &str) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query_as::<_, (i64, String)>(
+            "SELECT block_number, block_hash FROM indexer_cursor WHERE name = $1",
+        )
+        .bind(name)
+        .fetch_optional(db)
+        .await?;
+
+        Ok(row.map(|(block_number, block_hash)| Cursor {
+            block_number: block_number as u64,
+            // Only ever written from a Felt, so the stored hash always parses.
+            block_hash: Felt::from_hex(&block_hash).unwrap_or(Felt::ZERO),
+        }))
+    }
+
+    pub async fn save(&self, db: &PgPool, name: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO indexer_cursor (name, block_number, block_hash)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (name)
+            DO UPDATE SET block_number = $2, block_hash = $3
+            "#,
+        )
+        .bind(name)
+        .bind(self.block_number as i64)
+        .bind(format!("{:#x}", self.block_hash))
+        .execute(db)
+        .await?;
+        Ok(())
+    }
+}
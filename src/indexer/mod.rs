@@ -0,0 +1,269 @@
+// On-chain ERC20 Transfer indexer.
+//
+// Polls `starknet_getEvents` for Transfer events of every token that has an
+// active subscription and queues a swap job for every transfer to a subscribed
+// wallet. Progress is stored in `indexer_cursor`; when the hash of the last
+// scanned block changes the indexer rewinds and re-scans recent blocks, and
+// `indexed_transfer` makes sure a re-scanned transfer is only queued once.
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::{anyhow, Result};
+use starknet::{
+    core::types::{BlockId, EventFilter, Felt, MaybePendingBlockWithTxHashes},
+    providers::Provider,
+};
+
+use crate::{
+    service::auto_swap::{process_transfer, IncomingTransfer},
+    utils::amount::Amount,
+    Configuration, Db,
+};
+
+mod cursor;
+mod transfer;
+
+pub use cursor::Cursor;
+pub use transfer::{decode_transfer, parse_transfer, Transfer, TRANSFER_EVENT_KEY};
+
+// Name of the cursor used by the ERC20 Transfer indexer.
+pub const TRANSFER_CURSOR: &str = "erc20_transfers";
+
+// Largest block range scanned in a single poll.
+const MAX_BLOCK_RANGE: u64 = 1000;
+
+// Events requested per `starknet_getEvents` page.
+const EVENTS_CHUNK_SIZE: u64 = 1000;
+
+pub struct IndexerSettings {
+    pub cursor_name: String,
+    pub start_block: Option<u64>,
+    pub reorg_depth: u64,
+    pub poll_interval: Duration,
+    pub swap_job_max_attempts: i32,
+}
+
+impl IndexerSettings {
+    pub fn from_config(config: &Configuration) -> Self {
+        IndexerSettings {
+            cursor_name: TRANSFER_CURSOR.to_string(),
+            start_block: config.indexer_start_block,
+            reorg_depth: config.indexer_reorg_depth,
+            poll_interval: Duration::from_secs(config.indexer_poll_interval_secs),
+            swap_job_max_attempts: config.swap_job_max_attempts,
+        }
+    }
+}
+
+// Subscribed wallets of a token, keyed by their felt value so that differently
+// formatted addresses still match.
+struct WatchedToken {
+    address: String,
+    wallets: HashMap<Felt, String>,
+}
+
+pub struct TransferIndexer<P> {
+    provider: P,
+    db: Db,
+    settings: IndexerSettings,
+}
+
+impl<P> TransferIndexer<P>
+where
+    P: Provider + Send + Sync,
+{
+    pub fn new(provider: P, db: Db, settings: IndexerSettings) -> Self {
+        TransferIndexer {
+            provider,
+            db,
+            settings,
+        }
+    }
+
+    // Poll for new blocks forever.
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(self.settings.poll_interval);
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.poll().await {
+                tracing::error!("Transfer indexer poll failed: {:#}", err);
+            }
+        }
+    }
+
+    // Scan the blocks produced since the last poll once. Returns the number of
+    // swap jobs queued.
+    pub async fn poll(&self) -> Result<usize> {
+        let head = self.provider.block_number().await?;
+        let cursor = Cursor::load(&self.db.pool, &self.settings.cursor_name).await?;
+
+        let from_block = match cursor {
+            Some(cursor) => {
+                let block_hash = self.block_hash(cursor.block_number).await?;
+                match block_hash == cursor.block_hash {
+                    true => cursor.block_number + 1,
+                    false => {
+                        tracing::warn!(
+                            "Reorg detected at block {}, re-scanning the last {} blocks",
+                            cursor.block_number,
+                            self.settings.reorg_depth
+                        );
+                        cursor
+                            .block_number
+                            .saturating_sub(self.settings.reorg_depth)
+                    }
+                }
+            }
+            None => self.settings.start_block.unwrap_or(head),
+        };
+
+        if from_block > head {
+            return Ok(0);
+        }
+        let to_block = head.min(from_block + MAX_BLOCK_RANGE - 1);
+
+        let mut queued = 0;
+        for (token, watched) in self.watched_tokens().await? {
+            queued += self
+                .scan_token(token, &watched, from_block, to_block)
+                .await?;
+        }
+
+        let cursor = Cursor {
+            block_number: to_block,
+            block_hash: self.block_hash(to_block).await?,
+        };
+        cursor
+            .save(&self.db.pool, &self.settings.cursor_name)
+            .await?;
+
+        Ok(queued)
+    }
+
+    // Queue a swap for every new Transfer of `token` to a subscribed wallet.
+    async fn scan_token(
+        &self,
+        token: Felt,
+        watched: &WatchedToken,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<usize> {
+        let filter = EventFilter {
+            from_block: Some(BlockId::Number(from_block)),
+            to_block: Some(BlockId::Number(to_block)),
+            address: Some(token),
+            keys: Some(vec![vec![TRANSFER_EVENT_KEY]]),
+        };
+
+        let mut queued = 0;
+        let mut event_indexes: HashMap<Felt, i32> = HashMap::new();
+        let mut continuation_token = None;
+        loop {
+            let page = self
+                .provider
+                .get_events(filter.clone(), continuation_token, EVENTS_CHUNK_SIZE)
+                .await?;
+
+            for event in page.events {
+                // Position of the event among this token's events in its transaction.
+                let event_index = {
+                    let index = event_indexes.entry(event.transaction_hash).or_insert(0);
+                    *index += 1;
+                    *index
+                };
+
+                let transfer = match parse_transfer(&event) {
+                    Some(transfer) => transfer,
+                    None => continue,
+                };
+                let wallet_address = match watched.wallets.get(&transfer.to) {
+                    Some(wallet_address) => wallet_address,
+                    None => continue,
+                };
+                let incoming = IncomingTransfer {
+                    wallet_address: wallet_address.clone(),
+                    token_from: watched.address.clone(),
+                    amount: transfer.amount,
+                };
+                let mut tx = self.db.pool.begin().await?;
+                let inserted = sqlx::query(
+                    r#"
+                    INSERT INTO indexed_transfer
+                    (transaction_hash, token_address, event_index, wallet_address, amount, block_number)
+                    VALUES ($1, $2, $3, $4, $5::NUMERIC, $6)
+                    ON CONFLICT DO NOTHING
+                    "#,
+                )
+                .bind(format!("{:#x}", event.transaction_hash))
+                .bind(&incoming.token_from)
+                .bind(event_index)
+                .bind(&incoming.wallet_address)
+                .bind(Amount::from_raw(incoming.amount, 0).base_units())
+                .bind(event.block_number.unwrap_or(to_block) as i64)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected()
+                    == 1;
+
+                if inserted {
+                    let legs =
+                        process_transfer(&mut tx, &incoming, self.settings.swap_job_max_attempts)
+                            .await?;
+                    queued += legs.len();
+                }
+                tx.commit().await?;
+            }
+
+            continuation_token = page.continuation_token;
+            if continuation_token.is_none() {
+                break;
+            }
+        }
+
+        Ok(queued)
+    }
+
+    // Tokens with at least one active subscription.
+    async fn watched_tokens(&self) -> Result<HashMap<Felt, WatchedToken>> {
+        let rows = sqlx::query_as::<_, (String, String)>(
+            r#"
+            SELECT sf.from_token, sf.wallet_address
+            FROM swap_subscription_from_token sf
+            INNER JOIN swap_subscription s ON s.wallet_address = sf.wallet_address
+            WHERE s.is_active = true
+            "#,
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+
+        let mut watched: HashMap<Felt, WatchedToken> = HashMap::new();
+        for (from_token, wallet_address) in rows {
+            let (token, wallet) =
+                match (Felt::from_hex(&from_token), Felt::from_hex(&wallet_address)) {
+                    (Ok(token), Ok(wallet)) => (token, wallet),
+                    _ => continue,
+                };
+            watched
+                .entry(token)
+                .or_insert_with(|| WatchedToken {
+                    address: from_token,
+                    wallets: HashMap::new(),
+                })
+                .wallets
+                .insert(wallet, wallet_address);
+        }
+        Ok(watched)
+    }
+
+    async fn block_hash(&self, block_number: u64) -> Result<Felt> {
+        match self
+            .provider
+            .get_block_with_tx_hashes(BlockId::Number(block_number))
+            .await?
+        {
+            MaybePendingBlockWithTxHashes::Block(block) => Ok(block.block_hash),
+            MaybePendingBlockWithTxHashes::PendingBlock(_) => {
+                Err(anyhow!("Block {} is still pending", block_number))
+            }
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use starknet::{
+    core::types::{EmittedEvent, Felt, U256},
+    macros::selector,
+};
+
+use crate::utils::starknet::felt_to_u128;
+
+// Key of the ERC20 `Transfer` event.
+pub const TRANSFER_EVENT_KEY: Felt = selector!("Transfer");
+
+// Decoded ERC20 `Transfer` event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transfer {
+    pub from: Felt,
+    pub to: Felt,
+    pub amount: U256,
+}
+
+// Decode a `Transfer` event. Cairo 1 tokens index `from` and `to` as keys while
+// legacy tokens put every member in the event data, so both layouts are accepted.
+pub fn parse_transfer(event: &EmittedEvent) -> Option<Transfer> {
+    decode_transfer(&event.keys, &event.data)
+}
+
+// Decode the keys and data of an event, e.g. one found in a transaction receipt.
+pub fn decode_transfer(keys: &[Felt], data: &[Felt]) -> Option<Transfer> {
+    if keys.first() != Some(&TRANSFER_EVENT_KEY) {
+        return None;
+    }
+
+    let (from, to, low, high) = match (keys, data) {
+        ([_, from, to], [low, high]) => (*from, *to, *low, *high),
+        ([_], [from, to, low, high]) => (*from, *to, *low, *high),
+        _ => return None,
+    };
+
+    Some(Transfer {
+        from,
+        to,
+        amount: U256::from_words(felt_to_u128(low)?, felt_to_u128(high)?),
+    })
+}
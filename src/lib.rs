@@ -1,13 +1,20 @@
+use std::sync::Arc;
+
 use axum::Router;
+use service::{pricing::Pricing, venues::VenueRegistry};
+use utils::starknet::{rpc_provider, swap_account, RpcProvider, SwapAccount};
 
 pub mod api_error;
+pub mod auth;
 pub mod config;
 pub mod db;
 pub mod http;
+pub mod indexer;
 pub mod middleware;
 pub mod service;
 pub mod telemetry;
 pub mod utils;
+pub mod worker;
 
 pub use config::*;
 pub use db::*;
@@ -17,12 +24,27 @@ pub use db::*;
 pub struct AppState {
     pub db: Db,
     pub config: Config,
+    pub venues: VenueRegistry,
+    pub provider: Arc<RpcProvider>,
+    pub account: Arc<SwapAccount>,
+    pub pricing: Pricing,
 }
 
 // Requests Router.
 pub fn router(config: Config, db: Db) -> Router {
     // Initialize App State.
-    let app_state = AppState { db, config };
+    let venues = VenueRegistry::from_config(&config, &db);
+    let provider = Arc::new(rpc_provider(&config.rpc_url));
+    let account = Arc::new(swap_account(&config));
+    let pricing = Pricing::from_config(&config, provider.clone());
+    let app_state = AppState {
+        db,
+        config,
+        venues,
+        provider,
+        account,
+        pricing,
+    };
 
     // Initialize Middlewares.
     let trace_layer = telemetry::trace_layer();
@@ -1,4 +1,15 @@
-use autoswappr_backend::{telemetry, Configuration, Db};
+use autoswappr_backend::{
+    indexer::{IndexerSettings, TransferIndexer},
+    service::{nonces::NonceManager, pricing::Pricing, venues::VenueRegistry},
+    telemetry,
+    utils::starknet::{rpc_provider, swap_accounts},
+    worker::{
+        run_swap_worker, BatchSettings, DcaScheduler, PriceSnapshotter, ProtectionEvaluator,
+        ReceiptTracker, RouterExecutor, TriggerEvaluator,
+    },
+    Configuration, Db,
+};
+use std::{sync::Arc, time::Duration};
 use tokio::net::TcpListener;
 
 #[tokio::main]
@@ -23,6 +34,85 @@ async fn main() {
     tracing::debug!("Running Migrations");
     db.migrate().await.expect("Failed to run migrations");
 
+    // Start the Transfer indexer that queues swaps for incoming transfers.
+    tracing::debug!("Starting Transfer indexer");
+    let provider = rpc_provider(&config.rpc_url);
+    let indexer = TransferIndexer::new(provider, db.clone(), IndexerSettings::from_config(&config));
+    tokio::spawn(indexer.run());
+
+    // Start the swap workers that drain the job queue.
+    tracing::debug!("Starting {} swap workers", config.swap_workers);
+    // Workers share the nonces of the swap accounts.
+    let nonces = NonceManager::new(swap_accounts(&config));
+    for _ in 0..config.swap_workers {
+        let executor = RouterExecutor::new(
+            VenueRegistry::from_config(&config, &db),
+            nonces.clone(),
+            config.contract_address,
+        );
+        tokio::spawn(run_swap_worker(
+            db.clone(),
+            executor,
+            BatchSettings::from_config(&config),
+        ));
+    }
+
+    // Prices are shared by the receipt tracker, the price snapshotter and the
+    // trigger and protection evaluators.
+    let pricing = Pricing::from_config(&config, Arc::new(rpc_provider(&config.rpc_url)));
+
+    // Start the receipt tracker that records the outcome of submitted swaps.
+    tracing::debug!("Starting receipt tracker");
+    let tracker = ReceiptTracker::new(
+        rpc_provider(&config.rpc_url),
+        db.clone(),
+        pricing.clone(),
+        nonces.clone(),
+        Duration::from_secs(config.receipt_submission_timeout_secs),
+        Duration::from_secs(config.receipt_poll_interval_secs),
+    );
+    tokio::spawn(tracker.run());
+
+    // Start the price snapshotter that keeps the price history.
+    tracing::debug!("Starting price snapshotter");
+    let snapshotter = PriceSnapshotter::new(
+        pricing.clone(),
+        db.clone(),
+        Duration::from_secs(config.price_snapshot_interval_mins * 60),
+    );
+    tokio::spawn(snapshotter.run());
+
+    // Start the scheduler that queues the swaps of due DCA schedules.
+    tracing::debug!("Starting DCA scheduler");
+    let scheduler = DcaScheduler::new(
+        db.clone(),
+        config.swap_job_max_attempts,
+        Duration::from_secs(config.dca_poll_interval_secs),
+    );
+    tokio::spawn(scheduler.run());
+
+    // Start the evaluator that queues the swaps held for a price trigger.
+    tracing::debug!("Starting trigger evaluator");
+    let evaluator = TriggerEvaluator::new(
+        pricing.clone(),
+        db.clone(),
+        config.swap_job_max_attempts,
+        Duration::from_secs(config.trigger_poll_interval_secs),
+    );
+    tokio::spawn(evaluator.run());
+
+    // Start the evaluator that swaps balances whose protection rule is met.
+    tracing::debug!("Starting protection evaluator");
+    let protection = ProtectionEvaluator::new(
+        pricing,
+        rpc_provider(&config.rpc_url),
+        db.clone(),
+        config.account_address,
+        config.swap_job_max_attempts,
+        Duration::from_secs(config.protection_poll_interval_secs),
+    );
+    tokio::spawn(protection.run());
+
     // Listen for requests on specified port.
     tracing::info!("Starting server on {}", config.listen_address);
     let listener = TcpListener::bind(&config.listen_address)
@@ -0,0 +1,139 @@
+use sqlx::{FromRow, PgConnection};
+use starknet::core::types::U256;
+use uuid::Uuid;
+
+use super::{
+    swap_jobs::{enqueue, NewSwapJob},
+    triggers::{hold, SubscriptionTrigger, TriggerCondition},
+};
+use crate::utils::amount::Amount;
+
+// An ERC20 transfer received by a subscribed wallet, in raw base units.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncomingTransfer {
+    pub wallet_address: String,
+    pub token_from: String,
+    pub amount: U256,
+}
+
+// Subscription of a wallet to a received token, for one of its targets.
+#[derive(FromRow)]
+struct Preference {
+    to_token: String,
+    weight: i16,
+    percentage: i16,
+    token_decimals: i16,
+    max_slippage_bps: i16,
+    venue: String,
+    trigger_condition: Option<TriggerCondition>,
+    trigger_price_usd: Option<String>,
+    trigger_expires_at: Option<String>,
+}
+
+// What became of the swap of an incoming transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferOutcome {
+    // Queued as this swap job.
+    Queued(Uuid),
+    // Held as this triggered swap until the price trigger of the subscription is met.
+    Held(Uuid),
+}
+
+// Swap of an incoming transfer into one target token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferLeg {
+    pub to_token: String,
+    pub outcome: TransferOutcome,
+}
+
+// Queue swaps of the subscribed percentage of an incoming transfer into the
+// targets of the received token, split by their weights, or hold them if the
+// subscribed token has a price trigger. Returns one leg per target with a
+// non-zero share, and none if the wallet has no active subscription for the
+// received token or the token is not registered.
+pub async fn process_transfer(
+    conn: &mut PgConnection,
+    transfer: &IncomingTransfer,
+    max_attempts: i32,
+) -> Result<Vec<TransferLeg>, sqlx::Error> {
+    // Tokens without targets swap into the subscription's `to_token`.
+    let preferences = sqlx::query_as::<_, Preference>(
+        r#"
+        SELECT
+            COALESCE(st.to_token, s.to_token) AS to_token,
+            COALESCE(st.weight, 1::SMALLINT) AS weight,
+            sf.percentage,
+            t.token_decimals,
+            s.max_slippage_bps,
+            s.venue,
+            sf.trigger_condition,
+            TRIM_SCALE(sf.trigger_price_usd)::TEXT AS trigger_price_usd,
+            TO_CHAR(sf.trigger_expires_at, 'YYYY-MM-DD"T"HH24:MI:SSZ') AS trigger_expires_at
+        FROM swap_subscription s
+        INNER JOIN swap_subscription_from_token sf ON s.wallet_address = sf.wallet_address
+        INNER JOIN token t ON LOWER(t.contract_address) = LOWER(sf.from_token) AND t.is_active
+        LEFT JOIN swap_subscription_target st
+            ON st.wallet_address = sf.wallet_address AND st.from_token = sf.from_token
+        WHERE s.wallet_address = $1 AND sf.from_token = $2 AND s.is_active = true
+        ORDER BY st.created_at, st.to_token
+        "#,
+    )
+    .bind(&transfer.wallet_address)
+    .bind(&transfer.token_from)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let first = match preferences.first() {
+        Some(first) => first,
+        None => return Ok(Vec::new()),
+    };
+
+    // Rounded down, so the swaps never spend more than the subscribed share.
+    let received = Amount::from_raw(transfer.amount, first.token_decimals as u8);
+    let weights: Vec<u16> = preferences
+        .iter()
+        .map(|pref| pref.weight.max(0) as u16)
+        .collect();
+    let shares = match received
+        .percentage(first.percentage as u16)
+        .and_then(|amount| amount.split(&weights))
+    {
+        Ok(shares) => shares,
+        Err(_) => return Ok(Vec::new()),
+    };
+
+    let mut legs = Vec::with_capacity(preferences.len());
+    for (pref, amount) in preferences.into_iter().zip(shares) {
+        if amount.is_zero() {
+            continue;
+        }
+        // Every leg records the percentage subscribed for the whole token.
+        let job = NewSwapJob {
+            wallet_address: transfer.wallet_address.clone(),
+            from_token: transfer.token_from.clone(),
+            to_token: pref.to_token.clone(),
+            percentage: pref.percentage,
+            amount,
+            max_slippage_bps: pref.max_slippage_bps,
+            venue: pref.venue,
+            pull_from_wallet: false,
+            max_attempts,
+        };
+        let outcome = match (pref.trigger_condition, pref.trigger_price_usd) {
+            (Some(condition), Some(price_usd)) => {
+                let trigger = SubscriptionTrigger {
+                    condition,
+                    price_usd,
+                    expires_at: pref.trigger_expires_at,
+                };
+                TransferOutcome::Held(hold(conn, &job, &trigger).await?)
+            }
+            _ => TransferOutcome::Queued(enqueue(conn, &job).await?),
+        };
+        legs.push(TransferLeg {
+            to_token: pref.to_token,
+            outcome,
+        });
+    }
+    Ok(legs)
+}
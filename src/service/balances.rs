@@ -0,0 +1,54 @@
+use anyhow::{anyhow, Context, Result};
+use starknet::{
+    core::types::{BlockId, BlockTag, Felt, FunctionCall, U256},
+    macros::selector,
+    providers::Provider,
+};
+
+use crate::utils::starknet::felt_to_u128;
+
+// Balance of `owner` in the ERC20 token at `token`.
+pub async fn balance_of<P>(provider: &P, token: Felt, owner: Felt) -> Result<U256>
+where
+    P: Provider + Sync,
+{
+    let result = provider
+        .call(
+            FunctionCall {
+                contract_address: token,
+                entry_point_selector: selector!("balanceOf"),
+                calldata: vec![owner],
+            },
+            BlockId::Tag(BlockTag::Latest),
+        )
+        .await
+        .context("Failed to call balanceOf")?;
+    decode_u256(&result).ok_or_else(|| anyhow!("Invalid balance"))
+}
+
+// Amount of `owner`'s ERC20 tokens at `token` that `spender` may transfer.
+pub async fn allowance<P>(provider: &P, token: Felt, owner: Felt, spender: Felt) -> Result<U256>
+where
+    P: Provider + Sync,
+{
+    let result = provider
+        .call(
+            FunctionCall {
+                contract_address: token,
+                entry_point_selector: selector!("allowance"),
+                calldata: vec![owner, spender],
+            },
+            BlockId::Tag(BlockTag::Latest),
+        )
+        .await
+        .context("Failed to call allowance")?;
+    decode_u256(&result).ok_or_else(|| anyhow!("Invalid allowance"))
+}
+
+// A u256 returned as its low and high 128 bits.
+fn decode_u256(result: &[Felt]) -> Option<U256> {
+    match result {
+        [low, high] => Some(U256::from_words(felt_to_u128(*low)?, felt_to_u128(*high)?)),
+        _ => None,
+    }
+}
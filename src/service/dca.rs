@@ -0,0 +1,382 @@
+use serde::Serialize;
+use sqlx::{FromRow, PgConnection, PgPool};
+use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime};
+use uuid::Uuid;
+
+use super::swap_jobs::{enqueue, NewSwapJob};
+use crate::utils::{
+    amount::Amount,
+    cron::CronSchedule,
+    filters::{Column, Filters},
+    pagination::{Keyset, Page, Paged, Pagination},
+};
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[sqlx(type_name = "dca_schedule_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DcaStatus {
+    Active,
+    Paused,
+    Ended,
+}
+
+// Swaps of `amount` base units of `from_token` into `to_token` every time
+// `schedule` is due.
+#[derive(FromRow, Debug, Clone, Serialize)]
+pub struct DcaSchedule {
+    pub id: Uuid,
+    pub wallet_address: String,
+    pub from_token: String,
+    pub to_token: String,
+    pub amount: String,
+    pub from_decimals: i16,
+    pub schedule: String,
+    pub max_slippage_bps: i16,
+    pub venue: String,
+    pub status: DcaStatus,
+    pub starts_at: String,
+    pub ends_at: Option<String>,
+    pub next_run_at: Option<String>,
+    pub last_run_at: Option<String>,
+    pub created_at: String,
+    // Creation time with microseconds, the position of the row in listings.
+    #[serde(skip)]
+    pub sort_key: String,
+}
+
+// Columns selected into a `DcaSchedule`.
+const DCA_COLUMNS: &str = r#"
+    id,
+    wallet_address,
+    from_token,
+    to_token,
+    amount::TEXT AS amount,
+    from_decimals,
+    schedule,
+    max_slippage_bps,
+    venue,
+    status,
+    TO_CHAR(starts_at, 'YYYY-MM-DD"T"HH24:MI:SSZ') AS starts_at,
+    TO_CHAR(ends_at, 'YYYY-MM-DD"T"HH24:MI:SSZ') AS ends_at,
+    TO_CHAR(next_run_at, 'YYYY-MM-DD"T"HH24:MI:SSZ') AS next_run_at,
+    TO_CHAR(last_run_at, 'YYYY-MM-DD"T"HH24:MI:SSZ') AS last_run_at,
+    TO_CHAR(created_at, 'YYYY-MM-DD"T"HH24:MI:SSZ') AS created_at,
+    TO_CHAR(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"') AS sort_key
+"#;
+
+// Newest first.
+pub const KEYSET: Keyset = Keyset {
+    key: Column::cast("created_at", "TIMESTAMPTZ"),
+    id: Column::cast("id", "UUID"),
+    descending: true,
+};
+
+impl Paged for DcaSchedule {
+    fn position(&self) -> (String, String) {
+        (self.sort_key.clone(), self.id.to_string())
+    }
+}
+
+impl DcaSchedule {
+    pub fn amount(&self) -> Option<Amount> {
+        let decimals = u8::try_from(self.from_decimals).ok()?;
+        Amount::from_base_units(&self.amount, decimals).ok()
+    }
+
+    pub fn cron(&self) -> Option<CronSchedule> {
+        self.schedule.parse().ok()
+    }
+
+    pub fn starts_at(&self) -> Option<OffsetDateTime> {
+        OffsetDateTime::parse(&self.starts_at, &Rfc3339).ok()
+    }
+
+    pub fn ends_at(&self) -> Option<OffsetDateTime> {
+        OffsetDateTime::parse(self.ends_at.as_deref()?, &Rfc3339).ok()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NewDcaSchedule {
+    pub wallet_address: String,
+    pub from_token: String,
+    pub to_token: String,
+    pub amount: Amount,
+    pub schedule: CronSchedule,
+    pub max_slippage_bps: i16,
+    // `BEST_PRICE` or the name of a venue.
+    pub venue: String,
+    pub starts_at: OffsetDateTime,
+    pub ends_at: Option<OffsetDateTime>,
+}
+
+// Settings written over a schedule's current ones.
+#[derive(Debug, Clone)]
+pub struct DcaUpdate {
+    pub amount: Amount,
+    pub schedule: String,
+    pub max_slippage_bps: i16,
+    pub venue: String,
+    pub status: DcaStatus,
+    pub ends_at: Option<OffsetDateTime>,
+    pub next_run_at: Option<OffsetDateTime>,
+}
+
+// The first run strictly after `after`, or `None` once the schedule is past `ends_at`.
+pub fn next_run(
+    schedule: &CronSchedule,
+    after: OffsetDateTime,
+    ends_at: Option<OffsetDateTime>,
+) -> Option<OffsetDateTime> {
+    schedule
+        .next_after(after)
+        .filter(|next| ends_at.map_or(true, |ends_at| *next <= ends_at))
+}
+
+// The first run of a schedule starting at `starts_at`, which may be `starts_at` itself.
+pub fn first_run(
+    schedule: &CronSchedule,
+    starts_at: OffsetDateTime,
+    ends_at: Option<OffsetDateTime>,
+) -> Option<OffsetDateTime> {
+    next_run(schedule, starts_at - Duration::seconds(1), ends_at)
+}
+
+fn rfc3339(time: OffsetDateTime) -> String {
+    time.format(&Rfc3339).unwrap()
+}
+
+// Whether `wallet_address` has an active subscription.
+pub async fn is_subscribed(pool: &PgPool, wallet_address: &str) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM swap_subscription WHERE wallet_address = $1 AND is_active)",
+    )
+    .bind(wallet_address)
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn create(pool: &PgPool, dca: &NewDcaSchedule) -> Result<DcaSchedule, sqlx::Error> {
+    let next_run_at = first_run(&dca.schedule, dca.starts_at, dca.ends_at);
+    let status = match next_run_at {
+        Some(_) => DcaStatus::Active,
+        None => DcaStatus::Ended,
+    };
+    sqlx::query_as::<_, DcaSchedule>(&format!(
+        r#"
+        INSERT INTO dca_schedule
+        (wallet_address, from_token, to_token, amount, from_decimals, schedule,
+         max_slippage_bps, venue, status, starts_at, ends_at, next_run_at)
+        VALUES ($1, $2, $3, $4::NUMERIC, $5, $6, $7, $8, $9,
+                $10::TIMESTAMPTZ, $11::TIMESTAMPTZ, $12::TIMESTAMPTZ)
+        RETURNING {}
+        "#,
+        DCA_COLUMNS
+    ))
+    .bind(&dca.wallet_address)
+    .bind(&dca.from_token)
+    .bind(&dca.to_token)
+    .bind(dca.amount.base_units())
+    .bind(i16::from(dca.amount.decimals()))
+    .bind(dca.schedule.to_string())
+    .bind(dca.max_slippage_bps)
+    .bind(&dca.venue)
+    .bind(status)
+    .bind(rfc3339(dca.starts_at))
+    .bind(dca.ends_at.map(rfc3339))
+    .bind(next_run_at.map(rfc3339))
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn list(
+    pool: &PgPool,
+    wallet_address: &str,
+    pagination: &Pagination,
+) -> Result<Page<DcaSchedule>, sqlx::Error> {
+    let mut filters = Filters::new(&format!("SELECT {} FROM dca_schedule", DCA_COLUMNS));
+    filters.eq("LOWER(wallet_address)", Some(wallet_address.to_lowercase()));
+    let rows = pagination
+        .apply(filters)
+        .build_query_as::<DcaSchedule>()
+        .fetch_all(pool)
+        .await?;
+    Ok(pagination.page(rows))
+}
+
+pub async fn find(conn: &mut PgConnection, id: Uuid) -> Result<Option<DcaSchedule>, sqlx::Error> {
+    sqlx::query_as::<_, DcaSchedule>(&format!(
+        "SELECT {} FROM dca_schedule WHERE id = $1",
+        DCA_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(conn)
+    .await
+}
+
+// Lock a schedule until the surrounding transaction ends, so it is not run
+// while it changes.
+pub async fn find_for_update(
+    conn: &mut PgConnection,
+    id: Uuid,
+) -> Result<Option<DcaSchedule>, sqlx::Error> {
+    sqlx::query_as::<_, DcaSchedule>(&format!(
+        "SELECT {} FROM dca_schedule WHERE id = $1 FOR UPDATE",
+        DCA_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(conn)
+    .await
+}
+
+pub async fn update(
+    conn: &mut PgConnection,
+    id: Uuid,
+    update: &DcaUpdate,
+) -> Result<DcaSchedule, sqlx::Error> {
+    sqlx::query_as::<_, DcaSchedule>(&format!(
+        r#"
+        UPDATE dca_schedule
+        SET amount = $2::NUMERIC,
+            from_decimals = $3,
+            schedule = $4,
+            max_slippage_bps = $5,
+            venue = $6,
+            status = $7,
+            ends_at = $8::TIMESTAMPTZ,
+            next_run_at = $9::TIMESTAMPTZ
+        WHERE id = $1
+        RETURNING {}
+        "#,
+        DCA_COLUMNS
+    ))
+    .bind(id)
+    .bind(update.amount.base_units())
+    .bind(i16::from(update.amount.decimals()))
+    .bind(&update.schedule)
+    .bind(update.max_slippage_bps)
+    .bind(&update.venue)
+    .bind(update.status)
+    .bind(update.ends_at.map(rfc3339))
+    .bind(update.next_run_at.map(rfc3339))
+    .fetch_one(conn)
+    .await
+}
+
+// Returns false if no schedule has this id.
+pub async fn delete(conn: &mut PgConnection, id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM dca_schedule WHERE id = $1")
+        .bind(id)
+        .execute(conn)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+// Lock the active schedule that has been due the longest at `now`. The row
+// stays locked, and invisible to other schedulers, until the surrounding
+// transaction ends.
+pub async fn claim_due(
+    conn: &mut PgConnection,
+    now: OffsetDateTime,
+) -> Result<Option<DcaSchedule>, sqlx::Error> {
+    sqlx::query_as::<_, DcaSchedule>(&format!(
+        r#"
+        SELECT {}
+        FROM dca_schedule
+        WHERE status = 'active' AND next_run_at <= $1::TIMESTAMPTZ
+        ORDER BY next_run_at
+        LIMIT 1
+        FOR UPDATE SKIP LOCKED
+        "#,
+        DCA_COLUMNS
+    ))
+    .bind(rfc3339(now))
+    .fetch_optional(conn)
+    .await
+}
+
+// Run a claimed schedule that is due at `now`: queue its swap and move it to its
+// next run after `now`, ending it when there is none. Runs missed while nothing
+// was scheduling are not caught up. A run is queued at most once, and is skipped
+// while either token is disabled. Returns the id of the queued swap job.
+pub async fn run_due(
+    conn: &mut PgConnection,
+    dca: &DcaSchedule,
+    now: OffsetDateTime,
+    max_attempts: i32,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let next_run_at = dca
+        .cron()
+        .and_then(|schedule| next_run(&schedule, now, dca.ends_at()));
+
+    let first = sqlx::query(
+        r#"
+        INSERT INTO dca_run (schedule_id, run_at)
+        VALUES ($1, $2::TIMESTAMPTZ)
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(dca.id)
+    .bind(&dca.next_run_at)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected()
+        > 0;
+
+    let tradable = sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COUNT(*)
+        FROM token
+        WHERE is_active AND LOWER(contract_address) IN (LOWER($1), LOWER($2))
+        "#,
+    )
+    .bind(&dca.from_token)
+    .bind(&dca.to_token)
+    .fetch_one(&mut *conn)
+    .await?
+        == 2;
+
+    let job_id = match (first && tradable, dca.amount()) {
+        (true, Some(amount)) => {
+            let job = NewSwapJob {
+                wallet_address: dca.wallet_address.clone(),
+                from_token: dca.from_token.clone(),
+                to_token: dca.to_token.clone(),
+                percentage: 100,
+                amount,
+                max_slippage_bps: dca.max_slippage_bps,
+                venue: dca.venue.clone(),
+                // Runs spend the wallet's tokens, never the swap account's.
+                pull_from_wallet: true,
+                max_attempts,
+            };
+            let id = enqueue(conn, &job).await?;
+            sqlx::query(
+                "UPDATE dca_run SET swap_job_id = $3 WHERE schedule_id = $1 AND run_at = $2::TIMESTAMPTZ",
+            )
+            .bind(dca.id)
+            .bind(&dca.next_run_at)
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+            Some(id)
+        }
+        _ => None,
+    };
+
+    sqlx::query(
+        r#"
+        UPDATE dca_schedule
+        SET next_run_at = $2::TIMESTAMPTZ,
+            last_run_at = CASE WHEN $3 THEN next_run_at ELSE last_run_at END,
+            status = CASE WHEN $2 IS NULL THEN 'ended' ELSE status END
+        WHERE id = $1
+        "#,
+    )
+    .bind(dca.id)
+    .bind(next_run_at.map(rfc3339))
+    .bind(job_id.is_some())
+    .execute(conn)
+    .await?;
+    Ok(job_id)
+}
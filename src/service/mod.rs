@@ -1 +1,16 @@
+pub mod auto_swap;
+pub mod balances;
+pub mod dca;
+pub mod nonces;
+pub mod pools;
+pub mod price_history;
+pub mod pricing;
+pub mod protection;
+pub mod quote;
+pub mod slippage;
+pub mod swap_jobs;
+pub mod tokens;
 pub mod transaction_logs;
+pub mod triggers;
+pub mod venues;
+pub mod wallet_stats;
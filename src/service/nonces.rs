@@ -0,0 +1,191 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use anyhow::{anyhow, Context, Result};
+use starknet::{
+    accounts::{Account, AccountError, ConnectedAccount},
+    core::types::{Call, Felt, StarknetError},
+    providers::ProviderError,
+};
+use tokio::sync::Mutex;
+
+use crate::utils::starknet::SwapAccount;
+
+// Sends the swap transactions of the backend accounts. Nonces are handed out
+// locally, so that concurrent swaps from one account never reuse a nonce, and
+// swaps take turns between the accounts.
+#[derive(Clone)]
+pub struct NonceManager {
+    accounts: Arc<[ManagedAccount]>,
+    next_account: Arc<AtomicUsize>,
+}
+
+struct ManagedAccount {
+    account: Arc<SwapAccount>,
+    state: Mutex<NonceState>,
+}
+
+#[derive(Default)]
+struct NonceState {
+    // Nonce of the next transaction, read from chain when `None`.
+    next: Option<Felt>,
+    // Hashes of the transactions sent and not yet settled by the receipt
+    // tracker, by nonce.
+    in_flight: BTreeMap<Felt, Felt>,
+}
+
+impl NonceState {
+    // Read the nonce from chain and hand out nonces from it again.
+    async fn sync(&mut self, account: &SwapAccount) -> Result<Felt> {
+        let nonce = chain_nonce(account).await?;
+        self.next = Some(nonce);
+        Ok(nonce)
+    }
+}
+
+impl NonceManager {
+    pub fn new(accounts: Vec<SwapAccount>) -> Self {
+        assert!(!accounts.is_empty(), "NonceManager needs an account");
+        NonceManager {
+            accounts: accounts
+                .into_iter()
+                .map(|account| ManagedAccount {
+                    account: Arc::new(account),
+                    state: Mutex::new(NonceState::default()),
+                })
+                .collect(),
+            next_account: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub fn addresses(&self) -> Vec<Felt> {
+        self.accounts
+            .iter()
+            .map(|managed| managed.account.address())
+            .collect()
+    }
+
+    // Account the next swap is quoted and sent from.
+    pub fn next_account(&self) -> Arc<SwapAccount> {
+        let index = self.next_account.fetch_add(1, Ordering::Relaxed) % self.accounts.len();
+        self.accounts[index].account.clone()
+    }
+
+    // The first account, which wallets allow to spend the tokens their swaps
+    // pull from them.
+    pub fn primary_account(&self) -> Arc<SwapAccount> {
+        self.accounts[0].account.clone()
+    }
+
+    // Nonces and hashes of the transactions sent from `address` whose outcome
+    // is not recorded yet.
+    pub async fn in_flight(&self, address: Felt) -> Result<Vec<(Felt, Felt)>> {
+        let state = self.managed(address)?.state.lock().await;
+        Ok(state
+            .in_flight
+            .iter()
+            .map(|(nonce, hash)| (*nonce, *hash))
+            .collect())
+    }
+
+    // Whether the chain nonce of the account that sent the transaction `hash`
+    // is past the transaction's nonce, so that it can no longer run if the node
+    // does not know it. `None` if it is not a transaction in flight, e.g. one
+    // sent before a restart.
+    pub async fn counted(&self, hash: Felt) -> Result<Option<bool>> {
+        for managed in self.accounts.iter() {
+            let nonce = {
+                let state = managed.state.lock().await;
+                state
+                    .in_flight
+                    .iter()
+                    .find(|(_, sent)| **sent == hash)
+                    .map(|(nonce, _)| *nonce)
+            };
+            if let Some(nonce) = nonce {
+                return Ok(Some(chain_nonce(&managed.account).await? > nonce));
+            }
+        }
+        Ok(None)
+    }
+
+    // Stop tracking the transaction `hash` once its outcome is recorded.
+    pub async fn settle(&self, hash: Felt) {
+        for managed in self.accounts.iter() {
+            managed
+                .state
+                .lock()
+                .await
+                .in_flight
+                .retain(|_, sent| *sent != hash);
+        }
+    }
+
+    // Send `calls` from `account` with its next nonce and return the transaction
+    // hash. Transactions from one account are sent one at a time. A nonce the
+    // node rejects is read again from chain and the transaction sent once more.
+    pub async fn send(&self, account: &SwapAccount, calls: Vec<Call>) -> Result<Felt> {
+        let mut state = self.managed(account.address())?.state.lock().await;
+        let mut resynced = false;
+        loop {
+            let nonce = match state.next {
+                Some(nonce) => nonce,
+                None => state.sync(account).await?,
+            };
+            match account.execute_v3(calls.clone()).nonce(nonce).send().await {
+                Ok(result) => {
+                    state.next = Some(nonce + Felt::ONE);
+                    state.in_flight.insert(nonce, result.transaction_hash);
+                    return Ok(result.transaction_hash);
+                }
+                Err(err) if is_invalid_nonce(&err) => {
+                    tracing::warn!(
+                        "Nonce {:#x} of account {:#x} was rejected",
+                        nonce,
+                        account.address()
+                    );
+                    state.next = None;
+                    if resynced {
+                        return Err(err.into());
+                    }
+                    resynced = true;
+                }
+                // The nonce was not used, so it is handed out again.
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    fn managed(&self, address: Felt) -> Result<&ManagedAccount> {
+        self.accounts
+            .iter()
+            .find(|managed| managed.account.address() == address)
+            .ok_or_else(|| anyhow!("Unknown swap account {:#x}", address))
+    }
+}
+
+async fn chain_nonce(account: &SwapAccount) -> Result<Felt> {
+    account
+        .get_nonce()
+        .await
+        .context("Failed to get account nonce")
+}
+
+fn is_invalid_nonce<S>(err: &AccountError<S>) -> bool {
+    match err {
+        AccountError::Provider(ProviderError::StarknetError(
+            StarknetError::InvalidTransactionNonce,
+        )) => true,
+        // Fee estimation reports the nonce in the execution error.
+        AccountError::Provider(err) => err
+            .to_string()
+            .to_lowercase()
+            .contains("invalid transaction nonce"),
+        _ => false,
+    }
+}
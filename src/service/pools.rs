@@ -0,0 +1,139 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use starknet::core::types::Felt;
+use uuid::Uuid;
+
+use crate::utils::{
+    ekubo::{sort_tokens, PoolKey, PoolTier},
+    uint256::Uint256,
+};
+
+// An Ekubo pool registered for a pair of tokens.
+#[derive(FromRow, Debug, Clone, Serialize)]
+pub struct Pool {
+    pub id: Uuid,
+    pub token0: String,
+    pub token1: String,
+    pub fee: Uint256,
+    pub tick_spacing: i64,
+    pub extension: String,
+    pub created_at: String,
+}
+
+impl Pool {
+    pub fn key(&self) -> Result<PoolKey> {
+        Ok(PoolKey {
+            token0: Felt::from_hex(&self.token0).context("Invalid token0")?,
+            token1: Felt::from_hex(&self.token1).context("Invalid token1")?,
+            fee: match self.fee.0 {
+                fee if fee.high() == 0 => fee.low(),
+                _ => return Err(anyhow!("Invalid fee")),
+            },
+            tick_spacing: u128::try_from(self.tick_spacing).context("Invalid tick spacing")?,
+            extension: Felt::from_hex(&self.extension).context("Invalid extension")?,
+        })
+    }
+}
+
+// Columns selected into a `Pool`.
+const POOL_COLUMNS: &str = r#"
+    id,
+    token0,
+    token1,
+    fee,
+    tick_spacing,
+    extension,
+    TO_CHAR(created_at, 'YYYY-MM-DD"T"HH24:MI:SSZ') AS created_at
+"#;
+
+// How addresses are written in the `ekubo_pool` table.
+fn address(felt: Felt) -> String {
+    format!("{:#066x}", felt)
+}
+
+pub async fn list(pool: &PgPool) -> Result<Vec<Pool>, sqlx::Error> {
+    sqlx::query_as::<_, Pool>(&format!(
+        "SELECT {} FROM ekubo_pool ORDER BY token0, token1, fee, tick_spacing",
+        POOL_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await
+}
+
+// Pools registered for a pair, in either order.
+pub async fn list_pair(
+    pool: &PgPool,
+    token_a: Felt,
+    token_b: Felt,
+) -> Result<Vec<Pool>, sqlx::Error> {
+    let (token0, token1) = sort_tokens(token_a, token_b);
+    sqlx::query_as::<_, Pool>(&format!(
+        r#"
+        SELECT {}
+        FROM ekubo_pool
+        WHERE token0 = $1 AND token1 = $2
+        ORDER BY fee, tick_spacing
+        "#,
+        POOL_COLUMNS
+    ))
+    .bind(address(token0))
+    .bind(address(token1))
+    .fetch_all(pool)
+    .await
+}
+
+// Register `tier` for a pair. Returns `None` if the pool is already registered.
+pub async fn create(
+    pool: &PgPool,
+    token_a: Felt,
+    token_b: Felt,
+    tier: &PoolTier,
+) -> Result<Option<Pool>, sqlx::Error> {
+    let key = PoolKey::new(token_a, token_b, tier);
+    let tick_spacing =
+        i64::try_from(key.tick_spacing).map_err(|err| sqlx::Error::Encode(Box::new(err)))?;
+    sqlx::query_as::<_, Pool>(&format!(
+        r#"
+        INSERT INTO ekubo_pool (token0, token1, fee, tick_spacing, extension)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT DO NOTHING
+        RETURNING {}
+        "#,
+        POOL_COLUMNS
+    ))
+    .bind(address(key.token0))
+    .bind(address(key.token1))
+    .bind(Uint256::from(key.fee))
+    .bind(tick_spacing)
+    .bind(address(key.extension))
+    .fetch_optional(pool)
+    .await
+}
+
+// Returns false if no pool has this id.
+pub async fn delete(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM ekubo_pool WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+// Pools a swap between two tokens is quoted in: the pools registered for the
+// pair, or else a pool in each of `default_tiers`.
+pub async fn candidates(
+    pool: &PgPool,
+    token_a: Felt,
+    token_b: Felt,
+    default_tiers: &[PoolTier],
+) -> Result<Vec<PoolKey>> {
+    let registered = list_pair(pool, token_a, token_b).await?;
+    if registered.is_empty() {
+        return Ok(default_tiers
+            .iter()
+            .map(|tier| PoolKey::new(token_a, token_b, tier))
+            .collect());
+    }
+    registered.iter().map(Pool::key).collect()
+}
@@ -0,0 +1,97 @@
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+// Prices of one time bucket, in dollars.
+#[derive(FromRow, Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PriceCandle {
+    // Start of the bucket.
+    pub time: String,
+    pub open: String,
+    pub high: String,
+    pub low: String,
+    pub close: String,
+    // Snapshots in the bucket.
+    pub samples: i64,
+}
+
+// Store the price of `token_address` in dollars, such as "2500.5", at
+// `recorded_at`. A second price for the same time is ignored.
+pub async fn record(
+    pool: &PgPool,
+    token_address: &str,
+    price_usd: &str,
+    recorded_at: OffsetDateTime,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO token_price_history (token_address, price_usd, recorded_at)
+        VALUES (LOWER($1), $2::NUMERIC, $3::TIMESTAMPTZ)
+        ON CONFLICT (token_address, recorded_at) DO NOTHING
+        "#,
+    )
+    .bind(token_address)
+    .bind(price_usd)
+    .bind(recorded_at.format(&Rfc3339).unwrap())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+// Open, high, low and close prices of `token_address` recorded from `from` up
+// to `to`, in buckets of `interval_secs` seconds. Buckets without a snapshot
+// are left out.
+pub async fn candles(
+    pool: &PgPool,
+    token_address: &str,
+    from: OffsetDateTime,
+    to: OffsetDateTime,
+    interval_secs: i64,
+) -> Result<Vec<PriceCandle>, sqlx::Error> {
+    sqlx::query_as::<_, PriceCandle>(
+        r#"
+        SELECT
+            TO_CHAR(bucket, 'YYYY-MM-DD"T"HH24:MI:SSZ') AS time,
+            TRIM_SCALE(prices[1])::TEXT AS open,
+            TRIM_SCALE(high)::TEXT AS high,
+            TRIM_SCALE(low)::TEXT AS low,
+            TRIM_SCALE(prices[ARRAY_LENGTH(prices, 1)])::TEXT AS close,
+            samples
+        FROM (
+            SELECT
+                TO_TIMESTAMP(FLOOR(EXTRACT(EPOCH FROM recorded_at) / $4) * $4) AS bucket,
+                ARRAY_AGG(price_usd ORDER BY recorded_at) AS prices,
+                MAX(price_usd) AS high,
+                MIN(price_usd) AS low,
+                COUNT(*) AS samples
+            FROM token_price_history
+            WHERE token_address = LOWER($1)
+              AND recorded_at >= $2::TIMESTAMPTZ
+              AND recorded_at < $3::TIMESTAMPTZ
+            GROUP BY bucket
+        ) AS buckets
+        ORDER BY bucket
+        "#,
+    )
+    .bind(token_address)
+    .bind(from.format(&Rfc3339).unwrap())
+    .bind(to.format(&Rfc3339).unwrap())
+    .bind(interval_secs)
+    .fetch_all(pool)
+    .await
+}
+
+// Parse a bucket length such as "15m", "1h" or "1d" into seconds.
+pub fn parse_interval(interval: &str) -> Option<i64> {
+    let unit = match interval.chars().last()? {
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        _ => return None,
+    };
+    let count = interval[..interval.len() - 1].parse::<i64>().ok()?;
+    match count > 0 {
+        true => count.checked_mul(unit),
+        false => None,
+    }
+}
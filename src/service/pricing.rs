@@ -0,0 +1,234 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt,
+    future::Future,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use num_bigint::BigUint;
+use starknet::core::{types::Felt, utils::cairo_short_string_to_felt};
+
+use super::tokens::Token;
+use crate::{
+    utils::{
+        amount::{to_biguint, to_u256, Amount},
+        starknet::{
+            get_pragma_price_and_decimal, get_token_usd_price_and_decimal, RpcProvider, TokenType,
+        },
+    },
+    Configuration,
+};
+
+// Decimals USD values are kept with.
+pub const USD_DECIMALS: u8 = 8;
+
+// Where the USD price of a token is read from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PriceFeed {
+    // The oracle functions of the router contract.
+    RouterEth,
+    RouterStrk,
+    // A Pragma spot feed, by pair id such as "ETH/USD".
+    Pragma(String),
+}
+
+// A USD price with `decimals` decimals, so $2500.5 with 8 decimals is
+// 250_050_000_000.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsdPrice {
+    pub price: u128,
+    pub decimals: u32,
+}
+
+impl UsdPrice {
+    // One dollar, the price of stable tokens.
+    pub fn one() -> Self {
+        UsdPrice {
+            price: 1,
+            decimals: 0,
+        }
+    }
+
+    // Parse a price in dollars such as "0.55", kept with 18 decimals.
+    pub fn parse(value: &str) -> Option<Self> {
+        let price = Amount::parse(value, 18).ok()?.to_u128()?;
+        Some(UsdPrice {
+            price,
+            decimals: 18,
+        })
+    }
+
+    // Compare two prices by value, whatever their decimals.
+    pub fn cmp_value(&self, other: &UsdPrice) -> Ordering {
+        let scaled = |price: &UsdPrice, decimals: u32| {
+            BigUint::from(price.price) * BigUint::from(10u8).pow(decimals)
+        };
+        scaled(self, other.decimals).cmp(&scaled(other, self.decimals))
+    }
+
+    // The price in dollars, such as "2500.5".
+    pub fn to_decimal_string(&self) -> Option<String> {
+        let decimals = u8::try_from(self.decimals).ok()?;
+        Some(Amount::from_raw(self.price.into(), decimals).to_string())
+    }
+
+    // USD value of `amount`, with `USD_DECIMALS` decimals and rounded down.
+    pub fn value_of(&self, amount: &Amount) -> Option<Amount> {
+        let scale = u32::from(amount.decimals()) + self.decimals;
+        let value =
+            to_biguint(amount.raw()) * self.price * BigUint::from(10u8).pow(USD_DECIMALS.into())
+                / BigUint::from(10u8).pow(scale);
+        Some(Amount::from_raw(to_u256(&value).ok()?, USD_DECIMALS))
+    }
+}
+
+// Something that prices tokens in USD, such as the oracles behind `Pricing`.
+pub trait PriceSource: Send + Sync {
+    // USD price of `token`, or `None` if it has no price.
+    fn usd_price(&self, token: &Token) -> impl Future<Output = Result<Option<UsdPrice>>> + Send;
+}
+
+// Reads USD prices from the oracles and keeps them for `ttl`.
+#[derive(Clone)]
+pub struct Pricing {
+    provider: Arc<RpcProvider>,
+    router_address: Felt,
+    pragma_address: Option<Felt>,
+    ttl: Duration,
+    cache: Arc<Mutex<HashMap<PriceFeed, (Instant, UsdPrice)>>>,
+}
+
+impl Pricing {
+    pub fn new(
+        provider: Arc<RpcProvider>,
+        router_address: Felt,
+        pragma_address: Option<Felt>,
+        ttl: Duration,
+    ) -> Self {
+        Pricing {
+            provider,
+            router_address,
+            pragma_address,
+            ttl,
+            cache: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn from_config(config: &Configuration, provider: Arc<RpcProvider>) -> Self {
+        Pricing::new(
+            provider,
+            config.contract_address,
+            config.pragma_address,
+            Duration::from_secs(config.price_cache_ttl_secs),
+        )
+    }
+
+    // USD price of `token`, or `None` if the token has no price feed. Stable
+    // tokens without a feed are worth a dollar.
+    pub async fn usd_price(&self, token: &Token) -> Result<Option<UsdPrice>> {
+        let feed = match token.price_feed.as_deref().map(PriceFeed::from_str) {
+            Some(Ok(feed)) => feed,
+            Some(Err(err)) => return Err(anyhow!(err)),
+            None if token.is_stable => return Ok(Some(UsdPrice::one())),
+            None => return Ok(None),
+        };
+        self.feed_price(&feed).await.map(Some)
+    }
+
+    // USD value of `amount` of `token`, or `None` if the token has no price feed.
+    pub async fn usd_value(&self, token: &Token, amount: &Amount) -> Result<Option<Amount>> {
+        let price = match self.usd_price(token).await? {
+            Some(price) => price,
+            None => return Ok(None),
+        };
+        price
+            .value_of(amount)
+            .map(Some)
+            .ok_or_else(|| anyhow!("USD value of {} does not fit in 256 bits", amount))
+    }
+
+    async fn feed_price(&self, feed: &PriceFeed) -> Result<UsdPrice> {
+        if let Some((read_at, price)) = self.cache.lock().unwrap().get(feed) {
+            if read_at.elapsed() < self.ttl {
+                return Ok(*price);
+            }
+        }
+
+        let price = self.read_feed(feed).await?;
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(feed.clone(), (Instant::now(), price));
+        Ok(price)
+    }
+
+    async fn read_feed(&self, feed: &PriceFeed) -> Result<UsdPrice> {
+        let (price, decimals) = match feed {
+            PriceFeed::RouterEth | PriceFeed::RouterStrk => {
+                let token = match feed {
+                    PriceFeed::RouterEth => TokenType::ETH,
+                    _ => TokenType::STRK,
+                };
+                let (price, decimals) =
+                    get_token_usd_price_and_decimal(&*self.provider, self.router_address, token)
+                        .await
+                        .with_context(|| format!("Failed to read the {} price", feed))?;
+                (u128::from(price), u32::try_from(decimals)?)
+            }
+            PriceFeed::Pragma(pair_id) => {
+                let oracle = self
+                    .pragma_address
+                    .ok_or_else(|| anyhow!("No Pragma oracle is configured for {}", feed))?;
+                let pair_id = cairo_short_string_to_felt(pair_id)?;
+                get_pragma_price_and_decimal(&*self.provider, oracle, pair_id)
+                    .await
+                    .with_context(|| format!("Failed to read the {} price", feed))?
+            }
+        };
+        match price {
+            0 => Err(anyhow!("The {} price is not available", feed)),
+            _ => Ok(UsdPrice { price, decimals }),
+        }
+    }
+}
+
+impl PriceSource for Pricing {
+    async fn usd_price(&self, token: &Token) -> Result<Option<UsdPrice>> {
+        Pricing::usd_price(self, token).await
+    }
+}
+
+// Parse `eth`, `strk` or `pragma:<pair id>`.
+impl FromStr for PriceFeed {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "eth" => Ok(PriceFeed::RouterEth),
+            None if s == "strk" => Ok(PriceFeed::RouterStrk),
+            Some(("pragma", pair_id))
+                if !pair_id.is_empty() && cairo_short_string_to_felt(pair_id).is_ok() =>
+            {
+                Ok(PriceFeed::Pragma(pair_id.to_string()))
+            }
+            _ => Err(format!(
+                "Invalid price feed: {}. \"eth\", \"strk\" or \"pragma:<pair id>\" currently supported",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for PriceFeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PriceFeed::RouterEth => f.write_str("eth"),
+            PriceFeed::RouterStrk => f.write_str("strk"),
+            PriceFeed::Pragma(pair_id) => write!(f, "pragma:{}", pair_id),
+        }
+    }
+}
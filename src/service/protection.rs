@@ -0,0 +1,294 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgConnection, PgPool};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use uuid::Uuid;
+
+use super::{
+    pricing::UsdPrice,
+    triggers::{PriceTrigger, TriggerCondition},
+};
+use crate::utils::{
+    filters::{Column, Filters},
+    pagination::{Keyset, Page, Paged, Pagination},
+};
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "protection_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ProtectionKind {
+    StopLoss,
+    TakeProfit,
+}
+
+impl ProtectionKind {
+    // A stop-loss fires at or below its price, a take-profit at or above it.
+    pub fn condition(self) -> TriggerCondition {
+        match self {
+            ProtectionKind::StopLoss => TriggerCondition::Below,
+            ProtectionKind::TakeProfit => TriggerCondition::Above,
+        }
+    }
+}
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[sqlx(type_name = "protection_rule_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ProtectionStatus {
+    Active,
+    Fired,
+}
+
+// Swaps a wallet's whole balance of `token` into `to_token` once the USD price
+// of `token` crosses `price_usd`.
+#[derive(FromRow, Debug, Clone, Serialize)]
+pub struct ProtectionRule {
+    pub id: Uuid,
+    pub wallet_address: String,
+    pub token: String,
+    pub to_token: String,
+    pub kind: ProtectionKind,
+    // In dollars, such as "0.55".
+    pub price_usd: String,
+    pub status: ProtectionStatus,
+    pub swap_job_id: Option<Uuid>,
+    pub fired_price_usd: Option<String>,
+    pub fired_at: Option<String>,
+    pub last_error: Option<String>,
+    pub created_at: String,
+    // Creation time with microseconds, the position of the row in listings.
+    #[serde(skip)]
+    pub sort_key: String,
+}
+
+// Columns selected into a `ProtectionRule`.
+const PROTECTION_RULE_COLUMNS: &str = r#"
+    id,
+    wallet_address,
+    token,
+    to_token,
+    kind,
+    TRIM_SCALE(price_usd)::TEXT AS price_usd,
+    status,
+    swap_job_id,
+    TRIM_SCALE(fired_price_usd)::TEXT AS fired_price_usd,
+    TO_CHAR(fired_at, 'YYYY-MM-DD"T"HH24:MI:SSZ') AS fired_at,
+    last_error,
+    TO_CHAR(created_at, 'YYYY-MM-DD"T"HH24:MI:SSZ') AS created_at,
+    TO_CHAR(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"') AS sort_key
+"#;
+
+// Newest first.
+pub const KEYSET: Keyset = Keyset {
+    key: Column::cast("created_at", "TIMESTAMPTZ"),
+    id: Column::cast("id", "UUID"),
+    descending: true,
+};
+
+impl Paged for ProtectionRule {
+    fn position(&self) -> (String, String) {
+        (self.sort_key.clone(), self.id.to_string())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NewProtectionRule {
+    pub wallet_address: String,
+    pub token: String,
+    pub to_token: String,
+    pub kind: ProtectionKind,
+    // In dollars, such as "0.55".
+    pub price_usd: String,
+}
+
+// An active rule of an active subscription, with what its swap needs.
+#[derive(FromRow, Debug, Clone)]
+pub struct WatchedRule {
+    pub id: Uuid,
+    pub wallet_address: String,
+    pub token: String,
+    pub to_token: String,
+    pub kind: ProtectionKind,
+    pub price_usd: String,
+    pub token_decimals: i16,
+    // Swap settings of the wallet's subscription.
+    pub max_slippage_bps: i16,
+    pub venue: String,
+    // Creation time with microseconds, where the next listing resumes.
+    pub sort_key: String,
+}
+
+impl WatchedRule {
+    pub fn trigger(&self) -> Option<PriceTrigger> {
+        Some(PriceTrigger {
+            condition: self.kind.condition(),
+            price: UsdPrice::parse(&self.price_usd)?,
+            expires_at: None,
+        })
+    }
+}
+
+// The token that swaps of `wallet_address` go to, if it has a subscription.
+pub async fn subscription_target(
+    pool: &PgPool,
+    wallet_address: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar::<_, String>(
+        "SELECT to_token FROM swap_subscription WHERE wallet_address = $1",
+    )
+    .bind(wallet_address)
+    .fetch_optional(pool)
+    .await
+}
+
+// Add a rule, or return `None` if the token already has an active rule of the
+// same kind.
+pub async fn create(
+    pool: &PgPool,
+    rule: &NewProtectionRule,
+) -> Result<Option<ProtectionRule>, sqlx::Error> {
+    sqlx::query_as::<_, ProtectionRule>(&format!(
+        r#"
+        INSERT INTO protection_rule (wallet_address, token, to_token, kind, price_usd)
+        VALUES ($1, $2, $3, $4, $5::NUMERIC)
+        ON CONFLICT DO NOTHING
+        RETURNING {}
+        "#,
+        PROTECTION_RULE_COLUMNS
+    ))
+    .bind(&rule.wallet_address)
+    .bind(&rule.token)
+    .bind(&rule.to_token)
+    .bind(rule.kind)
+    .bind(&rule.price_usd)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn list(
+    pool: &PgPool,
+    wallet_address: &str,
+    pagination: &Pagination,
+) -> Result<Page<ProtectionRule>, sqlx::Error> {
+    let mut filters = Filters::new(&format!(
+        "SELECT {} FROM protection_rule",
+        PROTECTION_RULE_COLUMNS
+    ));
+    filters.eq("LOWER(wallet_address)", Some(wallet_address.to_lowercase()));
+    let rows = pagination
+        .apply(filters)
+        .build_query_as::<ProtectionRule>()
+        .fetch_all(pool)
+        .await?;
+    Ok(pagination.page(rows))
+}
+
+pub async fn find_for_update(
+    conn: &mut PgConnection,
+    id: Uuid,
+) -> Result<Option<ProtectionRule>, sqlx::Error> {
+    sqlx::query_as::<_, ProtectionRule>(&format!(
+        "SELECT {} FROM protection_rule WHERE id = $1 FOR UPDATE",
+        PROTECTION_RULE_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(conn)
+    .await
+}
+
+pub async fn delete(conn: &mut PgConnection, id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM protection_rule WHERE id = $1")
+        .bind(id)
+        .execute(conn)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+// Active rules of active subscriptions on active tokens, oldest first,
+// starting after `after`.
+pub async fn list_watched(
+    pool: &PgPool,
+    after: Option<&WatchedRule>,
+    limit: i64,
+) -> Result<Vec<WatchedRule>, sqlx::Error> {
+    sqlx::query_as::<_, WatchedRule>(
+        r#"
+        SELECT
+            r.id,
+            r.wallet_address,
+            r.token,
+            r.to_token,
+            r.kind,
+            TRIM_SCALE(r.price_usd)::TEXT AS price_usd,
+            t.token_decimals,
+            s.max_slippage_bps,
+            s.venue,
+            TO_CHAR(r.created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"') AS sort_key
+        FROM protection_rule r
+        INNER JOIN swap_subscription s ON s.wallet_address = r.wallet_address AND s.is_active
+        INNER JOIN token t ON LOWER(t.contract_address) = LOWER(r.token) AND t.is_active
+        WHERE r.status = 'active'
+          AND ($1::TIMESTAMPTZ IS NULL OR (r.created_at, r.id) > ($1::TIMESTAMPTZ, $2))
+        ORDER BY r.created_at, r.id
+        LIMIT $3
+        "#,
+    )
+    .bind(after.map(|r| r.sort_key.as_str()))
+    .bind(after.map(|r| r.id))
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+// Lock a rule that is still active until the surrounding transaction ends.
+// Returns false if it fired or another evaluator holds it.
+pub async fn lock_active(conn: &mut PgConnection, id: Uuid) -> Result<bool, sqlx::Error> {
+    let locked = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        SELECT id
+        FROM protection_rule
+        WHERE id = $1 AND status = 'active'
+        FOR UPDATE SKIP LOCKED
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(conn)
+    .await?;
+    Ok(locked.is_some())
+}
+
+pub async fn mark_fired(
+    conn: &mut PgConnection,
+    id: Uuid,
+    swap_job_id: Uuid,
+    price_usd: Option<&str>,
+    fired_at: OffsetDateTime,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE protection_rule
+        SET status = 'fired',
+            swap_job_id = $2,
+            fired_price_usd = $3::NUMERIC,
+            fired_at = $4::TIMESTAMPTZ,
+            last_error = NULL
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(swap_job_id)
+    .bind(price_usd)
+    .bind(fired_at.format(&Rfc3339).unwrap())
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+// Note why a rule whose price was met could not fire.
+pub async fn set_error(pool: &PgPool, id: Uuid, error: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE protection_rule SET last_error = $2 WHERE id = $1")
+        .bind(id)
+        .bind(error)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
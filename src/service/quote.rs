@@ -0,0 +1,305 @@
+use std::{future::Future, sync::Arc};
+
+use anyhow::{anyhow, Context, Result};
+use num_bigint::BigUint;
+use serde::Serialize;
+use starknet::{
+    accounts::{Account, ConnectedAccount},
+    core::types::{Call, ExecuteInvocation, Felt, FunctionInvocation, PriceUnit, TransactionTrace},
+    macros::selector,
+};
+
+use super::venues::SwapVenue;
+use crate::{
+    indexer::decode_transfer,
+    utils::{
+        amount::{to_biguint, to_u256, Amount},
+        avnu::Route,
+        ekubo::PoolKey,
+        starknet::{felt_to_u128, SwapAccount},
+        uint256::Uint256,
+    },
+};
+
+// Price impact is measured against a swap of this fraction of the amount.
+const REFERENCE_DIVISOR: u128 = 1000;
+
+const BPS: u32 = 10_000;
+
+// Swap to quote.
+#[derive(Debug, Clone)]
+pub struct QuoteParams {
+    pub from_token: Felt,
+    pub to_token: Felt,
+    pub amount: Amount,
+    // Router contract the swap calls go through.
+    pub contract_address: Felt,
+    // Wallet the swapped tokens are pulled from, with the allowance it gave
+    // the swapping account. `None` swaps the account's own tokens.
+    pub funded_by: Option<Felt>,
+}
+
+impl QuoteParams {
+    // `calls` swapping `amount` from `account`, preceded by the pull of the
+    // tokens from the funding wallet into the account.
+    pub fn funded(&self, account: Felt, amount: Amount, calls: Vec<Call>) -> Vec<Call> {
+        let wallet = match self.funded_by {
+            Some(wallet) => wallet,
+            None => return calls,
+        };
+        let pull = Call {
+            to: self.from_token,
+            selector: selector!("transferFrom"),
+            calldata: [vec![wallet, account], amount.calldata().to_vec()].concat(),
+        };
+        std::iter::once(pull).chain(calls).collect()
+    }
+
+    // Where the swapped tokens go: back to the funding wallet, or else to the
+    // swapping account.
+    pub fn recipient(&self, account: Felt) -> Felt {
+        self.funded_by.unwrap_or(account)
+    }
+}
+
+// Simulated result of a swap on one venue.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VenueQuote {
+    pub venue: String,
+    pub amount_out: Uint256,
+    pub price_impact_bps: Option<u32>,
+    pub gas_fee: Uint256,
+    pub fee_unit: String,
+    // Ekubo pool the swap was quoted in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pool_key: Option<PoolKey>,
+    // AVNU routes the swap was quoted along.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub routes: Option<Vec<Route>>,
+    // The small swap the price impact is measured against.
+    #[serde(skip)]
+    pub reference_in: Uint256,
+    #[serde(skip)]
+    pub reference_out: Uint256,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnavailableVenue {
+    pub venue: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Quote {
+    pub amount_in: Uint256,
+    pub quotes: Vec<VenueQuote>,
+    pub unavailable: Vec<UnavailableVenue>,
+    // Venue with the largest output.
+    pub best_venue: Option<String>,
+}
+
+// What a simulated swap transaction would do.
+struct Simulation {
+    amount_out: Uint256,
+    gas_fee: Uint256,
+    fee_unit: PriceUnit,
+}
+
+// Calls of a swap on a venue, with the pool or routes they go through.
+pub struct VenueSwap {
+    pub calls: Vec<Call>,
+    pub pool_key: Option<PoolKey>,
+    pub routes: Option<Vec<Route>>,
+}
+
+// Quote a swap on each of `venues` by simulating the calls each would send from
+// `account`. Nothing is submitted.
+pub async fn quote(
+    account: &SwapAccount,
+    venues: &[Arc<dyn SwapVenue>],
+    params: &QuoteParams,
+) -> Result<Quote> {
+    let nonce = account
+        .get_nonce()
+        .await
+        .context("Failed to get account nonce")?;
+
+    let mut quotes = vec![];
+    let mut unavailable = vec![];
+    for venue in venues {
+        match venue.quote(account, nonce, params).await {
+            Ok(quote) => quotes.push(quote),
+            Err(err) => unavailable.push(UnavailableVenue {
+                venue: venue.name().to_string(),
+                reason: format!("{:#}", err),
+            }),
+        }
+    }
+    let best_venue = quotes
+        .iter()
+        .max_by_key(|quote| quote.amount_out)
+        .map(|quote| quote.venue.clone());
+
+    Ok(Quote {
+        amount_in: params.amount.raw().into(),
+        quotes,
+        unavailable,
+        best_venue,
+    })
+}
+
+// Quote a swap whose calls on `venue` are built by `swap` for a given amount.
+// The swap is simulated along with a small reference swap its price impact is
+// measured against.
+pub async fn simulate_quote<F, Fut>(
+    account: &SwapAccount,
+    nonce: Felt,
+    params: &QuoteParams,
+    venue: &str,
+    swap: F,
+) -> Result<VenueQuote>
+where
+    F: Fn(Amount) -> Fut,
+    Fut: Future<Output = Result<VenueSwap>>,
+{
+    let main = swap(params.amount).await?;
+    let calls = params.funded(account.address(), params.amount, main.calls);
+    let fee = account
+        .execute_v3(calls.clone())
+        .nonce(nonce)
+        .estimate_fee()
+        .await
+        .context("Failed to estimate fee")?;
+    let gas_price = felt_to_u128(fee.gas_price)
+        .filter(|price| *price > 0)
+        .ok_or_else(|| anyhow!("Invalid gas price"))?;
+    let gas = felt_to_u128(fee.overall_fee)
+        .map(|fee| fee.div_ceil(gas_price))
+        .and_then(|gas| u64::try_from(gas).ok())
+        .ok_or_else(|| anyhow!("Invalid fee estimate"))?;
+
+    let simulated = simulate(account, nonce, gas, gas_price, calls, params).await?;
+
+    // Compare against a small swap to see how far the amount moves the price.
+    let reference_amount = match params.amount.mul_div(1, REFERENCE_DIVISOR)? {
+        amount if amount.is_zero() => Amount::from_raw(1u128.into(), params.amount.decimals()),
+        amount => amount,
+    };
+    let reference = swap(reference_amount).await?;
+    let reference = simulate(
+        account,
+        nonce,
+        gas,
+        gas_price,
+        params.funded(account.address(), reference_amount, reference.calls),
+        params,
+    )
+    .await?;
+
+    Ok(VenueQuote {
+        venue: venue.to_string(),
+        amount_out: simulated.amount_out,
+        price_impact_bps: price_impact_bps(
+            params.amount.raw().into(),
+            simulated.amount_out,
+            reference_amount.raw().into(),
+            reference.amount_out,
+        ),
+        gas_fee: simulated.gas_fee,
+        fee_unit: match simulated.fee_unit {
+            PriceUnit::Wei => "WEI",
+            PriceUnit::Fri => "FRI",
+        }
+        .to_string(),
+        pool_key: main.pool_key,
+        routes: main.routes,
+        reference_in: reference_amount.raw().into(),
+        reference_out: reference.amount_out,
+    })
+}
+
+async fn simulate(
+    account: &SwapAccount,
+    nonce: Felt,
+    gas: u64,
+    gas_price: u128,
+    calls: Vec<Call>,
+    params: &QuoteParams,
+) -> Result<Simulation> {
+    let simulated = account
+        .execute_v3(calls)
+        .nonce(nonce)
+        .gas(gas)
+        .gas_price(gas_price)
+        .simulate(true, true)
+        .await
+        .context("Failed to simulate swap")?;
+
+    let invocation = match simulated.transaction_trace {
+        TransactionTrace::Invoke(trace) => match trace.execute_invocation {
+            ExecuteInvocation::Success(invocation) => invocation,
+            ExecuteInvocation::Reverted(reverted) => {
+                return Err(anyhow!("Swap reverts: {}", reverted.revert_reason))
+            }
+        },
+        _ => return Err(anyhow!("Unexpected transaction trace")),
+    };
+
+    // Venues pay the swap to the account or straight to the funding wallet.
+    let recipients = [account.address(), params.recipient(account.address())];
+    let mut received = BigUint::from(0u8);
+    add_received(&invocation, params.to_token, &recipients, &mut received);
+    Ok(Simulation {
+        amount_out: Uint256(to_u256(&received)?),
+        gas_fee: Uint256(to_u256(&BigUint::from_bytes_be(
+            &simulated.fee_estimation.overall_fee.to_bytes_be(),
+        ))?),
+        fee_unit: simulated.fee_estimation.unit,
+    })
+}
+
+// Add up the `token` transfers to any of `recipients` from elsewhere, emitted
+// anywhere in the call tree.
+fn add_received(
+    invocation: &FunctionInvocation,
+    token: Felt,
+    recipients: &[Felt],
+    total: &mut BigUint,
+) {
+    if invocation.contract_address == token {
+        for event in &invocation.events {
+            if let Some(transfer) = decode_transfer(&event.keys, &event.data) {
+                if recipients.contains(&transfer.to) && !recipients.contains(&transfer.from) {
+                    *total += to_biguint(transfer.amount);
+                }
+            }
+        }
+    }
+    for call in &invocation.calls {
+        add_received(call, token, recipients, total);
+    }
+}
+
+// How much worse the swap's rate is than the reference swap's, in basis points.
+// `None` when the reference swap returns nothing to compare against.
+pub fn price_impact_bps(
+    amount_in: Uint256,
+    amount_out: Uint256,
+    reference_in: Uint256,
+    reference_out: Uint256,
+) -> Option<u32> {
+    let (amount_in, amount_out) = (to_biguint(amount_in.0), to_biguint(amount_out.0));
+    let (reference_in, reference_out) = (to_biguint(reference_in.0), to_biguint(reference_out.0));
+    let zero = BigUint::from(0u8);
+    if reference_in == zero || reference_out == zero {
+        return None;
+    }
+
+    // Output at the reference rate.
+    let expected = reference_out * &amount_in / reference_in;
+    if amount_out >= expected {
+        return Some(0);
+    }
+    let impact = (&expected - amount_out) * BPS / expected;
+    Some(u32::try_from(impact).unwrap_or(BPS))
+}
@@ -0,0 +1,76 @@
+use starknet::core::types::U256;
+use thiserror::Error;
+
+use super::quote::VenueQuote;
+use crate::utils::{
+    amount::{to_biguint, to_u256},
+    ekubo,
+    uint256::Uint256,
+};
+
+// Slippage of subscriptions that do not set one.
+pub const DEFAULT_MAX_SLIPPAGE_BPS: i16 = 50;
+
+// Largest slippage a subscription can set.
+pub const MAX_SLIPPAGE_BPS: i16 = 10_000;
+
+const BPS: u32 = 10_000;
+
+// A swap that is not sent because it would trade at a worse price than allowed.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{0}")]
+pub struct SwapAborted(pub String);
+
+// Bounds a swap is sent with so that it never trades beyond the allowed slippage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlippageLimits {
+    // Least output an AVNU swap accepts.
+    pub min_amount_out: Uint256,
+    pub max_slippage_bps: u16,
+    reference_in: Uint256,
+    reference_out: Uint256,
+}
+
+impl SlippageLimits {
+    // Limits for a swap with the output and price of `quote`. Aborts the swap if
+    // its quoted price impact already exceeds `max_slippage_bps`.
+    pub fn from_quote(quote: &VenueQuote, max_slippage_bps: u16) -> Result<Self, SwapAborted> {
+        if quote.amount_out == Uint256::zero() {
+            return Err(SwapAborted("Quoted swap returns nothing".to_string()));
+        }
+        let price_impact_bps = quote.price_impact_bps.ok_or_else(|| {
+            SwapAborted("Quoted swap has no reference price to measure slippage".to_string())
+        })?;
+        if price_impact_bps > u32::from(max_slippage_bps) {
+            return Err(SwapAborted(format!(
+                "Quoted price impact of {} bps exceeds the maximum slippage of {} bps",
+                price_impact_bps, max_slippage_bps
+            )));
+        }
+
+        Ok(SlippageLimits {
+            min_amount_out: min_amount_out(quote.amount_out, max_slippage_bps),
+            max_slippage_bps,
+            reference_in: quote.reference_in,
+            reference_out: quote.reference_out,
+        })
+    }
+
+    // Ekubo sqrt ratio limit of the swap, see `ekubo::sqrt_ratio_limit`.
+    pub fn sqrt_ratio_limit(&self, is_token1: bool) -> U256 {
+        ekubo::sqrt_ratio_limit(
+            self.reference_in,
+            self.reference_out,
+            self.max_slippage_bps,
+            is_token1,
+        )
+    }
+}
+
+// `expected` less `max_slippage_bps`, rounded down.
+pub fn min_amount_out(expected: Uint256, max_slippage_bps: u16) -> Uint256 {
+    let kept = BPS.saturating_sub(u32::from(max_slippage_bps));
+    let amount = to_biguint(expected.0) * kept / BPS;
+    // Never more than `expected`, so it fits in a u256.
+    Uint256(to_u256(&amount).unwrap())
+}
@@ -0,0 +1,418 @@
+use std::time::Duration;
+
+use serde::Serialize;
+use sqlx::{FromRow, PgConnection};
+use starknet::core::types::Felt;
+use uuid::Uuid;
+
+use crate::utils::amount::Amount;
+
+// Delay before the first retry, doubled after every failed attempt.
+const BASE_BACKOFF: Duration = Duration::from_secs(5);
+
+// Longest delay between two attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(15 * 60);
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[sqlx(type_name = "swap_job_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SwapJobStatus {
+    Pending,
+    Sending,
+    Submitted,
+    Confirmed,
+    Failed,
+    Dead,
+    Aborted,
+    Unknown,
+}
+
+#[derive(FromRow, Debug, Clone, Serialize)]
+pub struct SwapJob {
+    pub id: Uuid,
+    pub wallet_address: String,
+    pub from_token: String,
+    pub to_token: String,
+    pub percentage: i16,
+    pub amount: String,
+    pub from_decimals: i16,
+    pub max_slippage_bps: i16,
+    pub venue: String,
+    pub pull_from_wallet: bool,
+    pub status: SwapJobStatus,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub last_error: Option<String>,
+    pub transaction_hash: Option<String>,
+    pub created_at: String,
+}
+
+// Columns selected into a `SwapJob`.
+const SWAP_JOB_COLUMNS: &str = r#"
+    id,
+    wallet_address,
+    from_token,
+    to_token,
+    percentage,
+    amount::TEXT AS amount,
+    from_decimals,
+    max_slippage_bps,
+    venue,
+    pull_from_wallet,
+    status,
+    attempts,
+    max_attempts,
+    last_error,
+    transaction_hash,
+    TO_CHAR(created_at, 'YYYY-MM-DD"T"HH24:MI:SSZ') AS created_at
+"#;
+
+#[derive(Debug, Clone)]
+pub struct NewSwapJob {
+    pub wallet_address: String,
+    pub from_token: String,
+    pub to_token: String,
+    pub percentage: i16,
+    pub amount: Amount,
+    pub max_slippage_bps: i16,
+    // `BEST_PRICE` or the name of a venue.
+    pub venue: String,
+    // Swap tokens pulled from the wallet with the allowance it gave the swap
+    // account, rather than the swap account's own.
+    pub pull_from_wallet: bool,
+    pub max_attempts: i32,
+}
+
+impl SwapJob {
+    pub fn amount(&self) -> Option<Amount> {
+        let decimals = u8::try_from(self.from_decimals).ok()?;
+        Amount::from_base_units(&self.amount, decimals).ok()
+    }
+}
+
+// Add a swap to the queue and return its id.
+pub async fn enqueue(conn: &mut PgConnection, job: &NewSwapJob) -> Result<Uuid, sqlx::Error> {
+    let (id,) = sqlx::query_as::<_, (Uuid,)>(
+        r#"
+        INSERT INTO swap_jobs
+        (wallet_address, from_token, to_token, percentage, amount, from_decimals,
+         max_slippage_bps, venue, pull_from_wallet, max_attempts)
+        VALUES ($1, $2, $3, $4, $5::NUMERIC, $6, $7, $8, $9, $10)
+        RETURNING id
+        "#,
+    )
+    .bind(&job.wallet_address)
+    .bind(&job.from_token)
+    .bind(&job.to_token)
+    .bind(job.percentage)
+    .bind(job.amount.base_units())
+    .bind(i16::from(job.amount.decimals()))
+    .bind(job.max_slippage_bps)
+    .bind(&job.venue)
+    .bind(job.pull_from_wallet)
+    .bind(job.max_attempts)
+    .fetch_one(conn)
+    .await?;
+    Ok(id)
+}
+
+pub async fn find(conn: &mut PgConnection, id: Uuid) -> Result<Option<SwapJob>, sqlx::Error> {
+    sqlx::query_as::<_, SwapJob>(&format!(
+        "SELECT {} FROM swap_jobs WHERE id = $1",
+        SWAP_JOB_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(conn)
+    .await
+}
+
+// Claim the next job that has been due for at least `window`, along with up to
+// `max_size - 1` more due jobs swapping the same pair, and mark them sending.
+// Once the surrounding transaction commits, the jobs are no longer due and no
+// other worker picks them up, whatever happens to their swap.
+pub async fn claim_batch(
+    conn: &mut PgConnection,
+    max_size: i64,
+    window: Duration,
+) -> Result<Vec<SwapJob>, sqlx::Error> {
+    let first = sqlx::query_as::<_, SwapJob>(&format!(
+        r#"
+        SELECT {}
+        FROM swap_jobs
+        WHERE status IN ('pending', 'failed')
+          AND next_attempt_at <= NOW() - MAKE_INTERVAL(secs => $1)
+        ORDER BY next_attempt_at
+        LIMIT 1
+        FOR UPDATE SKIP LOCKED
+        "#,
+        SWAP_JOB_COLUMNS
+    ))
+    .bind(window.as_secs_f64())
+    .fetch_optional(&mut *conn)
+    .await?;
+    let first = match first {
+        Some(job) => job,
+        None => return Ok(vec![]),
+    };
+    let rest = match max_size > 1 {
+        true => claim_pair(conn, &first, max_size - 1).await?,
+        false => vec![],
+    };
+
+    let mut jobs: Vec<SwapJob> = std::iter::once(first).chain(rest).collect();
+    let ids: Vec<Uuid> = jobs.iter().map(|job| job.id).collect();
+    // A hash left from an earlier attempt would be mistaken for this send's.
+    sqlx::query(
+        r#"
+        UPDATE swap_jobs
+        SET status = 'sending', sent_at = NOW(), transaction_hash = NULL
+        WHERE id = ANY($1)
+        "#,
+    )
+    .bind(&ids)
+    .execute(&mut *conn)
+    .await?;
+    for job in &mut jobs {
+        job.status = SwapJobStatus::Sending;
+    }
+    Ok(jobs)
+}
+
+// Lock up to `limit` more due jobs swapping the pair of `first`.
+async fn claim_pair(
+    conn: &mut PgConnection,
+    first: &SwapJob,
+    limit: i64,
+) -> Result<Vec<SwapJob>, sqlx::Error> {
+    sqlx::query_as::<_, SwapJob>(&format!(
+        r#"
+        SELECT {}
+        FROM swap_jobs
+        WHERE status IN ('pending', 'failed')
+          AND next_attempt_at <= NOW()
+          AND LOWER(from_token) = LOWER($1)
+          AND LOWER(to_token) = LOWER($2)
+          AND id <> $3
+        ORDER BY next_attempt_at
+        LIMIT $4
+        FOR UPDATE SKIP LOCKED
+        "#,
+        SWAP_JOB_COLUMNS
+    ))
+    .bind(&first.from_token)
+    .bind(&first.to_token)
+    .bind(first.id)
+    .bind(limit)
+    .fetch_all(conn)
+    .await
+}
+
+pub async fn mark_submitted(
+    conn: &mut PgConnection,
+    id: Uuid,
+    transaction_hash: Felt,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE swap_jobs
+        SET status = 'submitted',
+            attempts = attempts + 1,
+            transaction_hash = $2,
+            last_error = NULL,
+            submitted_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(format!("{:#x}", transaction_hash))
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+// Keep the transaction a claimed job was sent in, ahead of its result, so that
+// the job can still be tracked if the result is never recorded.
+pub async fn mark_sent(
+    conn: &mut PgConnection,
+    id: Uuid,
+    transaction_hash: Felt,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE swap_jobs SET transaction_hash = $2 WHERE id = $1 AND status = 'sending'")
+        .bind(id)
+        .bind(format!("{:#x}", transaction_hash))
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+// Settle the jobs left sending for more than `timeout`, e.g. by a worker that
+// stopped before recording their results. Jobs whose transaction is known are
+// submitted for their receipt to be tracked. The others may or may not have been
+// sent, so they are marked unknown rather than sent again. Returns the settled
+// jobs and their new status.
+pub async fn recover_sending(
+    conn: &mut PgConnection,
+    timeout: Duration,
+) -> Result<Vec<(Uuid, SwapJobStatus)>, sqlx::Error> {
+    sqlx::query_as::<_, (Uuid, SwapJobStatus)>(
+        r#"
+        UPDATE swap_jobs
+        SET status = CASE
+                WHEN transaction_hash IS NULL THEN 'unknown'
+                ELSE 'submitted'
+            END::swap_job_status,
+            attempts = CASE WHEN transaction_hash IS NULL THEN attempts ELSE attempts + 1 END,
+            submitted_at = sent_at,
+            last_error = CASE
+                WHEN transaction_hash IS NULL THEN 'Claimed for sending, but its outcome was never recorded'
+            END
+        WHERE status = 'sending' AND sent_at < NOW() - MAKE_INTERVAL(secs => $1)
+        RETURNING id, status
+        "#,
+    )
+    .bind(timeout.as_secs_f64())
+    .fetch_all(conn)
+    .await
+}
+
+// Record a failed attempt. The job is retried after an exponential backoff until
+// it runs out of attempts, after which it is marked dead.
+pub async fn mark_failed(
+    conn: &mut PgConnection,
+    job: &SwapJob,
+    error: &str,
+) -> Result<SwapJobStatus, sqlx::Error> {
+    schedule_retry(conn, job, job.attempts + 1, error).await
+}
+
+// Record that the submitted transaction of a job reverted. The attempt was already
+// counted when the transaction was sent.
+pub async fn mark_reverted(
+    conn: &mut PgConnection,
+    job: &SwapJob,
+    revert_reason: &str,
+) -> Result<SwapJobStatus, sqlx::Error> {
+    schedule_retry(conn, job, job.attempts, revert_reason).await
+}
+
+// Give up on a job without sending it, e.g. because the price moved too much.
+pub async fn mark_aborted(
+    conn: &mut PgConnection,
+    id: Uuid,
+    reason: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE swap_jobs SET status = 'aborted', last_error = $2 WHERE id = $1")
+        .bind(id)
+        .bind(reason)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+pub async fn mark_confirmed(conn: &mut PgConnection, id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE swap_jobs SET status = 'confirmed' WHERE id = $1")
+        .bind(id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+// Jobs whose transaction has been sent but not yet seen on chain, oldest first.
+pub async fn list_submitted(
+    conn: &mut PgConnection,
+    limit: i64,
+) -> Result<Vec<SwapJob>, sqlx::Error> {
+    sqlx::query_as::<_, SwapJob>(&format!(
+        r#"
+        SELECT {}
+        FROM swap_jobs
+        WHERE status = 'submitted'
+        ORDER BY created_at
+        LIMIT $1
+        "#,
+        SWAP_JOB_COLUMNS
+    ))
+    .bind(limit)
+    .fetch_all(conn)
+    .await
+}
+
+// Submitted jobs sent in the transaction `transaction_hash`.
+pub async fn list_by_transaction(
+    conn: &mut PgConnection,
+    transaction_hash: &str,
+) -> Result<Vec<SwapJob>, sqlx::Error> {
+    sqlx::query_as::<_, SwapJob>(&format!(
+        r#"
+        SELECT {}
+        FROM swap_jobs
+        WHERE status = 'submitted' AND transaction_hash = $1
+        ORDER BY created_at, id
+        "#,
+        SWAP_JOB_COLUMNS
+    ))
+    .bind(transaction_hash)
+    .fetch_all(conn)
+    .await
+}
+
+// Submitted jobs sent in the transaction `transaction_hash` more than `timeout`
+// ago.
+pub async fn list_expired(
+    conn: &mut PgConnection,
+    transaction_hash: &str,
+    timeout: Duration,
+) -> Result<Vec<SwapJob>, sqlx::Error> {
+    sqlx::query_as::<_, SwapJob>(&format!(
+        r#"
+        SELECT {}
+        FROM swap_jobs
+        WHERE status = 'submitted'
+          AND transaction_hash = $1
+          AND submitted_at < NOW() - MAKE_INTERVAL(secs => $2)
+        ORDER BY created_at, id
+        "#,
+        SWAP_JOB_COLUMNS
+    ))
+    .bind(transaction_hash)
+    .bind(timeout.as_secs_f64())
+    .fetch_all(conn)
+    .await
+}
+
+async fn schedule_retry(
+    conn: &mut PgConnection,
+    job: &SwapJob,
+    attempts: i32,
+    error: &str,
+) -> Result<SwapJobStatus, sqlx::Error> {
+    let status = match attempts >= job.max_attempts {
+        true => SwapJobStatus::Dead,
+        false => SwapJobStatus::Failed,
+    };
+
+    sqlx::query(
+        r#"
+        UPDATE swap_jobs
+        SET status = $2,
+            attempts = $3,
+            last_error = $4,
+            next_attempt_at = NOW() + MAKE_INTERVAL(secs => $5)
+        WHERE id = $1
+        "#,
+    )
+    .bind(job.id)
+    .bind(status)
+    .bind(attempts)
+    .bind(error)
+    .bind(backoff(attempts as u32).as_secs_f64())
+    .execute(conn)
+    .await?;
+    Ok(status)
+}
+
+// Delay before retrying a job that has failed `attempts` times.
+pub fn backoff(attempts: u32) -> Duration {
+    let exponent = attempts.saturating_sub(1).min(16);
+    BASE_BACKOFF.saturating_mul(1 << exponent).min(MAX_BACKOFF)
+}
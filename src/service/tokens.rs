@@ -0,0 +1,250 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use starknet::{
+    core::types::{BlockId, BlockTag, Felt, FunctionCall},
+    macros::selector,
+    providers::Provider,
+};
+
+use crate::utils::starknet::felt_to_u128;
+
+// A token registered for swapping.
+#[derive(FromRow, Debug, Clone, Serialize)]
+pub struct Token {
+    pub contract_address: String,
+    pub token_name: String,
+    pub token_symbol: String,
+    pub token_decimals: i16,
+    pub is_stable: bool,
+    pub is_active: bool,
+    // Where the USD price is read from, see `PriceFeed`.
+    pub price_feed: Option<String>,
+    pub created_at: String,
+}
+
+// Columns selected into a `Token`.
+const TOKEN_COLUMNS: &str = r#"
+    contract_address,
+    token_name,
+    token_symbol,
+    token_decimals,
+    is_stable,
+    is_active,
+    price_feed,
+    TO_CHAR(created_at, 'YYYY-MM-DD"T"HH24:MI:SSZ') AS created_at
+"#;
+
+#[derive(Debug, Clone)]
+pub struct NewToken {
+    pub contract_address: String,
+    pub token_name: String,
+    pub token_symbol: String,
+    pub token_decimals: i16,
+    pub is_stable: bool,
+    pub price_feed: Option<String>,
+}
+
+// Fields to change on a token. `None` leaves the field as it is.
+#[derive(Debug, Clone, Default)]
+pub struct TokenUpdate {
+    pub token_name: Option<String>,
+    pub token_symbol: Option<String>,
+    pub token_decimals: Option<i16>,
+    pub is_stable: Option<bool>,
+    pub is_active: Option<bool>,
+    pub price_feed: Option<String>,
+}
+
+// ERC20 metadata read from the token contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+pub async fn list(pool: &PgPool, include_disabled: bool) -> Result<Vec<Token>, sqlx::Error> {
+    sqlx::query_as::<_, Token>(&format!(
+        "SELECT {} FROM token WHERE is_active OR $1 ORDER BY token_symbol, contract_address",
+        TOKEN_COLUMNS
+    ))
+    .bind(include_disabled)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get(pool: &PgPool, contract_address: &str) -> Result<Option<Token>, sqlx::Error> {
+    sqlx::query_as::<_, Token>(&format!(
+        "SELECT {} FROM token WHERE LOWER(contract_address) = LOWER($1)",
+        TOKEN_COLUMNS
+    ))
+    .bind(contract_address)
+    .fetch_optional(pool)
+    .await
+}
+
+// Register a token. Returns `None` if it is already registered.
+pub async fn create(pool: &PgPool, token: &NewToken) -> Result<Option<Token>, sqlx::Error> {
+    sqlx::query_as::<_, Token>(&format!(
+        r#"
+        INSERT INTO token
+        (contract_address, token_name, token_symbol, token_decimals, is_stable, price_feed)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT DO NOTHING
+        RETURNING {}
+        "#,
+        TOKEN_COLUMNS
+    ))
+    .bind(&token.contract_address)
+    .bind(&token.token_name)
+    .bind(&token.token_symbol)
+    .bind(token.token_decimals)
+    .bind(token.is_stable)
+    .bind(&token.price_feed)
+    .fetch_optional(pool)
+    .await
+}
+
+// Apply `update` to a token. Returns `None` if the token is not registered.
+pub async fn update(
+    pool: &PgPool,
+    contract_address: &str,
+    update: &TokenUpdate,
+) -> Result<Option<Token>, sqlx::Error> {
+    sqlx::query_as::<_, Token>(&format!(
+        r#"
+        UPDATE token
+        SET token_name = COALESCE($2, token_name),
+            token_symbol = COALESCE($3, token_symbol),
+            token_decimals = COALESCE($4, token_decimals),
+            is_stable = COALESCE($5, is_stable),
+            is_active = COALESCE($6, is_active),
+            price_feed = COALESCE($7, price_feed)
+        WHERE LOWER(contract_address) = LOWER($1)
+        RETURNING {}
+        "#,
+        TOKEN_COLUMNS
+    ))
+    .bind(contract_address)
+    .bind(&update.token_name)
+    .bind(&update.token_symbol)
+    .bind(update.token_decimals)
+    .bind(update.is_stable)
+    .bind(update.is_active)
+    .bind(&update.price_feed)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn disable(pool: &PgPool, contract_address: &str) -> Result<Option<Token>, sqlx::Error> {
+    let update = TokenUpdate {
+        is_active: Some(false),
+        ..Default::default()
+    };
+    self::update(pool, contract_address, &update).await
+}
+
+// The addresses in `addresses` that are not registered as active tokens.
+pub async fn unregistered(pool: &PgPool, addresses: &[&str]) -> Result<Vec<String>, sqlx::Error> {
+    let lowered: Vec<String> = addresses.iter().map(|a| a.to_lowercase()).collect();
+    let registered = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT LOWER(contract_address)
+        FROM token
+        WHERE is_active AND LOWER(contract_address) = ANY($1)
+        "#,
+    )
+    .bind(&lowered)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(addresses
+        .iter()
+        .zip(lowered)
+        .filter(|(_, lower)| !registered.contains(lower))
+        .map(|(address, _)| address.to_string())
+        .collect())
+}
+
+// Read `name`, `symbol` and `decimals` from an ERC20 contract.
+pub async fn fetch_metadata<P>(provider: &P, contract_address: Felt) -> Result<TokenMetadata>
+where
+    P: Provider + Sync,
+{
+    let call = |entry_point_selector| {
+        provider.call(
+            FunctionCall {
+                contract_address,
+                entry_point_selector,
+                calldata: vec![],
+            },
+            BlockId::Tag(BlockTag::Latest),
+        )
+    };
+
+    let name = call(selector!("name"))
+        .await
+        .context("Failed to call name")?;
+    let symbol = call(selector!("symbol"))
+        .await
+        .context("Failed to call symbol")?;
+    let decimals = call(selector!("decimals"))
+        .await
+        .context("Failed to call decimals")?;
+
+    let decimals = decimals
+        .first()
+        .copied()
+        .and_then(felt_to_u128)
+        .and_then(|decimals| u8::try_from(decimals).ok())
+        .ok_or_else(|| anyhow!("Invalid decimals"))?;
+
+    Ok(TokenMetadata {
+        name: decode_string(&name).ok_or_else(|| anyhow!("Invalid name"))?,
+        symbol: decode_string(&symbol).ok_or_else(|| anyhow!("Invalid symbol"))?,
+        decimals,
+    })
+}
+
+// Decode a string returned by a contract call. Legacy tokens return a single
+// short string felt, Cairo 1 tokens return a serialized `ByteArray`.
+fn decode_string(result: &[Felt]) -> Option<String> {
+    let bytes = match result {
+        [short_string] => felt_bytes(*short_string, 31)?,
+        [len, rest @ ..] => {
+            let len = usize::try_from(felt_to_u128(*len)?).ok()?;
+            let (words, pending) = (rest.get(..len)?, rest.get(len..)?);
+            let (pending_word, pending_len) = match pending {
+                [word, pending_len] => (*word, felt_to_u128(*pending_len)?),
+                _ => return None,
+            };
+            let pending_len = usize::try_from(pending_len).ok()?;
+
+            let mut bytes = Vec::new();
+            for word in words {
+                bytes.extend(felt_bytes(*word, 31)?);
+            }
+            bytes.extend(felt_bytes(pending_word, pending_len)?);
+            bytes
+        }
+        [] => return None,
+    };
+
+    // Short strings are left padded with zeros.
+    let start = match result.len() {
+        1 => bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len()),
+        _ => 0,
+    };
+    String::from_utf8(bytes[start..].to_vec()).ok()
+}
+
+// The last `len` bytes of a felt, or `None` if it holds more than `len` bytes.
+fn felt_bytes(felt: Felt, len: usize) -> Option<Vec<u8>> {
+    let bytes = felt.to_bytes_be();
+    let (head, tail) = bytes.split_at(bytes.len().checked_sub(len)?);
+    match head.iter().all(|b| *b == 0) {
+        true => Some(tail.to_vec()),
+        false => None,
+    }
+}
@@ -1,4 +1,46 @@
-use sqlx::PgPool;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use starknet::core::types::U256;
+use uuid::Uuid;
+
+use super::{
+    swap_jobs::{NewSwapJob, SwapJob},
+    tokens,
+};
+use crate::utils::{amount::Amount, uint256::Uint256};
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "transaction_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionStatus {
+    #[sqlx(rename = "accepted_on_l2")]
+    #[serde(rename = "accepted_on_l2")]
+    AcceptedOnL2,
+    #[sqlx(rename = "accepted_on_l1")]
+    #[serde(rename = "accepted_on_l1")]
+    AcceptedOnL1,
+    Reverted,
+}
+
+// What a swap transaction did on chain, read from its receipt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionOutcome {
+    pub tx_hash: String,
+    pub status: TransactionStatus,
+    pub block_number: u64,
+    pub revert_reason: Option<String>,
+    pub actual_fee: String,
+    pub fee_unit: String,
+    pub amount_from: U256,
+    pub amount_to: U256,
+}
+
+// USD values of a swap's amounts when it executed, if its tokens are priced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UsdValues {
+    pub amount_from: Option<Amount>,
+    pub amount_to: Option<Amount>,
+}
 
 #[derive(Debug, Clone)]
 pub struct TransactionLog {
@@ -6,8 +48,8 @@ pub struct TransactionLog {
     pub from_token: String,
     pub to_token: String,
     pub percentage: u16,
-    pub amount_from: u64,
-    pub amount_to: u64,
+    pub amount_from: Amount,
+    pub amount_to: Amount,
 }
 
 impl TransactionLog {
@@ -16,8 +58,8 @@ impl TransactionLog {
         from_token: &str,
         to_token: &str,
         percentage: u16,
-        amount_from: u64,
-        amount_to: u64,
+        amount_from: Amount,
+        amount_to: Amount,
     ) -> Self {
         Self {
             wallet_address: wallet_address.to_string(),
@@ -36,8 +78,8 @@ impl TransactionLog {
         self.validate_address(&self.to_token)
             .map_err(|_| "Invalid  to_token")?;
         self.validate_percentage(self.percentage)?;
-        self.validate_amount(self.amount_to)?;
-        self.validate_amount(self.amount_from)?;
+        self.validate_amount(&self.amount_to)?;
+        self.validate_amount(&self.amount_from)?;
         Ok(())
     }
 
@@ -58,15 +100,13 @@ impl TransactionLog {
         }
     }
 
-    fn validate_amount(&self, _amount: u64) -> Result<(), String> {
+    fn validate_amount(&self, _amount: &Amount) -> Result<(), String> {
         Ok(())
     }
 
     async fn save(&mut self, db: &PgPool) -> Result<(), String> {
         self.validate().map_err(|_| "Transaction log is invalid")?;
         let i_percentage = self.percentage as i16;
-        let i_amount_from = self.amount_from as i64;
-        let i_amount_to = self.amount_to as i64;
         sqlx::query(
             r#"INSERT INTO transactions_log (wallet_address, from_token, to_token, percentage, amount_from, amount_to)
         VALUES ($1, $2, $3, $4, $5, $6)"#
@@ -75,8 +115,8 @@ impl TransactionLog {
         .bind(&self.from_token)
         .bind(&self.to_token)
         .bind(i_percentage)
-        .bind(i_amount_from)
-        .bind(i_amount_to)
+        .bind(Uint256::from(self.amount_from.raw()))
+        .bind(Uint256::from(self.amount_to.raw()))
         .execute(db)
         .await.expect("Failed to save transaction log to db");
         Ok(())
@@ -88,18 +128,177 @@ pub async fn log_transaction(
     from_token: &str,
     to_token: &str,
     percentage: u16,
-    amount_from: u64,
-    amount_to: u64,
+    amount_from: U256,
+    amount_to: U256,
     db: &PgPool,
 ) -> Result<TransactionLog, String> {
+    let from_decimals = token_decimals(db, from_token).await?;
+    let to_decimals = token_decimals(db, to_token).await?;
     let mut tx = TransactionLog::new(
         wallet_address,
         from_token,
         to_token,
         percentage,
-        amount_from,
-        amount_to,
+        Amount::from_raw(amount_from, from_decimals),
+        Amount::from_raw(amount_to, to_decimals),
     );
     tx.save(db).await?;
     Ok(tx)
 }
+
+// Decimals of a registered token. Unregistered tokens cannot be logged.
+async fn token_decimals(db: &PgPool, token: &str) -> Result<u8, String> {
+    match tokens::get(db, token).await {
+        Ok(Some(token)) if token.is_active => Ok(token.token_decimals as u8),
+        Ok(_) => Err(format!("Unregistered token: {}", token)),
+        Err(_) => Err("Failed to look up tokens".to_string()),
+    }
+}
+
+// Log the firing of a protection rule, which queued `job` as `swap_job_id`.
+// The row has no transaction until the outcome of the swap is recorded.
+pub async fn record_firing(
+    conn: &mut PgConnection,
+    protection_rule_id: Uuid,
+    swap_job_id: Uuid,
+    job: &NewSwapJob,
+    amount_from_usd: Option<Amount>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO transactions_log
+        (wallet_address, from_token, to_token, percentage, amount_from, amount_to,
+         swap_job_id, protection_rule_id, amount_from_usd)
+        VALUES ($1, $2, $3, $4, $5, 0, $6, $7, $8::NUMERIC)
+        "#,
+    )
+    .bind(&job.wallet_address)
+    .bind(&job.from_token)
+    .bind(&job.to_token)
+    .bind(job.percentage)
+    .bind(Uint256::from(job.amount.raw()))
+    .bind(swap_job_id)
+    .bind(protection_rule_id)
+    .bind(amount_from_usd.map(|value| value.to_string()))
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+// Write the on-chain outcome of a swap job, or update it if the job's
+// transaction was already logged. The first outcome of a job logged when a
+// protection rule fired completes that row.
+pub async fn record_outcome(
+    conn: &mut PgConnection,
+    job: &SwapJob,
+    outcome: &TransactionOutcome,
+    usd: &UsdValues,
+) -> Result<(), sqlx::Error> {
+    let fired = sqlx::query(
+        r#"
+        UPDATE transactions_log
+        SET amount_from = $2,
+            amount_to = $3,
+            tx_hash = $4,
+            status = $5,
+            block_number = $6,
+            revert_reason = $7,
+            actual_fee = $8::NUMERIC,
+            fee_unit = $9,
+            amount_from_usd = $10::NUMERIC,
+            amount_to_usd = $11::NUMERIC
+        WHERE swap_job_id = $1 AND tx_hash IS NULL
+        "#,
+    )
+    .bind(job.id)
+    .bind(Uint256::from(outcome.amount_from))
+    .bind(Uint256::from(outcome.amount_to))
+    .bind(&outcome.tx_hash)
+    .bind(outcome.status)
+    .bind(outcome.block_number as i64)
+    .bind(&outcome.revert_reason)
+    .bind(&outcome.actual_fee)
+    .bind(&outcome.fee_unit)
+    .bind(usd.amount_from.map(|value| value.to_string()))
+    .bind(usd.amount_to.map(|value| value.to_string()))
+    .execute(&mut *conn)
+    .await?;
+    if fired.rows_affected() > 0 {
+        return Ok(());
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO transactions_log
+        (wallet_address, from_token, to_token, percentage, amount_from, amount_to,
+         tx_hash, status, block_number, revert_reason, actual_fee, fee_unit, swap_job_id,
+         amount_from_usd, amount_to_usd, protection_rule_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11::NUMERIC, $12, $13,
+                $14::NUMERIC, $15::NUMERIC,
+                (SELECT id FROM protection_rule WHERE swap_job_id = $13))
+        ON CONFLICT (swap_job_id, tx_hash) DO UPDATE
+        SET amount_from = EXCLUDED.amount_from,
+            amount_to = EXCLUDED.amount_to,
+            status = EXCLUDED.status,
+            block_number = EXCLUDED.block_number,
+            revert_reason = EXCLUDED.revert_reason,
+            actual_fee = EXCLUDED.actual_fee,
+            fee_unit = EXCLUDED.fee_unit,
+            amount_from_usd = EXCLUDED.amount_from_usd,
+            amount_to_usd = EXCLUDED.amount_to_usd
+        "#,
+    )
+    .bind(&job.wallet_address)
+    .bind(&job.from_token)
+    .bind(&job.to_token)
+    .bind(job.percentage)
+    .bind(Uint256::from(outcome.amount_from))
+    .bind(Uint256::from(outcome.amount_to))
+    .bind(&outcome.tx_hash)
+    .bind(outcome.status)
+    .bind(outcome.block_number as i64)
+    .bind(&outcome.revert_reason)
+    .bind(&outcome.actual_fee)
+    .bind(&outcome.fee_unit)
+    .bind(job.id)
+    .bind(usd.amount_from.map(|value| value.to_string()))
+    .bind(usd.amount_to.map(|value| value.to_string()))
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+// Hashes of logged transactions that are accepted on L2 but not yet on L1.
+pub async fn list_unproven(
+    conn: &mut PgConnection,
+    limit: i64,
+) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT tx_hash
+        FROM transactions_log
+        WHERE status = 'accepted_on_l2' AND tx_hash IS NOT NULL
+        GROUP BY tx_hash
+        ORDER BY MIN(created_at)
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(conn)
+    .await
+}
+
+pub async fn update_status(
+    conn: &mut PgConnection,
+    tx_hash: &str,
+    status: TransactionStatus,
+    block_number: u64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE transactions_log SET status = $2, block_number = $3 WHERE tx_hash = $1")
+        .bind(tx_hash)
+        .bind(status)
+        .bind(block_number as i64)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,381 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgConnection, PgPool};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use uuid::Uuid;
+
+use super::{pricing::UsdPrice, swap_jobs::NewSwapJob};
+use crate::utils::{
+    amount::Amount,
+    filters::{Column, Filters},
+    pagination::{Keyset, Page, Paged, Pagination},
+};
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[sqlx(type_name = "trigger_condition", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum TriggerCondition {
+    Above,
+    Below,
+}
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[sqlx(type_name = "triggered_swap_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum TriggeredSwapStatus {
+    Waiting,
+    Fired,
+    Expired,
+}
+
+#[derive(sqlx::Type, Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[sqlx(type_name = "trigger_outcome", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum TriggerOutcome {
+    Waiting,
+    Fired,
+    Expired,
+    Unpriced,
+}
+
+// Lets a swap through once the USD price of its token is at or above, or at or
+// below, `price`, unless it expired first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriceTrigger {
+    pub condition: TriggerCondition,
+    pub price: UsdPrice,
+    pub expires_at: Option<OffsetDateTime>,
+}
+
+// Check a trigger against the current `price` of its token, `None` if the token
+// could not be priced. An expired trigger never fires.
+pub fn evaluate(
+    trigger: &PriceTrigger,
+    price: Option<UsdPrice>,
+    now: OffsetDateTime,
+) -> TriggerOutcome {
+    if trigger
+        .expires_at
+        .is_some_and(|expires_at| now >= expires_at)
+    {
+        return TriggerOutcome::Expired;
+    }
+    let price = match price {
+        Some(price) => price,
+        None => return TriggerOutcome::Unpriced,
+    };
+    let met = match trigger.condition {
+        TriggerCondition::Above => price.cmp_value(&trigger.price) != Ordering::Less,
+        TriggerCondition::Below => price.cmp_value(&trigger.price) != Ordering::Greater,
+    };
+    match met {
+        true => TriggerOutcome::Fired,
+        false => TriggerOutcome::Waiting,
+    }
+}
+
+// The swap of an incoming transfer, held until its price trigger is met.
+#[derive(FromRow, Debug, Clone, Serialize)]
+pub struct TriggeredSwap {
+    pub id: Uuid,
+    pub wallet_address: String,
+    pub from_token: String,
+    pub to_token: String,
+    pub percentage: i16,
+    pub amount: String,
+    pub from_decimals: i16,
+    pub max_slippage_bps: i16,
+    pub venue: String,
+    pub condition: TriggerCondition,
+    // In dollars, such as "0.55".
+    pub price_usd: String,
+    pub expires_at: Option<String>,
+    pub status: TriggeredSwapStatus,
+    pub swap_job_id: Option<Uuid>,
+    pub created_at: String,
+    // Creation time with microseconds, the position of the row in listings.
+    #[serde(skip)]
+    pub sort_key: String,
+}
+
+// Columns selected into a `TriggeredSwap`.
+const TRIGGERED_SWAP_COLUMNS: &str = r#"
+    id,
+    wallet_address,
+    from_token,
+    to_token,
+    percentage,
+    amount::TEXT AS amount,
+    from_decimals,
+    max_slippage_bps,
+    venue,
+    condition,
+    TRIM_SCALE(price_usd)::TEXT AS price_usd,
+    TO_CHAR(expires_at, 'YYYY-MM-DD"T"HH24:MI:SSZ') AS expires_at,
+    status,
+    swap_job_id,
+    TO_CHAR(created_at, 'YYYY-MM-DD"T"HH24:MI:SSZ') AS created_at,
+    TO_CHAR(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"') AS sort_key
+"#;
+
+// Newest first.
+pub const KEYSET: Keyset = Keyset {
+    key: Column::cast("created_at", "TIMESTAMPTZ"),
+    id: Column::cast("id", "UUID"),
+    descending: true,
+};
+
+impl Paged for TriggeredSwap {
+    fn position(&self) -> (String, String) {
+        (self.sort_key.clone(), self.id.to_string())
+    }
+}
+
+impl TriggeredSwap {
+    pub fn trigger(&self) -> Option<PriceTrigger> {
+        Some(PriceTrigger {
+            condition: self.condition,
+            price: UsdPrice::parse(&self.price_usd)?,
+            expires_at: match &self.expires_at {
+                Some(expires_at) => Some(OffsetDateTime::parse(expires_at, &Rfc3339).ok()?),
+                None => None,
+            },
+        })
+    }
+
+    // The swap queued once the trigger fires.
+    pub fn swap_job(&self, max_attempts: i32) -> Option<NewSwapJob> {
+        let decimals = u8::try_from(self.from_decimals).ok()?;
+        Some(NewSwapJob {
+            wallet_address: self.wallet_address.clone(),
+            from_token: self.from_token.clone(),
+            to_token: self.to_token.clone(),
+            percentage: self.percentage,
+            amount: Amount::from_base_units(&self.amount, decimals).ok()?,
+            max_slippage_bps: self.max_slippage_bps,
+            venue: self.venue.clone(),
+            pull_from_wallet: false,
+            max_attempts,
+        })
+    }
+}
+
+// A price trigger as set on a subscribed token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionTrigger {
+    pub condition: TriggerCondition,
+    // In dollars, such as "0.55".
+    pub price_usd: String,
+    // RFC 3339 time.
+    pub expires_at: Option<String>,
+}
+
+#[derive(FromRow, Debug, Clone, Serialize)]
+pub struct TriggerEvaluation {
+    #[serde(skip)]
+    pub id: Uuid,
+    // Price of the token in dollars, if it could be priced.
+    pub price_usd: Option<String>,
+    pub outcome: TriggerOutcome,
+    pub evaluated_at: String,
+    // Evaluation time with microseconds, the position of the row in listings.
+    #[serde(skip)]
+    pub sort_key: String,
+}
+
+// Oldest first.
+pub const EVALUATION_KEYSET: Keyset = Keyset {
+    key: Column::cast("evaluated_at", "TIMESTAMPTZ"),
+    id: Column::cast("id", "UUID"),
+    descending: false,
+};
+
+impl Paged for TriggerEvaluation {
+    fn position(&self) -> (String, String) {
+        (self.sort_key.clone(), self.id.to_string())
+    }
+}
+
+// Hold the swap `job` until `trigger` is met, and return the id of the held swap.
+pub async fn hold(
+    conn: &mut PgConnection,
+    job: &NewSwapJob,
+    trigger: &SubscriptionTrigger,
+) -> Result<Uuid, sqlx::Error> {
+    let (id,) = sqlx::query_as::<_, (Uuid,)>(
+        r#"
+        INSERT INTO triggered_swap
+        (wallet_address, from_token, to_token, percentage, amount, from_decimals,
+         max_slippage_bps, venue, condition, price_usd, expires_at)
+        VALUES ($1, $2, $3, $4, $5::NUMERIC, $6, $7, $8, $9, $10::NUMERIC, $11::TIMESTAMPTZ)
+        RETURNING id
+        "#,
+    )
+    .bind(&job.wallet_address)
+    .bind(&job.from_token)
+    .bind(&job.to_token)
+    .bind(job.percentage)
+    .bind(job.amount.base_units())
+    .bind(i16::from(job.amount.decimals()))
+    .bind(job.max_slippage_bps)
+    .bind(&job.venue)
+    .bind(trigger.condition)
+    .bind(&trigger.price_usd)
+    .bind(&trigger.expires_at)
+    .fetch_one(conn)
+    .await?;
+    Ok(id)
+}
+
+pub async fn list(
+    pool: &PgPool,
+    wallet_address: &str,
+    pagination: &Pagination,
+) -> Result<Page<TriggeredSwap>, sqlx::Error> {
+    let mut filters = Filters::new(&format!(
+        "SELECT {} FROM triggered_swap",
+        TRIGGERED_SWAP_COLUMNS
+    ));
+    filters.eq("LOWER(wallet_address)", Some(wallet_address.to_lowercase()));
+    let rows = pagination
+        .apply(filters)
+        .build_query_as::<TriggeredSwap>()
+        .fetch_all(pool)
+        .await?;
+    Ok(pagination.page(rows))
+}
+
+pub async fn find(pool: &PgPool, id: Uuid) -> Result<Option<TriggeredSwap>, sqlx::Error> {
+    sqlx::query_as::<_, TriggeredSwap>(&format!(
+        "SELECT {} FROM triggered_swap WHERE id = $1",
+        TRIGGERED_SWAP_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+// Swaps waiting for their trigger, oldest first, starting after `after`.
+pub async fn list_waiting(
+    pool: &PgPool,
+    after: Option<&TriggeredSwap>,
+    limit: i64,
+) -> Result<Vec<TriggeredSwap>, sqlx::Error> {
+    // Qualified, as `created_at` alone names the formatted column.
+    sqlx::query_as::<_, TriggeredSwap>(&format!(
+        r#"
+        SELECT {}
+        FROM triggered_swap
+        WHERE status = 'waiting'
+          AND ($1::TIMESTAMPTZ IS NULL
+               OR (triggered_swap.created_at, id) > ($1::TIMESTAMPTZ, $2))
+        ORDER BY triggered_swap.created_at, id
+        LIMIT $3
+        "#,
+        TRIGGERED_SWAP_COLUMNS
+    ))
+    .bind(after.map(|swap| swap.sort_key.as_str()))
+    .bind(after.map(|swap| swap.id))
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+// Lock a swap that is still waiting until the surrounding transaction ends.
+// Returns `None` if it no longer waits or another evaluator holds it.
+pub async fn lock_waiting(
+    conn: &mut PgConnection,
+    id: Uuid,
+) -> Result<Option<TriggeredSwap>, sqlx::Error> {
+    sqlx::query_as::<_, TriggeredSwap>(&format!(
+        r#"
+        SELECT {}
+        FROM triggered_swap
+        WHERE id = $1 AND status = 'waiting'
+        FOR UPDATE SKIP LOCKED
+        "#,
+        TRIGGERED_SWAP_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(conn)
+    .await
+}
+
+// Log an evaluation of a held swap, unless its last logged evaluation had the
+// same outcome.
+pub async fn record_evaluation(
+    conn: &mut PgConnection,
+    id: Uuid,
+    price_usd: Option<&str>,
+    outcome: TriggerOutcome,
+    evaluated_at: OffsetDateTime,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO trigger_evaluation (triggered_swap_id, price_usd, outcome, evaluated_at)
+        SELECT $1, $2::NUMERIC, $3, $4::TIMESTAMPTZ
+        WHERE $3 IS DISTINCT FROM (
+            SELECT outcome
+            FROM trigger_evaluation
+            WHERE triggered_swap_id = $1
+            ORDER BY evaluated_at DESC
+            LIMIT 1
+        )
+        "#,
+    )
+    .bind(id)
+    .bind(price_usd)
+    .bind(outcome)
+    .bind(evaluated_at.format(&Rfc3339).unwrap())
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn mark_fired(
+    conn: &mut PgConnection,
+    id: Uuid,
+    swap_job_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE triggered_swap SET status = 'fired', swap_job_id = $2 WHERE id = $1")
+        .bind(id)
+        .bind(swap_job_id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+pub async fn mark_expired(conn: &mut PgConnection, id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE triggered_swap SET status = 'expired' WHERE id = $1")
+        .bind(id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+// Evaluations of a held swap, oldest first.
+pub async fn evaluations(
+    pool: &PgPool,
+    id: Uuid,
+    pagination: &Pagination,
+) -> Result<Page<TriggerEvaluation>, sqlx::Error> {
+    let mut filters = Filters::new(
+        r#"
+        SELECT
+            id,
+            TRIM_SCALE(price_usd)::TEXT AS price_usd,
+            outcome,
+            TO_CHAR(evaluated_at, 'YYYY-MM-DD"T"HH24:MI:SSZ') AS evaluated_at,
+            TO_CHAR(evaluated_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"') AS sort_key
+        FROM trigger_evaluation
+        "#,
+    );
+    filters.eq("triggered_swap_id", Some(id));
+    let rows = pagination
+        .apply(filters)
+        .build_query_as::<TriggerEvaluation>()
+        .fetch_all(pool)
+        .await?;
+    Ok(pagination.page(rows))
+}
@@ -0,0 +1,111 @@
+use anyhow::{anyhow, Result};
+use starknet::{
+    accounts::Account,
+    core::types::{Call, Felt},
+};
+
+use super::{SwapVenue, VenueFuture};
+use crate::{
+    service::{
+        quote::{simulate_quote, QuoteParams, VenueQuote, VenueSwap},
+        slippage::SlippageLimits,
+    },
+    utils::{
+        amount::Amount,
+        avnu::{avnu_swap_calls, AvnuClient, TokenFrom, TokenTo},
+        starknet::SwapAccount,
+    },
+};
+
+const AVNU: &str = "avnu";
+
+// Swaps through the router along the routes the AVNU API finds.
+pub struct AvnuVenue {
+    client: AvnuClient,
+}
+
+impl AvnuVenue {
+    pub fn new(client: AvnuClient) -> Self {
+        AvnuVenue { client }
+    }
+
+    // An AVNU swap of `amount` with no minimum output, as simulated for a quote.
+    async fn swap(&self, account: Felt, params: &QuoteParams, amount: Amount) -> Result<VenueSwap> {
+        // The router contract is the one that trades with AVNU.
+        let quote = self
+            .client
+            .quote(
+                params.from_token,
+                params.to_token,
+                amount,
+                params.contract_address,
+            )
+            .await?;
+        let routes = self.client.routes(&quote, params.contract_address).await?;
+
+        let zero = Amount::zero(0);
+        let calls = avnu_swap_calls(
+            TokenFrom::new(params.from_token, amount),
+            TokenTo::new(params.to_token, zero, zero),
+            params.recipient(account),
+            0,
+            Felt::ZERO,
+            routes.clone(),
+            params.contract_address,
+        );
+        Ok(VenueSwap {
+            calls,
+            pool_key: None,
+            routes: Some(routes),
+        })
+    }
+}
+
+impl SwapVenue for AvnuVenue {
+    fn name(&self) -> &'static str {
+        AVNU
+    }
+
+    fn quote<'a>(
+        &'a self,
+        account: &'a SwapAccount,
+        nonce: Felt,
+        params: &'a QuoteParams,
+    ) -> VenueFuture<'a, VenueQuote> {
+        Box::pin(simulate_quote(
+            account,
+            nonce,
+            params,
+            AVNU,
+            move |amount| self.swap(account.address(), params, amount),
+        ))
+    }
+
+    fn build_calls(
+        &self,
+        account: Felt,
+        params: &QuoteParams,
+        quote: &VenueQuote,
+        limits: &SlippageLimits,
+    ) -> Result<Vec<Call>> {
+        let routes = quote
+            .routes
+            .clone()
+            .ok_or_else(|| anyhow!("Quote has no AVNU route"))?;
+        // Only the raw amounts are sent, the output decimals do not matter.
+        let token_to = TokenTo::new(
+            params.to_token,
+            Amount::from_raw(quote.amount_out.0, 0),
+            Amount::from_raw(limits.min_amount_out.0, 0),
+        );
+        Ok(avnu_swap_calls(
+            TokenFrom::new(params.from_token, params.amount),
+            token_to,
+            params.recipient(account),
+            0,
+            Felt::ZERO,
+            routes,
+            params.contract_address,
+        ))
+    }
+}
@@ -0,0 +1,139 @@
+use std::future::ready;
+
+use anyhow::{anyhow, Result};
+use starknet::{
+    accounts::Account,
+    core::types::{Call, Felt},
+    macros::selector,
+};
+
+use super::{SwapVenue, VenueFuture};
+use crate::{
+    service::{
+        pools,
+        quote::{simulate_quote, QuoteParams, VenueQuote, VenueSwap},
+        slippage::SlippageLimits,
+    },
+    utils::{
+        amount::Amount,
+        ekubo::{ekubo_swap_calls, no_sqrt_ratio_limit, PoolKey, PoolTier},
+        starknet::SwapAccount,
+    },
+    Db,
+};
+
+const EKUBO: &str = "ekubo";
+
+// Swaps through the Ekubo router, in whichever of the pair's pools quotes the
+// largest output.
+pub struct EkuboVenue {
+    db: Db,
+    // Pools quoted for pairs without a registered pool.
+    default_tiers: Vec<PoolTier>,
+}
+
+impl EkuboVenue {
+    pub fn new(db: Db, default_tiers: Vec<PoolTier>) -> Self {
+        EkuboVenue { db, default_tiers }
+    }
+}
+
+impl SwapVenue for EkuboVenue {
+    fn name(&self) -> &'static str {
+        EKUBO
+    }
+
+    fn quote<'a>(
+        &'a self,
+        account: &'a SwapAccount,
+        nonce: Felt,
+        params: &'a QuoteParams,
+    ) -> VenueFuture<'a, VenueQuote> {
+        Box::pin(async move {
+            let pool_keys = pools::candidates(
+                &self.db.pool,
+                params.from_token,
+                params.to_token,
+                &self.default_tiers,
+            )
+            .await?;
+
+            // Quote every pool and keep the one with the largest output.
+            let mut best: Option<VenueQuote> = None;
+            let mut last_error = anyhow!("No Ekubo pool configured");
+            for pool_key in &pool_keys {
+                let swap =
+                    |amount| ready(swap_in_pool(account.address(), params, pool_key, amount));
+                let quote = match simulate_quote(account, nonce, params, EKUBO, swap).await {
+                    Ok(quote) => quote,
+                    Err(err) => {
+                        last_error = err;
+                        continue;
+                    }
+                };
+                if !best
+                    .as_ref()
+                    .is_some_and(|best| best.amount_out >= quote.amount_out)
+                {
+                    best = Some(quote);
+                }
+            }
+            best.ok_or(last_error)
+        })
+    }
+
+    fn build_calls(
+        &self,
+        account: Felt,
+        params: &QuoteParams,
+        quote: &VenueQuote,
+        limits: &SlippageLimits,
+    ) -> Result<Vec<Call>> {
+        let pool_key = quote
+            .pool_key
+            .clone()
+            .ok_or_else(|| anyhow!("Quote has no Ekubo pool"))?;
+        let is_token1 = pool_key.is_token1(params.from_token);
+        let mut calls = ekubo_swap_calls(
+            params.from_token,
+            pool_key,
+            params.amount,
+            limits.sqrt_ratio_limit(is_token1),
+            account,
+            params.contract_address,
+        )?;
+        // The router pays the caller, which sends the swap on to the funding
+        // wallet. The least accepted output is what is sure to be received.
+        if let Some(wallet) = params.funded_by {
+            let amount_out = Amount::from_raw(limits.min_amount_out.0, 0);
+            calls.push(Call {
+                to: params.to_token,
+                selector: selector!("transfer"),
+                calldata: [vec![wallet], amount_out.calldata().to_vec()].concat(),
+            });
+        }
+        Ok(calls)
+    }
+}
+
+// An unbounded swap of `amount` in `pool_key`, as simulated for a quote.
+fn swap_in_pool(
+    account: Felt,
+    params: &QuoteParams,
+    pool_key: &PoolKey,
+    amount: Amount,
+) -> Result<VenueSwap> {
+    let calls = ekubo_swap_calls(
+        params.from_token,
+        pool_key.clone(),
+        amount,
+        no_sqrt_ratio_limit(pool_key.is_token1(params.from_token)),
+        account,
+        params.contract_address,
+    )?;
+    Ok(VenueSwap {
+        calls,
+        pool_key: Some(pool_key.clone()),
+        routes: None,
+    })
+}
@@ -0,0 +1,117 @@
+// Venues swaps are quoted and sent on. A new DEX only needs a `SwapVenue`
+// implementation registered in `VenueRegistry::from_config`.
+mod avnu;
+mod ekubo;
+
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use anyhow::Result;
+use starknet::{
+    accounts::Account,
+    core::types::{Call, Felt},
+};
+
+use super::{
+    nonces::NonceManager,
+    quote::{QuoteParams, VenueQuote},
+    slippage::SlippageLimits,
+};
+use crate::{
+    utils::{avnu::AvnuClient, starknet::SwapAccount},
+    Configuration, Db,
+};
+
+pub use avnu::AvnuVenue;
+pub use ekubo::EkuboVenue;
+
+// Venue preference of subscriptions that take whichever venue quotes the most.
+pub const BEST_PRICE: &str = "best_price";
+
+pub type VenueFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+pub trait SwapVenue: Send + Sync {
+    // Name subscriptions pick the venue by.
+    fn name(&self) -> &'static str;
+
+    // Quote a swap by simulating it from `account` with `nonce`.
+    fn quote<'a>(
+        &'a self,
+        account: &'a SwapAccount,
+        nonce: Felt,
+        params: &'a QuoteParams,
+    ) -> VenueFuture<'a, VenueQuote>;
+
+    // Calls that send the swap of `quote` from `account`, bounded by `limits`.
+    fn build_calls(
+        &self,
+        account: Felt,
+        params: &QuoteParams,
+        quote: &VenueQuote,
+        limits: &SlippageLimits,
+    ) -> Result<Vec<Call>>;
+
+    // Send the swap of `quote` from `account` with a nonce from `nonces` and
+    // return its transaction hash.
+    fn execute<'a>(
+        &'a self,
+        nonces: &'a NonceManager,
+        account: &'a SwapAccount,
+        params: &'a QuoteParams,
+        quote: &'a VenueQuote,
+        limits: &'a SlippageLimits,
+    ) -> VenueFuture<'a, Felt> {
+        Box::pin(async move {
+            let calls = self.build_calls(account.address(), params, quote, limits)?;
+            nonces.send(account, calls).await
+        })
+    }
+}
+
+// The venues swaps can go through.
+#[derive(Clone, Default)]
+pub struct VenueRegistry {
+    venues: Vec<Arc<dyn SwapVenue>>,
+}
+
+impl VenueRegistry {
+    // Ekubo, and AVNU when its API is configured.
+    pub fn from_config(config: &Configuration, db: &Db) -> Self {
+        let mut registry = VenueRegistry::default();
+        registry.register(EkuboVenue::new(db.clone(), config.ekubo_pool_tiers.clone()));
+        if let Some(url) = &config.avnu_api_url {
+            registry.register(AvnuVenue::new(AvnuClient::new(url)));
+        }
+        registry
+    }
+
+    // Add `venue`, replacing any venue of the same name.
+    pub fn register(&mut self, venue: impl SwapVenue + 'static) {
+        self.venues
+            .retain(|registered| registered.name() != venue.name());
+        self.venues.push(Arc::new(venue));
+    }
+
+    pub fn all(&self) -> &[Arc<dyn SwapVenue>] {
+        &self.venues
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn SwapVenue>> {
+        self.venues
+            .iter()
+            .find(|venue| venue.name() == name)
+            .cloned()
+    }
+
+    // Whether a subscription can pick `preference`.
+    pub fn accepts(&self, preference: &str) -> bool {
+        preference == BEST_PRICE || self.get(preference).is_some()
+    }
+
+    // Venues a swap with the venue `preference` is quoted on.
+    pub fn preferred(&self, preference: &str) -> Vec<Arc<dyn SwapVenue>> {
+        match preference {
+            BEST_PRICE => self.venues.clone(),
+            name => self.get(name).into_iter().collect(),
+        }
+    }
+}
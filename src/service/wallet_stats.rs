@@ -0,0 +1,141 @@
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+
+use crate::utils::uint256::Uint256;
+
+// Swaps of a wallet from one token to another. USD amounts are in dollars and
+// left out when a token cannot be priced.
+#[derive(FromRow, Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PairStats {
+    pub from_token: String,
+    pub to_token: String,
+    pub swap_count: i64,
+    // Amounts swapped, in base units.
+    pub volume_in: Uint256,
+    pub volume_out: Uint256,
+    // USD values of the amounts swapped, when each swap executed.
+    pub volume_in_usd: Option<String>,
+    pub volume_out_usd: Option<String>,
+    // Whole `to_token` tokens received per whole `from_token` token.
+    pub average_price: Option<String>,
+    // What the tokens swapped away would be worth now, had they been held.
+    pub held_value_usd: Option<String>,
+    // What the tokens received are worth now.
+    pub swapped_value_usd: Option<String>,
+    // `swapped_value_usd - held_value_usd`.
+    pub savings_usd: Option<String>,
+    pub first_swap_at: String,
+    pub last_swap_at: String,
+}
+
+// Stats of the swaps of `wallet_address` that did not revert, per token pair,
+// most recently swapped first. Swaps logged without a USD value are valued at
+// the last price in the price history before they executed, and current values
+// use the latest price in the history.
+pub async fn pair_stats(
+    pool: &PgPool,
+    wallet_address: &str,
+) -> Result<Vec<PairStats>, sqlx::Error> {
+    sqlx::query_as::<_, PairStats>(
+        r#"
+        WITH swaps AS (
+            SELECT
+                LOWER(log.from_token) AS from_token,
+                LOWER(log.to_token) AS to_token,
+                log.amount_from,
+                log.amount_to,
+                log.amount_from / POWER(10::NUMERIC, from_token.token_decimals) AS tokens_from,
+                log.amount_to / POWER(10::NUMERIC, to_token.token_decimals) AS tokens_to,
+                COALESCE(
+                    log.amount_from_usd,
+                    log.amount_from / POWER(10::NUMERIC, from_token.token_decimals) * from_price.price_usd
+                ) AS from_usd,
+                COALESCE(
+                    log.amount_to_usd,
+                    log.amount_to / POWER(10::NUMERIC, to_token.token_decimals) * to_price.price_usd
+                ) AS to_usd,
+                log.created_at
+            FROM transactions_log AS log
+            LEFT JOIN token AS from_token
+                ON LOWER(from_token.contract_address) = LOWER(log.from_token)
+            LEFT JOIN token AS to_token
+                ON LOWER(to_token.contract_address) = LOWER(log.to_token)
+            LEFT JOIN LATERAL (
+                SELECT price_usd
+                FROM token_price_history
+                WHERE token_address = LOWER(log.from_token) AND recorded_at <= log.created_at
+                ORDER BY recorded_at DESC
+                LIMIT 1
+            ) AS from_price ON TRUE
+            LEFT JOIN LATERAL (
+                SELECT price_usd
+                FROM token_price_history
+                WHERE token_address = LOWER(log.to_token) AND recorded_at <= log.created_at
+                ORDER BY recorded_at DESC
+                LIMIT 1
+            ) AS to_price ON TRUE
+            WHERE LOWER(log.wallet_address) = LOWER($1)
+              AND log.status IS DISTINCT FROM 'reverted'
+              -- Firings of protection rules that have not been sent yet.
+              AND NOT (log.swap_job_id IS NOT NULL AND log.tx_hash IS NULL)
+        ),
+        pairs AS (
+            SELECT
+                from_token,
+                to_token,
+                COUNT(*) AS swap_count,
+                SUM(amount_from) AS volume_in,
+                SUM(amount_to) AS volume_out,
+                SUM(tokens_from) AS tokens_from,
+                SUM(tokens_to) AS tokens_to,
+                -- A total is only given when every swap has a value.
+                CASE WHEN COUNT(from_usd) = COUNT(*) THEN SUM(from_usd) END AS volume_in_usd,
+                CASE WHEN COUNT(to_usd) = COUNT(*) THEN SUM(to_usd) END AS volume_out_usd,
+                MIN(created_at) AS first_swap_at,
+                MAX(created_at) AS last_swap_at
+            FROM swaps
+            GROUP BY from_token, to_token
+        ),
+        valued AS (
+            SELECT
+                pairs.*,
+                pairs.tokens_from * from_price.price_usd AS held_value_usd,
+                pairs.tokens_to * to_price.price_usd AS swapped_value_usd
+            FROM pairs
+            LEFT JOIN LATERAL (
+                SELECT price_usd
+                FROM token_price_history
+                WHERE token_address = pairs.from_token
+                ORDER BY recorded_at DESC
+                LIMIT 1
+            ) AS from_price ON TRUE
+            LEFT JOIN LATERAL (
+                SELECT price_usd
+                FROM token_price_history
+                WHERE token_address = pairs.to_token
+                ORDER BY recorded_at DESC
+                LIMIT 1
+            ) AS to_price ON TRUE
+        )
+        SELECT
+            from_token,
+            to_token,
+            swap_count,
+            volume_in,
+            volume_out,
+            TRIM_SCALE(ROUND(volume_in_usd, 8))::TEXT AS volume_in_usd,
+            TRIM_SCALE(ROUND(volume_out_usd, 8))::TEXT AS volume_out_usd,
+            TRIM_SCALE(ROUND(tokens_to / NULLIF(tokens_from, 0), 18))::TEXT AS average_price,
+            TRIM_SCALE(ROUND(held_value_usd, 8))::TEXT AS held_value_usd,
+            TRIM_SCALE(ROUND(swapped_value_usd, 8))::TEXT AS swapped_value_usd,
+            TRIM_SCALE(ROUND(swapped_value_usd - held_value_usd, 8))::TEXT AS savings_usd,
+            TO_CHAR(first_swap_at, 'YYYY-MM-DD"T"HH24:MI:SSZ') AS first_swap_at,
+            TO_CHAR(last_swap_at, 'YYYY-MM-DD"T"HH24:MI:SSZ') AS last_swap_at
+        FROM valued
+        ORDER BY valued.last_swap_at DESC, from_token, to_token
+        "#,
+    )
+    .bind(wallet_address)
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,162 @@
+use std::fmt;
+
+use num_bigint::BigUint;
+use starknet::core::types::{Felt, U256};
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AmountError {
+    #[error("Invalid amount: {0:?}")]
+    Invalid(String),
+    #[error("Amount has more than {0} decimal places")]
+    TooPrecise(u8),
+    #[error("Amount does not fit in 256 bits")]
+    Overflow,
+    #[error("Division by zero")]
+    DivisionByZero,
+}
+
+// A token amount in raw base units together with the decimals of its token, so
+// 1.5 USDC is stored as 1_500_000 with 6 decimals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Amount {
+    raw: U256,
+    decimals: u8,
+}
+
+impl Amount {
+    pub fn from_raw(raw: U256, decimals: u8) -> Self {
+        Amount { raw, decimals }
+    }
+
+    pub fn zero(decimals: u8) -> Self {
+        Amount::from_raw(U256::from(0u8), decimals)
+    }
+
+    // Parse a human-readable decimal string such as "1.5". Amounts with more
+    // decimal places than the token has are rejected instead of rounded.
+    pub fn parse(value: &str, decimals: u8) -> Result<Self, AmountError> {
+        let invalid = || AmountError::Invalid(value.to_string());
+        let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+        let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if (whole.is_empty() && fraction.is_empty()) || !is_digits(whole) || !is_digits(fraction) {
+            return Err(invalid());
+        }
+
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.len() > decimals as usize {
+            return Err(AmountError::TooPrecise(decimals));
+        }
+        let digits = format!(
+            "0{}{}{}",
+            whole,
+            fraction,
+            "0".repeat(decimals as usize - fraction.len())
+        );
+        let raw = digits.parse::<BigUint>().map_err(|_| invalid())?;
+        Ok(Amount::from_raw(to_u256(&raw)?, decimals))
+    }
+
+    // Parse an integer amount of base units, e.g. a NUMERIC column read as text.
+    pub fn from_base_units(value: &str, decimals: u8) -> Result<Self, AmountError> {
+        let amount = Amount::parse(value, 0)?;
+        Ok(Amount::from_raw(amount.raw, decimals))
+    }
+
+    // The amount in base units as an integer string.
+    pub fn base_units(&self) -> String {
+        to_biguint(self.raw).to_string()
+    }
+
+    pub fn raw(&self) -> U256 {
+        self.raw
+    }
+
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.raw.low() == 0 && self.raw.high() == 0
+    }
+
+    // The raw amount if it fits in a u128, as required by Ekubo's i129 amounts.
+    pub fn to_u128(&self) -> Option<u128> {
+        match self.raw.high() {
+            0 => Some(self.raw.low()),
+            _ => None,
+        }
+    }
+
+    // Calldata of the amount as a Cairo u256: low word, then high word.
+    pub fn calldata(&self) -> [Felt; 2] {
+        [Felt::from(self.raw.low()), Felt::from(self.raw.high())]
+    }
+
+    // `self * numerator / denominator`, rounded down.
+    pub fn mul_div(&self, numerator: u128, denominator: u128) -> Result<Self, AmountError> {
+        if denominator == 0 {
+            return Err(AmountError::DivisionByZero);
+        }
+        let raw = to_biguint(self.raw) * numerator / denominator;
+        Ok(Amount::from_raw(to_u256(&raw)?, self.decimals))
+    }
+
+    // `percent` percent of the amount, rounded down so that a share never exceeds
+    // what it was taken from. 50% of 3 base units is 1.
+    pub fn percentage(&self, percent: u16) -> Result<Self, AmountError> {
+        self.mul_div(percent.into(), 100)
+    }
+
+    // Split the amount into shares proportional to `weights`. Every share is
+    // rounded down and the remainder goes to the last share, so the shares always
+    // add up to exactly the amount. Splitting 10 by [1, 1, 1] gives [3, 3, 4].
+    pub fn split(&self, weights: &[u16]) -> Result<Vec<Self>, AmountError> {
+        let total: u128 = weights.iter().map(|w| u128::from(*w)).sum();
+        if total == 0 {
+            return Err(AmountError::DivisionByZero);
+        }
+
+        let raw = to_biguint(self.raw);
+        let mut remaining = raw.clone();
+        let mut shares = Vec::with_capacity(weights.len());
+        for (i, weight) in weights.iter().enumerate() {
+            let share = match i + 1 == weights.len() {
+                true => remaining.clone(),
+                false => &raw * u128::from(*weight) / total,
+            };
+            remaining -= &share;
+            shares.push(Amount::from_raw(to_u256(&share)?, self.decimals));
+        }
+        Ok(shares)
+    }
+}
+
+// Human-readable decimal string without trailing zeros, e.g. "1.5".
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = to_biguint(self.raw).to_string();
+        let decimals = self.decimals as usize;
+        let digits = format!("{:0>width$}", digits, width = decimals + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - decimals);
+        match fraction.trim_end_matches('0') {
+            "" => f.pad(whole),
+            fraction => f.pad(&format!("{}.{}", whole, fraction)),
+        }
+    }
+}
+
+pub(crate) fn to_biguint(value: U256) -> BigUint {
+    (BigUint::from(value.high()) << 128) | BigUint::from(value.low())
+}
+
+pub(crate) fn to_u256(value: &BigUint) -> Result<U256, AmountError> {
+    if value.bits() > 256 {
+        return Err(AmountError::Overflow);
+    }
+    let mut words = value.to_u64_digits();
+    words.resize(4, 0);
+    let low = u128::from(words[0]) | (u128::from(words[1]) << 64);
+    let high = u128::from(words[2]) | (u128::from(words[3]) << 64);
+    Ok(U256::from_words(low, high))
+}
@@ -0,0 +1,302 @@
+use anyhow::{anyhow, Context, Result};
+use num_bigint::BigUint;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+use serde_json::json;
+use starknet::core::types::{Call, Felt};
+use starknet::macros::selector;
+
+use super::amount::{to_biguint, to_u256, Amount};
+use super::starknet::felt_to_u128;
+use super::uint256::Uint256;
+
+// Slippage AVNU builds swaps with. Only the routes of a built swap are used,
+// the least output a swap accepts is set by the caller.
+const BUILD_SLIPPAGE: f64 = 0.01;
+
+// Felts before the routes in the calldata of AVNU's `multi_route_swap`: token_from,
+// its u256 amount, token_to, its u256 amount and min amount, the beneficiary, the
+// integrator fee and its recipient.
+const ROUTES_OFFSET: usize = 11;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Route {
+    token_from: Felt,
+    token_to: Felt,
+    exchange_address: Felt,
+    percent: u128,
+    additional_swap_params: Vec<Felt>,
+}
+
+impl Route {
+    pub fn new(
+        token_from: Felt,
+        token_to: Felt,
+        exchange_address: Felt,
+        percent: u128,
+        additional_swap_params: Vec<Felt>,
+    ) -> Self {
+        Route {
+            token_from,
+            token_to,
+            exchange_address,
+            percent,
+            additional_swap_params,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TokenFrom {
+    address: Felt,
+    amount: Amount,
+}
+
+impl TokenFrom {
+    pub fn new(address: Felt, amount: Amount) -> Self {
+        TokenFrom { address, amount }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TokenTo {
+    address: Felt,
+    amount: Amount,
+    min_amount: Amount,
+}
+
+impl TokenTo {
+    pub fn new(address: Felt, amount: Amount, min_amount: Amount) -> Self {
+        TokenTo {
+            address,
+            amount,
+            min_amount,
+        }
+    }
+}
+
+// Calls that approve the router for `token_from` and swap it along `routes`.
+pub fn avnu_swap_calls(
+    token_from: TokenFrom,
+    token_to: TokenTo,
+    beneficiary: Felt,
+    integrator_fee_amount_bps: u128,
+    integrator_fee_recipient: Felt,
+    routes: Vec<Route>,
+    contract_address: Felt,
+) -> Vec<Call> {
+    let routes_len = routes.len();
+    let routes_calldata: Vec<Felt> = routes
+        .into_iter()
+        .flat_map(|route| {
+            let mut route_data = vec![
+                route.token_from,
+                route.token_to,
+                route.exchange_address,
+                Felt::from(route.percent),
+                Felt::from(route.additional_swap_params.len()),
+            ];
+            route_data.extend(route.additional_swap_params);
+            route_data
+        })
+        .collect();
+
+    let approve_call = Call {
+        to: token_from.address,
+        selector: selector!("approve"),
+        calldata: [
+            vec![contract_address],
+            token_from.amount.calldata().to_vec(),
+        ]
+        .concat(),
+    };
+
+    let swap_call = Call {
+        to: contract_address,
+        // The router's entry point is spelled `anvu_swap`.
+        selector: selector!("anvu_swap"),
+        calldata: [
+            vec![token_from.address],
+            token_from.amount.calldata().to_vec(),
+            vec![token_to.address],
+            token_to.amount.calldata().to_vec(),
+            token_to.min_amount.calldata().to_vec(),
+            vec![
+                beneficiary,
+                integrator_fee_amount_bps.into(),
+                integrator_fee_recipient,
+                Felt::from(routes_len),
+            ],
+            routes_calldata,
+        ]
+        .concat(),
+    };
+
+    vec![approve_call, swap_call]
+}
+
+// Best quote the AVNU API found for a swap.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvnuQuote {
+    pub quote_id: String,
+    pub sell_token_address: Felt,
+    #[serde(deserialize_with = "hex_u256")]
+    pub sell_amount: Uint256,
+    pub buy_token_address: Felt,
+    #[serde(deserialize_with = "hex_u256")]
+    pub buy_amount: Uint256,
+}
+
+impl AvnuQuote {
+    pub fn token_from(&self, decimals: u8) -> TokenFrom {
+        TokenFrom::new(
+            self.sell_token_address,
+            Amount::from_raw(self.sell_amount.0, decimals),
+        )
+    }
+
+    // The quoted output, accepting no less than `min_amount`.
+    pub fn token_to(&self, min_amount: Amount) -> TokenTo {
+        TokenTo::new(
+            self.buy_token_address,
+            Amount::from_raw(self.buy_amount.0, min_amount.decimals()),
+            min_amount,
+        )
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BuiltSwap {
+    calls: Vec<BuiltCall>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BuiltCall {
+    entrypoint: String,
+    calldata: Vec<Felt>,
+}
+
+// Client of the AVNU swap API at `base_url`, e.g. https://starknet.api.avnu.fi.
+#[derive(Debug, Clone)]
+pub struct AvnuClient {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl AvnuClient {
+    pub fn new(base_url: &str) -> Self {
+        AvnuClient {
+            http: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    // Best quote for selling `sell_amount` of `sell_token` for `buy_token` from `taker`.
+    pub async fn quote(
+        &self,
+        sell_token: Felt,
+        buy_token: Felt,
+        sell_amount: Amount,
+        taker: Felt,
+    ) -> Result<AvnuQuote> {
+        let quotes: Vec<AvnuQuote> = self
+            .http
+            .get(format!("{}/swap/v2/quotes", self.base_url))
+            .query(&[
+                ("sellTokenAddress", format!("{:#x}", sell_token)),
+                ("buyTokenAddress", format!("{:#x}", buy_token)),
+                (
+                    "sellAmount",
+                    format!("{:#x}", to_biguint(sell_amount.raw())),
+                ),
+                ("takerAddress", format!("{:#x}", taker)),
+                ("size", "1".to_string()),
+            ])
+            .send()
+            .await
+            .context("Failed to request AVNU quotes")?
+            .error_for_status()
+            .context("AVNU quote request failed")?
+            .json()
+            .await
+            .context("Invalid AVNU quotes")?;
+
+        quotes
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("AVNU has no route for this swap"))
+    }
+
+    // Routes of `quote`, as the router's swap takes them.
+    pub async fn routes(&self, quote: &AvnuQuote, taker: Felt) -> Result<Vec<Route>> {
+        let built: BuiltSwap = self
+            .http
+            .post(format!("{}/swap/v2/build", self.base_url))
+            .json(&json!({
+                "quoteId": quote.quote_id,
+                "takerAddress": format!("{:#x}", taker),
+                "slippage": BUILD_SLIPPAGE,
+                "includeApprove": false,
+            }))
+            .send()
+            .await
+            .context("Failed to build AVNU swap")?
+            .error_for_status()
+            .context("AVNU build request failed")?
+            .json()
+            .await
+            .context("Invalid AVNU swap")?;
+
+        let swap = built
+            .calls
+            .iter()
+            .find(|call| call.entrypoint == "multi_route_swap")
+            .ok_or_else(|| anyhow!("AVNU swap has no multi_route_swap call"))?;
+        parse_routes(&swap.calldata)
+    }
+}
+
+// Read the routes out of `multi_route_swap` calldata.
+pub fn parse_routes(calldata: &[Felt]) -> Result<Vec<Route>> {
+    let invalid = || anyhow!("Invalid multi_route_swap calldata");
+    let mut felts = calldata.iter().skip(ROUTES_OFFSET).copied();
+    let mut next = || felts.next().ok_or_else(invalid);
+    let len = |felt: Felt| {
+        felt_to_u128(felt)
+            .and_then(|len| usize::try_from(len).ok())
+            .ok_or_else(invalid)
+    };
+
+    let routes_len = len(next()?)?;
+    let mut routes = Vec::with_capacity(routes_len);
+    for _ in 0..routes_len {
+        let token_from = next()?;
+        let token_to = next()?;
+        let exchange_address = next()?;
+        let percent = felt_to_u128(next()?).ok_or_else(invalid)?;
+        let params_len = len(next()?)?;
+        let additional_swap_params = (0..params_len)
+            .map(|_| next())
+            .collect::<Result<Vec<_>>>()?;
+        routes.push(Route::new(
+            token_from,
+            token_to,
+            exchange_address,
+            percent,
+            additional_swap_params,
+        ));
+    }
+    Ok(routes)
+}
+
+// AVNU writes amounts as hex strings.
+fn hex_u256<'de, D>(deserializer: D) -> Result<Uint256, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    let invalid = || D::Error::custom(format!("Invalid hex amount: {}", value));
+    let digits = value.strip_prefix("0x").ok_or_else(invalid)?;
+    let amount = BigUint::parse_bytes(digits.as_bytes(), 16).ok_or_else(invalid)?;
+    Ok(Uint256(to_u256(&amount).map_err(D::Error::custom)?))
+}
@@ -0,0 +1,136 @@
+use std::{fmt, str::FromStr};
+
+use time::{Date, Duration, OffsetDateTime, Time};
+
+// How far ahead the next run is looked for, so schedules such as `0 0 30 2 *`
+// that never match give up.
+const MAX_LOOKAHEAD_DAYS: i64 = 5 * 366;
+
+// A cron schedule in UTC, written as the five fields `minute hour day-of-month
+// month day-of-week`, or one of `@hourly`, `@daily`, `@weekly` and `@monthly`.
+// Fields take `*`, values, `a-b` ranges, `*/n` or `a-b/n` steps and comma
+// separated lists of those. Sunday is 0 or 7. As in cron, a run matches either
+// day field when both are restricted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl CronSchedule {
+    // The first run strictly after `after`, at the start of a minute.
+    pub fn next_after(&self, after: OffsetDateTime) -> Option<OffsetDateTime> {
+        let after = after.to_offset(time::UtcOffset::UTC);
+        let limit = after + Duration::days(MAX_LOOKAHEAD_DAYS);
+        let mut next = after.replace_time(Time::from_hms(after.hour(), after.minute(), 0).ok()?)
+            + Duration::minutes(1);
+        while next <= limit {
+            if !self.matches_day(next.date()) {
+                next = next.replace_time(Time::MIDNIGHT) + Duration::days(1);
+            } else if !has(self.hours, next.hour()) {
+                next =
+                    next.replace_time(Time::from_hms(next.hour(), 0, 0).ok()?) + Duration::hours(1);
+            } else if !has(self.minutes, next.minute()) {
+                next += Duration::minutes(1);
+            } else {
+                return Some(next);
+            }
+        }
+        None
+    }
+
+    fn matches_day(&self, date: Date) -> bool {
+        if !has(self.months, u8::from(date.month())) {
+            return false;
+        }
+        let day_of_month = has(self.days_of_month, date.day());
+        let day_of_week = has(self.days_of_week, date.weekday().number_days_from_sunday());
+        match (self.any_day_of_month, self.any_day_of_week) {
+            (false, false) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expression = s.trim();
+        let fields = match expression {
+            "@hourly" => "0 * * * *",
+            "@daily" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            fields => fields,
+        };
+        let parts: Vec<&str> = fields.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = parts[..] else {
+            return Err(format!("Invalid schedule, expected 5 fields: {:?}", s));
+        };
+
+        // Sunday can be written as 7.
+        let mut days_of_week_set = parse_field(days_of_week, 0, 7)?;
+        if has(days_of_week_set, 7) {
+            days_of_week_set |= 1;
+        }
+        Ok(CronSchedule {
+            expression: expression.to_string(),
+            minutes: parse_field(minutes, 0, 59)?,
+            hours: parse_field(hours, 0, 23)?,
+            days_of_month: parse_field(days_of_month, 1, 31)?,
+            months: parse_field(months, 1, 12)?,
+            days_of_week: days_of_week_set,
+            any_day_of_month: days_of_month == "*",
+            any_day_of_week: days_of_week == "*",
+        })
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+fn has(set: u64, value: u8) -> bool {
+    set & (1 << value) != 0
+}
+
+// The values a field matches, as a bit set.
+fn parse_field(field: &str, min: u8, max: u8) -> Result<u64, String> {
+    let invalid = || format!("Invalid schedule field: {:?}", field);
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u8>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (
+                start.parse::<u8>().map_err(|_| invalid())?,
+                end.parse::<u8>().map_err(|_| invalid())?,
+            ),
+            // `5/15` runs from 5 to the end of the range.
+            None if part.contains('/') => (range.parse::<u8>().map_err(|_| invalid())?, max),
+            None => {
+                let value = range.parse::<u8>().map_err(|_| invalid())?;
+                (value, value)
+            }
+        };
+        if step == 0 || start < min || end > max || start > end {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
@@ -1,30 +1,99 @@
-use serde::Deserialize;
-use starknet::accounts::Account;
+use std::{cmp::Ordering, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize, Serializer};
 use starknet::core::codec::{Decode, Encode};
-use starknet::core::types::{BlockId, BlockTag, Call, Felt, U256};
+use starknet::core::types::{Call, Felt, U256};
 use starknet::macros::selector;
 
-use super::starknet::{contract_address_felt, signer_account};
+use num_bigint::BigUint;
+
+use super::amount::{to_biguint, to_u256, Amount};
+use super::uint256::Uint256;
+
+// Bounds of Ekubo's sqrt ratio, a 64.128 fixed point number.
+const MIN_SQRT_RATIO: u128 = 18446748437148339063;
+const MAX_SQRT_RATIO: &str = "6277100250585753475930931601400621808602321654880405518632";
+
+const BPS: u32 = 10_000;
+
+// The 0.05% fee tier with a tick spacing of 1000.
+pub const DEFAULT_POOL_TIERS: &str = "170141183460469235273462165868118016:1000";
 
-#[derive(Debug, PartialEq, Eq, Deserialize, Clone, Encode, Decode)]
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Encode, Decode)]
 pub struct PoolKey {
     pub token0: Felt,
     pub token1: Felt,
+    // A 0.128 fixed point fraction of the amount, too large for JSON numbers.
+    #[serde(serialize_with = "u128_string")]
     pub fee: u128,
     pub tick_spacing: u128,
     pub extension: Felt,
 }
 
 impl PoolKey {
-    pub fn new(token0: Felt, token1: Felt) -> Self {
+    // The pool of `tier` between two tokens, in the order Ekubo requires.
+    pub fn new(token_a: Felt, token_b: Felt, tier: &PoolTier) -> Self {
+        let (token0, token1) = sort_tokens(token_a, token_b);
         PoolKey {
             token0,
             token1,
-            fee: 170141183460469235273462165868118016,
-            tick_spacing: 1000,
-            extension: Felt::ZERO,
+            fee: tier.fee,
+            tick_spacing: tier.tick_spacing,
+            extension: tier.extension,
         }
     }
+
+    // Whether selling `token` in this pool sells token1.
+    pub fn is_token1(&self, token: Felt) -> bool {
+        token == self.token1
+    }
+}
+
+// Fee, tick spacing and extension of an Ekubo pool, written as
+// `fee:tick_spacing` or `fee:tick_spacing:extension`.
+#[derive(Debug, PartialEq, Eq, Deserialize, Clone, Copy)]
+pub struct PoolTier {
+    pub fee: u128,
+    pub tick_spacing: u128,
+    pub extension: Felt,
+}
+
+impl FromStr for PoolTier {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid pool tier: {:?}", s);
+        let parts: Vec<&str> = s.trim().split(':').collect();
+        let (fee, tick_spacing, extension) = match parts[..] {
+            [fee, tick_spacing] => (fee, tick_spacing, None),
+            [fee, tick_spacing, extension] => (fee, tick_spacing, Some(extension)),
+            _ => return Err(invalid()),
+        };
+        Ok(PoolTier {
+            fee: fee.parse().map_err(|_| invalid())?,
+            tick_spacing: tick_spacing.parse().map_err(|_| invalid())?,
+            extension: match extension {
+                Some(extension) => Felt::from_hex(extension).map_err(|_| invalid())?,
+                None => Felt::ZERO,
+            },
+        })
+    }
+}
+
+// Order two tokens the way Ekubo orders the tokens of a pool, by address.
+pub fn sort_tokens(token_a: Felt, token_b: Felt) -> (Felt, Felt) {
+    match token_a.to_bytes_be().cmp(&token_b.to_bytes_be()) {
+        Ordering::Greater => (token_b, token_a),
+        _ => (token_a, token_b),
+    }
+}
+
+fn u128_string<S>(value: &u128, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_str(value)
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Encode, Decode)]
@@ -48,16 +117,57 @@ pub struct SwapParameters {
 }
 
 impl SwapParameters {
-    pub fn new(amount: I129, is_token1: bool) -> Self {
+    pub fn new(amount: I129, is_token1: bool, sqrt_ratio_limit: U256) -> Self {
         SwapParameters {
             amount,
             is_token1,
-            sqrt_ratio_limit: U256::from(18446748437148339061u128),
+            sqrt_ratio_limit,
             skip_ahead: 0,
         }
     }
 }
 
+// The sqrt ratio limit that lets a swap move the price as far as it goes.
+// Selling token0 lowers the price, selling token1 raises it.
+pub fn no_sqrt_ratio_limit(is_token1: bool) -> U256 {
+    match is_token1 {
+        false => U256::from(MIN_SQRT_RATIO),
+        true => max_sqrt_ratio(),
+    }
+}
+
+// The sqrt ratio limit that stops a swap once its price is `max_slippage_bps`
+// worse than the rate of swapping `amount_in` for `amount_out`. The pool price is
+// token1 per token0, so a swap selling token0 for the rate `out / in` may lower
+// the price to `out / in * (1 - slippage)`, and a swap selling token1 may raise
+// it to `in / out / (1 - slippage)`.
+pub fn sqrt_ratio_limit(
+    amount_in: Uint256,
+    amount_out: Uint256,
+    max_slippage_bps: u16,
+    is_token1: bool,
+) -> U256 {
+    let (amount_in, amount_out) = (to_biguint(amount_in.0), to_biguint(amount_out.0));
+    let kept = BPS.saturating_sub(u32::from(max_slippage_bps));
+    let (numerator, denominator) = match is_token1 {
+        false => (amount_out * kept, amount_in * BPS),
+        true => (amount_in * BPS, amount_out * kept),
+    };
+    if denominator == BigUint::from(0u8) {
+        return no_sqrt_ratio_limit(is_token1);
+    }
+
+    let ratio = ((numerator << 256) / denominator).sqrt();
+    let ratio = ratio.clamp(BigUint::from(MIN_SQRT_RATIO), to_biguint(max_sqrt_ratio()));
+    // Clamped to the bounds, which fit in a u256.
+    to_u256(&ratio).unwrap()
+}
+
+fn max_sqrt_ratio() -> U256 {
+    let ratio: BigUint = MAX_SQRT_RATIO.parse().unwrap();
+    to_u256(&ratio).unwrap()
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Encode, Decode)]
 pub struct SwapData {
     pub params: SwapParameters,
@@ -75,30 +185,32 @@ impl SwapData {
     }
 }
 
-type EkuboResponse = Result<
-    starknet::core::types::InvokeTransactionResult,
-    starknet::accounts::AccountError<
-        starknet::accounts::single_owner::SignError<starknet::signers::local_wallet::SignError>,
-    >,
->;
-
-pub async fn ekubo_swap(token0: Felt, token1: Felt, swap_amount: u128) -> EkuboResponse {
-    let mut account = signer_account();
-    let contract_address = contract_address_felt();
-
-    let pool_key = PoolKey::new(token0, token1);
-    let swap_parameters = SwapParameters::new(I129::new(swap_amount, false), false);
-    let swap_data = SwapData::new(swap_parameters, pool_key, account.address());
-
-    account.set_block_id(BlockId::Tag(BlockTag::Pending));
+// Calls that pay `swap_amount` of `from_token` to the router and swap it in
+// `pool_key` on behalf of `caller`. Ekubo amounts are i129, so swaps are limited
+// to amounts that fit in a u128.
+pub fn ekubo_swap_calls(
+    from_token: Felt,
+    pool_key: PoolKey,
+    swap_amount: Amount,
+    sqrt_ratio_limit: U256,
+    caller: Felt,
+    contract_address: Felt,
+) -> Result<Vec<Call>> {
+    let magnitude = swap_amount
+        .to_u128()
+        .ok_or_else(|| anyhow!("Swap amount {} is too large for Ekubo", swap_amount))?;
+    let is_token1 = pool_key.is_token1(from_token);
+    let swap_parameters =
+        SwapParameters::new(I129::new(magnitude, false), is_token1, sqrt_ratio_limit);
+    let swap_data = SwapData::new(swap_parameters, pool_key, caller);
 
     let mut serialized = vec![];
     swap_data.encode(&mut serialized).unwrap();
 
     let transfer_call = Call {
-        to: token0,
+        to: from_token,
         selector: selector!("transfer"),
-        calldata: vec![contract_address, Felt::from(swap_amount), Felt::ZERO],
+        calldata: [vec![contract_address], swap_amount.calldata().to_vec()].concat(),
     };
 
     let swap_call = Call {
@@ -107,8 +219,5 @@ pub async fn ekubo_swap(token0: Felt, token1: Felt, swap_amount: u128) -> EkuboR
         calldata: serialized,
     };
 
-    account
-        .execute_v3(vec![transfer_call, swap_call])
-        .send()
-        .await
+    Ok(vec![transfer_call, swap_call])
 }
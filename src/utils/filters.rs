@@ -0,0 +1,213 @@
+use sqlx::{Encode, Postgres, QueryBuilder, Type};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use uuid::Uuid;
+
+use super::uint256::Uint256;
+
+// A column compared by filters. Values compared to a column with a cast are
+// bound as text and cast in SQL, such as RFC 3339 times to TIMESTAMPTZ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Column {
+    name: &'static str,
+    cast: Option<&'static str>,
+}
+
+impl Column {
+    pub const fn new(name: &'static str) -> Self {
+        Column { name, cast: None }
+    }
+
+    pub const fn cast(name: &'static str, cast: &'static str) -> Self {
+        Column {
+            name,
+            cast: Some(cast),
+        }
+    }
+
+    // The column with its cast. ORDER BY reads a bare name as the output column
+    // of that name, such as `created_at` formatted as text, and reads an
+    // expression as the table column.
+    pub fn expression(&self) -> String {
+        match self.cast {
+            Some(cast) => format!("{}::{}", self.name, cast),
+            None => self.name.to_string(),
+        }
+    }
+
+    // Whether a text value can be cast to the column's type, so that a
+    // malformed value from a client is rejected before it fails a query.
+    pub fn accepts(&self, value: &str) -> bool {
+        match self.cast {
+            Some("TIMESTAMPTZ") => OffsetDateTime::parse(value, &Rfc3339).is_ok(),
+            Some("UUID") => Uuid::parse_str(value).is_ok(),
+            Some("NUMERIC") => value.parse::<Uint256>().is_ok(),
+            _ => true,
+        }
+    }
+}
+
+impl From<&'static str> for Column {
+    fn from(name: &'static str) -> Self {
+        Column::new(name)
+    }
+}
+
+// Comparison of a column to a bound value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    fn as_sql(self) -> &'static str {
+        match self {
+            Op::Eq => " = ",
+            Op::Lt => " < ",
+            Op::Le => " <= ",
+            Op::Gt => " > ",
+            Op::Ge => " >= ",
+        }
+    }
+}
+
+// WHERE clause of a query built from optional filters. Column names and
+// operators are static SQL and every value is a bind parameter, so filters can
+// come straight from request parameters. Filters given `None` are left out.
+pub struct Filters<'args> {
+    builder: QueryBuilder<'args, Postgres>,
+    filtered: bool,
+}
+
+impl<'args> Filters<'args> {
+    // Filter the rows of `query`, a statement without a WHERE clause.
+    pub fn new(query: &str) -> Self {
+        Filters {
+            builder: QueryBuilder::new(query),
+            filtered: false,
+        }
+    }
+
+    // `column = value`.
+    pub fn eq<T>(&mut self, column: impl Into<Column>, value: Option<T>) -> &mut Self
+    where
+        T: 'args + Encode<'args, Postgres> + Type<Postgres> + Send,
+    {
+        self.compare(column, Op::Eq, value)
+    }
+
+    // `column op value`.
+    pub fn compare<T>(&mut self, column: impl Into<Column>, op: Op, value: Option<T>) -> &mut Self
+    where
+        T: 'args + Encode<'args, Postgres> + Type<Postgres> + Send,
+    {
+        if let Some(value) = value {
+            let column = column.into();
+            self.condition().push(column.name).push(op.as_sql());
+            self.push_value(column, value);
+        }
+        self
+    }
+
+    // `(columns) op (values)`, comparing rows in the order of `columns`, with
+    // one value per column.
+    pub fn compare_row<T>(
+        &mut self,
+        columns: &[Column],
+        op: Op,
+        values: Option<Vec<T>>,
+    ) -> &mut Self
+    where
+        T: 'args + Encode<'args, Postgres> + Type<Postgres> + Send,
+    {
+        let values = match values {
+            Some(values) => values,
+            None => return self,
+        };
+
+        self.condition().push("(");
+        for (i, column) in columns.iter().enumerate() {
+            if i > 0 {
+                self.builder.push(", ");
+            }
+            self.builder.push(column.name);
+        }
+        self.builder.push(")").push(op.as_sql()).push("(");
+        for (i, (column, value)) in columns.iter().zip(values).enumerate() {
+            if i > 0 {
+                self.builder.push(", ");
+            }
+            self.push_value(*column, value);
+        }
+        self.builder.push(")");
+        self
+    }
+
+    // `min <= column <= max`, either bound being optional.
+    pub fn range<T>(
+        &mut self,
+        column: impl Into<Column>,
+        min: Option<T>,
+        max: Option<T>,
+    ) -> &mut Self
+    where
+        T: 'args + Encode<'args, Postgres> + Type<Postgres> + Send,
+    {
+        let column = column.into();
+        self.compare(column, Op::Ge, min)
+            .compare(column, Op::Le, max)
+    }
+
+    // `column IN (values)`. No row matches an empty list.
+    pub fn any_of<T>(&mut self, column: impl Into<Column>, values: Option<Vec<T>>) -> &mut Self
+    where
+        T: 'args + Encode<'args, Postgres> + Type<Postgres> + Send,
+    {
+        let values = match values {
+            Some(values) => values,
+            None => return self,
+        };
+        if values.is_empty() {
+            self.condition().push("FALSE");
+            return self;
+        }
+
+        let column = column.into();
+        self.condition().push(column.name).push(" IN (");
+        for (i, value) in values.into_iter().enumerate() {
+            if i > 0 {
+                self.builder.push(", ");
+            }
+            self.push_value(column, value);
+        }
+        self.builder.push(")");
+        self
+    }
+
+    // The query built so far, to add ORDER BY and LIMIT clauses to.
+    pub fn into_builder(self) -> QueryBuilder<'args, Postgres> {
+        self.builder
+    }
+
+    fn condition(&mut self) -> &mut QueryBuilder<'args, Postgres> {
+        match self.filtered {
+            true => self.builder.push(" AND "),
+            false => self.builder.push(" WHERE "),
+        };
+        self.filtered = true;
+        &mut self.builder
+    }
+
+    fn push_value<T>(&mut self, column: Column, value: T)
+    where
+        T: 'args + Encode<'args, Postgres> + Type<Postgres> + Send,
+    {
+        self.builder.push_bind(value);
+        if let Some(cast) = column.cast {
+            self.builder.push("::").push(cast);
+        }
+    }
+}
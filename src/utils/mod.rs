@@ -1,3 +1,8 @@
-pub mod anvu;
+pub mod amount;
+pub mod avnu;
+pub mod cron;
 pub mod ekubo;
+pub mod filters;
+pub mod pagination;
 pub mod starknet;
+pub mod uint256;
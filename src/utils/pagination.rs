@@ -0,0 +1,170 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, QueryBuilder};
+use thiserror::Error;
+
+use super::filters::{Column, Filters, Op};
+
+// Rows in a page unless the client asks for another size.
+pub const DEFAULT_PAGE_SIZE: i64 = 10;
+// Most rows a client can ask for in a page.
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PaginationError {
+    #[error("Invalid cursor")]
+    InvalidCursor,
+    #[error("limit must be between 1 and {}", MAX_PAGE_SIZE)]
+    InvalidLimit,
+}
+
+// Order of a listing: by `key`, then by `id` between rows with the same key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Keyset {
+    pub key: Column,
+    pub id: Column,
+    pub descending: bool,
+}
+
+// A row of a paged listing.
+pub trait Paged {
+    // Values of the row's key and id columns, as text.
+    fn position(&self) -> (String, String);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Direction {
+    Next,
+    Prev,
+}
+
+// The rows after or before a row of a listing. Clients get it as URL-safe
+// base64 JSON and pass it back unchanged.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Cursor {
+    key: String,
+    id: String,
+    direction: Direction,
+}
+
+impl Cursor {
+    fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).expect("Cursor is serializable"))
+    }
+
+    fn decode(cursor: &str) -> Option<Self> {
+        let json = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+        serde_json::from_slice(&json).ok()
+    }
+}
+
+// A page of a listing, with the cursors of the pages next to it.
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub data: Vec<T>,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+}
+
+// The page of a listing a client asked for.
+#[derive(Debug, Clone)]
+pub struct Pagination {
+    keyset: Keyset,
+    cursor: Option<Cursor>,
+    limit: i64,
+}
+
+impl Pagination {
+    pub fn new(
+        keyset: Keyset,
+        cursor: Option<&str>,
+        limit: Option<i64>,
+    ) -> Result<Self, PaginationError> {
+        let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
+        if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+            return Err(PaginationError::InvalidLimit);
+        }
+        let cursor = match cursor {
+            Some(cursor) => match Cursor::decode(cursor) {
+                Some(cursor)
+                    if keyset.key.accepts(&cursor.key) && keyset.id.accepts(&cursor.id) =>
+                {
+                    Some(cursor)
+                }
+                _ => return Err(PaginationError::InvalidCursor),
+            },
+            None => None,
+        };
+        Ok(Pagination {
+            keyset,
+            cursor,
+            limit,
+        })
+    }
+
+    // Restrict a query to the rows of the page and order them. One row more
+    // than the page holds is fetched to tell whether another page follows.
+    pub fn apply<'args>(&self, mut filters: Filters<'args>) -> QueryBuilder<'args, Postgres> {
+        // Pages before the cursor are read backwards from it.
+        let ascending = self.keyset.descending == self.backwards();
+        if let Some(cursor) = &self.cursor {
+            filters.compare_row(
+                &[self.keyset.key, self.keyset.id],
+                if ascending { Op::Gt } else { Op::Lt },
+                Some(vec![cursor.key.clone(), cursor.id.clone()]),
+            );
+        }
+
+        let order = if ascending { "ASC" } else { "DESC" };
+        let mut query = filters.into_builder();
+        query
+            .push(" ORDER BY ")
+            .push(self.keyset.key.expression())
+            .push(" ")
+            .push(order)
+            .push(", ")
+            .push(self.keyset.id.expression())
+            .push(" ")
+            .push(order)
+            .push(" LIMIT ")
+            .push_bind(self.limit + 1);
+        query
+    }
+
+    // The page of the rows fetched by a query `apply` was applied to.
+    pub fn page<T: Paged>(&self, mut rows: Vec<T>) -> Page<T> {
+        let more = rows.len() as i64 > self.limit;
+        rows.truncate(self.limit as usize);
+        if self.backwards() {
+            rows.reverse();
+        }
+
+        // A page reached from a cursor has rows on the side it came from.
+        let (prev, next) = match self.backwards() {
+            true => (more, self.cursor.is_some()),
+            false => (self.cursor.is_some(), more),
+        };
+        let cursor = |row: &T, direction| {
+            let (key, id) = row.position();
+            Cursor { key, id, direction }.encode()
+        };
+        Page {
+            prev_cursor: rows
+                .first()
+                .filter(|_| prev)
+                .map(|row| cursor(row, Direction::Prev)),
+            next_cursor: rows
+                .last()
+                .filter(|_| next)
+                .map(|row| cursor(row, Direction::Next)),
+            data: rows,
+        }
+    }
+
+    fn backwards(&self) -> bool {
+        self.cursor
+            .as_ref()
+            .is_some_and(|cursor| cursor.direction == Direction::Prev)
+    }
+}
@@ -1,28 +1,64 @@
-use std::{env::var, sync::LazyLock};
-
 use starknet::{
     accounts::{ExecutionEncoding, SingleOwnerAccount},
-    core::{
-        chain_id,
-        types::{BlockId, BlockTag, Felt, FunctionCall},
-    },
+    core::types::{BlockId, BlockTag, Felt, FunctionCall},
     macros::selector,
     providers::{
         jsonrpc::{HttpTransport, JsonRpcClient},
-        Provider, Url,
+        Provider, ProviderError, Url,
     },
     signers::{LocalWallet, SigningKey},
 };
 
-static RPC_URL: LazyLock<String> = LazyLock::new(|| var("RPC_URL").unwrap());
-static CONTRACT_ADDRESS: LazyLock<String> = LazyLock::new(|| var("CONTRACT_ADDRESS").unwrap());
+use crate::Configuration;
+
+pub type RpcProvider = JsonRpcClient<HttpTransport>;
+
+// Account that quotes and sends the swaps.
+pub type SwapAccount = SingleOwnerAccount<RpcProvider, LocalWallet>;
+
+pub fn rpc_provider(rpc_url: &Url) -> RpcProvider {
+    JsonRpcClient::new(HttpTransport::new(rpc_url.clone()))
+}
+
+// The configured swap account.
+pub fn swap_account(config: &Configuration) -> SwapAccount {
+    account_at(config, config.account_address, config.private_key)
+}
+
+// The configured swap account followed by the executor accounts.
+pub fn swap_accounts(config: &Configuration) -> Vec<SwapAccount> {
+    let executors = config
+        .executor_accounts
+        .iter()
+        .filter(|executor| executor.address != config.account_address)
+        .map(|executor| account_at(config, executor.address, executor.private_key));
+    std::iter::once(swap_account(config))
+        .chain(executors)
+        .collect()
+}
 
-fn rpc_provider() -> JsonRpcClient<HttpTransport> {
-    JsonRpcClient::new(HttpTransport::new(Url::parse(&RPC_URL).unwrap()))
+// An account on the configured node. Its nonce is read from the pending block,
+// so that transactions still waiting to be included are counted.
+fn account_at(config: &Configuration, address: Felt, private_key: Felt) -> SwapAccount {
+    let signer = LocalWallet::from(SigningKey::from_secret_scalar(private_key));
+    let mut account = SingleOwnerAccount::new(
+        rpc_provider(&config.rpc_url),
+        signer,
+        address,
+        config.chain.chain_id(),
+        ExecutionEncoding::New,
+    );
+    account.set_block_id(BlockId::Tag(BlockTag::Pending));
+    account
 }
 
-pub fn contract_address_felt() -> Felt {
-    Felt::from_hex(&CONTRACT_ADDRESS).unwrap()
+// Convert a felt to u128, returning None if it does not fit.
+pub fn felt_to_u128(value: Felt) -> Option<u128> {
+    let digits = value.to_le_digits();
+    match digits[2] == 0 && digits[3] == 0 {
+        true => Some(((digits[1] as u128) << 64) | digits[0] as u128),
+        false => None,
+    }
 }
 
 // Define an enum for supported tokens
@@ -41,10 +77,12 @@ impl TokenType {
     }
 }
 
-pub async fn get_token_usd_price_and_decimal(token: TokenType) -> (u64, u64) {
-    let provider = rpc_provider();
-    let contract_address = contract_address_felt();
-
+// USD price of `token` and its decimals, as reported by the router contract.
+pub async fn get_token_usd_price_and_decimal<P: Provider + Sync>(
+    provider: &P,
+    contract_address: Felt,
+    token: TokenType,
+) -> Result<(u64, u64), ProviderError> {
     let call_result = provider
         .call(
             FunctionCall {
@@ -54,38 +92,46 @@ pub async fn get_token_usd_price_and_decimal(token: TokenType) -> (u64, u64) {
             },
             BlockId::Tag(BlockTag::Latest),
         )
-        .await
-        .unwrap_or_else(|_| panic!("failed to get {:?} price", token));
-
-    let (price, decimal) = (
-        call_result[0].to_le_digits()[0],
-        call_result[1].to_le_digits()[0],
-    );
+        .await?;
 
-    (price, decimal)
+    let digit = |i: usize| call_result.get(i).map_or(0, |felt| felt.to_le_digits()[0]);
+    Ok((digit(0), digit(1)))
 }
 
-pub async fn get_eth_usd_price_and_decimal() -> (u64, u64) {
-    get_token_usd_price_and_decimal(TokenType::ETH).await
+pub async fn get_eth_usd_price_and_decimal<P: Provider + Sync>(
+    provider: &P,
+    contract_address: Felt,
+) -> Result<(u64, u64), ProviderError> {
+    get_token_usd_price_and_decimal(provider, contract_address, TokenType::ETH).await
 }
 
-pub async fn get_strk_usd_price_and_decimal() -> (u64, u64) {
-    get_token_usd_price_and_decimal(TokenType::STRK).await
+pub async fn get_strk_usd_price_and_decimal<P: Provider + Sync>(
+    provider: &P,
+    contract_address: Felt,
+) -> Result<(u64, u64), ProviderError> {
+    get_token_usd_price_and_decimal(provider, contract_address, TokenType::STRK).await
 }
 
-pub fn signer_account() -> SingleOwnerAccount<JsonRpcClient<HttpTransport>, LocalWallet> {
-    let provider = rpc_provider();
-    let private_key = var("PRIVATE_KEY").unwrap();
-    let public_key = var("PUBLIC_KEY").unwrap();
-    let signer = LocalWallet::from(SigningKey::from_secret_scalar(
-        Felt::from_hex(&private_key).unwrap(),
-    ));
-    let address = Felt::from_hex(&public_key).unwrap();
-    SingleOwnerAccount::new(
-        provider,
-        signer,
-        address,
-        chain_id::MAINNET,
-        ExecutionEncoding::New,
-    )
+// Median spot price of `pair_id`, e.g. "ETH/USD" as a short string, and its
+// decimals, as reported by a Pragma oracle.
+pub async fn get_pragma_price_and_decimal<P: Provider + Sync>(
+    provider: &P,
+    oracle_address: Felt,
+    pair_id: Felt,
+) -> Result<(u128, u32), ProviderError> {
+    let call_result = provider
+        .call(
+            FunctionCall {
+                contract_address: oracle_address,
+                entry_point_selector: selector!("get_data_median"),
+                // `DataType::SpotEntry(pair_id)`.
+                calldata: vec![Felt::ZERO, pair_id],
+            },
+            BlockId::Tag(BlockTag::Latest),
+        )
+        .await?;
+
+    let value = |i: usize| call_result.get(i).copied().and_then(felt_to_u128);
+    let decimals = value(1).and_then(|decimals| u32::try_from(decimals).ok());
+    Ok((value(0).unwrap_or(0), decimals.unwrap_or(0)))
 }
@@ -0,0 +1,203 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use num_bigint::BigUint;
+use serde::{de::Visitor, Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::{
+    encode::IsNull,
+    error::BoxDynError,
+    postgres::{PgArgumentBuffer, PgTypeInfo, PgValueFormat, PgValueRef},
+    Decode, Encode, Postgres, Type,
+};
+use starknet::core::types::U256;
+
+use super::amount::{to_biguint, to_u256, AmountError};
+
+// Base of the digits of a Postgres NUMERIC.
+const NBASE: u16 = 10_000;
+// Sign of a positive NUMERIC.
+const NUMERIC_POS: u16 = 0x0000;
+
+// A u256 stored as NUMERIC(78, 0) in Postgres and written as a decimal string
+// in JSON, so amounts keep their full precision everywhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Uint256(pub U256);
+
+impl Uint256 {
+    pub fn zero() -> Self {
+        Uint256(U256::from(0u8))
+    }
+}
+
+impl Ord for Uint256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.0.high(), self.0.low()).cmp(&(other.0.high(), other.0.low()))
+    }
+}
+
+impl PartialOrd for Uint256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<U256> for Uint256 {
+    fn from(value: U256) -> Self {
+        Uint256(value)
+    }
+}
+
+impl From<Uint256> for U256 {
+    fn from(value: Uint256) -> Self {
+        value.0
+    }
+}
+
+impl From<u128> for Uint256 {
+    fn from(value: u128) -> Self {
+        Uint256(U256::from(value))
+    }
+}
+
+impl fmt::Display for Uint256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&to_biguint(self.0).to_string())
+    }
+}
+
+// Parse a decimal integer such as "1000".
+impl FromStr for Uint256 {
+    type Err = AmountError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(AmountError::Invalid(value.to_string()));
+        }
+        let value = value
+            .parse::<BigUint>()
+            .map_err(|_| AmountError::Invalid(value.to_string()))?;
+        Ok(Uint256(to_u256(&value)?))
+    }
+}
+
+impl Serialize for Uint256 {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+// Accepts a decimal string, or a JSON number for amounts that fit in a u64.
+impl<'de> Deserialize<'de> for Uint256 {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct Uint256Visitor;
+
+        impl Visitor<'_> for Uint256Visitor {
+            type Value = Uint256;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.pad("a non-negative integer or decimal string")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                v.parse().map_err(E::custom)
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(Uint256::from(u128::from(v)))
+            }
+        }
+
+        deserializer.deserialize_any(Uint256Visitor)
+    }
+}
+
+impl Type<Postgres> for Uint256 {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("numeric")
+    }
+}
+
+// Binary NUMERIC: digit count, weight of the first digit, sign and display
+// scale, followed by the digits in base 10000, most significant first.
+impl Encode<'_, Postgres> for Uint256 {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        let decimal = self.to_string();
+        let padding = (4 - decimal.len() % 4) % 4;
+        let decimal = format!("{}{}", "0".repeat(padding), decimal);
+        let mut digits: Vec<i16> = decimal
+            .as_bytes()
+            .chunks(4)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .fold(0, |acc, b| acc * 10 + i16::from(b - b'0'))
+            })
+            .collect();
+
+        let weight = digits.len() as i16 - 1;
+        // Trailing zero digits are implied by the weight.
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        let weight = if digits.is_empty() { 0 } else { weight };
+
+        buf.extend_from_slice(&(digits.len() as i16).to_be_bytes());
+        buf.extend_from_slice(&weight.to_be_bytes());
+        buf.extend_from_slice(&NUMERIC_POS.to_be_bytes());
+        buf.extend_from_slice(&0u16.to_be_bytes());
+        for digit in digits {
+            buf.extend_from_slice(&digit.to_be_bytes());
+        }
+        Ok(IsNull::No)
+    }
+}
+
+impl Decode<'_, Postgres> for Uint256 {
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        match value.format() {
+            PgValueFormat::Binary => decode_numeric(value.as_bytes()?),
+            PgValueFormat::Text => Ok(value.as_str()?.parse()?),
+        }
+    }
+}
+
+fn decode_numeric(bytes: &[u8]) -> Result<Uint256, BoxDynError> {
+    let word = |i: usize| -> Result<[u8; 2], BoxDynError> {
+        let word = bytes
+            .get(i * 2..i * 2 + 2)
+            .ok_or("NUMERIC value is truncated")?;
+        Ok([word[0], word[1]])
+    };
+    let count = i16::from_be_bytes(word(0)?);
+    let weight = i16::from_be_bytes(word(1)?);
+    let sign = u16::from_be_bytes(word(2)?);
+    let digits = (0..count.max(0) as usize)
+        .map(|i| word(i + 4).map(u16::from_be_bytes))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if sign != NUMERIC_POS {
+        return Err("NUMERIC value is negative or not a number".into());
+    }
+    // Digits past the weight are the fractional part.
+    let whole = (weight.max(-1) + 1) as usize;
+    if digits.iter().skip(whole).any(|digit| *digit != 0) {
+        return Err("NUMERIC value is not an integer".into());
+    }
+
+    let mut value = BigUint::from(0u8);
+    for i in 0..whole {
+        value = value * NBASE + digits.get(i).copied().unwrap_or(0);
+    }
+    Ok(Uint256(to_u256(&value)?))
+}
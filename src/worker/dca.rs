@@ -0,0 +1,61 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use time::OffsetDateTime;
+
+use crate::{
+    service::dca::{claim_due, run_due},
+    Db,
+};
+
+// Queues the swaps of dollar-cost averaging schedules when they are due,
+// checking every `interval`.
+pub struct DcaScheduler {
+    db: Db,
+    max_attempts: i32,
+    interval: Duration,
+}
+
+impl DcaScheduler {
+    pub fn new(db: Db, max_attempts: i32, interval: Duration) -> Self {
+        DcaScheduler {
+            db,
+            max_attempts,
+            interval,
+        }
+    }
+
+    // Run due schedules forever.
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.tick(OffsetDateTime::now_utc()).await {
+                tracing::error!("DCA scheduling failed: {:#}", err);
+            }
+        }
+    }
+
+    // Run every schedule that is due at `now` once. Each schedule is claimed,
+    // queued and moved to its next run in one transaction, so a run is never
+    // queued twice, whether by several schedulers or after a restart. Returns the
+    // number of swaps queued.
+    pub async fn tick(&self, now: OffsetDateTime) -> Result<usize> {
+        let mut queued = 0;
+        loop {
+            let mut tx = self.db.pool.begin().await?;
+            let dca = match claim_due(&mut tx, now).await? {
+                Some(dca) => dca,
+                None => return Ok(queued),
+            };
+            match run_due(&mut tx, &dca, now, self.max_attempts).await? {
+                Some(job_id) => {
+                    tracing::debug!("DCA schedule {} queued swap job {}", dca.id, job_id);
+                    queued += 1;
+                }
+                None => tracing::warn!("DCA schedule {} skipped its run", dca.id),
+            }
+            tx.commit().await?;
+        }
+    }
+}
@@ -0,0 +1,19 @@
+// Background workers that drain the swap job queue, follow the submitted
+// transactions on chain, keep a history of token prices, run DCA schedules,
+// release the swaps held for a price trigger and fire protection rules.
+mod dca;
+mod prices;
+mod protection;
+mod receipts;
+mod swap;
+mod triggers;
+
+pub use dca::DcaScheduler;
+pub use prices::PriceSnapshotter;
+pub use protection::ProtectionEvaluator;
+pub use receipts::{split_outcome, transaction_outcome, ReceiptTracker};
+pub use swap::{
+    process_next_batch, process_next_job, run_swap_worker, BatchSettings, RouterExecutor,
+    SwapExecutor,
+};
+pub use triggers::TriggerEvaluator;
@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use time::OffsetDateTime;
+
+use crate::{
+    service::{price_history::record, pricing::PriceSource, tokens},
+    Db,
+};
+
+// Stores the USD price of every active token in `token_price_history` once
+// per `interval`.
+pub struct PriceSnapshotter<S> {
+    source: S,
+    db: Db,
+    interval: Duration,
+}
+
+impl<S: PriceSource> PriceSnapshotter<S> {
+    pub fn new(source: S, db: Db, interval: Duration) -> Self {
+        PriceSnapshotter {
+            source,
+            db,
+            interval,
+        }
+    }
+
+    // Take snapshots forever.
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.snapshot(OffsetDateTime::now_utc()).await {
+                tracing::error!("Price snapshot failed: {:#}", err);
+            }
+        }
+    }
+
+    // Store the price of every active token at `now` once. Tokens without a
+    // price, or whose price cannot be read, are skipped. Returns the number of
+    // prices stored.
+    pub async fn snapshot(&self, now: OffsetDateTime) -> Result<usize> {
+        let mut recorded = 0;
+        for token in tokens::list(&self.db.pool, false).await? {
+            let price = match self.source.usd_price(&token).await {
+                Ok(Some(price)) => price,
+                Ok(None) => continue,
+                Err(err) => {
+                    tracing::warn!("Unable to price {}: {:#}", token.contract_address, err);
+                    continue;
+                }
+            };
+            let price_usd = match price.to_decimal_string() {
+                Some(price_usd) if price.price > 0 => price_usd,
+                _ => continue,
+            };
+            record(&self.db.pool, &token.contract_address, &price_usd, now).await?;
+            recorded += 1;
+        }
+        Ok(recorded)
+    }
+}
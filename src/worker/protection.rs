@@ -0,0 +1,200 @@
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::{anyhow, Context, Result};
+use starknet::{core::types::Felt, providers::Provider};
+use time::OffsetDateTime;
+
+use crate::{
+    service::{
+        balances::{allowance, balance_of},
+        pricing::{PriceSource, UsdPrice},
+        protection::{list_watched, lock_active, mark_fired, set_error, WatchedRule},
+        swap_jobs::{enqueue, NewSwapJob},
+        tokens,
+        transaction_logs::record_firing,
+        triggers::{evaluate, TriggerOutcome},
+    },
+    utils::{amount::Amount, uint256::Uint256},
+    Db,
+};
+
+// Most rules read at once.
+const BATCH_SIZE: i64 = 500;
+
+// Checks the protection rules of subscribed wallets against the prices of their
+// tokens every `interval`. A rule whose price is met queues a swap of the
+// wallet's whole balance, pulled from the wallet once the swap account is
+// allowed to spend it.
+pub struct ProtectionEvaluator<S, P> {
+    source: S,
+    provider: P,
+    db: Db,
+    // Swap account that pulls the swapped tokens from wallets.
+    spender: Felt,
+    max_attempts: i32,
+    interval: Duration,
+}
+
+impl<S, P> ProtectionEvaluator<S, P>
+where
+    S: PriceSource,
+    P: Provider + Send + Sync,
+{
+    pub fn new(
+        source: S,
+        provider: P,
+        db: Db,
+        spender: Felt,
+        max_attempts: i32,
+        interval: Duration,
+    ) -> Self {
+        ProtectionEvaluator {
+            source,
+            provider,
+            db,
+            spender,
+            max_attempts,
+            interval,
+        }
+    }
+
+    // Evaluate rules forever.
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.check(OffsetDateTime::now_utc()).await {
+                tracing::error!("Protection rule evaluation failed: {:#}", err);
+            }
+        }
+    }
+
+    // Evaluate every active rule once at `now`, each token being priced once.
+    // Returns the number of rules fired.
+    pub async fn check(&self, now: OffsetDateTime) -> Result<usize> {
+        let mut prices: HashMap<String, Option<UsdPrice>> = HashMap::new();
+        let mut fired = 0;
+        // Rules are read in batches past the last one read, so that the rules
+        // that stay active do not hold back the ones behind them.
+        let mut after: Option<WatchedRule> = None;
+        loop {
+            let batch = list_watched(&self.db.pool, after.as_ref(), BATCH_SIZE).await?;
+            for rule in &batch {
+                if self.check_rule(rule, &mut prices, now).await? {
+                    fired += 1;
+                }
+            }
+            if batch.len() < BATCH_SIZE as usize {
+                return Ok(fired);
+            }
+            after = batch.into_iter().last();
+        }
+    }
+
+    // Evaluate an active rule. Returns whether it fired.
+    async fn check_rule(
+        &self,
+        rule: &WatchedRule,
+        prices: &mut HashMap<String, Option<UsdPrice>>,
+        now: OffsetDateTime,
+    ) -> Result<bool> {
+        let token = rule.token.to_lowercase();
+        let price = match prices.get(&token) {
+            Some(price) => *price,
+            None => {
+                let price = self.price(&token).await;
+                prices.insert(token, price);
+                price
+            }
+        };
+        let trigger = match rule.trigger() {
+            Some(trigger) => trigger,
+            None => {
+                tracing::warn!("Protection rule {} has an invalid price", rule.id);
+                return Ok(false);
+            }
+        };
+        if evaluate(&trigger, price, now) != TriggerOutcome::Fired {
+            return Ok(false);
+        }
+
+        let job = match self.swap_job(rule).await {
+            Ok(Some(job)) => job,
+            // Nothing to protect.
+            Ok(None) => return Ok(false),
+            Err(err) => {
+                let error = format!("{:#}", err);
+                tracing::warn!("Protection rule {} cannot fire: {}", rule.id, error);
+                set_error(&self.db.pool, rule.id, &error).await?;
+                return Ok(false);
+            }
+        };
+
+        let mut tx = self.db.pool.begin().await?;
+        // Skip rules another evaluator got to first.
+        if !lock_active(&mut tx, rule.id).await? {
+            return Ok(false);
+        }
+        let job_id = enqueue(&mut tx, &job).await?;
+        let price_usd = price.and_then(|price| price.to_decimal_string());
+        mark_fired(&mut tx, rule.id, job_id, price_usd.as_deref(), now).await?;
+        let amount_usd = price.and_then(|price| price.value_of(&job.amount));
+        record_firing(&mut tx, rule.id, job_id, &job, amount_usd).await?;
+        tx.commit().await?;
+        tracing::info!("Protection rule {} fired as swap job {}", rule.id, job_id);
+        Ok(true)
+    }
+
+    // The swap of the wallet's whole balance of the rule's token, or `None` if
+    // it holds none. Fails if the swap account may not spend the whole balance.
+    async fn swap_job(&self, rule: &WatchedRule) -> Result<Option<NewSwapJob>> {
+        let token = Felt::from_hex(&rule.token).context("Invalid token")?;
+        let owner = Felt::from_hex(&rule.wallet_address).context("Invalid wallet")?;
+        let decimals = u8::try_from(rule.token_decimals).context("Invalid decimals")?;
+
+        let balance = balance_of(&self.provider, token, owner).await?;
+        let amount = Amount::from_raw(balance, decimals);
+        if amount.is_zero() {
+            return Ok(None);
+        }
+        let allowed = allowance(&self.provider, token, owner, self.spender).await?;
+        if Uint256::from(allowed) < Uint256::from(balance) {
+            return Err(anyhow!(
+                "Allowance of {} is below the balance of {}",
+                Amount::from_raw(allowed, decimals),
+                amount
+            ));
+        }
+
+        Ok(Some(NewSwapJob {
+            wallet_address: rule.wallet_address.clone(),
+            from_token: rule.token.clone(),
+            to_token: rule.to_token.clone(),
+            percentage: 100,
+            amount,
+            max_slippage_bps: rule.max_slippage_bps,
+            venue: rule.venue.clone(),
+            pull_from_wallet: true,
+            max_attempts: self.max_attempts,
+        }))
+    }
+
+    // USD price of a token, or `None` if it has none or cannot be read.
+    async fn price(&self, address: &str) -> Option<UsdPrice> {
+        let token = match tokens::get(&self.db.pool, address).await {
+            Ok(Some(token)) => token,
+            Ok(None) => return None,
+            Err(err) => {
+                tracing::warn!("Unable to read token {}: {}", address, err);
+                return None;
+            }
+        };
+        match self.source.usd_price(&token).await {
+            Ok(price) => price,
+            Err(err) => {
+                tracing::warn!("Unable to price {}: {:#}", address, err);
+                None
+            }
+        }
+    }
+}
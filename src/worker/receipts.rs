@@ -0,0 +1,447 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use num_bigint::BigUint;
+use starknet::{
+    core::types::{
+        Event, ExecutionResult, Felt, PriceUnit, ReceiptBlock, StarknetError,
+        TransactionFinalityStatus, TransactionReceipt, TransactionReceiptWithBlockInfo,
+        TransactionStatus as NodeStatus, U256,
+    },
+    providers::{Provider, ProviderError},
+};
+
+use crate::{
+    indexer::decode_transfer,
+    service::{
+        nonces::NonceManager,
+        pricing::Pricing,
+        swap_jobs::{
+            list_by_transaction, list_expired, list_submitted, mark_confirmed, mark_failed,
+            mark_reverted, recover_sending, SwapJob, SwapJobStatus,
+        },
+        tokens::{self, Token},
+        transaction_logs::{
+            list_unproven, record_outcome, update_status, TransactionOutcome, TransactionStatus,
+            UsdValues,
+        },
+    },
+    utils::amount::{to_biguint, to_u256, Amount},
+    Db,
+};
+
+// Transactions checked per poll.
+const BATCH_SIZE: i64 = 100;
+
+// Follows submitted swap transactions until they are accepted or reverted and
+// records the outcome in `transactions_log`.
+pub struct ReceiptTracker<P> {
+    provider: P,
+    db: Db,
+    // Prices the swapped amounts in USD.
+    pricing: Pricing,
+    // Nonces of the accounts the swaps are sent from.
+    nonces: NonceManager,
+    // How long a sent transaction may stay unknown to the node before it is
+    // checked for having been dropped, and a claimed job may stay sending.
+    submission_timeout: Duration,
+    poll_interval: Duration,
+}
+
+impl<P> ReceiptTracker<P>
+where
+    P: Provider + Send + Sync,
+{
+    pub fn new(
+        provider: P,
+        db: Db,
+        pricing: Pricing,
+        nonces: NonceManager,
+        submission_timeout: Duration,
+        poll_interval: Duration,
+    ) -> Self {
+        ReceiptTracker {
+            provider,
+            db,
+            pricing,
+            nonces,
+            submission_timeout,
+            poll_interval,
+        }
+    }
+
+    // Poll for receipts forever.
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(self.poll_interval);
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.poll().await {
+                tracing::error!("Receipt tracker poll failed: {:#}", err);
+            }
+        }
+    }
+
+    // Check the receipts of submitted swaps once. Returns the number of swaps
+    // whose outcome was recorded.
+    pub async fn poll(&self) -> Result<usize> {
+        let mut conn = self.db.pool.acquire().await?;
+        // Jobs a worker claimed but never recorded a result for.
+        for (id, status) in recover_sending(&mut conn, self.submission_timeout).await? {
+            match status {
+                SwapJobStatus::Unknown => tracing::error!(
+                    "Swap job {} may have been sent without a recorded transaction, check it on chain",
+                    id
+                ),
+                _ => tracing::warn!("Swap job {} was sent without a recorded result", id),
+            }
+        }
+        let jobs = list_submitted(&mut conn, BATCH_SIZE).await?;
+
+        // Jobs sent in one transaction are recorded together.
+        let mut hashes: Vec<&str> = vec![];
+        for job in &jobs {
+            match job.transaction_hash.as_deref() {
+                Some(hash) if !hashes.contains(&hash) => hashes.push(hash),
+                Some(_) => {}
+                None => tracing::warn!("Swap job {} has no transaction hash", job.id),
+            }
+        }
+        let mut recorded = 0;
+        for hash in hashes {
+            let batch = list_by_transaction(&mut conn, hash).await?;
+            if self.track_transaction(hash, &batch).await? {
+                recorded += batch.len();
+            }
+        }
+
+        // Successful swaps stay `accepted_on_l2` until their block is proven on L1.
+        for tx_hash in list_unproven(&mut conn, BATCH_SIZE).await? {
+            let hash = match Felt::from_hex(&tx_hash) {
+                Ok(hash) => hash,
+                Err(_) => continue,
+            };
+            let receipt = match self.receipt(hash).await? {
+                Some(receipt) => receipt,
+                None => continue,
+            };
+            if let (
+                TransactionFinalityStatus::AcceptedOnL1,
+                ReceiptBlock::Block { block_number, .. },
+            ) = (receipt.receipt.finality_status(), &receipt.block)
+            {
+                update_status(
+                    &mut conn,
+                    &tx_hash,
+                    TransactionStatus::AcceptedOnL1,
+                    *block_number,
+                )
+                .await?;
+            }
+        }
+
+        Ok(recorded)
+    }
+
+    // Record the outcome of the transaction the swaps of `jobs` were sent in, if
+    // it has one yet.
+    async fn track_transaction(&self, tx_hash: &str, jobs: &[SwapJob]) -> Result<bool> {
+        let hash = match Felt::from_hex(tx_hash) {
+            Ok(hash) => hash,
+            Err(_) => {
+                tracing::warn!("Swap jobs have an invalid transaction hash {}", tx_hash);
+                return Ok(false);
+            }
+        };
+        // Batched jobs all swap the same pair.
+        let first = match jobs.first() {
+            Some(job) => job,
+            None => return Ok(false),
+        };
+        let receipt = match self.receipt(hash).await? {
+            Some(receipt) => receipt,
+            None => return self.expire(tx_hash, hash).await,
+        };
+        let (from_token, to_token) = match (
+            Felt::from_hex(&first.from_token),
+            Felt::from_hex(&first.to_token),
+        ) {
+            (Ok(from_token), Ok(to_token)) => (from_token, to_token),
+            _ => return Ok(false),
+        };
+        let account = match fee_payer(&receipt, &self.nonces.addresses()) {
+            Some(account) => account,
+            None => return Ok(false),
+        };
+        // Swaps funded by a wallet are paid back to it.
+        let wallets: Vec<Felt> = jobs
+            .iter()
+            .filter(|job| job.pull_from_wallet)
+            .filter_map(|job| Felt::from_hex(&job.wallet_address).ok())
+            .collect();
+        let outcome = match transaction_outcome(&receipt, from_token, to_token, account, &wallets) {
+            Some(outcome) => outcome,
+            None => return Ok(false),
+        };
+
+        let amounts: Vec<U256> = jobs
+            .iter()
+            .map(|job| job.amount().map_or(U256::from(0u8), |amount| amount.raw()))
+            .collect();
+        let outcomes = split_outcome(&outcome, &amounts);
+
+        // Prices are read before the rows are locked.
+        let from = tokens::get(&self.db.pool, &first.from_token).await?;
+        let to = tokens::get(&self.db.pool, &first.to_token).await?;
+        let mut values = Vec::with_capacity(outcomes.len());
+        for outcome in &outcomes {
+            values.push(UsdValues {
+                amount_from: self.usd_value(from.as_ref(), outcome.amount_from).await,
+                amount_to: self.usd_value(to.as_ref(), outcome.amount_to).await,
+            });
+        }
+
+        let mut tx = self.db.pool.begin().await?;
+        for ((job, outcome), usd) in jobs.iter().zip(&outcomes).zip(&values) {
+            record_outcome(&mut tx, job, outcome, usd).await?;
+            match &outcome.revert_reason {
+                Some(reason) => {
+                    let status = mark_reverted(&mut tx, job, reason).await?;
+                    tracing::warn!(
+                        "Swap job {} reverted in transaction {}, now {:?}: {}",
+                        job.id,
+                        outcome.tx_hash,
+                        status,
+                        reason
+                    );
+                }
+                None => {
+                    mark_confirmed(&mut tx, job.id).await?;
+                    tracing::info!(
+                        "Swap job {} confirmed in transaction {}",
+                        job.id,
+                        outcome.tx_hash
+                    );
+                }
+            }
+        }
+        tx.commit().await?;
+        self.nonces.settle(hash).await;
+        Ok(true)
+    }
+
+    // Fail the swaps sent in `tx_hash` once it can no longer run: the node
+    // rejected it, or the nonce of its account moved past it while the node
+    // does not know it. A transaction that is only slow to be included stays
+    // submitted, so that its swaps are never sent twice. Returns whether any
+    // were failed.
+    async fn expire(&self, tx_hash: &str, hash: Felt) -> Result<bool> {
+        let mut conn = self.db.pool.acquire().await?;
+        let jobs = list_expired(&mut conn, tx_hash, self.submission_timeout).await?;
+        if jobs.is_empty() {
+            return Ok(false);
+        }
+        let error = match self.provider.get_transaction_status(hash).await {
+            Ok(NodeStatus::Rejected) => format!("Transaction {} was rejected", tx_hash),
+            Ok(_) => return Ok(false),
+            Err(ProviderError::StarknetError(StarknetError::TransactionHashNotFound)) => {
+                match self.nonces.counted(hash).await? {
+                    Some(true) => format!("Transaction {} was dropped", tx_hash),
+                    Some(false) => return Ok(false),
+                    None => {
+                        tracing::warn!(
+                            "Transaction {} of swap job {} is unknown and was not sent by this instance",
+                            tx_hash,
+                            jobs[0].id
+                        );
+                        return Ok(false);
+                    }
+                }
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut tx = self.db.pool.begin().await?;
+        for job in &jobs {
+            match mark_failed(&mut tx, job, &error).await? {
+                SwapJobStatus::Dead => tracing::error!("Swap job {} is dead: {}", job.id, error),
+                _ => tracing::warn!("Swap job {} failed, will retry: {}", job.id, error),
+            }
+        }
+        tx.commit().await?;
+        self.nonces.settle(hash).await;
+        Ok(true)
+    }
+
+    // USD value of `amount` base units of `token` at its current price. Swaps
+    // are recorded without a value when the token cannot be priced.
+    async fn usd_value(&self, token: Option<&Token>, amount: U256) -> Option<Amount> {
+        let token = token?;
+        let decimals = u8::try_from(token.token_decimals).ok()?;
+        let amount = Amount::from_raw(amount, decimals);
+        match self.pricing.usd_value(token, &amount).await {
+            Ok(value) => value,
+            Err(err) => {
+                tracing::warn!("Unable to price {}: {:#}", token.contract_address, err);
+                None
+            }
+        }
+    }
+
+    // Receipt of a transaction, or `None` if the node does not know it yet.
+    async fn receipt(&self, hash: Felt) -> Result<Option<TransactionReceiptWithBlockInfo>> {
+        match self.provider.get_transaction_receipt(hash).await {
+            Ok(receipt) => Ok(Some(receipt)),
+            Err(ProviderError::StarknetError(StarknetError::TransactionHashNotFound)) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+// Read the outcome of a swap from its receipt. The swapped amounts are the
+// `from_token` sent by the swapping account and the `to_token` it or one of
+// the funding `wallets` received from elsewhere. Returns `None` while the
+// transaction is still in the pending block.
+pub fn transaction_outcome(
+    receipt: &TransactionReceiptWithBlockInfo,
+    from_token: Felt,
+    to_token: Felt,
+    account_address: Felt,
+    wallets: &[Felt],
+) -> Option<TransactionOutcome> {
+    let block_number = match receipt.block {
+        ReceiptBlock::Block { block_number, .. } => block_number,
+        ReceiptBlock::Pending => return None,
+    };
+    let invoke = match &receipt.receipt {
+        TransactionReceipt::Invoke(invoke) => invoke,
+        _ => return None,
+    };
+
+    let (status, revert_reason) = match (&invoke.execution_result, &invoke.finality_status) {
+        (ExecutionResult::Reverted { reason }, _) => {
+            (TransactionStatus::Reverted, Some(reason.clone()))
+        }
+        (ExecutionResult::Succeeded, TransactionFinalityStatus::AcceptedOnL1) => {
+            (TransactionStatus::AcceptedOnL1, None)
+        }
+        (ExecutionResult::Succeeded, TransactionFinalityStatus::AcceptedOnL2) => {
+            (TransactionStatus::AcceptedOnL2, None)
+        }
+    };
+
+    let fee_unit = match invoke.actual_fee.unit {
+        PriceUnit::Wei => "WEI",
+        PriceUnit::Fri => "FRI",
+    };
+
+    // The fee is paid with a Transfer emitted last; leave it out of the swapped amounts.
+    let events = match invoke.events.split_last() {
+        Some((last, rest)) if is_fee_transfer(last, account_address, invoke.actual_fee.amount) => {
+            rest
+        }
+        _ => &invoke.events[..],
+    };
+    let (amount_from, amount_to) = match status {
+        TransactionStatus::Reverted => (U256::from(0u8), U256::from(0u8)),
+        _ => (
+            transferred(events, from_token, |from, _| from == account_address),
+            transferred(events, to_token, |from, to| {
+                let received = |address| address == account_address || wallets.contains(&address);
+                received(to) && !received(from)
+            }),
+        ),
+    };
+
+    Some(TransactionOutcome {
+        tx_hash: format!("{:#x}", invoke.transaction_hash),
+        status,
+        block_number,
+        revert_reason,
+        actual_fee: invoke.actual_fee.amount.to_string(),
+        fee_unit: fee_unit.to_string(),
+        amount_from,
+        amount_to,
+    })
+}
+
+// Split the outcome of a transaction that swapped `amounts` for several jobs
+// into one outcome per job. The swapped amounts and the fee are shared in
+// proportion to the amount of each job.
+pub fn split_outcome(outcome: &TransactionOutcome, amounts: &[U256]) -> Vec<TransactionOutcome> {
+    let weights: Vec<BigUint> = amounts.iter().map(|amount| to_biguint(*amount)).collect();
+    let amount_from = shares(&to_biguint(outcome.amount_from), &weights);
+    let amount_to = shares(&to_biguint(outcome.amount_to), &weights);
+    let actual_fee = match outcome.actual_fee.parse::<BigUint>() {
+        Ok(fee) => shares(&fee, &weights)
+            .iter()
+            .map(BigUint::to_string)
+            .collect(),
+        Err(_) => vec![outcome.actual_fee.clone(); weights.len()],
+    };
+
+    (0..weights.len())
+        .map(|i| TransactionOutcome {
+            // Shares of a u256 fit in a u256.
+            amount_from: to_u256(&amount_from[i]).unwrap_or(outcome.amount_from),
+            amount_to: to_u256(&amount_to[i]).unwrap_or(outcome.amount_to),
+            actual_fee: actual_fee[i].clone(),
+            ..outcome.clone()
+        })
+        .collect()
+}
+
+// `total` shared in proportion to `weights`, rounded down. The last share gets
+// what rounding leaves over, so the shares add up to `total`.
+fn shares(total: &BigUint, weights: &[BigUint]) -> Vec<BigUint> {
+    let sum: BigUint = weights.iter().sum();
+    let mut shares: Vec<BigUint> = weights
+        .iter()
+        .map(|weight| match sum.bits() {
+            0 => BigUint::from(0u8),
+            _ => total * weight / &sum,
+        })
+        .collect();
+    let shared: BigUint = shares.iter().sum();
+    if let Some(last) = shares.last_mut() {
+        *last += total - shared;
+    }
+    shares
+}
+
+// Sum of the `token` Transfer events matching `filter(from, to)`, capped at
+// the largest u256.
+fn transferred(events: &[Event], token: Felt, filter: impl Fn(Felt, Felt) -> bool) -> U256 {
+    let total: BigUint = events
+        .iter()
+        .filter(|event| event.from_address == token)
+        .filter_map(|event| decode_transfer(&event.keys, &event.data))
+        .filter(|transfer| filter(transfer.from, transfer.to))
+        .map(|transfer| to_biguint(transfer.amount))
+        .sum();
+    to_u256(&total).unwrap_or(U256::from_words(u128::MAX, u128::MAX))
+}
+
+// The one of `accounts` that paid the fee of a transaction, or else the first.
+fn fee_payer(receipt: &TransactionReceiptWithBlockInfo, accounts: &[Felt]) -> Option<Felt> {
+    let payer = match &receipt.receipt {
+        TransactionReceipt::Invoke(invoke) => invoke.events.last().and_then(|event| {
+            accounts
+                .iter()
+                .copied()
+                .find(|account| is_fee_transfer(event, *account, invoke.actual_fee.amount))
+        }),
+        _ => None,
+    };
+    payer.or_else(|| accounts.first().copied())
+}
+
+fn is_fee_transfer(event: &Event, account_address: Felt, fee: Felt) -> bool {
+    match decode_transfer(&event.keys, &event.data) {
+        Some(transfer) => {
+            transfer.from == account_address
+                && transfer.amount.high() == 0
+                && Felt::from(transfer.amount.low()) == fee
+        }
+        None => false,
+    }
+}
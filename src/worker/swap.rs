@@ -0,0 +1,282 @@
+use std::{future::Future, time::Duration};
+
+use anyhow::{anyhow, Context, Result};
+use sqlx::PgConnection;
+use starknet::{
+    accounts::ConnectedAccount,
+    core::types::{Call, Felt},
+};
+
+use crate::{
+    service::{
+        balances::allowance,
+        nonces::NonceManager,
+        quote::{quote, QuoteParams},
+        slippage::{SlippageLimits, SwapAborted},
+        swap_jobs::{
+            claim_batch, mark_aborted, mark_failed, mark_sent, mark_submitted, SwapJob,
+            SwapJobStatus,
+        },
+        venues::VenueRegistry,
+    },
+    utils::{amount::Amount, starknet::SwapAccount, uint256::Uint256},
+    Configuration, Db,
+};
+
+// How long an idle worker waits before looking for due jobs again.
+const IDLE_INTERVAL: Duration = Duration::from_secs(2);
+
+// How due jobs are grouped into one transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchSettings {
+    // Most jobs sent in one transaction.
+    pub max_size: i64,
+    // How long the oldest job waits for others swapping the same pair.
+    pub window: Duration,
+}
+
+impl BatchSettings {
+    pub fn from_config(config: &Configuration) -> Self {
+        BatchSettings {
+            max_size: config.swap_batch_size,
+            window: Duration::from_secs(config.swap_batch_window_secs),
+        }
+    }
+}
+
+// One job per transaction, sent as soon as it is due.
+impl Default for BatchSettings {
+    fn default() -> Self {
+        BatchSettings {
+            max_size: 1,
+            window: Duration::ZERO,
+        }
+    }
+}
+
+// Sends the swap transaction for a job and returns its hash. Failing with
+// `SwapAborted` gives up on the job instead of retrying it.
+pub trait SwapExecutor: Send + Sync {
+    fn execute(&self, job: &SwapJob) -> impl Future<Output = Result<Felt>> + Send;
+
+    // Send the swaps of `jobs`, returning the result of each job in order. Jobs
+    // sent together share a transaction hash.
+    fn execute_batch(&self, jobs: &[SwapJob]) -> impl Future<Output = Vec<Result<Felt>>> + Send {
+        async move {
+            let mut results = Vec::with_capacity(jobs.len());
+            for job in jobs {
+                results.push(self.execute(job).await);
+            }
+            results
+        }
+    }
+}
+
+// Executes swaps through the router contract, on the venue of the job or on
+// whichever venue quotes the largest output. Swaps take turns between the
+// accounts of `nonces`, and the swaps of a batch are sent as one multicall.
+pub struct RouterExecutor {
+    venues: VenueRegistry,
+    nonces: NonceManager,
+    contract_address: Felt,
+}
+
+impl RouterExecutor {
+    pub fn new(venues: VenueRegistry, nonces: NonceManager, contract_address: Felt) -> Self {
+        RouterExecutor {
+            venues,
+            nonces,
+            contract_address,
+        }
+    }
+
+    // Calls that swap the amount of `job` from `account`.
+    async fn swap_calls(&self, account: &SwapAccount, job: &SwapJob) -> Result<Vec<Call>> {
+        let from_token = Felt::from_hex(&job.from_token).context("Invalid from token")?;
+        let to_token = Felt::from_hex(&job.to_token).context("Invalid to token")?;
+        let amount = job.amount().context("Invalid swap amount")?;
+        let venues = self.venues.preferred(&job.venue);
+        if venues.is_empty() {
+            return Err(anyhow!("Unknown venue: {}", job.venue));
+        }
+
+        let funded_by = match job.pull_from_wallet {
+            true => Some(Felt::from_hex(&job.wallet_address).context("Invalid wallet")?),
+            false => None,
+        };
+        if let Some(wallet) = funded_by {
+            // The wallet may have lowered its allowance since the job was queued.
+            let allowed =
+                allowance(account.provider(), from_token, wallet, account.address()).await?;
+            if Uint256::from(allowed) < Uint256::from(amount.raw()) {
+                return Err(anyhow!(
+                    "Allowance of {} is below the swapped {}",
+                    Amount::from_raw(allowed, amount.decimals()),
+                    amount
+                ));
+            }
+        }
+
+        // Quote the swap first, so that it is never sent at a worse price than
+        // the subscription accepts.
+        let params = QuoteParams {
+            from_token,
+            to_token,
+            amount,
+            contract_address: self.contract_address,
+            funded_by,
+        };
+        let quote = quote(account, &venues, &params)
+            .await
+            .context("Failed to quote swap")?;
+        let reasons: Vec<String> = quote
+            .unavailable
+            .iter()
+            .map(|venue| format!("{}: {}", venue.venue, venue.reason))
+            .collect();
+        let best = quote
+            .quotes
+            .into_iter()
+            .max_by_key(|quote| quote.amount_out)
+            .ok_or_else(|| anyhow!("No venue can quote the swap. {}", reasons.join(", ")))?;
+        let max_slippage_bps = u16::try_from(job.max_slippage_bps).context("Invalid slippage")?;
+        let limits = SlippageLimits::from_quote(&best, max_slippage_bps)?;
+
+        let venue = venues
+            .iter()
+            .find(|venue| venue.name() == best.venue)
+            .ok_or_else(|| anyhow!("Unknown venue: {}", best.venue))?;
+        let calls = venue.build_calls(account.address(), &params, &best, &limits)?;
+        Ok(params.funded(account.address(), amount, calls))
+    }
+}
+
+impl SwapExecutor for RouterExecutor {
+    async fn execute(&self, job: &SwapJob) -> Result<Felt> {
+        let mut results = self.execute_batch(std::slice::from_ref(job)).await;
+        results
+            .pop()
+            .unwrap_or_else(|| Err(anyhow!("Swap was not executed")))
+    }
+
+    async fn execute_batch(&self, jobs: &[SwapJob]) -> Vec<Result<Felt>> {
+        // Tokens pulled from wallets are spent by the account they allowed.
+        let account = match jobs.iter().any(|job| job.pull_from_wallet) {
+            true => self.nonces.primary_account(),
+            false => self.nonces.next_account(),
+        };
+        let mut prepared = Vec::with_capacity(jobs.len());
+        for job in jobs {
+            prepared.push(self.swap_calls(&account, job).await);
+        }
+
+        // Jobs that could not be quoted are left out of the transaction.
+        let calls: Vec<Call> = prepared
+            .iter()
+            .filter_map(|calls| calls.as_ref().ok())
+            .flatten()
+            .cloned()
+            .collect();
+        let sent = match calls.is_empty() {
+            true => None,
+            false => Some(
+                self.nonces
+                    .send(&account, calls)
+                    .await
+                    .map_err(|err| format!("Swap failed: {:#}", err)),
+            ),
+        };
+
+        prepared
+            .into_iter()
+            .map(|calls| match (calls, &sent) {
+                (Err(err), _) => Err(err),
+                (Ok(_), Some(Ok(transaction_hash))) => Ok(*transaction_hash),
+                (Ok(_), Some(Err(error))) => Err(anyhow!("{}", error)),
+                (Ok(_), None) => Err(anyhow!("Swap was not sent")),
+            })
+            .collect()
+    }
+}
+
+// Execute due swap jobs forever.
+pub async fn run_swap_worker<E: SwapExecutor>(db: Db, executor: E, batch: BatchSettings) {
+    loop {
+        match process_next_batch(&db, &executor, &batch).await {
+            Ok(true) => continue,
+            Ok(false) => {}
+            Err(err) => tracing::error!("Swap worker failed: {:#}", err),
+        }
+        tokio::time::sleep(IDLE_INTERVAL).await;
+    }
+}
+
+// Claim and execute one due job. Returns false if no job was due.
+pub async fn process_next_job<E: SwapExecutor>(db: &Db, executor: &E) -> Result<bool> {
+    process_next_batch(db, executor, &BatchSettings::default()).await
+}
+
+// Claim and execute the next batch of due jobs. Returns false if no job was due.
+pub async fn process_next_batch<E: SwapExecutor>(
+    db: &Db,
+    executor: &E,
+    batch: &BatchSettings,
+) -> Result<bool> {
+    // The claim is committed before the swap is sent, so that the jobs are not
+    // due again if the worker stops or their results cannot be recorded. Rows
+    // are not kept locked while the swap is quoted and sent either.
+    let mut tx = db.pool.begin().await?;
+    let jobs = claim_batch(&mut tx, batch.max_size, batch.window).await?;
+    if jobs.is_empty() {
+        return Ok(false);
+    }
+    tx.commit().await?;
+
+    let results = executor.execute_batch(&jobs).await;
+
+    // The transactions are kept first, so that the receipt tracker can still
+    // reconcile the jobs if their results cannot be recorded.
+    let mut conn = db.pool.acquire().await?;
+    for (job, result) in jobs.iter().zip(&results) {
+        if let Ok(transaction_hash) = result {
+            mark_sent(&mut conn, job.id, *transaction_hash).await?;
+        }
+    }
+    drop(conn);
+
+    let mut tx = db.pool.begin().await?;
+    for (job, result) in jobs.iter().zip(results) {
+        record_result(&mut tx, job, result).await?;
+    }
+    tx.commit().await?;
+    Ok(true)
+}
+
+async fn record_result(conn: &mut PgConnection, job: &SwapJob, result: Result<Felt>) -> Result<()> {
+    match result {
+        Ok(transaction_hash) => {
+            mark_submitted(conn, job.id, transaction_hash).await?;
+            tracing::info!(
+                "Swap job {} submitted in transaction {:#x}",
+                job.id,
+                transaction_hash
+            );
+        }
+        Err(err) if err.is::<SwapAborted>() => {
+            let reason = err.to_string();
+            mark_aborted(conn, job.id, &reason).await?;
+            tracing::warn!("Swap job {} aborted: {}", job.id, reason);
+        }
+        Err(err) => {
+            let error = format!("{:#}", err);
+            let status = mark_failed(conn, job, &error).await?;
+            match status {
+                SwapJobStatus::Dead => {
+                    tracing::error!("Swap job {} is dead: {}", job.id, error)
+                }
+                _ => tracing::warn!("Swap job {} failed, will retry: {}", job.id, error),
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::Result;
+use time::OffsetDateTime;
+
+use crate::{
+    service::{
+        pricing::{PriceSource, UsdPrice},
+        swap_jobs::enqueue,
+        tokens,
+        triggers::{
+            evaluate, list_waiting, lock_waiting, mark_expired, mark_fired, record_evaluation,
+            TriggerOutcome, TriggeredSwap,
+        },
+    },
+    Db,
+};
+
+// Most held swaps read at once.
+const BATCH_SIZE: i64 = 500;
+
+// Checks the swaps held for a price trigger against the prices of their tokens
+// every `interval`, and queues the ones whose trigger is met.
+pub struct TriggerEvaluator<S> {
+    source: S,
+    db: Db,
+    max_attempts: i32,
+    interval: Duration,
+}
+
+impl<S: PriceSource> TriggerEvaluator<S> {
+    pub fn new(source: S, db: Db, max_attempts: i32, interval: Duration) -> Self {
+        TriggerEvaluator {
+            source,
+            db,
+            max_attempts,
+            interval,
+        }
+    }
+
+    // Evaluate triggers forever.
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.check(OffsetDateTime::now_utc()).await {
+                tracing::error!("Trigger evaluation failed: {:#}", err);
+            }
+        }
+    }
+
+    // Evaluate every waiting swap once at `now`, each token being priced once.
+    // Evaluations are logged when their outcome changes. Met triggers queue their swap and expired ones
+    // drop it. Returns the number of swaps queued.
+    pub async fn check(&self, now: OffsetDateTime) -> Result<usize> {
+        let mut prices: HashMap<String, Option<UsdPrice>> = HashMap::new();
+        let mut fired = 0;
+        // Swaps are read in batches past the last one read, so that the swaps
+        // that keep waiting do not hold back the ones behind them.
+        let mut after: Option<TriggeredSwap> = None;
+        loop {
+            let batch = list_waiting(&self.db.pool, after.as_ref(), BATCH_SIZE).await?;
+            for waiting in &batch {
+                if self.check_swap(waiting, &mut prices, now).await? {
+                    fired += 1;
+                }
+            }
+            if batch.len() < BATCH_SIZE as usize {
+                return Ok(fired);
+            }
+            after = batch.into_iter().last();
+        }
+    }
+
+    // Evaluate a waiting swap. Returns whether it was queued.
+    async fn check_swap(
+        &self,
+        waiting: &TriggeredSwap,
+        prices: &mut HashMap<String, Option<UsdPrice>>,
+        now: OffsetDateTime,
+    ) -> Result<bool> {
+        let token = waiting.from_token.to_lowercase();
+        let price = match prices.get(&token) {
+            Some(price) => *price,
+            None => {
+                let price = self.price(&token).await;
+                prices.insert(token, price);
+                price
+            }
+        };
+        let trigger = match waiting.trigger() {
+            Some(trigger) => trigger,
+            None => {
+                tracing::warn!("Triggered swap {} has an invalid trigger", waiting.id);
+                return Ok(false);
+            }
+        };
+        let outcome = evaluate(&trigger, price, now);
+
+        let mut tx = self.db.pool.begin().await?;
+        // Skip swaps another evaluator got to first.
+        let held = match lock_waiting(&mut tx, waiting.id).await? {
+            Some(held) => held,
+            None => return Ok(false),
+        };
+        let price_usd = price.and_then(|price| price.to_decimal_string());
+        record_evaluation(&mut tx, held.id, price_usd.as_deref(), outcome, now).await?;
+        let mut fired = false;
+        match outcome {
+            TriggerOutcome::Fired => match held.swap_job(self.max_attempts) {
+                Some(job) => {
+                    let job_id = enqueue(&mut tx, &job).await?;
+                    mark_fired(&mut tx, held.id, job_id).await?;
+                    fired = true;
+                }
+                None => tracing::warn!("Triggered swap {} has an invalid amount", held.id),
+            },
+            TriggerOutcome::Expired => mark_expired(&mut tx, held.id).await?,
+            TriggerOutcome::Waiting | TriggerOutcome::Unpriced => {}
+        }
+        tx.commit().await?;
+        Ok(fired)
+    }
+
+    // USD price of a token, or `None` if it has none or cannot be read.
+    async fn price(&self, address: &str) -> Option<UsdPrice> {
+        let token = match tokens::get(&self.db.pool, address).await {
+            Ok(Some(token)) => token,
+            Ok(None) => return None,
+            Err(err) => {
+                tracing::warn!("Unable to read token {}: {}", address, err);
+                return None;
+            }
+        };
+        match self.source.usd_price(&token).await {
+            Ok(price) => price,
+            Err(err) => {
+                tracing::warn!("Unable to price {}: {:#}", address, err);
+                None
+            }
+        }
+    }
+}
@@ -9,8 +9,9 @@ use sqlx::PgPool;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 struct ActivityLogGetResponse {
-    pub transactions: Vec<ActivityLogData>,
+    pub data: Vec<ActivityLogData>,
     pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
@@ -19,8 +20,9 @@ struct ActivityLogData {
     pub from_token: String,
     pub to_token: String,
     pub percentage: i16,
-    pub amount_from: i64,
-    pub amount_to: i64,
+    pub amount_from: String,
+    pub amount_to: String,
+    pub status: Option<String>,
     pub created_at: String,
 }
 
@@ -53,6 +55,14 @@ async fn populate_db(pool: &PgPool) -> bool {
     true
 }
 
+async fn get_page(app: &TestApp, uri: &str) -> ActivityLogGetResponse {
+    let req = Request::get(uri).body(Body::empty()).unwrap();
+    let resp = app.request(req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body_bytes = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body_bytes).unwrap()
+}
+
 #[tokio::test]
 async fn test_log_retrieval_pagination() {
     let app = TestApp::new().await;
@@ -62,44 +72,101 @@ async fn test_log_retrieval_pagination() {
         .await
         .unwrap();
 
-    let req = Request::get("/log_retrieval?cursor=2024-11-30T10:49:36.20Z&limit=10")
-        .body(Body::empty())
-        .unwrap();
-    let resp = app.request(req).await;
-    assert_eq!(resp.status(), StatusCode::OK);
-
-    let body_bytes = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
-    let response_body: ActivityLogGetResponse = serde_json::from_slice(&body_bytes).unwrap();
-    assert_eq!(
-        response_body.transactions.len(),
-        0,
-        "Expected no transactions"
-    );
+    let response_body = get_page(&app, "/log_retrieval?limit=10").await;
+    assert_eq!(response_body.data.len(), 0, "Expected no transactions");
+    assert_eq!(response_body.next_cursor, None);
+    assert_eq!(response_body.prev_cursor, None);
 
     let _t = populate_db(&app.db.pool).await;
 
-    let req = Request::get("/log_retrieval?cursor=2024-11-30T10:49:36Z&limit=10")
-        .body(Body::empty())
+    let first_page = get_page(&app, "/log_retrieval?limit=10").await;
+    assert_eq!(first_page.data.len(), 10);
+    assert_eq!(first_page.data[0].created_at, "2024-11-29T10:49:42Z");
+    assert_eq!(first_page.prev_cursor, None);
+
+    let next_cursor = first_page.next_cursor.clone().unwrap();
+    let url = format!("/log_retrieval?cursor={}&limit=10", next_cursor);
+    let second_page = get_page(&app, &url).await;
+    assert_eq!(second_page.data.len(), 3);
+    assert_eq!(second_page.data[0].created_at, "2024-11-28T12:02:49Z");
+    assert_eq!(second_page.next_cursor, None);
+
+    // The previous page is the first page again.
+    let prev_cursor = second_page.prev_cursor.unwrap();
+    let url = format!("/log_retrieval?cursor={}&limit=10", prev_cursor);
+    let page = get_page(&app, &url).await;
+    assert_eq!(page.data, first_page.data);
+    assert_eq!(page.prev_cursor, None);
+    assert_eq!(page.next_cursor, Some(next_cursor));
+}
+
+#[tokio::test]
+async fn test_log_retrieval_pages_rows_with_the_same_time() {
+    let app = TestApp::new().await;
+
+    // Five swaps logged at the same instant.
+    for amount in 1..=5 {
+        sqlx::query(
+            r#"
+            INSERT INTO transactions_log
+            (wallet_address, from_token, to_token, percentage, amount_from, amount_to, created_at)
+            VALUES ($1, $2, $2, 50, $3, 1, '2024-11-28 12:02:49.5+00')
+            "#,
+        )
+        .bind("0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef")
+        .bind("0x9876543210fedcba9876543210fedcba9876543210fedcba9876543210fedcba")
+        .bind(amount)
+        .execute(&app.db.pool)
+        .await
         .unwrap();
-    let resp = app.request(req).await;
-    assert_eq!(resp.status(), StatusCode::OK);
+    }
 
-    let body_bytes = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
-    let response_body: ActivityLogGetResponse = serde_json::from_slice(&body_bytes).unwrap();
-    assert_eq!(response_body.transactions.len(), 10);
+    let mut amounts = Vec::new();
+    let mut uri = "/log_retrieval?limit=2".to_string();
+    loop {
+        let page = get_page(&app, &uri).await;
+        assert!(page.data.len() <= 2);
+        amounts.extend(page.data.into_iter().map(|row| row.amount_from));
+        match page.next_cursor {
+            Some(cursor) => uri = format!("/log_retrieval?limit=2&cursor={}", cursor),
+            None => break,
+        }
+    }
+    amounts.sort();
+    assert_eq!(amounts, ["1", "2", "3", "4", "5"]);
+}
 
-    let next_cursor = response_body.next_cursor.unwrap();
-    assert_eq!(next_cursor, "2024-11-29T10:49:36Z".to_string());
+#[tokio::test]
+async fn test_log_retrieval_rejects_invalid_limits() {
+    let app = TestApp::new().await;
 
-    let url = format!("/log_retrieval?cursor={}&limit=10", next_cursor);
-    let req = Request::get(&url).body(Body::empty()).unwrap();
-    let resp = app.request(req).await;
-    assert_eq!(resp.status(), StatusCode::OK);
+    for limit in ["0", "101", "-1"] {
+        let req = Request::get(format!("/log_retrieval?limit={}", limit))
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.request(req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "limit={}", limit);
+    }
+    get_page(&app, "/log_retrieval?limit=100").await;
+}
 
-    let body_bytes = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
-    let response_body: ActivityLogGetResponse = serde_json::from_slice(&body_bytes).unwrap();
-    assert_eq!(response_body.transactions.len(), 3);
-    assert_eq!(response_body.next_cursor, None);
+#[tokio::test]
+async fn test_log_retrieval_rejects_forged_cursors() {
+    let app = TestApp::new().await;
+
+    // A timestamp, and base64 JSON cursors with an invalid time and id.
+    for cursor in [
+        "2024-11-30T10:49:36Z",
+        "eyJrZXkiOiJub3cifQ",
+        "eyJrZXkiOiJub3ciLCJpZCI6IngiLCJkaXJlY3Rpb24iOiJuZXh0In0",
+        "eyJrZXkiOiIyMDI0LTExLTMwVDEwOjQ5OjM2WiIsImlkIjoieCIsImRpcmVjdGlvbiI6Im5leHQifQ",
+    ] {
+        let req = Request::get(format!("/log_retrieval?cursor={}", cursor))
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.request(req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "cursor={}", cursor);
+    }
 }
 
 #[tokio::test]
@@ -168,3 +235,187 @@ async fn test_log_retrieval_filter_with_amount_to() {
     let resp = app.request(req).await;
     assert_eq!(resp.status(), StatusCode::OK);
 }
+
+const WALLET_A: &str = "0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef";
+const WALLET_B: &str = "0x2234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef";
+const WALLET_C: &str = "0x3234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef";
+const TOKEN: &str = "0x9876543210fedcba9876543210fedcba9876543210fedcba9876543210fedcba";
+
+async fn log_swap(
+    pool: &PgPool,
+    wallet: &str,
+    amount_from: i64,
+    amount_to: i64,
+    created_at: &str,
+    status: Option<&str>,
+) {
+    sqlx::query(
+        r#"
+        INSERT INTO transactions_log
+        (wallet_address, from_token, to_token, percentage, amount_from, amount_to, status, created_at)
+        VALUES ($1, $2, $2, 50, $3, $4, $5::transaction_status, $6::TIMESTAMPTZ)
+        "#,
+    )
+    .bind(wallet)
+    .bind(TOKEN)
+    .bind(amount_from)
+    .bind(amount_to)
+    .bind(status)
+    .bind(created_at)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+// Wallet A swaps 100, 200 and 300 on the 1st, 2nd and 3rd of December, and
+// wallets B and C swap 400 and 500 on the 4th and 5th.
+async fn log_swaps(pool: &PgPool) {
+    for (wallet, amount, day, status) in [
+        (WALLET_A, 100, 1, Some("accepted_on_l1")),
+        (WALLET_A, 200, 2, Some("reverted")),
+        (WALLET_A, 300, 3, None),
+        (WALLET_B, 400, 4, Some("accepted_on_l2")),
+        (WALLET_C, 500, 5, Some("accepted_on_l1")),
+    ] {
+        let created_at = format!("2024-12-0{}T12:00:00Z", day);
+        log_swap(pool, wallet, amount, amount / 2, &created_at, status).await;
+    }
+}
+
+fn amounts(page: &ActivityLogGetResponse) -> Vec<&str> {
+    page.data
+        .iter()
+        .map(|row| row.amount_from.as_str())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_log_retrieval_filters_by_ranges_and_lists() {
+    let app = TestApp::new().await;
+    log_swaps(&app.db.pool).await;
+
+    for (query, expected) in [
+        (
+            format!("wallet_address={},{}", WALLET_A, WALLET_B),
+            vec!["400", "300", "200", "100"],
+        ),
+        (format!("wallet_address={}", WALLET_C), vec!["500"]),
+        (
+            "from=2024-12-02T00:00:00Z&to=2024-12-04T12:00:00Z".to_string(),
+            vec!["400", "300", "200"],
+        ),
+        ("from=2024-12-04T12:00:00Z".to_string(), vec!["500", "400"]),
+        (
+            "min_amount_from=200&max_amount_from=400".to_string(),
+            vec!["400", "300", "200"],
+        ),
+        ("min_amount_to=200".to_string(), vec!["500", "400"]),
+        ("max_amount_to=100".to_string(), vec!["200", "100"]),
+        ("status=accepted_on_l1".to_string(), vec!["500", "100"]),
+        (
+            "status=accepted_on_l2,reverted".to_string(),
+            vec!["400", "200"],
+        ),
+        (
+            format!(
+                "wallet_address={}&status=accepted_on_l1,reverted&min_amount_from=150",
+                WALLET_A
+            ),
+            vec!["200"],
+        ),
+    ] {
+        let page = get_page(&app, &format!("/log_retrieval?{}", query)).await;
+        assert_eq!(amounts(&page), expected, "{}", query);
+    }
+
+    let page = get_page(&app, "/log_retrieval?status=reverted").await;
+    assert_eq!(page.data[0].status.as_deref(), Some("reverted"));
+}
+
+#[tokio::test]
+async fn test_log_retrieval_sorts_by_amount_and_time() {
+    let app = TestApp::new().await;
+    log_swaps(&app.db.pool).await;
+    // The largest amount is the oldest swap.
+    log_swap(&app.db.pool, WALLET_C, 600, 1, "2024-11-30T12:00:00Z", None).await;
+
+    for (query, expected) in [
+        ("", vec!["500", "400", "300", "200", "100", "600"]),
+        ("order=asc", vec!["600", "100", "200", "300", "400", "500"]),
+        (
+            "sort_by=amount_from",
+            vec!["600", "500", "400", "300", "200", "100"],
+        ),
+        (
+            "sort_by=amount_from&order=asc",
+            vec!["100", "200", "300", "400", "500", "600"],
+        ),
+        (
+            "sort_by=amount_to&order=asc",
+            vec!["600", "100", "200", "300", "400", "500"],
+        ),
+    ] {
+        // Page through two rows at a time.
+        let mut rows = Vec::new();
+        let mut uri = format!("/log_retrieval?{}&limit=2", query);
+        loop {
+            let page = get_page(&app, &uri).await;
+            rows.extend(amounts(&page).into_iter().map(str::to_string));
+            match page.next_cursor {
+                Some(cursor) => uri = format!("/log_retrieval?{}&limit=2&cursor={}", query, cursor),
+                None => break,
+            }
+        }
+        assert_eq!(rows, expected, "{}", query);
+    }
+
+    // Going back from the last page sorted by amount.
+    let first = get_page(&app, "/log_retrieval?sort_by=amount_from&limit=4").await;
+    let last = get_page(
+        &app,
+        &format!(
+            "/log_retrieval?sort_by=amount_from&limit=4&cursor={}",
+            first.next_cursor.unwrap()
+        ),
+    )
+    .await;
+    assert_eq!(amounts(&last), ["200", "100"]);
+    let back = get_page(
+        &app,
+        &format!(
+            "/log_retrieval?sort_by=amount_from&limit=4&cursor={}",
+            last.prev_cursor.unwrap()
+        ),
+    )
+    .await;
+    assert_eq!(amounts(&back), ["600", "500", "400", "300"]);
+}
+
+#[tokio::test]
+async fn test_log_retrieval_rejects_invalid_filters() {
+    let app = TestApp::new().await;
+    log_swaps(&app.db.pool).await;
+
+    // A cursor of a listing sorted by time does not page one sorted by amount.
+    let page = get_page(&app, "/log_retrieval?limit=1").await;
+    let time_cursor = page.next_cursor.unwrap();
+    let too_many = vec![WALLET_A; 51].join(",");
+
+    for query in [
+        "from=yesterday".to_string(),
+        "to=2024-12-01".to_string(),
+        "from=2024-12-03T00:00:00Z&to=2024-12-02T00:00:00Z".to_string(),
+        "min_amount_from=-1".to_string(),
+        "status=pending".to_string(),
+        "sort_by=percentage".to_string(),
+        "order=up".to_string(),
+        format!("wallet_address={}", too_many),
+        format!("sort_by=amount_from&cursor={}", time_cursor),
+    ] {
+        let req = Request::get(format!("/log_retrieval?{}", query))
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.request(req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{}", query);
+    }
+}
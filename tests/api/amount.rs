@@ -0,0 +1,113 @@
+use starknet::core::types::{Felt, U256};
+
+use autoswappr_backend::utils::{
+    amount::{Amount, AmountError},
+    uint256::Uint256,
+};
+
+fn raw(amount: u128, decimals: u8) -> Amount {
+    Amount::from_raw(U256::from(amount), decimals)
+}
+
+#[test]
+fn test_parse_uses_token_decimals() {
+    assert_eq!(Amount::parse("1.5", 6).unwrap(), raw(1_500_000, 6));
+    assert_eq!(
+        Amount::parse("2", 18).unwrap(),
+        raw(2_000_000_000_000_000_000, 18)
+    );
+    assert_eq!(Amount::parse("0.000001", 6).unwrap(), raw(1, 6));
+    assert_eq!(Amount::parse(".5", 1).unwrap(), raw(5, 1));
+    assert_eq!(Amount::parse("7.", 0).unwrap(), raw(7, 0));
+    assert_eq!(Amount::parse("1.2300", 2).unwrap(), raw(123, 2));
+}
+
+#[test]
+fn test_parse_rejects_invalid_amounts() {
+    for value in ["", ".", "-1", "1e18", " 1", "1.2.3", "0x10", "1,5"] {
+        assert!(
+            matches!(Amount::parse(value, 18), Err(AmountError::Invalid(_))),
+            "{:?} should be invalid",
+            value
+        );
+    }
+    assert_eq!(
+        Amount::parse("0.0000001", 6),
+        Err(AmountError::TooPrecise(6))
+    );
+    let too_large = format!("1{}", "0".repeat(78));
+    assert_eq!(Amount::parse(&too_large, 0), Err(AmountError::Overflow));
+}
+
+#[test]
+fn test_format_trims_trailing_zeros() {
+    assert_eq!(raw(1_500_000, 6).to_string(), "1.5");
+    assert_eq!(raw(1, 6).to_string(), "0.000001");
+    assert_eq!(raw(2_000_000_000_000_000_000, 18).to_string(), "2");
+    assert_eq!(raw(0, 18).to_string(), "0");
+    assert_eq!(raw(42, 0).to_string(), "42");
+    assert_eq!(raw(1_500_000, 6).base_units(), "1500000");
+
+    let max = Amount::from_raw(U256::from_words(u128::MAX, u128::MAX), 18);
+    assert_eq!(Amount::parse(&max.to_string(), 18).unwrap(), max);
+}
+
+#[test]
+fn test_percentage_rounds_down() {
+    assert_eq!(raw(3, 0).percentage(50).unwrap(), raw(1, 0));
+    assert_eq!(raw(1_000_001, 6).percentage(50).unwrap(), raw(500_000, 6));
+    assert_eq!(
+        raw(1_000_000, 6).percentage(100).unwrap(),
+        raw(1_000_000, 6)
+    );
+
+    let max = Amount::from_raw(U256::from_words(u128::MAX, u128::MAX), 18);
+    let half = Amount::from_raw(U256::from_words(u128::MAX, u128::MAX >> 1), 18);
+    assert_eq!(max.percentage(50).unwrap(), half);
+}
+
+#[test]
+fn test_split_adds_up_to_the_amount() {
+    assert_eq!(
+        raw(10, 0).split(&[1, 1, 1]).unwrap(),
+        vec![raw(3, 0), raw(3, 0), raw(4, 0)]
+    );
+    assert_eq!(
+        raw(1_000_000, 6).split(&[60, 40]).unwrap(),
+        vec![raw(600_000, 6), raw(400_000, 6)]
+    );
+    assert_eq!(raw(10, 0).split(&[0, 0]), Err(AmountError::DivisionByZero));
+}
+
+#[test]
+fn test_calldata_is_a_cairo_u256() {
+    let amount = Amount::from_raw(U256::from_words(5, 7), 18);
+    assert_eq!(amount.calldata(), [Felt::from(5u8), Felt::from(7u8)]);
+    assert_eq!(amount.to_u128(), None);
+    assert_eq!(raw(5, 18).to_u128(), Some(5));
+}
+
+#[test]
+fn test_uint256_round_trips_through_json() {
+    let max = Uint256(U256::from_words(u128::MAX, u128::MAX));
+    let json = serde_json::to_value(max).unwrap();
+    assert_eq!(
+        json,
+        "115792089237316195423570985008687907853269984665640564039457584007913129639935"
+    );
+    assert_eq!(serde_json::from_value::<Uint256>(json).unwrap(), max);
+    assert_eq!(
+        serde_json::from_value::<Uint256>(serde_json::json!(5000)).unwrap(),
+        Uint256::from(5000u128)
+    );
+}
+
+#[test]
+fn test_uint256_rejects_invalid_values() {
+    for value in ["", "-1", "1.5", "0x10", " 1"] {
+        assert!(value.parse::<Uint256>().is_err(), "{:?}", value);
+    }
+    // 2^256
+    let overflow = "115792089237316195423570985008687907853269984665640564039457584007913129639936";
+    assert_eq!(overflow.parse::<Uint256>(), Err(AmountError::Overflow));
+}
@@ -0,0 +1,126 @@
+use axum::{
+    body::Body,
+    http::{header::CONTENT_TYPE, Request, StatusCode},
+};
+use serde_json::{json, Value};
+use starknet::{core::types::Felt, signers::SigningKey};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::helpers::*;
+
+const WALLET: &str = "0xdbfcab49bd9bced4636b04319d71fbd0d84bde78a1d38e9e2fc391e83187c1c3";
+
+fn unsubscribe_payload() -> Value {
+    json!({
+        "wallet_address": WALLET,
+        "from_token": "0x07ab8059db97aab8ced83b37a1d60b8eef540f6cdc96acc153d583a59bedd125"
+    })
+}
+
+fn expiry_in(seconds: i64) -> u64 {
+    (OffsetDateTime::now_utc().unix_timestamp() + seconds) as u64
+}
+
+#[tokio::test]
+async fn test_signed_request_ok() {
+    let app = TestApp::new().await;
+
+    let req = signed_request("POST", "/unsubscribe", WALLET, &unsubscribe_payload());
+    let resp = app.request(req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_missing_signature_is_rejected() {
+    let app = TestApp::new().await;
+
+    let req = Request::builder()
+        .method("POST")
+        .uri("/unsubscribe")
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(unsubscribe_payload().to_string()))
+        .unwrap();
+    let resp = app.request(req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_signature_from_other_key_is_rejected() {
+    let app = TestApp::new().await;
+
+    let other_key = SigningKey::from_secret_scalar(Felt::from_hex("0x1234").unwrap());
+    let req = signed_request_with(
+        &other_key,
+        "POST",
+        "/unsubscribe",
+        WALLET,
+        &unsubscribe_payload(),
+        Felt::from(1u8),
+        expiry_in(300),
+    );
+    let resp = app.request(req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_expired_signature_is_rejected() {
+    let app = TestApp::new().await;
+
+    let req = signed_request_with(
+        &test_signing_key(),
+        "POST",
+        "/unsubscribe",
+        WALLET,
+        &unsubscribe_payload(),
+        Felt::from(2u8),
+        expiry_in(-10),
+    );
+    let resp = app.request(req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_replayed_nonce_is_rejected() {
+    let app = TestApp::new().await;
+
+    let nonce = Felt::from(Uuid::now_v7().as_u128());
+    let expiry = expiry_in(300);
+    let first = signed_request_with(
+        &test_signing_key(),
+        "POST",
+        "/unsubscribe",
+        WALLET,
+        &unsubscribe_payload(),
+        nonce,
+        expiry,
+    );
+    let replay = signed_request_with(
+        &test_signing_key(),
+        "POST",
+        "/unsubscribe",
+        WALLET,
+        &unsubscribe_payload(),
+        nonce,
+        expiry,
+    );
+
+    assert_eq!(app.request(first).await.status(), StatusCode::OK);
+    assert_eq!(app.request(replay).await.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_tampered_body_is_rejected() {
+    let app = TestApp::new().await;
+
+    let signed = signed_request("POST", "/unsubscribe", WALLET, &unsubscribe_payload());
+    let (parts, _) = signed.into_parts();
+    let tampered = json!({
+        "wallet_address": WALLET,
+        "from_token": "0x40ca979f20ed76f960dc719457eaf0cef3b2c3932d58435b9192a58bc56c1e40"
+    });
+    let req = Request::from_parts(parts, Body::from(tampered.to_string()));
+
+    let resp = app.request(req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
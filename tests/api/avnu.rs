@@ -0,0 +1,152 @@
+use serde_json::json;
+use starknet::core::types::{Felt, U256};
+
+use autoswappr_backend::utils::{
+    amount::Amount,
+    avnu::{avnu_swap_calls, parse_routes, AvnuClient, Route},
+    uint256::Uint256,
+};
+
+use crate::mock_avnu::*;
+
+const ROUTER: &str = "0xe4b";
+const ETH: &str = "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
+const STRK: &str = "0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d";
+const EKUBO: &str = "0x5dd3d2f4429af886cd1a3b08289dbcea99a294197e9eb43b0e0325b4b";
+
+fn felt(hex: &str) -> Felt {
+    Felt::from_hex(hex).unwrap()
+}
+
+fn recorded_route() -> Route {
+    Route::new(
+        felt(ETH),
+        felt(STRK),
+        felt(EKUBO),
+        1_000_000_000_000,
+        vec![
+            felt(STRK),
+            felt(ETH),
+            felt("0x20c49ba5e353f80000000000000000"),
+            felt("0x3e8"),
+            Felt::ZERO,
+            felt("0x6c"),
+        ],
+    )
+}
+
+#[tokio::test]
+async fn test_client_fetches_quote_and_routes() {
+    let avnu = MockAvnu::recorded().await;
+    // A trailing slash on the base URL is ignored.
+    let client = AvnuClient::new(&format!("{}/", avnu.url));
+
+    let amount = Amount::from_raw(U256::from(1_000_000u128), 18);
+    let quote = client
+        .quote(felt(ETH), felt(STRK), amount, felt(ROUTER))
+        .await
+        .unwrap();
+    assert_eq!(quote.quote_id, "8f3e5a52-1c64-4f0b-9a55-3c1d2e7b6a90");
+    assert_eq!(quote.sell_amount, Uint256::from(1_000_000u128));
+    assert_eq!(quote.buy_amount, Uint256::from(2_010_000u128));
+
+    let routes = client.routes(&quote, felt(ROUTER)).await.unwrap();
+    assert_eq!(routes, vec![recorded_route()]);
+
+    assert_eq!(
+        avnu.requests(),
+        vec![
+            json!({
+                "sellTokenAddress": "0x49d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7",
+                "buyTokenAddress": "0x4718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d",
+                "sellAmount": "0xf4240",
+                "takerAddress": ROUTER,
+                "size": "1"
+            }),
+            json!({
+                "quoteId": "8f3e5a52-1c64-4f0b-9a55-3c1d2e7b6a90",
+                "takerAddress": ROUTER,
+                "slippage": 0.01,
+                "includeApprove": false
+            }),
+        ]
+    );
+
+    // The quote maps to the router's swap calldata.
+    let min_amount = Amount::from_raw(U256::from(2_000_000u128), 18);
+    let calls = avnu_swap_calls(
+        quote.token_from(18),
+        quote.token_to(min_amount),
+        felt("0xacc"),
+        0,
+        Felt::ZERO,
+        routes,
+        felt(ROUTER),
+    );
+    assert_eq!(
+        calls[1].calldata[..12],
+        [
+            felt(ETH),
+            felt("0xf4240"),
+            Felt::ZERO,
+            felt(STRK),
+            felt("0x1eab90"),
+            Felt::ZERO,
+            felt("0x1e8480"),
+            Felt::ZERO,
+            felt("0xacc"),
+            Felt::ZERO,
+            Felt::ZERO,
+            Felt::ONE,
+        ]
+    );
+    assert_eq!(
+        parse_routes(&calls[1].calldata).unwrap(),
+        vec![recorded_route()]
+    );
+}
+
+#[tokio::test]
+async fn test_client_without_route() {
+    let build: serde_json::Value = serde_json::from_str(RECORDED_BUILD).unwrap();
+    let avnu = MockAvnu::start(json!([]), build).await;
+    let client = AvnuClient::new(&avnu.url);
+
+    let amount = Amount::from_raw(U256::from(1_000_000u128), 18);
+    let err = client
+        .quote(felt(ETH), felt(STRK), amount, felt(ROUTER))
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "AVNU has no route for this swap");
+}
+
+#[tokio::test]
+async fn test_client_rejects_build_without_swap() {
+    let quotes: serde_json::Value = serde_json::from_str(RECORDED_QUOTES).unwrap();
+    let build = json!({
+        "chainId": "0x534e5f4d41494e",
+        "calls": [{ "contractAddress": ETH, "entrypoint": "approve", "calldata": ["0x1"] }]
+    });
+    let avnu = MockAvnu::start(quotes, build).await;
+    let client = AvnuClient::new(&avnu.url);
+
+    let amount = Amount::from_raw(U256::from(1_000_000u128), 18);
+    let quote = client
+        .quote(felt(ETH), felt(STRK), amount, felt(ROUTER))
+        .await
+        .unwrap();
+    let err = client.routes(&quote, felt(ROUTER)).await.unwrap_err();
+    assert_eq!(err.to_string(), "AVNU swap has no multi_route_swap call");
+}
+
+#[test]
+fn test_parse_routes_rejects_truncated_calldata() {
+    let build: serde_json::Value = serde_json::from_str(RECORDED_BUILD).unwrap();
+    let calldata: Vec<Felt> =
+        serde_json::from_value(build["calls"][0]["calldata"].clone()).unwrap();
+
+    assert!(parse_routes(&calldata).is_ok());
+    for len in [0, 11, 12, 17, calldata.len() - 1] {
+        assert!(parse_routes(&calldata[..len]).is_err(), "{}", len);
+    }
+}
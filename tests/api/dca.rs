@@ -0,0 +1,499 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::time::Duration;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use uuid::Uuid;
+
+use autoswappr_backend::{
+    service::swap_jobs::{find, SwapJob},
+    utils::cron::CronSchedule,
+    worker::DcaScheduler,
+};
+
+use crate::helpers::*;
+
+const WALLET: &str = "0xdbfcab49bd9bced4636b04319d71fbd0d84bde78a1d38e9e2fc391e83187c1c3";
+const OTHER_WALLET: &str = "0x0000000000000000000000000000000000000000000000000000000000000b22";
+const FROM_TOKEN: &str = "0x053c91253bc9682c04929ca02ed00b3e423f6710d2ee7e0d5ebb06f3ecf368a8";
+const TO_TOKEN: &str = "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
+
+fn at(time: &str) -> OffsetDateTime {
+    OffsetDateTime::parse(time, &Rfc3339).unwrap()
+}
+
+fn next(schedule: &str, after: &str) -> Option<String> {
+    let schedule: CronSchedule = schedule.parse().unwrap();
+    schedule
+        .next_after(at(after))
+        .map(|next| next.format(&Rfc3339).unwrap())
+}
+
+fn scheduler(app: &TestApp) -> DcaScheduler {
+    DcaScheduler::new(app.db.clone(), 5, Duration::from_secs(30))
+}
+
+async fn setup(pool: &PgPool) {
+    register_token_with_decimals(pool, FROM_TOKEN, 6).await;
+    register_token(pool, TO_TOKEN).await;
+    sqlx::query("INSERT INTO swap_subscription (wallet_address, to_token) VALUES ($1, $2)")
+        .bind(WALLET)
+        .bind(TO_TOKEN)
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+async fn send(app: &TestApp, req: Request<Body>) -> (StatusCode, Value) {
+    let resp = app.request(req).await;
+    let status = resp.status();
+    let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+async fn create(app: &TestApp, payload: Value) -> (StatusCode, Value) {
+    send(app, signed_request("POST", "/dca", WALLET, &payload)).await
+}
+
+// An hourly schedule of 2.5 tokens starting at 10:00 on 2024-12-23.
+async fn create_hourly(app: &TestApp, ends_at: Option<&str>) -> Uuid {
+    let (status, json) = create(
+        app,
+        json!({
+            "wallet_address": WALLET,
+            "from_token": FROM_TOKEN,
+            "to_token": TO_TOKEN,
+            "amount": "2.5",
+            "schedule": "0 * * * *",
+            "starts_at": "2024-12-23T10:00:00Z",
+            "ends_at": ends_at,
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", json);
+    Uuid::parse_str(json["id"].as_str().unwrap()).unwrap()
+}
+
+async fn get(app: &TestApp, id: Uuid) -> (StatusCode, Value) {
+    let req = Request::get(format!("/dca/{}", id))
+        .body(Body::empty())
+        .unwrap();
+    send(app, req).await
+}
+
+async fn owner_action(app: &TestApp, id: Uuid, action: &str, wallet: &str) -> (StatusCode, Value) {
+    let (method, uri) = match action {
+        "delete" => ("DELETE", format!("/dca/{}", id)),
+        action => ("POST", format!("/dca/{}/{}", id, action)),
+    };
+    let payload = json!({ "wallet_address": wallet });
+    send(app, signed_request(method, &uri, wallet, &payload)).await
+}
+
+async fn runs(pool: &PgPool, id: Uuid) -> Vec<(String, Option<Uuid>)> {
+    sqlx::query_as::<_, (String, Option<Uuid>)>(
+        r#"
+        SELECT TO_CHAR(run_at, 'YYYY-MM-DD"T"HH24:MI:SSZ'), swap_job_id
+        FROM dca_run
+        WHERE schedule_id = $1
+        ORDER BY run_at
+        "#,
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+async fn job(pool: &PgPool, id: Uuid) -> SwapJob {
+    let mut conn = pool.acquire().await.unwrap();
+    find(&mut conn, id).await.unwrap().unwrap()
+}
+
+#[test]
+fn test_cron_next_run() {
+    let cases = [
+        (
+            "*/15 * * * *",
+            "2024-12-23T10:07:30Z",
+            "2024-12-23T10:15:00Z",
+        ),
+        // Strictly after.
+        (
+            "*/15 * * * *",
+            "2024-12-23T10:15:00Z",
+            "2024-12-23T10:30:00Z",
+        ),
+        (
+            "5/20 * * * *",
+            "2024-12-23T10:46:00Z",
+            "2024-12-23T11:05:00Z",
+        ),
+        (
+            "0,30 9-10 * * *",
+            "2024-12-23T10:30:00Z",
+            "2024-12-24T09:00:00Z",
+        ),
+        // Weekdays, from a Friday.
+        (
+            "0 9 * * 1-5",
+            "2024-12-20T10:00:00Z",
+            "2024-12-23T09:00:00Z",
+        ),
+        // Either the 1st or a Sunday.
+        ("30 8 1 * 7", "2024-12-23T00:00:00Z", "2024-12-29T08:30:00Z"),
+        ("30 8 1 * 0", "2024-12-30T00:00:00Z", "2025-01-01T08:30:00Z"),
+        ("@hourly", "2024-12-31T23:59:59Z", "2025-01-01T00:00:00Z"),
+        ("@daily", "2024-12-23T00:00:00Z", "2024-12-24T00:00:00Z"),
+        ("@weekly", "2024-12-23T00:00:00Z", "2024-12-29T00:00:00Z"),
+        ("@monthly", "2024-12-23T10:00:00Z", "2025-01-01T00:00:00Z"),
+        (
+            "0 12 29 2 *",
+            "2025-01-01T00:00:00Z",
+            "2028-02-29T12:00:00Z",
+        ),
+        // Times are in UTC.
+        (
+            "0 12 * * *",
+            "2024-12-23T13:00:00+02:00",
+            "2024-12-23T12:00:00Z",
+        ),
+    ];
+    for (schedule, after, expected) in cases {
+        assert_eq!(
+            next(schedule, after).as_deref(),
+            Some(expected),
+            "{} after {}",
+            schedule,
+            after
+        );
+    }
+    assert_eq!(next("0 0 30 2 *", "2024-12-23T00:00:00Z"), None);
+}
+
+#[test]
+fn test_cron_rejects_invalid_schedules() {
+    for schedule in [
+        "",
+        "* * * *",
+        "* * * * * *",
+        "60 * * * *",
+        "0 24 * * *",
+        "0 0 0 * *",
+        "0 0 * 13 *",
+        "0 0 * * 8",
+        "*/0 * * * *",
+        "5-1 * * * *",
+        "a * * * *",
+        "1,,2 * * * *",
+        "@yearly",
+    ] {
+        assert!(
+            schedule.parse::<CronSchedule>().is_err(),
+            "{:?} should be invalid",
+            schedule
+        );
+    }
+    let schedule: CronSchedule = " 0 9 * * 1-5 ".parse().unwrap();
+    assert_eq!(schedule.to_string(), "0 9 * * 1-5");
+}
+
+#[tokio::test]
+async fn test_create_and_list_dca() {
+    let app = TestApp::new().await;
+    setup(&app.db.pool).await;
+    let id = create_hourly(&app, Some("2024-12-31T00:00:00Z")).await;
+
+    let (status, json) = get(&app, id).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["wallet_address"], WALLET);
+    assert_eq!(json["amount"], "2500000");
+    assert_eq!(json["from_decimals"], 6);
+    assert_eq!(json["schedule"], "0 * * * *");
+    assert_eq!(json["max_slippage_bps"], 50);
+    assert_eq!(json["venue"], "best_price");
+    assert_eq!(json["status"], "active");
+    assert_eq!(json["starts_at"], "2024-12-23T10:00:00Z");
+    assert_eq!(json["ends_at"], "2024-12-31T00:00:00Z");
+    // The start is the first run when it matches the schedule.
+    assert_eq!(json["next_run_at"], "2024-12-23T10:00:00Z");
+    assert_eq!(json["last_run_at"], Value::Null);
+
+    let req = Request::get(format!("/dca?wallet_address={}", WALLET))
+        .body(Body::empty())
+        .unwrap();
+    let (status, json) = send(&app, req).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"].as_array().unwrap().len(), 1);
+    assert_eq!(json["data"][0]["id"], id.to_string());
+
+    let req = Request::get(format!("/dca?wallet_address={}", OTHER_WALLET))
+        .body(Body::empty())
+        .unwrap();
+    let (_, json) = send(&app, req).await;
+    assert_eq!(json["data"], json!([]));
+
+    let (status, _) = get(&app, Uuid::now_v7()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_create_dca_validation() {
+    let app = TestApp::new().await;
+    setup(&app.db.pool).await;
+    let valid = json!({
+        "wallet_address": WALLET,
+        "from_token": FROM_TOKEN,
+        "to_token": TO_TOKEN,
+        "amount": 10,
+        "schedule": "@daily",
+    });
+    let (status, json) = create(&app, valid.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["amount"], "10000000");
+
+    let unknown = "0x0000000000000000000000000000000000000000000000000000000000000fed";
+    for (field, value) in [
+        ("from_token", json!("0x123")),
+        ("from_token", json!(unknown)),
+        ("to_token", json!(unknown)),
+        ("to_token", json!(FROM_TOKEN)),
+        ("amount", json!("0")),
+        ("amount", json!("0.0000001")),
+        ("amount", json!("abc")),
+        ("schedule", json!("every day")),
+        ("schedule", json!("0 0 30 2 *")),
+        ("max_slippage_bps", json!(10_001)),
+        ("venue", json!("uniswap")),
+        ("starts_at", json!("tomorrow")),
+        ("ends_at", json!("2000-01-01T00:00:00Z")),
+    ] {
+        let mut payload = valid.clone();
+        payload[field] = value.clone();
+        let (status, _) = create(&app, payload).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{} = {}", field, value);
+    }
+
+    // The schedule has to run before it ends.
+    let mut payload = valid.clone();
+    payload["starts_at"] = json!("2024-12-23T10:01:00Z");
+    payload["ends_at"] = json!("2024-12-23T23:59:00Z");
+    let (status, _) = create(&app, payload).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Wallets need an active subscription.
+    sqlx::query("UPDATE swap_subscription SET is_active = false WHERE wallet_address = $1")
+        .bind(WALLET)
+        .execute(&app.db.pool)
+        .await
+        .unwrap();
+    let (status, json) = create(&app, valid).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        json["message"],
+        "Invalid request: Wallet has no active subscription"
+    );
+}
+
+#[tokio::test]
+async fn test_scheduler_runs_due_schedules_once() {
+    let app = TestApp::new().await;
+    setup(&app.db.pool).await;
+    let id = create_hourly(&app, Some("2024-12-23T12:00:00Z")).await;
+    let scheduler = scheduler(&app);
+
+    assert_eq!(scheduler.tick(at("2024-12-23T09:59:59Z")).await.unwrap(), 0);
+    assert_eq!(scheduler.tick(at("2024-12-23T10:00:00Z")).await.unwrap(), 1);
+    assert_eq!(scheduler.tick(at("2024-12-23T10:00:30Z")).await.unwrap(), 0);
+
+    let (_, json) = get(&app, id).await;
+    assert_eq!(json["next_run_at"], "2024-12-23T11:00:00Z");
+    assert_eq!(json["last_run_at"], "2024-12-23T10:00:00Z");
+    let runs_so_far = runs(&app.db.pool, id).await;
+    assert_eq!(runs_so_far.len(), 1);
+    let job = job(&app.db.pool, runs_so_far[0].1.unwrap()).await;
+    assert_eq!(job.wallet_address, WALLET);
+    assert_eq!(job.from_token, FROM_TOKEN);
+    assert_eq!(job.to_token, TO_TOKEN);
+    assert_eq!(job.amount, "2500000");
+    assert_eq!(job.from_decimals, 6);
+    assert_eq!(job.percentage, 100);
+    assert_eq!(job.max_attempts, 5);
+    // Runs are paid for by the wallet.
+    assert!(job.pull_from_wallet);
+
+    // A restarted scheduler does not run the schedule again.
+    let restarted = self::scheduler(&app);
+    assert_eq!(restarted.tick(at("2024-12-23T10:30:00Z")).await.unwrap(), 0);
+
+    // Several schedulers run a due schedule once.
+    let (a, b) = tokio::join!(
+        scheduler.tick(at("2024-12-23T11:00:00Z")),
+        restarted.tick(at("2024-12-23T11:00:00Z"))
+    );
+    assert_eq!(a.unwrap() + b.unwrap(), 1);
+
+    // The 12:00 run is queued once, late, and the schedule then ends.
+    assert_eq!(scheduler.tick(at("2024-12-23T14:00:00Z")).await.unwrap(), 1);
+    assert_eq!(scheduler.tick(at("2024-12-23T15:00:00Z")).await.unwrap(), 0);
+    let (_, json) = get(&app, id).await;
+    assert_eq!(json["status"], "ended");
+    assert_eq!(json["next_run_at"], Value::Null);
+    assert_eq!(json["last_run_at"], "2024-12-23T12:00:00Z");
+    let run_times: Vec<String> = runs(&app.db.pool, id)
+        .await
+        .into_iter()
+        .map(|(run_at, _)| run_at)
+        .collect();
+    assert_eq!(
+        run_times,
+        vec![
+            "2024-12-23T10:00:00Z",
+            "2024-12-23T11:00:00Z",
+            "2024-12-23T12:00:00Z"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_scheduler_skips_missed_runs() {
+    let app = TestApp::new().await;
+    setup(&app.db.pool).await;
+    let id = create_hourly(&app, None).await;
+    let scheduler = scheduler(&app);
+
+    // Down from 10:00 to 15:30: one swap, then the next hour.
+    assert_eq!(scheduler.tick(at("2024-12-23T15:30:00Z")).await.unwrap(), 1);
+    let (_, json) = get(&app, id).await;
+    assert_eq!(json["next_run_at"], "2024-12-23T16:00:00Z");
+
+    // Runs are skipped while a token is disabled.
+    sqlx::query("UPDATE token SET is_active = false WHERE LOWER(contract_address) = LOWER($1)")
+        .bind(TO_TOKEN)
+        .execute(&app.db.pool)
+        .await
+        .unwrap();
+    assert_eq!(scheduler.tick(at("2024-12-23T16:00:00Z")).await.unwrap(), 0);
+    let (_, json) = get(&app, id).await;
+    assert_eq!(json["next_run_at"], "2024-12-23T17:00:00Z");
+    assert_eq!(json["last_run_at"], "2024-12-23T10:00:00Z");
+    let runs = runs(&app.db.pool, id).await;
+    assert_eq!(runs[1], ("2024-12-23T16:00:00Z".to_string(), None));
+}
+
+#[tokio::test]
+async fn test_pause_and_resume_dca() {
+    let app = TestApp::new().await;
+    setup(&app.db.pool).await;
+    let id = create_hourly(&app, None).await;
+    let scheduler = scheduler(&app);
+
+    let (status, json) = owner_action(&app, id, "pause", WALLET).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["status"], "paused");
+    assert_eq!(scheduler.tick(at("2024-12-23T12:00:00Z")).await.unwrap(), 0);
+
+    // Only the owner can change a schedule.
+    for action in ["resume", "delete"] {
+        let (status, _) = owner_action(&app, id, action, OTHER_WALLET).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    // Runs due while paused are skipped.
+    let (status, json) = owner_action(&app, id, "resume", WALLET).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["status"], "active");
+    let next_run_at = at(json["next_run_at"].as_str().unwrap());
+    let now = OffsetDateTime::now_utc();
+    assert!(next_run_at > now && next_run_at <= now + time::Duration::hours(1));
+    assert_eq!(scheduler.tick(now).await.unwrap(), 0);
+    assert_eq!(scheduler.tick(next_run_at).await.unwrap(), 1);
+
+    let (status, json) = owner_action(&app, id, "delete", WALLET).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["success"], true);
+    let (status, _) = get(&app, id).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_signature_is_bound_to_its_route() {
+    let app = TestApp::new().await;
+    setup(&app.db.pool).await;
+    let a = create_hourly(&app, None).await;
+    let b = create_hourly(&app, None).await;
+
+    // A captured "pause schedule A" can't be replayed as "delete schedule B".
+    let payload = json!({ "wallet_address": WALLET });
+    let signed = signed_request("POST", &format!("/dca/{}/pause", a), WALLET, &payload);
+    let (mut parts, body) = signed.into_parts();
+    parts.method = axum::http::Method::DELETE;
+    parts.uri = format!("/dca/{}", b).parse().unwrap();
+    let (status, json) = send(&app, Request::from_parts(parts, body)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(json["message"], "Unauthorized: Invalid signature");
+
+    // Nor on the same action against another schedule.
+    let signed = signed_request("POST", &format!("/dca/{}/pause", a), WALLET, &payload);
+    let (mut parts, body) = signed.into_parts();
+    parts.uri = format!("/dca/{}/pause", b).parse().unwrap();
+    let (status, _) = send(&app, Request::from_parts(parts, body)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    for id in [a, b] {
+        let (status, json) = get(&app, id).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["status"], "active");
+    }
+}
+
+#[tokio::test]
+async fn test_update_dca() {
+    let app = TestApp::new().await;
+    setup(&app.db.pool).await;
+    let id = create_hourly(&app, None).await;
+
+    let update =
+        |payload: Value| signed_request("PATCH", &format!("/dca/{}", id), WALLET, &payload);
+
+    // Settings that do not change the timing keep the next run.
+    let (status, json) = send(
+        &app,
+        update(json!({ "wallet_address": WALLET, "amount": 4, "max_slippage_bps": 100 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["amount"], "4000000");
+    assert_eq!(json["max_slippage_bps"], 100);
+    assert_eq!(json["schedule"], "0 * * * *");
+    assert_eq!(json["next_run_at"], "2024-12-23T10:00:00Z");
+
+    // A new schedule runs from now.
+    let (status, json) = send(
+        &app,
+        update(json!({ "wallet_address": WALLET, "schedule": "*/5 * * * *" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["schedule"], "*/5 * * * *");
+    let next_run_at = at(json["next_run_at"].as_str().unwrap());
+    let now = OffsetDateTime::now_utc();
+    assert!(next_run_at > now && next_run_at <= now + time::Duration::minutes(5));
+
+    for payload in [
+        json!({ "wallet_address": WALLET, "schedule": "*/0 * * * *" }),
+        json!({ "wallet_address": WALLET, "amount": "0" }),
+        json!({ "wallet_address": WALLET, "ends_at": "2024-12-23T11:00:00Z" }),
+    ] {
+        let (status, _) = send(&app, update(payload.clone())).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", payload);
+    }
+
+    let payload = json!({ "wallet_address": OTHER_WALLET, "amount": 1 });
+    let req = signed_request("PATCH", &format!("/dca/{}", id), OTHER_WALLET, &payload);
+    let (status, _) = send(&app, req).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
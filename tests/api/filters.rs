@@ -0,0 +1,151 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use autoswappr_backend::utils::filters::{Column, Filters, Op};
+
+use crate::helpers::*;
+
+const WALLET: &str = "0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef";
+const FROM_TOKEN: &str = "0x9876543210fedcba9876543210fedcba9876543210fedcba9876543210fedcba";
+const TO_TOKEN: &str = "0x1111111111111111111111111111111111111111111111111111111111111111";
+
+// Percent-encoded query values that would match every row, or drop a table,
+// if they were formatted into SQL.
+const INJECTIONS: [&str; 4] = [
+    // ' OR '1'='1
+    "%27%20OR%20%271%27%3D%271",
+    // x' OR 1=1 --
+    "x%27%20OR%201%3D1%20--",
+    // '; DROP TABLE transactions_log; --
+    "%27%3B%20DROP%20TABLE%20transactions_log%3B%20--",
+    // '; DROP TABLE swap_subscription CASCADE; --
+    "%27%3B%20DROP%20TABLE%20swap_subscription%20CASCADE%3B%20--",
+];
+
+async fn get(app: &TestApp, uri: &str) -> (StatusCode, Value) {
+    let resp = app
+        .request(Request::get(uri).body(Body::empty()).unwrap())
+        .await;
+    let status = resp.status();
+    let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+async fn log_swap(pool: &PgPool) {
+    sqlx::query(
+        r#"
+        INSERT INTO transactions_log
+        (wallet_address, from_token, to_token, percentage, amount_from, amount_to, created_at)
+        VALUES ($1, $2, $3, 50, 100, 50, '2024-11-28 12:02:49+00')
+        "#,
+    )
+    .bind(WALLET)
+    .bind(FROM_TOKEN)
+    .bind(TO_TOKEN)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+async fn count(pool: &PgPool, table: &str) -> i64 {
+    sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table))
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[test]
+fn test_filters_bind_every_value() {
+    let mut filters = Filters::new("SELECT * FROM transactions_log");
+    filters
+        .eq("wallet_address", Some("' OR '1'='1"))
+        .eq("from_token", None::<String>)
+        .range(
+            Column::cast("created_at", "TIMESTAMPTZ"),
+            Some("2024-11-01T00:00:00Z"),
+            None,
+        )
+        .any_of("to_token", Some(vec!["a", "b"]))
+        .compare("percentage", Op::Gt, Some(10_i16));
+    assert_eq!(
+        filters.into_builder().sql(),
+        "SELECT * FROM transactions_log WHERE wallet_address = $1 \
+         AND created_at >= $2::TIMESTAMPTZ AND to_token IN ($3, $4) AND percentage > $5"
+    );
+}
+
+#[test]
+fn test_filters_without_values() {
+    let mut filters = Filters::new("SELECT * FROM transactions_log");
+    filters
+        .eq("wallet_address", None::<String>)
+        .range("amount_to", None::<i64>, None)
+        .any_of("to_token", None::<Vec<String>>);
+    assert_eq!(
+        filters.into_builder().sql(),
+        "SELECT * FROM transactions_log"
+    );
+}
+
+#[test]
+fn test_filters_match_nothing_in_empty_list() {
+    let mut filters = Filters::new("SELECT * FROM transactions_log");
+    filters.any_of("to_token", Some(Vec::<String>::new()));
+    assert_eq!(
+        filters.into_builder().sql(),
+        "SELECT * FROM transactions_log WHERE FALSE"
+    );
+}
+
+#[tokio::test]
+async fn test_log_retrieval_filters_are_not_injectable() {
+    let app = TestApp::new().await;
+    log_swap(&app.db.pool).await;
+
+    let (status, json) = get(&app, &format!("/log_retrieval?wallet_address={}", WALLET)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"].as_array().unwrap().len(), 1);
+
+    for param in ["wallet_address", "from_token", "to_token"] {
+        for injection in INJECTIONS {
+            let (status, json) =
+                get(&app, &format!("/log_retrieval?{}={}", param, injection)).await;
+            assert_eq!(status, StatusCode::OK, "{}={}", param, injection);
+            assert_eq!(json["data"], json!([]), "{}={}", param, injection);
+        }
+    }
+    assert_eq!(count(&app.db.pool, "transactions_log").await, 1);
+}
+
+#[tokio::test]
+async fn test_subscription_listing_is_not_injectable() {
+    let app = TestApp::new().await;
+    for token in [FROM_TOKEN, TO_TOKEN] {
+        register_token(&app.db.pool, token).await;
+    }
+    let payload = json!({
+        "wallet_address": WALLET,
+        "to_token": TO_TOKEN,
+        "from_token": [FROM_TOKEN],
+        "percentage": [50]
+    });
+    let resp = app
+        .request(signed_request("POST", "/subscriptions", WALLET, &payload))
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    for injection in INJECTIONS {
+        let (status, json) = get(
+            &app,
+            &format!("/subscriptions?wallet_address={}", injection),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{}", injection);
+        assert_eq!(json["data"], json!([]), "{}", injection);
+    }
+    assert_eq!(count(&app.db.pool, "swap_subscription").await, 1);
+}
@@ -1,28 +1,72 @@
 use axum::{
     body::Body,
-    http::{Request, Response},
+    http::{header::CONTENT_TYPE, Request, Response},
     Router,
 };
+use serde_json::{json, Value};
 use sqlx::{Connection, Executor, PgConnection, PgPool};
-use std::sync::Once;
+use starknet::{
+    core::{
+        crypto::Signature,
+        types::{Felt, U256},
+    },
+    macros::selector,
+    signers::SigningKey,
+};
+use std::sync::{Arc, Once};
+use time::OffsetDateTime;
 use tower::ServiceExt;
 use uuid::Uuid;
 
-use autoswappr_backend::{router, telemetry, Configuration, Db};
+use crate::mock_rpc::MockRpc;
+use autoswappr_backend::{
+    auth::{
+        typed_data::{body_hash, method_felt, path_hash, SignedRequest},
+        EXPIRY_HEADER, NONCE_HEADER, SIGNATURE_HEADER,
+    },
+    router,
+    service::auto_swap::{process_transfer, IncomingTransfer, TransferLeg},
+    telemetry, Configuration, Db,
+};
 
 static TRACING: Once = Once::new();
 
+// Key that owns every account on the mock RPC node.
+pub const TEST_PRIVATE_KEY: &str =
+    "0x0139fe4d6f02e666e86a6f58e65060f115cd3c185bd9e98bd829636931458f79";
+
+// Swap account and router contract of the test app.
+pub const TEST_ACCOUNT_ADDRESS: &str =
+    "0x0000000000000000000000000000000000000000000000000000000000000acc";
+pub const TEST_CONTRACT_ADDRESS: &str =
+    "0x0000000000000000000000000000000000000000000000000000000000000e4b";
+
+// Key accepted by the admin endpoints.
+pub const TEST_ADMIN_KEY: &str = "test-admin-key";
+
 pub struct TestApp {
     pub router: Router,
     pub db: Db,
+    pub rpc: MockRpc,
 }
 
 impl TestApp {
     pub async fn new() -> Self {
         dotenvy::dotenv().ok();
         std::env::set_var("PORT", "0");
+        std::env::set_var("RPC_URL", "http://localhost");
+        std::env::set_var("STARKNET_CHAIN", "mainnet");
+        std::env::set_var("PUBLIC_KEY", TEST_ACCOUNT_ADDRESS);
+        std::env::set_var("PRIVATE_KEY", TEST_PRIVATE_KEY);
+        std::env::set_var("CONTRACT_ADDRESS", TEST_CONTRACT_ADDRESS);
         TRACING.call_once(telemetry::setup_tracing);
-        let config = Configuration::new();
+        let rpc = MockRpc::start().await;
+        mock_accounts(&rpc);
+        let mut config = Configuration::new();
+        let test_config = Arc::get_mut(&mut config).unwrap();
+        test_config.set_rpc_url(rpc.url.clone());
+        test_config.set_admin_api_key(TEST_ADMIN_KEY.to_string());
+        test_config.set_avnu_api_url(None);
         let db_str = create_test_db(&config.db_str).await;
         let db = Db::new(&db_str, config.db_pool_max_size)
             .await
@@ -30,7 +74,7 @@ impl TestApp {
         tracing::debug!("Running migrations");
         db.migrate().await.expect("Failed to run migrations");
         let router = router(config, db.clone());
-        Self { db, router }
+        Self { db, router, rpc }
     }
 
     pub async fn request(&self, req: Request<Body>) -> Response<Body> {
@@ -38,6 +82,119 @@ impl TestApp {
     }
 }
 
+// Make every account on the mock node validate signatures made with `TEST_PRIVATE_KEY`.
+fn mock_accounts(rpc: &MockRpc) {
+    let verifying_key = test_signing_key().verifying_key();
+    rpc.on("starknet_call", move |params| {
+        let request = &params["request"];
+        let selector: Felt =
+            serde_json::from_value(request["entry_point_selector"].clone()).unwrap();
+        if selector != selector!("is_valid_signature") {
+            return Err(json!({ "code": 21, "message": "Invalid message selector" }));
+        }
+        let calldata: Vec<Felt> = serde_json::from_value(request["calldata"].clone()).unwrap();
+        let signature = Signature {
+            r: calldata[2],
+            s: calldata[3],
+        };
+        match verifying_key.verify(&calldata[0], &signature) {
+            Ok(true) => Ok(json!(["0x56414c4944"])),
+            _ => Ok(json!(["0x0"])),
+        }
+    });
+}
+
+pub fn test_signing_key() -> SigningKey {
+    SigningKey::from_secret_scalar(Felt::from_hex(TEST_PRIVATE_KEY).unwrap())
+}
+
+// Build a JSON request signed by `signer` with the test key.
+pub fn signed_request(method: &str, uri: &str, signer: &str, payload: &Value) -> Request<Body> {
+    let nonce = Felt::from(Uuid::now_v7().as_u128());
+    let expiry = (OffsetDateTime::now_utc().unix_timestamp() + 300) as u64;
+    signed_request_with(
+        &test_signing_key(),
+        method,
+        uri,
+        signer,
+        payload,
+        nonce,
+        expiry,
+    )
+}
+
+pub fn signed_request_with(
+    key: &SigningKey,
+    method: &str,
+    uri: &str,
+    signer: &str,
+    payload: &Value,
+    nonce: Felt,
+    expiry: u64,
+) -> Request<Body> {
+    let body = serde_json::to_string(payload).unwrap();
+    let message_hash = SignedRequest {
+        wallet_address: Felt::from_hex(signer).unwrap_or(Felt::ZERO),
+        method: method_felt(method),
+        path_hash: path_hash(uri.split('?').next().unwrap_or(uri)),
+        body_hash: body_hash(body.as_bytes()),
+        nonce,
+        expiry,
+    }
+    .message_hash(starknet::core::chain_id::MAINNET);
+    let signature = key.sign(&message_hash).unwrap();
+
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(CONTENT_TYPE, "application/json")
+        .header(
+            SIGNATURE_HEADER,
+            format!("{:#x},{:#x}", signature.r, signature.s),
+        )
+        .header(NONCE_HEADER, format!("{:#x}", nonce))
+        .header(EXPIRY_HEADER, expiry.to_string())
+        .body(Body::from(body))
+        .unwrap()
+}
+
+// Add an 18 decimals token to the registry so it can be subscribed to and logged.
+pub async fn register_token(pool: &PgPool, contract_address: &str) {
+    register_token_with_decimals(pool, contract_address, 18).await
+}
+
+pub async fn register_token_with_decimals(pool: &PgPool, contract_address: &str, decimals: i16) {
+    sqlx::query(
+        r#"
+        INSERT INTO token (contract_address, token_name, token_symbol, token_decimals)
+        VALUES ($1, 'Test Token', 'TEST', $2)
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(contract_address)
+    .bind(decimals)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+// Feed a transfer of `amount` base units of `token` to `wallet` into the swap
+// pipeline, as the indexer does.
+pub async fn receive_transfer(
+    pool: &PgPool,
+    wallet: &str,
+    token: &str,
+    amount: u128,
+) -> Vec<TransferLeg> {
+    let mut conn = pool.acquire().await.unwrap();
+    let transfer = IncomingTransfer {
+        wallet_address: wallet.to_string(),
+        token_from: token.to_string(),
+        amount: U256::from(amount),
+    };
+    process_transfer(&mut conn, &transfer, 3).await.unwrap()
+}
+
 pub async fn create_test_db(db_str: &str) -> String {
     let db_name =
         std::env::var("DATABASE_NAME").expect("DATABASE_NAME environment variable not specified.");
@@ -51,7 +208,7 @@ pub async fn create_test_db(db_str: &str) -> String {
     conn.execute(format!(r#"CREATE DATABASE "{}";"#, random_db_name).as_str())
         .await
         .expect("Failed to create test DB.");
-    db_str.to_owned()
+    format!("{}{}", db_str, random_db_name)
 }
 
 pub async fn clean_database(pool: &PgPool) {
@@ -0,0 +1,201 @@
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use starknet::providers::{
+    jsonrpc::{HttpTransport, JsonRpcClient},
+    Url,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use autoswappr_backend::indexer::{IndexerSettings, TransferIndexer, TRANSFER_EVENT_KEY};
+
+use crate::helpers::*;
+use crate::mock_rpc::MockRpc;
+
+const WALLET: &str = "0x0000000000000000000000000000000000000000000000000000000000000a11";
+const OTHER_WALLET: &str = "0x0000000000000000000000000000000000000000000000000000000000000b22";
+const TOKEN: &str = "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
+const TO_TOKEN: &str = "0x053c91253bc9682c04929ca02ed00b3e423f6710d2ee7e0d5ebb06f3ecf368a8";
+
+// Canned chain served by the mock node.
+#[derive(Clone, Default)]
+struct Chain {
+    head: Arc<Mutex<u64>>,
+    hashes: Arc<Mutex<HashMap<u64, String>>>,
+    events: Arc<Mutex<Vec<Value>>>,
+}
+
+impl Chain {
+    fn serve(&self, rpc: &MockRpc) {
+        let head = self.head.clone();
+        rpc.on("starknet_blockNumber", move |_| {
+            Ok(json!(*head.lock().unwrap()))
+        });
+
+        let hashes = self.hashes.clone();
+        rpc.on("starknet_getBlockWithTxHashes", move |params| {
+            let number = params["block_id"]["block_number"].as_u64().unwrap();
+            let hash = hashes
+                .lock()
+                .unwrap()
+                .get(&number)
+                .cloned()
+                .unwrap_or_else(|| format!("{:#x}", number));
+            Ok(block(number, &hash))
+        });
+
+        let events = self.events.clone();
+        rpc.on("starknet_getEvents", move |params| {
+            let filter = &params["filter"];
+            let from = filter["from_block"]["block_number"].as_u64().unwrap();
+            let to = filter["to_block"]["block_number"].as_u64().unwrap();
+            let events: Vec<Value> = events
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|event| {
+                    let number = event["block_number"].as_u64().unwrap();
+                    number >= from && number <= to
+                })
+                .cloned()
+                .collect();
+            Ok(json!({ "events": events, "continuation_token": null }))
+        });
+    }
+
+    fn push_transfer(&self, tx_hash: &str, block_number: u64, to: &str, amount: u128) {
+        self.events.lock().unwrap().push(json!({
+            "from_address": TOKEN,
+            "keys": [format!("{:#x}", TRANSFER_EVENT_KEY), "0x1", to],
+            "data": [format!("{:#x}", amount), "0x0"],
+            "block_hash": format!("{:#x}", block_number),
+            "block_number": block_number,
+            "transaction_hash": tx_hash
+        }));
+    }
+}
+
+fn block(number: u64, hash: &str) -> Value {
+    json!({
+        "status": "ACCEPTED_ON_L2",
+        "block_hash": hash,
+        "parent_hash": "0x0",
+        "block_number": number,
+        "new_root": "0x0",
+        "timestamp": 1733400000u64 + number,
+        "sequencer_address": "0x0",
+        "l1_gas_price": { "price_in_fri": "0x1", "price_in_wei": "0x1" },
+        "l1_data_gas_price": { "price_in_fri": "0x1", "price_in_wei": "0x1" },
+        "l1_da_mode": "BLOB",
+        "starknet_version": "0.13.2",
+        "transactions": []
+    })
+}
+
+async fn subscribe(pool: &PgPool, wallet_address: &str) {
+    register_token(pool, TOKEN).await;
+    sqlx::query("INSERT INTO swap_subscription (wallet_address, to_token) VALUES ($1, $2)")
+        .bind(wallet_address)
+        .bind(TO_TOKEN)
+        .execute(pool)
+        .await
+        .unwrap();
+    sqlx::query(
+        "INSERT INTO swap_subscription_from_token (wallet_address, from_token, percentage) VALUES ($1, $2, 50)",
+    )
+    .bind(wallet_address)
+    .bind(TOKEN)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+fn indexer(app: &TestApp) -> TransferIndexer<JsonRpcClient<HttpTransport>> {
+    let provider = JsonRpcClient::new(HttpTransport::new(Url::parse(&app.rpc.url).unwrap()));
+    let settings = IndexerSettings {
+        cursor_name: "test_transfers".to_string(),
+        start_block: Some(100),
+        reorg_depth: 5,
+        poll_interval: Duration::from_secs(1),
+        swap_job_max_attempts: 3,
+    };
+    TransferIndexer::new(provider, app.db.clone(), settings)
+}
+
+// Amounts of the queued swap jobs, oldest first.
+async fn queued_amounts(pool: &PgPool) -> Vec<String> {
+    sqlx::query_as::<_, (String,)>("SELECT amount::TEXT FROM swap_jobs ORDER BY created_at")
+        .fetch_all(pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|(amount,)| amount)
+        .collect()
+}
+
+#[tokio::test]
+async fn test_indexer_queues_swaps_for_subscribed_wallets() {
+    let app = TestApp::new().await;
+    subscribe(&app.db.pool, WALLET).await;
+
+    let chain = Chain::default();
+    *chain.head.lock().unwrap() = 105;
+    chain.push_transfer("0x111", 101, WALLET, 1000);
+    chain.push_transfer("0x222", 102, OTHER_WALLET, 500);
+    chain.serve(&app.rpc);
+
+    let indexer = indexer(&app);
+    assert_eq!(indexer.poll().await.unwrap(), 1);
+
+    let job = sqlx::query_as::<_, (String, String, String)>(
+        "SELECT wallet_address, from_token, amount::TEXT FROM swap_jobs",
+    )
+    .fetch_one(&app.db.pool)
+    .await
+    .unwrap();
+    // Subscribed to swap 50% of every transfer.
+    assert_eq!(
+        job,
+        (WALLET.to_string(), TOKEN.to_string(), "500".to_string())
+    );
+
+    let cursor = sqlx::query_as::<_, (i64,)>(
+        "SELECT block_number FROM indexer_cursor WHERE name = 'test_transfers'",
+    )
+    .fetch_one(&app.db.pool)
+    .await
+    .unwrap();
+    assert_eq!(cursor.0, 105);
+
+    // Nothing new on chain, nothing queued.
+    assert_eq!(indexer.poll().await.unwrap(), 0);
+    assert_eq!(queued_amounts(&app.db.pool).await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_indexer_rescans_after_reorg_without_duplicates() {
+    let app = TestApp::new().await;
+    subscribe(&app.db.pool, WALLET).await;
+
+    let chain = Chain::default();
+    *chain.head.lock().unwrap() = 105;
+    chain.push_transfer("0x111", 101, WALLET, 1000);
+    chain.serve(&app.rpc);
+
+    let indexer = indexer(&app);
+    assert_eq!(indexer.poll().await.unwrap(), 1);
+
+    // Block 105 is replaced and a transfer lands in a re-organised block.
+    chain
+        .hashes
+        .lock()
+        .unwrap()
+        .insert(105, "0xdeadbeef".to_string());
+    chain.push_transfer("0x333", 104, WALLET, 2000);
+
+    assert_eq!(indexer.poll().await.unwrap(), 1);
+    assert_eq!(queued_amounts(&app.db.pool).await, vec!["500", "1000"]);
+}
@@ -1,8 +1,30 @@
 mod activity_log_retrieval;
 mod address_validation;
+mod amount;
+mod auth;
+mod avnu;
+mod dca;
+mod filters;
 mod health_check;
 mod helpers;
+mod indexer;
+mod mock_avnu;
+mod mock_rpc;
+mod nonces;
 mod percentage_update;
+mod pools;
+mod price_history;
+mod pricing;
+mod protection;
+mod quote;
+mod receipts;
+mod slippage;
 mod subscription;
+mod subscription_targets;
+mod swap_jobs;
+mod tokens;
 mod transaction_logs;
+mod triggers;
 mod unsubscription;
+mod venues;
+mod wallet_stats;
@@ -0,0 +1,60 @@
+use axum::{extract::State, routing::post, Json, Router};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use tokio::net::TcpListener;
+
+type Handler = Box<dyn Fn(&Value) -> Result<Value, Value> + Send + Sync>;
+type Handlers = Arc<Mutex<HashMap<String, Handler>>>;
+
+// Local Starknet JSON-RPC node that answers with canned responses.
+pub struct MockRpc {
+    pub url: String,
+    handlers: Handlers,
+}
+
+impl MockRpc {
+    pub async fn start() -> Self {
+        let handlers: Handlers = Arc::new(Mutex::new(HashMap::new()));
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind mock RPC address");
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let router = Router::new()
+            .route("/", post(handle_rpc))
+            .with_state(handlers.clone());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        Self { url, handlers }
+    }
+
+    // Answer `method` with the result of `handler`. An `Err` is sent back as a JSON-RPC error.
+    pub fn on<F>(&self, method: &str, handler: F)
+    where
+        F: Fn(&Value) -> Result<Value, Value> + Send + Sync + 'static,
+    {
+        self.handlers
+            .lock()
+            .unwrap()
+            .insert(method.to_string(), Box::new(handler));
+    }
+}
+
+async fn handle_rpc(State(handlers): State<Handlers>, Json(req): Json<Value>) -> Json<Value> {
+    let id = req["id"].clone();
+    let method = req["method"].as_str().unwrap_or_default();
+    let handlers = handlers.lock().unwrap();
+    let resp = match handlers.get(method) {
+        Some(handler) => match handler(&req["params"]) {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+        },
+        None => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": -32601, "message": format!("Method not found: {}", method) }
+        }),
+    };
+    Json(resp)
+}
@@ -0,0 +1,206 @@
+use serde_json::json;
+use starknet::{
+    accounts::{ExecutionEncoding, SingleOwnerAccount},
+    core::{
+        chain_id,
+        types::{Call, Felt},
+    },
+    macros::selector,
+    providers::{
+        jsonrpc::{HttpTransport, JsonRpcClient},
+        Url,
+    },
+    signers::LocalWallet,
+};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+use tokio::task::JoinSet;
+
+use autoswappr_backend::{service::nonces::NonceManager, utils::starknet::SwapAccount};
+
+use crate::helpers::*;
+
+const EXECUTOR: &str = "0x0000000000000000000000000000000000000000000000000000000000000acd";
+
+// Sender and nonce of every transaction the node accepted.
+type Sent = Arc<Mutex<Vec<(Felt, u64)>>>;
+
+fn account(app: &TestApp, address: &str) -> SwapAccount {
+    let provider = JsonRpcClient::new(HttpTransport::new(Url::parse(&app.rpc.url).unwrap()));
+    SingleOwnerAccount::new(
+        provider,
+        LocalWallet::from(test_signing_key()),
+        Felt::from_hex(address).unwrap(),
+        chain_id::MAINNET,
+        ExecutionEncoding::New,
+    )
+}
+
+fn swap_calls() -> Vec<Call> {
+    vec![Call {
+        to: Felt::from_hex(TEST_CONTRACT_ADDRESS).unwrap(),
+        selector: selector!("swap"),
+        calldata: vec![],
+    }]
+}
+
+// A node that reports the nonces in `chain_nonces` one read after the other,
+// repeating the last, and rejects transactions with a nonce below `min_nonce`.
+// Returns the accepted transactions and the number of nonce reads.
+fn mock_node(app: &TestApp, chain_nonces: Vec<u64>, min_nonce: u64) -> (Sent, Arc<AtomicUsize>) {
+    let reads = Arc::new(AtomicUsize::new(0));
+    let read = reads.clone();
+    app.rpc.on("starknet_getNonce", move |_| {
+        let index = read
+            .fetch_add(1, Ordering::SeqCst)
+            .min(chain_nonces.len() - 1);
+        Ok(json!(format!("{:#x}", chain_nonces[index])))
+    });
+    app.rpc.on("starknet_estimateFee", |_| {
+        Ok(json!([{
+            "gas_consumed": "0x64",
+            "gas_price": "0x1",
+            "data_gas_consumed": "0x0",
+            "data_gas_price": "0x1",
+            "overall_fee": "0x64",
+            "unit": "FRI"
+        }]))
+    });
+
+    let sent: Sent = Arc::new(Mutex::new(vec![]));
+    let accepted = sent.clone();
+    app.rpc.on("starknet_addInvokeTransaction", move |params| {
+        let transaction = &params["invoke_transaction"];
+        let sender: Felt = serde_json::from_value(transaction["sender_address"].clone()).unwrap();
+        let nonce: Felt = serde_json::from_value(transaction["nonce"].clone()).unwrap();
+        let nonce = u64::try_from(nonce).unwrap();
+        if nonce < min_nonce {
+            return Err(json!({ "code": 52, "message": "Invalid transaction nonce" }));
+        }
+        accepted.lock().unwrap().push((sender, nonce));
+        Ok(json!({ "transaction_hash": format!("{:#x}", 0x1000 + nonce) }))
+    });
+    (sent, reads)
+}
+
+fn nonces(sent: &Sent) -> Vec<u64> {
+    let mut nonces: Vec<u64> = sent
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(_, nonce)| *nonce)
+        .collect();
+    nonces.sort();
+    nonces
+}
+
+#[tokio::test]
+async fn test_concurrent_swaps_get_distinct_nonces() {
+    let app = TestApp::new().await;
+    // The chain counts three of the swaps by the second read.
+    let (sent, reads) = mock_node(&app, vec![5, 8], 0);
+    let manager = NonceManager::new(vec![account(&app, TEST_ACCOUNT_ADDRESS)]);
+
+    let mut swaps = JoinSet::new();
+    for _ in 0..10 {
+        let manager = manager.clone();
+        swaps.spawn(async move {
+            let account = manager.next_account();
+            manager.send(&account, swap_calls()).await
+        });
+    }
+    while let Some(result) = swaps.join_next().await {
+        result.unwrap().unwrap();
+    }
+
+    assert_eq!(nonces(&sent), (5..15).collect::<Vec<_>>());
+    // The nonce is read from chain once and then handed out locally.
+    assert_eq!(reads.load(Ordering::SeqCst), 1);
+    let address = Felt::from_hex(TEST_ACCOUNT_ADDRESS).unwrap();
+    let in_flight = manager.in_flight(address).await.unwrap();
+    assert_eq!(in_flight.len(), 10);
+    assert_eq!(in_flight[0], (Felt::from(5u8), Felt::from(0x1005u16)));
+
+    // Swaps stay in flight until their outcome is settled, and whether the
+    // chain counted them is read from chain.
+    assert_eq!(
+        manager.counted(Felt::from(0x1007u16)).await.unwrap(),
+        Some(true)
+    );
+    assert_eq!(
+        manager.counted(Felt::from(0x1008u16)).await.unwrap(),
+        Some(false)
+    );
+    assert_eq!(manager.counted(Felt::from(0x2000u16)).await.unwrap(), None);
+    for nonce in 5..8u16 {
+        manager.settle(Felt::from(0x1000 + nonce)).await;
+    }
+    let in_flight = manager.in_flight(address).await.unwrap();
+    assert_eq!(in_flight.len(), 7);
+    assert_eq!(in_flight[0], (Felt::from(8u8), Felt::from(0x1008u16)));
+
+    // Nonces are still handed out locally.
+    let account = manager.next_account();
+    manager.send(&account, swap_calls()).await.unwrap();
+    assert_eq!(nonces(&sent).last(), Some(&15));
+}
+
+#[tokio::test]
+async fn test_rejected_nonce_is_read_again() {
+    let app = TestApp::new().await;
+    // Another sender used nonces 3 to 6 after the first read.
+    let (sent, reads) = mock_node(&app, vec![3, 7], 7);
+    let manager = NonceManager::new(vec![account(&app, TEST_ACCOUNT_ADDRESS)]);
+    let account = manager.next_account();
+
+    let hash = manager.send(&account, swap_calls()).await.unwrap();
+    assert_eq!(hash, Felt::from(0x1007u16));
+    manager.send(&account, swap_calls()).await.unwrap();
+
+    assert_eq!(nonces(&sent), vec![7, 8]);
+    assert_eq!(reads.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_nonce_rejected_after_resync_fails() {
+    let app = TestApp::new().await;
+    let (sent, reads) = mock_node(&app, vec![3], 7);
+    let manager = NonceManager::new(vec![account(&app, TEST_ACCOUNT_ADDRESS)]);
+    let account = manager.next_account();
+
+    assert!(manager.send(&account, swap_calls()).await.is_err());
+    assert!(nonces(&sent).is_empty());
+    assert_eq!(reads.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_swaps_take_turns_between_accounts() {
+    let app = TestApp::new().await;
+    let (sent, _) = mock_node(&app, vec![5], 0);
+    let manager = NonceManager::new(vec![
+        account(&app, TEST_ACCOUNT_ADDRESS),
+        account(&app, EXECUTOR),
+    ]);
+
+    for _ in 0..4 {
+        let account = manager.next_account();
+        manager.send(&account, swap_calls()).await.unwrap();
+    }
+
+    // Each account counts its own nonces.
+    let (main, executor) = (
+        Felt::from_hex(TEST_ACCOUNT_ADDRESS).unwrap(),
+        Felt::from_hex(EXECUTOR).unwrap(),
+    );
+    assert_eq!(
+        *sent.lock().unwrap(),
+        vec![(main, 5), (executor, 5), (main, 6), (executor, 6)]
+    );
+    assert_eq!(manager.addresses(), vec![main, executor]);
+
+    // Accounts outside the manager have no nonces to hand out.
+    let other = account(&app, "0x123");
+    assert!(manager.send(&other, swap_calls()).await.is_err());
+}
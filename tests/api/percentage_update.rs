@@ -1,7 +1,4 @@
-use axum::{
-    body::Body,
-    http::{header::CONTENT_TYPE, Request, StatusCode},
-};
+use axum::http::StatusCode;
 use serde_json::json;
 use sqlx::PgPool;
 
@@ -60,12 +57,12 @@ async fn test_update_percentage_success() {
         "percentage": 75
     });
 
-    let req = Request::builder()
-        .method("PATCH")
-        .uri("/update_percentage")
-        .header(CONTENT_TYPE, "application/json")
-        .body(Body::from(serde_json::to_string(&payload).unwrap()))
-        .unwrap();
+    let req = signed_request(
+        "PATCH",
+        "/update_percentage",
+        payload["wallet_address"].as_str().unwrap(),
+        &payload,
+    );
 
     let resp = app.request(req).await;
     assert_eq!(resp.status(), StatusCode::OK);
@@ -98,12 +95,12 @@ async fn test_update_percentage_not_found() {
         "percentage": 75
     });
 
-    let req = Request::builder()
-        .method("PATCH")
-        .uri("/update_percentage")
-        .header(CONTENT_TYPE, "application/json")
-        .body(Body::from(serde_json::to_string(&payload).unwrap()))
-        .unwrap();
+    let req = signed_request(
+        "PATCH",
+        "/update_percentage",
+        payload["wallet_address"].as_str().unwrap(),
+        &payload,
+    );
 
     let resp = app.request(req).await;
     assert_eq!(resp.status(), StatusCode::NOT_FOUND);
@@ -0,0 +1,196 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{header::CONTENT_TYPE, Request, Response, StatusCode},
+};
+use serde_json::{json, Value};
+use starknet::core::types::Felt;
+
+use autoswappr_backend::{
+    auth::ADMIN_KEY_HEADER,
+    utils::ekubo::{sort_tokens, PoolKey, PoolTier, DEFAULT_POOL_TIERS},
+};
+
+use crate::helpers::*;
+
+const ETH: &str = "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
+const STRK: &str = "0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d";
+
+fn admin_request(method: &str, uri: &str, body: Option<Value>) -> Request<Body> {
+    let builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(ADMIN_KEY_HEADER, TEST_ADMIN_KEY);
+    match body {
+        Some(body) => builder
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap(),
+        None => builder.body(Body::empty()).unwrap(),
+    }
+}
+
+async fn json_body(resp: Response<Body>) -> Value {
+    let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+fn get_pools(token_a: &str, token_b: &str) -> Request<Body> {
+    Request::get(format!("/pools?token_a={}&token_b={}", token_a, token_b))
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[test]
+fn test_pool_key_sorts_tokens() {
+    let eth = Felt::from_hex(ETH).unwrap();
+    let strk = Felt::from_hex(STRK).unwrap();
+    assert_eq!(sort_tokens(eth, strk), (strk, eth));
+    assert_eq!(sort_tokens(strk, eth), (strk, eth));
+
+    let tier: PoolTier = DEFAULT_POOL_TIERS.parse().unwrap();
+    let key = PoolKey::new(eth, strk, &tier);
+    assert_eq!(key, PoolKey::new(strk, eth, &tier));
+    assert_eq!((key.token0, key.token1), (strk, eth));
+    assert!(key.is_token1(eth));
+    assert!(!key.is_token1(strk));
+}
+
+#[test]
+fn test_parse_pool_tier() {
+    assert_eq!(
+        "100:10".parse::<PoolTier>(),
+        Ok(PoolTier {
+            fee: 100,
+            tick_spacing: 10,
+            extension: Felt::ZERO,
+        })
+    );
+    assert_eq!(
+        "100:10:0x123".parse::<PoolTier>(),
+        Ok(PoolTier {
+            fee: 100,
+            tick_spacing: 10,
+            extension: Felt::from_hex("0x123").unwrap(),
+        })
+    );
+    for tier in ["", "100", "100:abc", "100:10:xyz", "1:2:3:4"] {
+        assert!(tier.parse::<PoolTier>().is_err(), "{}", tier);
+    }
+}
+
+#[tokio::test]
+async fn test_pair_without_pools_uses_default_tiers() {
+    let app = TestApp::new().await;
+
+    let resp = app.request(get_pools(ETH, STRK)).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        json_body(resp).await,
+        json!([{
+            "token0": "0x4718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d",
+            "token1": "0x49d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7",
+            "fee": "170141183460469235273462165868118016",
+            "tick_spacing": 1000,
+            "extension": "0x0"
+        }])
+    );
+}
+
+#[tokio::test]
+async fn test_registered_pools_replace_default_tiers() {
+    let app = TestApp::new().await;
+
+    for fee in [
+        "340282366920938463463374607431768211",
+        "3402823669209384634633746074317682114",
+    ] {
+        let resp = app
+            .request(admin_request(
+                "POST",
+                "/admin/pools",
+                Some(json!({
+                    "token_a": ETH,
+                    "token_b": STRK,
+                    "fee": fee,
+                    "tick_spacing": 200
+                })),
+            ))
+            .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    // The pair is the same in either order.
+    let resp = app.request(get_pools(STRK, ETH)).await;
+    let fees: Vec<Value> = json_body(resp)
+        .await
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|pool| pool["fee"].clone())
+        .collect();
+    assert_eq!(
+        fees,
+        vec![
+            json!("340282366920938463463374607431768211"),
+            json!("3402823669209384634633746074317682114")
+        ]
+    );
+
+    // Registering a pool twice is rejected.
+    let resp = app
+        .request(admin_request(
+            "POST",
+            "/admin/pools",
+            Some(json!({
+                "token_a": STRK,
+                "token_b": ETH,
+                "fee": "340282366920938463463374607431768211",
+                "tick_spacing": 200
+            })),
+        ))
+        .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let resp = app
+        .request(admin_request("GET", "/admin/pools", None))
+        .await;
+    let pools = json_body(resp).await;
+    assert_eq!(pools.as_array().unwrap().len(), 2);
+    assert_eq!(pools[0]["token0"], STRK);
+
+    let uri = format!("/admin/pools/{}", pools[0]["id"].as_str().unwrap());
+    let resp = app.request(admin_request("DELETE", &uri, None)).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp = app.request(admin_request("DELETE", &uri, None)).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_pool_requests_are_validated() {
+    let app = TestApp::new().await;
+
+    let resp = app.request(get_pools(ETH, ETH)).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let resp = app.request(get_pools("0x123", STRK)).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    for body in [
+        json!({ "token_a": ETH, "token_b": STRK, "fee": "1", "tick_spacing": 0 }),
+        json!({
+            "token_a": ETH,
+            "token_b": STRK,
+            "fee": "340282366920938463463374607431768211456",
+            "tick_spacing": 1
+        }),
+        json!({ "token_a": ETH, "token_b": STRK, "fee": "1", "tick_spacing": 1, "extension": "0x1" }),
+    ] {
+        let resp = app
+            .request(admin_request("POST", "/admin/pools", Some(body.clone())))
+            .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{}", body);
+    }
+
+    let req = Request::get("/admin/pools").body(Body::empty()).unwrap();
+    let resp = app.request(req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
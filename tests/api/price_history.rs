@@ -0,0 +1,224 @@
+use anyhow::{anyhow, Result};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use serde_json::{json, Value};
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use autoswappr_backend::{
+    service::{
+        pricing::{PriceSource, UsdPrice},
+        tokens::Token,
+    },
+    worker::PriceSnapshotter,
+};
+
+use crate::helpers::*;
+
+const ETH: &str = "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
+const STRK: &str = "0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d";
+const UNPRICED: &str = "0x053c91253bc9682c04929ca02ed00b3e423f6710d2ee7e0d5ebb06f3ecf368a8";
+
+// Prices set by the test, by token address. Tokens set to `None` fail to be
+// priced, tokens that are not set have no price.
+#[derive(Default)]
+struct FakePrices {
+    prices: Mutex<HashMap<String, Option<UsdPrice>>>,
+}
+
+impl FakePrices {
+    fn set(&self, token: &str, price: Option<u128>) {
+        let price = price.map(|price| UsdPrice { price, decimals: 2 });
+        self.prices
+            .lock()
+            .unwrap()
+            .insert(token.to_lowercase(), price);
+    }
+}
+
+impl PriceSource for &FakePrices {
+    async fn usd_price(&self, token: &Token) -> Result<Option<UsdPrice>> {
+        match self
+            .prices
+            .lock()
+            .unwrap()
+            .get(&token.contract_address.to_lowercase())
+        {
+            Some(Some(price)) => Ok(Some(*price)),
+            Some(None) => Err(anyhow!("Oracle unavailable")),
+            None => Ok(None),
+        }
+    }
+}
+
+fn snapshotter<'a>(app: &TestApp, prices: &'a FakePrices) -> PriceSnapshotter<&'a FakePrices> {
+    PriceSnapshotter::new(prices, app.db.clone(), Duration::from_secs(300))
+}
+
+fn at(time: &str) -> OffsetDateTime {
+    OffsetDateTime::parse(time, &Rfc3339).unwrap()
+}
+
+async fn stored_prices(app: &TestApp) -> Vec<(String, String)> {
+    sqlx::query_as::<_, (String, String)>(
+        r#"
+        SELECT token_address, TRIM_SCALE(price_usd)::TEXT
+        FROM token_price_history
+        ORDER BY recorded_at, token_address
+        "#,
+    )
+    .fetch_all(&app.db.pool)
+    .await
+    .unwrap()
+}
+
+async fn get_prices(app: &TestApp, uri: &str) -> (StatusCode, Value) {
+    let req = Request::get(uri).body(Body::empty()).unwrap();
+    let resp = app.request(req).await;
+    let status = resp.status();
+    let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn test_snapshot_stores_active_token_prices() {
+    let app = TestApp::new().await;
+    for token in [ETH, STRK, UNPRICED] {
+        register_token(&app.db.pool, token).await;
+    }
+    let prices = FakePrices::default();
+    let snapshotter = snapshotter(&app, &prices);
+    prices.set(ETH, Some(250_050));
+    prices.set(STRK, None);
+
+    let now = at("2024-12-21T10:00:00Z");
+    assert_eq!(snapshotter.snapshot(now).await.unwrap(), 1);
+    // A second snapshot at the same time is ignored.
+    assert_eq!(snapshotter.snapshot(now).await.unwrap(), 1);
+    assert_eq!(
+        stored_prices(&app).await,
+        vec![(ETH.to_string(), "2500.5".to_string())]
+    );
+
+    // Disabled tokens are not priced.
+    sqlx::query("UPDATE token SET is_active = false WHERE contract_address = $1")
+        .bind(ETH)
+        .execute(&app.db.pool)
+        .await
+        .unwrap();
+    prices.set(STRK, Some(50));
+    assert_eq!(
+        snapshotter
+            .snapshot(at("2024-12-21T10:05:00Z"))
+            .await
+            .unwrap(),
+        1
+    );
+    assert_eq!(
+        stored_prices(&app).await[1],
+        (STRK.to_string(), "0.5".to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_price_history_candles() {
+    let app = TestApp::new().await;
+    register_token(&app.db.pool, ETH).await;
+    let fake = FakePrices::default();
+    let snapshotter = snapshotter(&app, &fake);
+    let prices = [
+        ("2024-12-21T10:00:00Z", 250_000),
+        ("2024-12-21T10:20:00Z", 252_000),
+        ("2024-12-21T10:40:00Z", 249_000),
+        ("2024-12-21T10:59:59Z", 251_000),
+        ("2024-12-21T12:10:00Z", 260_000),
+        // Outside of the range.
+        ("2024-12-21T13:00:00Z", 270_000),
+    ];
+    for (time, price) in prices {
+        fake.set(ETH, Some(price));
+        snapshotter.snapshot(at(time)).await.unwrap();
+    }
+
+    let (status, json) = get_prices(
+        &app,
+        &format!(
+            "/prices/{}?from=2024-12-21T10:00:00Z&to=2024-12-21T13:00:00Z&interval=1h",
+            ETH
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["token_address"], ETH);
+    assert_eq!(json["interval"], "1h");
+    // Hours without a snapshot are left out.
+    assert_eq!(
+        json["candles"],
+        json!([
+            {
+                "time": "2024-12-21T10:00:00Z",
+                "open": "2500",
+                "high": "2520",
+                "low": "2490",
+                "close": "2510",
+                "samples": 4
+            },
+            {
+                "time": "2024-12-21T12:00:00Z",
+                "open": "2600",
+                "high": "2600",
+                "low": "2600",
+                "close": "2600",
+                "samples": 1
+            }
+        ])
+    );
+
+    let (_, json) = get_prices(
+        &app,
+        &format!(
+            "/prices/{}?from=2024-12-21T10:00:00Z&to=2024-12-21T11:00:00Z&interval=30m",
+            ETH.to_uppercase().replace("0X", "0x")
+        ),
+    )
+    .await;
+    let closes: Vec<&str> = json["candles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|candle| candle["close"].as_str().unwrap())
+        .collect();
+    assert_eq!(closes, vec!["2520", "2510"]);
+}
+
+#[tokio::test]
+async fn test_price_history_validation() {
+    let app = TestApp::new().await;
+    register_token(&app.db.pool, ETH).await;
+
+    // The last day in hours by default.
+    let (status, json) = get_prices(&app, &format!("/prices/{}", ETH)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["interval"], "1h");
+    assert_eq!(json["candles"], json!([]));
+
+    let (status, _) = get_prices(&app, &format!("/prices/{}", STRK)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = get_prices(&app, "/prices/0x123").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    for query in [
+        "interval=1w",
+        "interval=0h",
+        "interval=h",
+        "from=yesterday",
+        "from=2024-12-21T11:00:00Z&to=2024-12-21T10:00:00Z",
+        // More than 1000 buckets.
+        "from=2024-01-01T00:00:00Z&to=2024-12-01T00:00:00Z&interval=5m",
+    ] {
+        let (status, _) = get_prices(&app, &format!("/prices/{}?{}", ETH, query)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", query);
+    }
+}
@@ -0,0 +1,226 @@
+use serde_json::json;
+use starknet::{
+    core::{types::Felt, utils::cairo_short_string_to_felt},
+    macros::selector,
+    providers::{
+        jsonrpc::{HttpTransport, JsonRpcClient},
+        Url,
+    },
+};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use autoswappr_backend::{
+    service::{
+        pricing::{PriceFeed, Pricing, UsdPrice},
+        tokens::Token,
+    },
+    utils::amount::Amount,
+};
+
+use crate::helpers::*;
+
+const PRAGMA: &str = "0x0000000000000000000000000000000000000000000000000000000000000fee";
+
+fn pricing(app: &TestApp, pragma: Option<&str>, ttl: Duration) -> Pricing {
+    let provider = JsonRpcClient::new(HttpTransport::new(Url::parse(&app.rpc.url).unwrap()));
+    Pricing::new(
+        Arc::new(provider),
+        Felt::from_hex(TEST_CONTRACT_ADDRESS).unwrap(),
+        pragma.map(|address| Felt::from_hex(address).unwrap()),
+        ttl,
+    )
+}
+
+fn token(decimals: i16, is_stable: bool, price_feed: Option<&str>) -> Token {
+    Token {
+        contract_address: "0x123".to_string(),
+        token_name: "Test Token".to_string(),
+        token_symbol: "TEST".to_string(),
+        token_decimals: decimals,
+        is_stable,
+        is_active: true,
+        price_feed: price_feed.map(str::to_string),
+        created_at: "2024-12-20T10:15:30Z".to_string(),
+    }
+}
+
+// Oracles with ETH at $2500.5 and STRK at $0.5 on the router, and ETH/USD at
+// $2400 on Pragma. Returns the number of oracle reads.
+fn mock_oracles(app: &TestApp) -> Arc<AtomicUsize> {
+    let reads = Arc::new(AtomicUsize::new(0));
+    let read = reads.clone();
+    app.rpc.on("starknet_call", move |params| {
+        read.fetch_add(1, Ordering::SeqCst);
+        let request = &params["request"];
+        let contract: Felt = serde_json::from_value(request["contract_address"].clone()).unwrap();
+        let selector: Felt =
+            serde_json::from_value(request["entry_point_selector"].clone()).unwrap();
+        let calldata: Vec<Felt> = serde_json::from_value(request["calldata"].clone()).unwrap();
+        let router = Felt::from_hex(TEST_CONTRACT_ADDRESS).unwrap();
+        match selector {
+            s if s == selector!("get_eth_usd_price") && contract == router => {
+                Ok(json!(["0x3a38243480", "0x8"]))
+            }
+            s if s == selector!("get_strk_usd_price") && contract == router => {
+                Ok(json!(["0x2faf080", "0x8"]))
+            }
+            s if s == selector!("get_data_median")
+                && contract == Felt::from_hex(PRAGMA).unwrap()
+                && calldata == vec![Felt::ZERO, cairo_short_string_to_felt("ETH/USD").unwrap()] =>
+            {
+                Ok(json!(["0x8f0d1800", "0x6", "0x6765a1f0", "0x5", "0x1"]))
+            }
+            _ => Err(json!({ "code": 21, "message": "Invalid message selector" })),
+        }
+    });
+    reads
+}
+
+#[test]
+fn test_usd_value_of_amount() {
+    let price = UsdPrice {
+        price: 250_050_000_000,
+        decimals: 8,
+    };
+    let amount = Amount::parse("1.5", 18).unwrap();
+    assert_eq!(price.value_of(&amount).unwrap().to_string(), "3750.75");
+
+    // Values are rounded down to 8 decimals.
+    let dust = Amount::from_raw(1000u128.into(), 18);
+    assert_eq!(price.value_of(&dust).unwrap().to_string(), "0");
+    assert_eq!(
+        UsdPrice::one()
+            .value_of(&Amount::parse("12.345678", 6).unwrap())
+            .unwrap()
+            .to_string(),
+        "12.345678"
+    );
+}
+
+#[tokio::test]
+async fn test_router_prices() {
+    let app = TestApp::new().await;
+    mock_oracles(&app);
+    let pricing = pricing(&app, None, Duration::from_secs(60));
+
+    let eth = token(18, false, Some("eth"));
+    let value = pricing
+        .usd_value(&eth, &Amount::parse("2", 18).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(value.unwrap().to_string(), "5001");
+
+    let strk = token(18, false, Some("strk"));
+    assert_eq!(
+        pricing.usd_price(&strk).await.unwrap(),
+        Some(UsdPrice {
+            price: 50_000_000,
+            decimals: 8
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_pragma_price() {
+    let app = TestApp::new().await;
+    mock_oracles(&app);
+    let eth = token(18, false, Some("pragma:ETH/USD"));
+
+    let price = pricing(&app, Some(PRAGMA), Duration::from_secs(60))
+        .usd_price(&eth)
+        .await
+        .unwrap();
+    assert_eq!(
+        price,
+        Some(UsdPrice {
+            price: 2_400_000_000,
+            decimals: 6
+        })
+    );
+
+    // Pragma feeds need an oracle address.
+    let unconfigured = pricing(&app, None, Duration::from_secs(60));
+    assert!(unconfigured.usd_price(&eth).await.is_err());
+}
+
+#[tokio::test]
+async fn test_tokens_without_feed() {
+    let app = TestApp::new().await;
+    let reads = mock_oracles(&app);
+    let pricing = pricing(&app, None, Duration::from_secs(60));
+
+    assert_eq!(
+        pricing.usd_price(&token(6, true, None)).await.unwrap(),
+        Some(UsdPrice::one())
+    );
+    assert_eq!(
+        pricing.usd_price(&token(18, false, None)).await.unwrap(),
+        None
+    );
+    assert_eq!(reads.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn test_prices_are_cached() {
+    let app = TestApp::new().await;
+    let reads = mock_oracles(&app);
+    let eth = token(18, false, Some("eth"));
+
+    let cached = pricing(&app, None, Duration::from_secs(60));
+    for _ in 0..3 {
+        cached.usd_price(&eth).await.unwrap();
+    }
+    // Clones share the cache.
+    cached.clone().usd_price(&eth).await.unwrap();
+    assert_eq!(reads.load(Ordering::SeqCst), 1);
+
+    // Expired prices are read again.
+    let expired = pricing(&app, None, Duration::ZERO);
+    expired.usd_price(&eth).await.unwrap();
+    expired.usd_price(&eth).await.unwrap();
+    assert_eq!(reads.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_unavailable_oracle_is_an_error() {
+    let app = TestApp::new().await;
+    app.rpc.on("starknet_call", |_| {
+        Err(json!({ "code": 20, "message": "Contract not found" }))
+    });
+    let pricing = pricing(&app, None, Duration::from_secs(60));
+
+    assert!(pricing
+        .usd_price(&token(18, false, Some("eth")))
+        .await
+        .is_err());
+
+    // A zero price means the oracle has none.
+    app.rpc.on("starknet_call", |_| Ok(json!(["0x0", "0x8"])));
+    assert!(pricing
+        .usd_price(&token(18, false, Some("strk")))
+        .await
+        .is_err());
+}
+
+#[test]
+fn test_parse_price_feed() {
+    assert_eq!("eth".parse::<PriceFeed>(), Ok(PriceFeed::RouterEth));
+    assert_eq!("strk".parse::<PriceFeed>(), Ok(PriceFeed::RouterStrk));
+    assert_eq!(
+        "pragma:BTC/USD".parse::<PriceFeed>(),
+        Ok(PriceFeed::Pragma("BTC/USD".to_string()))
+    );
+    assert_eq!(
+        PriceFeed::Pragma("BTC/USD".to_string()).to_string(),
+        "pragma:BTC/USD"
+    );
+    for feed in ["", "ETH", "pragma:", "pragma:éth", "oracle:ETH/USD"] {
+        assert!(feed.parse::<PriceFeed>().is_err());
+    }
+}
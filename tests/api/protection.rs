@@ -0,0 +1,479 @@
+use anyhow::{anyhow, Result};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use starknet::{
+    core::types::{Felt, U256},
+    macros::selector,
+    providers::{
+        jsonrpc::{HttpTransport, JsonRpcClient},
+        Url,
+    },
+};
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use uuid::Uuid;
+
+use autoswappr_backend::{
+    service::{
+        pricing::{PriceSource, UsdPrice},
+        protection::ProtectionKind,
+        swap_jobs::find,
+        tokens::Token,
+        transaction_logs::{record_outcome, TransactionOutcome, TransactionStatus, UsdValues},
+        triggers::TriggerCondition,
+    },
+    worker::ProtectionEvaluator,
+};
+
+use crate::helpers::*;
+
+const WALLET: &str = "0xdbfcab49bd9bced4636b04319d71fbd0d84bde78a1d38e9e2fc391e83187c1c3";
+const OTHER_WALLET: &str = "0x0000000000000000000000000000000000000000000000000000000000000b22";
+const TOKEN: &str = "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
+const OTHER_TOKEN: &str = "0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d";
+const STABLE: &str = "0x053c91253bc9682c04929ca02ed00b3e423f6710d2ee7e0d5ebb06f3ecf368a8";
+
+// One whole 18 decimals token.
+const ONE: u128 = 1_000_000_000_000_000_000;
+
+// Prices set by the test, by token address. Tokens set to `None` fail to be
+// priced, tokens that are not set have no price.
+#[derive(Default)]
+struct FakePrices {
+    prices: Mutex<HashMap<String, Option<UsdPrice>>>,
+}
+
+impl FakePrices {
+    fn set(&self, token: &str, price: Option<u128>) {
+        let price = price.map(|price| UsdPrice { price, decimals: 2 });
+        self.prices
+            .lock()
+            .unwrap()
+            .insert(token.to_lowercase(), price);
+    }
+}
+
+impl PriceSource for &FakePrices {
+    async fn usd_price(&self, token: &Token) -> Result<Option<UsdPrice>> {
+        match self
+            .prices
+            .lock()
+            .unwrap()
+            .get(&token.contract_address.to_lowercase())
+        {
+            Some(Some(price)) => Ok(Some(*price)),
+            Some(None) => Err(anyhow!("Oracle unavailable")),
+            None => Ok(None),
+        }
+    }
+}
+
+type Evaluator<'a> = ProtectionEvaluator<&'a FakePrices, JsonRpcClient<HttpTransport>>;
+
+fn evaluator<'a>(app: &TestApp, prices: &'a FakePrices) -> Evaluator<'a> {
+    ProtectionEvaluator::new(
+        prices,
+        JsonRpcClient::new(HttpTransport::new(Url::parse(&app.rpc.url).unwrap())),
+        app.db.clone(),
+        Felt::from_hex(TEST_ACCOUNT_ADDRESS).unwrap(),
+        5,
+        Duration::from_secs(30),
+    )
+}
+
+// Answer ERC20 calls with the wallet's `balance` of every token and the
+// `allowance` of the swap account. Signatures can no longer be checked afterwards.
+fn mock_erc20(app: &TestApp, balance: u128, allowance: u128) {
+    app.rpc.on("starknet_call", move |params| {
+        let request = &params["request"];
+        let selector: Felt =
+            serde_json::from_value(request["entry_point_selector"].clone()).unwrap();
+        let calldata: Vec<Felt> = serde_json::from_value(request["calldata"].clone()).unwrap();
+        let owner = Felt::from_hex(WALLET).unwrap();
+        let spender = Felt::from_hex(TEST_ACCOUNT_ADDRESS).unwrap();
+        let u256 = |value: u128| Ok(json!([format!("{:#x}", value), "0x0"]));
+        match selector {
+            s if s == selector!("balanceOf") && calldata == vec![owner] => u256(balance),
+            s if s == selector!("allowance") && calldata == vec![owner, spender] => u256(allowance),
+            s if s == selector!("balanceOf") || s == selector!("allowance") => u256(0),
+            _ => Err(json!({ "code": 21, "message": "Invalid message selector" })),
+        }
+    });
+}
+
+fn at(time: &str) -> OffsetDateTime {
+    OffsetDateTime::parse(time, &Rfc3339).unwrap()
+}
+
+async fn send(app: &TestApp, req: Request<Body>) -> (StatusCode, Value) {
+    let resp = app.request(req).await;
+    let status = resp.status();
+    let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+async fn get(app: &TestApp, uri: &str) -> (StatusCode, Value) {
+    send(app, Request::get(uri).body(Body::empty()).unwrap()).await
+}
+
+// Subscribe the wallet to swap TOKEN and OTHER_TOKEN into STABLE.
+async fn setup(app: &TestApp) {
+    for token in [TOKEN, OTHER_TOKEN, STABLE] {
+        register_token(&app.db.pool, token).await;
+    }
+    sqlx::query("UPDATE token SET is_stable = true WHERE contract_address = $1")
+        .bind(STABLE)
+        .execute(&app.db.pool)
+        .await
+        .unwrap();
+    let payload = json!({
+        "wallet_address": WALLET,
+        "to_token": STABLE,
+        "from_token": [TOKEN, OTHER_TOKEN],
+        "percentage": [50, 50]
+    });
+    let (status, _) = send(
+        app,
+        signed_request("POST", "/subscriptions", WALLET, &payload),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+async fn create(app: &TestApp, signer: &str, payload: Value) -> (StatusCode, Value) {
+    send(
+        app,
+        signed_request("POST", "/protection_rules", signer, &payload),
+    )
+    .await
+}
+
+async fn create_rule(app: &TestApp, token: &str, kind: &str, price_usd: &str) -> Uuid {
+    let (status, json) = create(
+        app,
+        WALLET,
+        json!({ "wallet_address": WALLET, "token": token, "kind": kind, "price_usd": price_usd }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", json);
+    Uuid::parse_str(json["id"].as_str().unwrap()).unwrap()
+}
+
+async fn rules(app: &TestApp) -> Vec<Value> {
+    let (status, json) = get(app, &format!("/protection_rules?wallet_address={}", WALLET)).await;
+    assert_eq!(status, StatusCode::OK);
+    json["data"].as_array().unwrap().clone()
+}
+
+async fn rule(app: &TestApp, id: Uuid) -> Value {
+    rules(app)
+        .await
+        .into_iter()
+        .find(|rule| rule["id"] == id.to_string())
+        .unwrap()
+}
+
+// Rows of the transaction log written for `rule_id`, as (amount_from, amount_to,
+// tx_hash, swap_job_id).
+async fn logged(
+    pool: &PgPool,
+    rule_id: Uuid,
+) -> Vec<(String, String, Option<String>, Option<Uuid>)> {
+    sqlx::query_as(
+        r#"
+        SELECT amount_from::TEXT, amount_to::TEXT, tx_hash, swap_job_id
+        FROM transactions_log
+        WHERE protection_rule_id = $1
+        ORDER BY created_at
+        "#,
+    )
+    .bind(rule_id)
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+#[test]
+fn test_protection_kind_conditions() {
+    assert_eq!(
+        ProtectionKind::StopLoss.condition(),
+        TriggerCondition::Below
+    );
+    assert_eq!(
+        ProtectionKind::TakeProfit.condition(),
+        TriggerCondition::Above
+    );
+}
+
+#[tokio::test]
+async fn test_create_and_list_protection_rules() {
+    let app = TestApp::new().await;
+    setup(&app).await;
+
+    let (status, json) = create(
+        &app,
+        WALLET,
+        json!({ "wallet_address": WALLET, "token": TOKEN, "kind": "stop_loss", "price_usd": 2000.5 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["kind"], "stop_loss");
+    assert_eq!(json["price_usd"], "2000.5");
+    assert_eq!(json["to_token"], STABLE);
+    assert_eq!(json["status"], "active");
+    assert_eq!(json["swap_job_id"], Value::Null);
+
+    // One active rule of each kind per token.
+    let (status, _) = create(
+        &app,
+        WALLET,
+        json!({ "wallet_address": WALLET, "token": TOKEN, "kind": "stop_loss", "price_usd": "1500" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    create_rule(&app, TOKEN, "take_profit", "4000").await;
+    create_rule(&app, OTHER_TOKEN, "stop_loss", "0.3").await;
+
+    let listed = rules(&app).await;
+    assert_eq!(listed.len(), 3);
+
+    let (status, _) = get(&app, "/protection_rules?wallet_address=0x123").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_create_protection_rule_validation() {
+    let app = TestApp::new().await;
+    setup(&app).await;
+    let unregistered = "0x0000000000000000000000000000000000000000000000000000000000000abc";
+
+    let cases = [
+        json!({ "token": "0x123", "kind": "stop_loss", "price_usd": "1" }),
+        json!({ "token": unregistered, "kind": "stop_loss", "price_usd": "1" }),
+        json!({ "token": TOKEN, "kind": "stop_gain", "price_usd": "1" }),
+        json!({ "token": TOKEN, "kind": "stop_loss", "price_usd": "0" }),
+        json!({ "token": TOKEN, "kind": "stop_loss", "price_usd": "-2" }),
+        json!({ "token": TOKEN, "kind": "stop_loss", "price_usd": "abc" }),
+        // Swaps go into a stable token.
+        json!({ "token": TOKEN, "kind": "stop_loss", "price_usd": "1", "to_token": OTHER_TOKEN }),
+        json!({ "token": STABLE, "kind": "stop_loss", "price_usd": "1" }),
+    ];
+    for mut payload in cases {
+        payload["wallet_address"] = json!(WALLET);
+        let (status, _) = create(&app, WALLET, payload.clone()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", payload);
+    }
+
+    // Wallets without a subscription have no rules.
+    let (status, _) = create(
+        &app,
+        OTHER_WALLET,
+        json!({ "wallet_address": OTHER_WALLET, "token": TOKEN, "kind": "stop_loss", "price_usd": "1" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(rules(&app).await.is_empty());
+}
+
+#[tokio::test]
+async fn test_delete_protection_rule() {
+    let app = TestApp::new().await;
+    setup(&app).await;
+    let id = create_rule(&app, TOKEN, "stop_loss", "1000").await;
+    let uri = format!("/protection_rules/{}", id);
+
+    let payload = json!({ "wallet_address": OTHER_WALLET });
+    let (status, _) = send(&app, signed_request("DELETE", &uri, OTHER_WALLET, &payload)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let payload = json!({ "wallet_address": WALLET });
+    let (status, _) = send(&app, signed_request("DELETE", &uri, WALLET, &payload)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(rules(&app).await.is_empty());
+}
+
+#[tokio::test]
+async fn test_stop_loss_fires_once_with_allowance() {
+    let app = TestApp::new().await;
+    let prices = FakePrices::default();
+    let evaluator = evaluator(&app, &prices);
+    setup(&app).await;
+    let id = create_rule(&app, TOKEN, "stop_loss", "0.90").await;
+    mock_erc20(&app, 5 * ONE, ONE);
+
+    // Unpriced, then above the stop.
+    assert_eq!(
+        evaluator.check(at("2024-12-25T10:00:00Z")).await.unwrap(),
+        0
+    );
+    prices.set(TOKEN, Some(95));
+    assert_eq!(
+        evaluator.check(at("2024-12-25T10:01:00Z")).await.unwrap(),
+        0
+    );
+    assert_eq!(rule(&app, id).await["last_error"], Value::Null);
+
+    // Met, but the swap account may only spend part of the balance.
+    prices.set(TOKEN, Some(85));
+    assert_eq!(
+        evaluator.check(at("2024-12-25T10:02:00Z")).await.unwrap(),
+        0
+    );
+    let blocked = rule(&app, id).await;
+    assert_eq!(blocked["status"], "active");
+    assert!(blocked["last_error"]
+        .as_str()
+        .unwrap()
+        .starts_with("Allowance of 1 is below the balance of 5"));
+    assert!(logged(&app.db.pool, id).await.is_empty());
+
+    mock_erc20(&app, 5 * ONE, u128::MAX);
+    assert_eq!(
+        evaluator.check(at("2024-12-25T10:03:00Z")).await.unwrap(),
+        1
+    );
+    assert_eq!(
+        evaluator.check(at("2024-12-25T10:04:00Z")).await.unwrap(),
+        0
+    );
+
+    let fired = rule(&app, id).await;
+    assert_eq!(fired["status"], "fired");
+    assert_eq!(fired["fired_price_usd"], "0.85");
+    assert_eq!(fired["fired_at"], "2024-12-25T10:03:00Z");
+    assert_eq!(fired["last_error"], Value::Null);
+    let job_id = Uuid::parse_str(fired["swap_job_id"].as_str().unwrap()).unwrap();
+
+    let mut conn = app.db.pool.acquire().await.unwrap();
+    let job = find(&mut conn, job_id).await.unwrap().unwrap();
+    assert_eq!(job.from_token, TOKEN);
+    assert_eq!(job.to_token, STABLE);
+    assert_eq!(job.percentage, 100);
+    assert_eq!(job.amount, (5 * ONE).to_string());
+    assert!(job.pull_from_wallet);
+
+    // The firing is logged without a transaction, and left out of the stats.
+    assert_eq!(
+        logged(&app.db.pool, id).await,
+        vec![((5 * ONE).to_string(), "0".to_string(), None, Some(job_id))]
+    );
+    let (_, stats) = get(&app, &format!("/wallets/{}/stats", WALLET)).await;
+    assert_eq!(stats["pairs"], json!([]));
+
+    // The outcome of the swap completes the logged firing.
+    let outcome = TransactionOutcome {
+        tx_hash: "0xabc".to_string(),
+        status: TransactionStatus::AcceptedOnL2,
+        block_number: 7,
+        revert_reason: None,
+        actual_fee: "100".to_string(),
+        fee_unit: "FRI".to_string(),
+        amount_from: U256::from(5 * ONE),
+        amount_to: U256::from(4_200_000u128),
+    };
+    record_outcome(&mut conn, &job, &outcome, &UsdValues::default())
+        .await
+        .unwrap();
+    record_outcome(&mut conn, &job, &outcome, &UsdValues::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        logged(&app.db.pool, id).await,
+        vec![(
+            (5 * ONE).to_string(),
+            "4200000".to_string(),
+            Some("0xabc".to_string()),
+            Some(job_id)
+        )]
+    );
+}
+
+#[tokio::test]
+async fn test_take_profit_needs_a_balance() {
+    let app = TestApp::new().await;
+    let prices = FakePrices::default();
+    let evaluator = evaluator(&app, &prices);
+    setup(&app).await;
+    let take_profit = create_rule(&app, TOKEN, "take_profit", "3000").await;
+    let stop_loss = create_rule(&app, TOKEN, "stop_loss", "2000").await;
+    mock_erc20(&app, 0, u128::MAX);
+
+    prices.set(TOKEN, Some(310_000));
+    assert_eq!(
+        evaluator.check(at("2024-12-25T10:00:00Z")).await.unwrap(),
+        0
+    );
+    assert_eq!(rule(&app, take_profit).await["status"], "active");
+
+    mock_erc20(&app, 2 * ONE, u128::MAX);
+    assert_eq!(
+        evaluator.check(at("2024-12-25T10:01:00Z")).await.unwrap(),
+        1
+    );
+    assert_eq!(rule(&app, take_profit).await["status"], "fired");
+    assert_eq!(rule(&app, stop_loss).await["status"], "active");
+}
+
+#[tokio::test]
+async fn test_every_active_rule_is_evaluated() {
+    let app = TestApp::new().await;
+    let prices = FakePrices::default();
+    let evaluator = evaluator(&app, &prices);
+    setup(&app).await;
+
+    // More rules than one batch holds stay active in front of the newest one,
+    // whose price is met.
+    sqlx::query(
+        r#"
+        WITH wallet AS (
+            INSERT INTO swap_subscription (wallet_address, to_token)
+            SELECT '0x' || LPAD(TO_HEX(i), 64, '0'), $1
+            FROM generate_series(1, 600) AS i
+            RETURNING wallet_address
+        )
+        INSERT INTO protection_rule (wallet_address, token, to_token, kind, price_usd, created_at)
+        SELECT wallet_address, $2, $1, 'stop_loss', 0.40, NOW() - INTERVAL '1 hour'
+        FROM wallet
+        "#,
+    )
+    .bind(STABLE)
+    .bind(TOKEN)
+    .execute(&app.db.pool)
+    .await
+    .unwrap();
+    let id = create_rule(&app, TOKEN, "take_profit", "0.50").await;
+    mock_erc20(&app, ONE, u128::MAX);
+
+    prices.set(TOKEN, Some(55));
+    assert_eq!(
+        evaluator.check(at("2024-12-25T10:00:00Z")).await.unwrap(),
+        1
+    );
+    assert_eq!(rule(&app, id).await["status"], "fired");
+}
+
+#[tokio::test]
+async fn test_inactive_subscription_rules_are_not_evaluated() {
+    let app = TestApp::new().await;
+    let prices = FakePrices::default();
+    let evaluator = evaluator(&app, &prices);
+    setup(&app).await;
+    let id = create_rule(&app, TOKEN, "stop_loss", "1").await;
+    sqlx::query("UPDATE swap_subscription SET is_active = false WHERE wallet_address = $1")
+        .bind(WALLET)
+        .execute(&app.db.pool)
+        .await
+        .unwrap();
+    mock_erc20(&app, ONE, u128::MAX);
+
+    prices.set(TOKEN, Some(50));
+    assert_eq!(
+        evaluator.check(at("2024-12-25T10:00:00Z")).await.unwrap(),
+        0
+    );
+    assert_eq!(rule(&app, id).await["status"], "active");
+}
@@ -0,0 +1,369 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use serde_json::{json, Value};
+use starknet::{
+    accounts::{ExecutionEncoding, SingleOwnerAccount},
+    core::{
+        chain_id,
+        types::{Felt, U256},
+    },
+    macros::selector,
+    providers::{
+        jsonrpc::{HttpTransport, JsonRpcClient},
+        Url,
+    },
+    signers::LocalWallet,
+};
+
+use autoswappr_backend::{
+    indexer::TRANSFER_EVENT_KEY,
+    service::{
+        pools,
+        quote::{price_impact_bps, quote, QuoteParams},
+        venues::{AvnuVenue, EkuboVenue, VenueRegistry},
+    },
+    utils::{
+        amount::Amount,
+        avnu::AvnuClient,
+        ekubo::{PoolKey, PoolTier, DEFAULT_POOL_TIERS},
+        starknet::{felt_to_u128, SwapAccount},
+        uint256::Uint256,
+    },
+};
+
+use crate::{helpers::*, mock_avnu::MockAvnu};
+
+const ACCOUNT: &str = TEST_ACCOUNT_ADDRESS;
+const ROUTER: &str = TEST_CONTRACT_ADDRESS;
+const FROM_TOKEN: &str = "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
+const TO_TOKEN: &str = "0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d";
+
+fn account(app: &TestApp) -> SwapAccount {
+    let provider = JsonRpcClient::new(HttpTransport::new(Url::parse(&app.rpc.url).unwrap()));
+    SingleOwnerAccount::new(
+        provider,
+        LocalWallet::from(test_signing_key()),
+        Felt::from_hex(ACCOUNT).unwrap(),
+        chain_id::MAINNET,
+        ExecutionEncoding::New,
+    )
+}
+
+fn pool(tier: &str) -> PoolKey {
+    let tier: PoolTier = tier.parse().unwrap();
+    PoolKey::new(
+        Felt::from_hex(FROM_TOKEN).unwrap(),
+        Felt::from_hex(TO_TOKEN).unwrap(),
+        &tier,
+    )
+}
+
+// Ekubo in the default pool tier, and AVNU if given its API.
+fn venues(app: &TestApp, avnu: Option<AvnuClient>) -> VenueRegistry {
+    let mut venues = VenueRegistry::default();
+    let tiers = vec![DEFAULT_POOL_TIERS.parse().unwrap()];
+    venues.register(EkuboVenue::new(app.db.clone(), tiers));
+    if let Some(client) = avnu {
+        venues.register(AvnuVenue::new(client));
+    }
+    venues
+}
+
+fn params() -> QuoteParams {
+    QuoteParams {
+        from_token: Felt::from_hex(FROM_TOKEN).unwrap(),
+        to_token: Felt::from_hex(TO_TOKEN).unwrap(),
+        amount: Amount::from_raw(U256::from(1_000_000u128), 6),
+        contract_address: Felt::from_hex(ROUTER).unwrap(),
+        funded_by: None,
+    }
+}
+
+fn fee_estimate() -> Value {
+    json!({
+        "gas_consumed": "0x64",
+        "gas_price": "0x1",
+        "data_gas_consumed": "0x0",
+        "data_gas_price": "0x1",
+        "overall_fee": "0x64",
+        "unit": "FRI"
+    })
+}
+
+fn invocation(contract: &str, calls: Vec<Value>, events: Vec<Value>) -> Value {
+    json!({
+        "contract_address": contract,
+        "entry_point_selector": format!("{:#x}", selector!("__execute__")),
+        "calldata": [],
+        "caller_address": "0x0",
+        "class_hash": "0x1",
+        "entry_point_type": "EXTERNAL",
+        "call_type": "CALL",
+        "result": [],
+        "calls": calls,
+        "events": events,
+        "messages": [],
+        "execution_resources": { "steps": 100 }
+    })
+}
+
+fn simulation(execute_invocation: Value) -> Value {
+    json!([{
+        "transaction_trace": {
+            "type": "INVOKE",
+            "execute_invocation": execute_invocation,
+            "execution_resources": {
+                "steps": 1000,
+                "data_availability": { "l1_gas": 0, "l1_data_gas": 128 }
+            }
+        },
+        "fee_estimation": fee_estimate()
+    }])
+}
+
+// A swap that sends `received` of `TO_TOKEN` from the router to the account.
+fn swap_trace(received: u128) -> Value {
+    let transfer = json!({
+        "order": 0,
+        "keys": [format!("{:#x}", TRANSFER_EVENT_KEY), ROUTER, ACCOUNT],
+        "data": [format!("{:#x}", received), "0x0"]
+    });
+    let token = invocation(TO_TOKEN, vec![], vec![transfer]);
+    simulation(invocation(
+        ACCOUNT,
+        vec![invocation(ROUTER, vec![token], vec![])],
+        vec![],
+    ))
+}
+
+fn simulated_calldata(params: &Value) -> Vec<Felt> {
+    serde_json::from_value(params["transactions"][0]["calldata"].clone()).unwrap()
+}
+
+// The venue selector and the amount of a simulated transfer-and-swap multicall.
+fn swapped(params: &Value) -> (Felt, u128) {
+    let calldata = simulated_calldata(params);
+    (calldata[8], felt_to_u128(calldata[5]).unwrap())
+}
+
+fn mock_node(app: &TestApp, simulate: impl Fn(Felt, u128) -> Value + Send + Sync + 'static) {
+    app.rpc.on("starknet_getNonce", |_| Ok(json!("0x1")));
+    app.rpc
+        .on("starknet_estimateFee", |_| Ok(json!([fee_estimate()])));
+    app.rpc.on("starknet_simulateTransactions", move |params| {
+        let (venue, amount) = swapped(params);
+        Ok(simulate(venue, amount))
+    });
+}
+
+#[tokio::test]
+async fn test_quote_simulates_each_venue() {
+    let app = TestApp::new().await;
+    // Ekubo loses output as the amount grows, AVNU pays a flat better rate.
+    mock_node(&app, |venue, amount| match venue {
+        s if s == selector!("swap") => swap_trace(2 * amount - amount * amount / 100_000_000),
+        s if s == selector!("anvu_swap") => swap_trace(2 * amount + amount / 100),
+        _ => panic!("Unexpected swap call"),
+    });
+
+    let avnu_api = MockAvnu::recorded().await;
+    let client = AvnuClient::new(&avnu_api.url);
+    let venues = venues(&app, Some(client));
+    let quote = quote(&account(&app), venues.all(), &params())
+        .await
+        .unwrap();
+
+    assert_eq!(quote.amount_in, Uint256::from(1_000_000u128));
+    assert!(quote.unavailable.is_empty());
+    assert_eq!(quote.quotes.len(), 2);
+
+    let ekubo = &quote.quotes[0];
+    assert_eq!(ekubo.venue, "ekubo");
+    assert_eq!(ekubo.amount_out, Uint256::from(1_990_000u128));
+    assert_eq!(ekubo.price_impact_bps, Some(50));
+    assert_eq!(ekubo.gas_fee, Uint256::from(100u128));
+    assert_eq!(ekubo.fee_unit, "FRI");
+    assert_eq!(ekubo.pool_key, Some(pool(DEFAULT_POOL_TIERS)));
+
+    let avnu = &quote.quotes[1];
+    assert_eq!(avnu.venue, "avnu");
+    assert_eq!(avnu.amount_out, Uint256::from(2_010_000u128));
+    assert_eq!(avnu.price_impact_bps, Some(0));
+    let routes = avnu.routes.as_ref().unwrap();
+    assert_eq!(routes.len(), 1);
+
+    assert_eq!(quote.best_venue.as_deref(), Some("avnu"));
+
+    // Routes are fetched for the swap and for the reference swap.
+    let sell_amounts: Vec<Value> = avnu_api
+        .requests()
+        .iter()
+        .filter_map(|request| request.get("sellAmount").cloned())
+        .collect();
+    assert_eq!(sell_amounts, vec![json!("0xf4240"), json!("0x3e8")]);
+}
+
+#[tokio::test]
+async fn test_quote_endpoint_uses_the_configured_account() {
+    let app = TestApp::new().await;
+    mock_node(&app, |_, amount| {
+        swap_trace(2 * amount - amount * amount / 100_000_000)
+    });
+    register_token_with_decimals(&app.db.pool, FROM_TOKEN, 6).await;
+    register_token(&app.db.pool, TO_TOKEN).await;
+    sqlx::query("UPDATE token SET is_stable = true WHERE contract_address = $1")
+        .bind(FROM_TOKEN)
+        .execute(&app.db.pool)
+        .await
+        .unwrap();
+
+    let req = Request::get(format!(
+        "/quote?from_token={}&to_token={}&amount=1",
+        FROM_TOKEN, TO_TOKEN
+    ))
+    .body(Body::empty())
+    .unwrap();
+    let resp = app.request(req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+
+    // Only transfers to the configured account count as output.
+    assert_eq!(json["amount_in"], "1000000");
+    assert_eq!(json["best_venue"], "ekubo");
+    assert_eq!(json["quotes"][0]["amount_out"], "1990000");
+    // Stable tokens are worth a dollar, tokens without a price feed are not valued.
+    assert_eq!(json["amount_in_usd"], "1");
+    assert_eq!(json["best_amount_out_usd"], Value::Null);
+}
+
+#[tokio::test]
+async fn test_quote_picks_the_best_ekubo_pool() {
+    let app = TestApp::new().await;
+    app.rpc.on("starknet_getNonce", |_| Ok(json!("0x1")));
+    app.rpc
+        .on("starknet_estimateFee", |_| Ok(json!([fee_estimate()])));
+    app.rpc.on("starknet_simulateTransactions", |params| {
+        let calldata = simulated_calldata(params);
+        // TO_TOKEN sorts first, so FROM_TOKEN is token1 of every pool.
+        assert_eq!(calldata[12], Felt::ONE);
+        assert_eq!(calldata[17], Felt::from_hex(FROM_TOKEN).unwrap());
+        let amount = felt_to_u128(calldata[5]).unwrap();
+        // The pool with the lower fee pays more.
+        let fee = felt_to_u128(calldata[18]).unwrap();
+        Ok(swap_trace(2 * amount - amount * fee / 100))
+    });
+
+    // Registered pools replace the default tier.
+    for tier in ["3:200", "1:10", "2:50"] {
+        let tier: PoolTier = tier.parse().unwrap();
+        pools::create(
+            &app.db.pool,
+            Felt::from_hex(FROM_TOKEN).unwrap(),
+            Felt::from_hex(TO_TOKEN).unwrap(),
+            &tier,
+        )
+        .await
+        .unwrap();
+    }
+    let venues = venues(&app, None);
+    let quote = quote(&account(&app), venues.all(), &params())
+        .await
+        .unwrap();
+
+    let ekubo = &quote.quotes[0];
+    assert_eq!(ekubo.amount_out, Uint256::from(1_990_000u128));
+    assert_eq!(ekubo.pool_key, Some(pool("1:10")));
+}
+
+#[tokio::test]
+async fn test_quote_without_ekubo_pools() {
+    let app = TestApp::new().await;
+    mock_node(&app, |_, amount| swap_trace(2 * amount));
+
+    let mut venues = VenueRegistry::default();
+    venues.register(EkuboVenue::new(app.db.clone(), vec![]));
+    let quote = quote(&account(&app), venues.all(), &params())
+        .await
+        .unwrap();
+    assert_eq!(quote.unavailable[0].reason, "No Ekubo pool configured");
+}
+
+#[tokio::test]
+async fn test_quote_reports_unavailable_venues() {
+    let app = TestApp::new().await;
+    mock_node(&app, |_, _| {
+        simulation(json!({ "revert_reason": "Insufficient liquidity" }))
+    });
+
+    let avnu_api = MockAvnu::recorded().await;
+    let venues = venues(&app, Some(AvnuClient::new(&avnu_api.url)));
+    let quote = quote(&account(&app), venues.all(), &params())
+        .await
+        .unwrap();
+
+    assert!(quote.quotes.is_empty());
+    assert_eq!(quote.best_venue, None);
+    let reasons: Vec<(&str, &str)> = quote
+        .unavailable
+        .iter()
+        .map(|venue| (venue.venue.as_str(), venue.reason.as_str()))
+        .collect();
+    assert_eq!(
+        reasons,
+        vec![
+            ("ekubo", "Swap reverts: Insufficient liquidity"),
+            ("avnu", "Swap reverts: Insufficient liquidity"),
+        ]
+    );
+}
+
+#[test]
+fn test_price_impact() {
+    let bps = |amount_in: u128, amount_out: u128, reference_in: u128, reference_out: u128| {
+        price_impact_bps(
+            amount_in.into(),
+            amount_out.into(),
+            reference_in.into(),
+            reference_out.into(),
+        )
+    };
+    assert_eq!(bps(1000, 1900, 10, 20), Some(500));
+    assert_eq!(bps(1000, 2000, 10, 20), Some(0));
+    assert_eq!(bps(1000, 2100, 10, 20), Some(0));
+    assert_eq!(bps(1000, 0, 10, 20), Some(10_000));
+    assert_eq!(bps(1000, 2000, 10, 0), None);
+}
+
+#[tokio::test]
+async fn test_quote_rejects_invalid_requests() {
+    let app = TestApp::new().await;
+    register_token(&app.db.pool, FROM_TOKEN).await;
+
+    let get = |from: &str, to: &str, amount: &str| {
+        Request::get(format!(
+            "/quote?from_token={}&to_token={}&amount={}",
+            from, to, amount
+        ))
+        .body(Body::empty())
+        .unwrap()
+    };
+
+    // The token to swap to is not registered.
+    let resp = app.request(get(FROM_TOKEN, TO_TOKEN, "1")).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    register_token(&app.db.pool, TO_TOKEN).await;
+    for (from, to, amount) in [
+        (FROM_TOKEN, FROM_TOKEN, "1"),
+        ("0x123", TO_TOKEN, "1"),
+        (FROM_TOKEN, TO_TOKEN, "abc"),
+        (FROM_TOKEN, TO_TOKEN, "0"),
+        (FROM_TOKEN, TO_TOKEN, "0.0000000000000000001"),
+    ] {
+        let resp = app.request(get(from, to, amount)).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{} {}", to, amount);
+    }
+}
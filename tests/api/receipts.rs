@@ -0,0 +1,715 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use starknet::{
+    accounts::{ExecutionEncoding, SingleOwnerAccount},
+    core::{
+        chain_id,
+        types::{Call, Felt, U256},
+    },
+    macros::selector,
+    providers::{
+        jsonrpc::{HttpTransport, JsonRpcClient},
+        Url,
+    },
+    signers::LocalWallet,
+};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use uuid::Uuid;
+
+use autoswappr_backend::{
+    indexer::TRANSFER_EVENT_KEY,
+    service::{
+        nonces::NonceManager,
+        pricing::Pricing,
+        swap_jobs::{enqueue, find, mark_submitted, NewSwapJob, SwapJobStatus},
+        transaction_logs::{TransactionOutcome, TransactionStatus},
+        venues::BEST_PRICE,
+    },
+    utils::{amount::Amount, starknet::SwapAccount, uint256::Uint256},
+    worker::{split_outcome, ReceiptTracker},
+};
+
+use crate::helpers::*;
+
+const WALLET: &str = "0x0000000000000000000000000000000000000000000000000000000000000a11";
+const ACCOUNT: &str = "0x0000000000000000000000000000000000000000000000000000000000000acc";
+const EXECUTOR: &str = "0x0000000000000000000000000000000000000000000000000000000000000acd";
+const ROUTER: &str = "0x0000000000000000000000000000000000000000000000000000000000000e4b";
+const SEQUENCER: &str = "0x0000000000000000000000000000000000000000000000000000000000000005";
+const FROM_TOKEN: &str = "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
+const TO_TOKEN: &str = "0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d";
+const TX_HASH: &str = "0xabc";
+
+type Receipt = Arc<Mutex<Option<Value>>>;
+
+fn transfer(token: &str, from: &str, to: &str, amount: u128) -> Value {
+    transfer_u256(token, from, to, U256::from(amount))
+}
+
+fn transfer_u256(token: &str, from: &str, to: &str, amount: U256) -> Value {
+    json!({
+        "from_address": token,
+        "keys": [format!("{:#x}", TRANSFER_EVENT_KEY), from, to],
+        "data": [format!("{:#x}", amount.low()), format!("{:#x}", amount.high())]
+    })
+}
+
+fn receipt(execution: Value, finality_status: &str, events: Vec<Value>) -> Value {
+    let mut receipt = json!({
+        "type": "INVOKE",
+        "transaction_hash": TX_HASH,
+        "actual_fee": { "amount": "0x64", "unit": "FRI" },
+        "finality_status": finality_status,
+        "block_hash": "0x1234",
+        "block_number": 640,
+        "messages_sent": [],
+        "events": events,
+        "execution_resources": {
+            "steps": 1000,
+            "data_availability": { "l1_gas": 0, "l1_data_gas": 128 }
+        }
+    });
+    receipt
+        .as_object_mut()
+        .unwrap()
+        .extend(execution.as_object().unwrap().clone());
+    receipt
+}
+
+fn serve_receipt(app: &TestApp) -> Receipt {
+    let receipt: Receipt = Arc::new(Mutex::new(None));
+    let served = receipt.clone();
+    app.rpc.on("starknet_getTransactionReceipt", move |_| {
+        match served.lock().unwrap().clone() {
+            Some(receipt) => Ok(receipt),
+            None => Err(json!({ "code": 29, "message": "Transaction hash not found" })),
+        }
+    });
+    receipt
+}
+
+fn tracker(app: &TestApp) -> ReceiptTracker<JsonRpcClient<HttpTransport>> {
+    tracker_for(app, &[ACCOUNT])
+}
+
+fn tracker_for(app: &TestApp, accounts: &[&str]) -> ReceiptTracker<JsonRpcClient<HttpTransport>> {
+    let accounts = accounts
+        .iter()
+        .map(|account| swap_account(app, account))
+        .collect();
+    tracker_with(app, NonceManager::new(accounts))
+}
+
+fn tracker_with(
+    app: &TestApp,
+    nonces: NonceManager,
+) -> ReceiptTracker<JsonRpcClient<HttpTransport>> {
+    let url = Url::parse(&app.rpc.url).unwrap();
+    let pricing = Pricing::new(
+        Arc::new(JsonRpcClient::new(HttpTransport::new(url.clone()))),
+        Felt::from_hex(ROUTER).unwrap(),
+        None,
+        Duration::from_secs(60),
+    );
+    ReceiptTracker::new(
+        JsonRpcClient::new(HttpTransport::new(url)),
+        app.db.clone(),
+        pricing,
+        nonces,
+        Duration::from_secs(60),
+        Duration::from_secs(1),
+    )
+}
+
+fn swap_account(app: &TestApp, address: &str) -> SwapAccount {
+    let provider = JsonRpcClient::new(HttpTransport::new(Url::parse(&app.rpc.url).unwrap()));
+    SingleOwnerAccount::new(
+        provider,
+        LocalWallet::from(test_signing_key()),
+        Felt::from_hex(address).unwrap(),
+        chain_id::MAINNET,
+        ExecutionEncoding::New,
+    )
+}
+
+// A node that accepts transactions as `TX_HASH` and reports the account nonce
+// set in the returned cell.
+fn accept_transactions(app: &TestApp, nonce: u64) -> Arc<Mutex<u64>> {
+    let chain_nonce = Arc::new(Mutex::new(nonce));
+    let read = chain_nonce.clone();
+    app.rpc.on("starknet_getNonce", move |_| {
+        Ok(json!(format!("{:#x}", *read.lock().unwrap())))
+    });
+    app.rpc.on("starknet_estimateFee", |_| {
+        Ok(json!([{
+            "gas_consumed": "0x64",
+            "gas_price": "0x1",
+            "data_gas_consumed": "0x0",
+            "data_gas_price": "0x1",
+            "overall_fee": "0x64",
+            "unit": "FRI"
+        }]))
+    });
+    app.rpc.on("starknet_addInvokeTransaction", |_| {
+        Ok(json!({ "transaction_hash": TX_HASH }))
+    });
+    chain_nonce
+}
+
+// Answer transaction status requests with `status`, or hash not found.
+fn serve_status(app: &TestApp, status: Option<&'static str>) {
+    app.rpc
+        .on("starknet_getTransactionStatus", move |_| match status {
+            Some(status) => Ok(json!({ "finality_status": status })),
+            None => Err(json!({ "code": 29, "message": "Transaction hash not found" })),
+        });
+}
+
+// Move the submission of a job past the tracker's timeout.
+async fn overdue(pool: &PgPool, id: Uuid) {
+    sqlx::query("UPDATE swap_jobs SET submitted_at = NOW() - INTERVAL '2 minutes' WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+async fn submitted_job(pool: &PgPool) -> Uuid {
+    submitted_job_of(pool, 1000).await
+}
+
+async fn submitted_job_of(pool: &PgPool, amount: u128) -> Uuid {
+    let mut conn = pool.acquire().await.unwrap();
+    let job = NewSwapJob {
+        wallet_address: WALLET.to_string(),
+        from_token: FROM_TOKEN.to_string(),
+        to_token: TO_TOKEN.to_string(),
+        percentage: 50,
+        amount: Amount::from_raw(U256::from(amount), 18),
+        max_slippage_bps: 50,
+        venue: BEST_PRICE.to_string(),
+        pull_from_wallet: false,
+        max_attempts: 3,
+    };
+    let id = enqueue(&mut conn, &job).await.unwrap();
+    mark_submitted(&mut conn, id, Felt::from_hex(TX_HASH).unwrap())
+        .await
+        .unwrap();
+    id
+}
+
+async fn job_status(pool: &PgPool, id: Uuid) -> SwapJobStatus {
+    let mut conn = pool.acquire().await.unwrap();
+    find(&mut conn, id).await.unwrap().unwrap().status
+}
+
+type LogRow = (
+    String,
+    Option<String>,
+    Option<i64>,
+    Option<String>,
+    Option<String>,
+    Uint256,
+    Uint256,
+);
+
+async fn log_row(pool: &PgPool) -> Option<LogRow> {
+    sqlx::query_as::<_, LogRow>(
+        r#"
+        SELECT tx_hash, status::TEXT, block_number, revert_reason, actual_fee::TEXT, amount_from, amount_to
+        FROM transactions_log
+        "#,
+    )
+    .fetch_optional(pool)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_unknown_transaction_stays_submitted() {
+    let app = TestApp::new().await;
+    serve_receipt(&app);
+    let id = submitted_job(&app.db.pool).await;
+
+    assert_eq!(tracker(&app).poll().await.unwrap(), 0);
+    assert_eq!(job_status(&app.db.pool, id).await, SwapJobStatus::Submitted);
+    assert!(log_row(&app.db.pool).await.is_none());
+}
+
+#[tokio::test]
+async fn test_dropped_transaction_is_failed_once_its_nonce_is_used() {
+    let app = TestApp::new().await;
+    serve_receipt(&app);
+    serve_status(&app, None);
+    let chain_nonce = accept_transactions(&app, 5);
+    let nonces = NonceManager::new(vec![swap_account(&app, ACCOUNT)]);
+    let account = nonces.next_account();
+    let swap = Call {
+        to: Felt::from_hex(ROUTER).unwrap(),
+        selector: selector!("swap"),
+        calldata: vec![],
+    };
+    nonces.send(&account, vec![swap]).await.unwrap();
+    let id = submitted_job(&app.db.pool).await;
+    overdue(&app.db.pool, id).await;
+    let tracker = tracker_with(&app, nonces.clone());
+
+    // Its nonce unused, the transaction may still be included.
+    assert_eq!(tracker.poll().await.unwrap(), 0);
+    assert_eq!(job_status(&app.db.pool, id).await, SwapJobStatus::Submitted);
+
+    // Another transaction used the nonce.
+    *chain_nonce.lock().unwrap() = 6;
+    assert_eq!(tracker.poll().await.unwrap(), 1);
+    let mut conn = app.db.pool.acquire().await.unwrap();
+    let job = find(&mut conn, id).await.unwrap().unwrap();
+    assert_eq!(job.status, SwapJobStatus::Failed);
+    assert!(job.last_error.unwrap().contains("dropped"));
+    assert!(log_row(&app.db.pool).await.is_none());
+    let address = Felt::from_hex(ACCOUNT).unwrap();
+    assert!(nonces.in_flight(address).await.unwrap().is_empty());
+
+    // The job is no longer tracked.
+    assert_eq!(tracker.poll().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_rejected_transaction_is_failed() {
+    let app = TestApp::new().await;
+    serve_receipt(&app);
+    serve_status(&app, Some("REJECTED"));
+    let id = submitted_job(&app.db.pool).await;
+    overdue(&app.db.pool, id).await;
+
+    assert_eq!(tracker(&app).poll().await.unwrap(), 1);
+    let mut conn = app.db.pool.acquire().await.unwrap();
+    let job = find(&mut conn, id).await.unwrap().unwrap();
+    assert_eq!(job.status, SwapJobStatus::Failed);
+    assert!(job.last_error.unwrap().contains("rejected"));
+}
+
+#[tokio::test]
+async fn test_unknown_transaction_not_in_flight_stays_submitted() {
+    let app = TestApp::new().await;
+    serve_receipt(&app);
+    serve_status(&app, None);
+    // Sent before a restart, so its nonce is not known.
+    let id = submitted_job(&app.db.pool).await;
+    overdue(&app.db.pool, id).await;
+
+    assert_eq!(tracker(&app).poll().await.unwrap(), 0);
+    assert_eq!(job_status(&app.db.pool, id).await, SwapJobStatus::Submitted);
+}
+
+#[tokio::test]
+async fn test_jobs_left_sending_are_recovered() {
+    let app = TestApp::new().await;
+    let served = serve_receipt(&app);
+    serve_status(&app, None);
+    let mut conn = app.db.pool.acquire().await.unwrap();
+    let mut claimed = vec![];
+    for _ in 0..3 {
+        let job = NewSwapJob {
+            wallet_address: WALLET.to_string(),
+            from_token: FROM_TOKEN.to_string(),
+            to_token: TO_TOKEN.to_string(),
+            percentage: 50,
+            amount: Amount::from_raw(U256::from(1000u128), 18),
+            max_slippage_bps: 50,
+            venue: BEST_PRICE.to_string(),
+            pull_from_wallet: false,
+            max_attempts: 3,
+        };
+        claimed.push(enqueue(&mut conn, &job).await.unwrap());
+    }
+    // A worker stopped after sending the first in a known transaction and the
+    // second without one. The third was only just claimed.
+    let (sent, unrecorded, sending) = (claimed[0], claimed[1], claimed[2]);
+    sqlx::query(
+        r#"
+        UPDATE swap_jobs
+        SET status = 'sending',
+            sent_at = NOW() - CASE WHEN id = $3 THEN INTERVAL '0' ELSE INTERVAL '2 minutes' END,
+            transaction_hash = CASE WHEN id = $1 THEN $4 END
+        WHERE id = ANY($2)
+        "#,
+    )
+    .bind(sent)
+    .bind(&claimed)
+    .bind(sending)
+    .bind(TX_HASH)
+    .execute(&app.db.pool)
+    .await
+    .unwrap();
+
+    assert_eq!(tracker(&app).poll().await.unwrap(), 0);
+    assert_eq!(
+        job_status(&app.db.pool, sent).await,
+        SwapJobStatus::Submitted
+    );
+    assert_eq!(
+        job_status(&app.db.pool, unrecorded).await,
+        SwapJobStatus::Unknown
+    );
+    assert_eq!(
+        job_status(&app.db.pool, sending).await,
+        SwapJobStatus::Sending
+    );
+
+    // The known transaction is then tracked as any other.
+    *served.lock().unwrap() = Some(receipt(
+        json!({ "execution_status": "SUCCEEDED" }),
+        "ACCEPTED_ON_L2",
+        vec![
+            transfer(FROM_TOKEN, ACCOUNT, ROUTER, 1000),
+            transfer(TO_TOKEN, ROUTER, ACCOUNT, 3500),
+            transfer(TO_TOKEN, ACCOUNT, SEQUENCER, 100),
+        ],
+    ));
+    assert_eq!(tracker(&app).poll().await.unwrap(), 1);
+    assert_eq!(
+        job_status(&app.db.pool, sent).await,
+        SwapJobStatus::Confirmed
+    );
+    assert_eq!(
+        job_status(&app.db.pool, unrecorded).await,
+        SwapJobStatus::Unknown
+    );
+}
+
+#[tokio::test]
+async fn test_accepted_swap_is_recorded() {
+    let app = TestApp::new().await;
+    let served = serve_receipt(&app);
+    let id = submitted_job(&app.db.pool).await;
+
+    *served.lock().unwrap() = Some(receipt(
+        json!({ "execution_status": "SUCCEEDED" }),
+        "ACCEPTED_ON_L2",
+        vec![
+            transfer(FROM_TOKEN, ACCOUNT, ROUTER, 1000),
+            transfer(TO_TOKEN, ROUTER, ACCOUNT, 3500),
+            transfer(TO_TOKEN, ACCOUNT, SEQUENCER, 100),
+        ],
+    ));
+    assert_eq!(tracker(&app).poll().await.unwrap(), 1);
+    assert_eq!(job_status(&app.db.pool, id).await, SwapJobStatus::Confirmed);
+
+    let (tx_hash, status, block_number, revert_reason, actual_fee, amount_from, amount_to) =
+        log_row(&app.db.pool).await.unwrap();
+    assert_eq!(tx_hash, TX_HASH);
+    assert_eq!(status.as_deref(), Some("accepted_on_l2"));
+    assert_eq!(block_number, Some(640));
+    assert_eq!(revert_reason, None);
+    assert_eq!(actual_fee.as_deref(), Some("100"));
+    assert_eq!(amount_from, Uint256::from(1000u128));
+    assert_eq!(amount_to, Uint256::from(3500u128));
+
+    // Once the block is proven the logged transaction is promoted to L1.
+    *served.lock().unwrap() = Some(receipt(
+        json!({ "execution_status": "SUCCEEDED" }),
+        "ACCEPTED_ON_L1",
+        vec![],
+    ));
+    assert_eq!(tracker(&app).poll().await.unwrap(), 0);
+    let (_, status, ..) = log_row(&app.db.pool).await.unwrap();
+    assert_eq!(status.as_deref(), Some("accepted_on_l1"));
+}
+
+#[tokio::test]
+async fn test_swap_above_u128_is_recorded() {
+    let app = TestApp::new().await;
+    let served = serve_receipt(&app);
+    submitted_job(&app.db.pool).await;
+
+    // Amounts of tokens with many decimals overflow the low word.
+    let amount_from = U256::from_words(1000, 1);
+    let amount_to = U256::from_words(3500, 2);
+    *served.lock().unwrap() = Some(receipt(
+        json!({ "execution_status": "SUCCEEDED" }),
+        "ACCEPTED_ON_L2",
+        vec![
+            transfer_u256(FROM_TOKEN, ACCOUNT, ROUTER, amount_from),
+            transfer_u256(TO_TOKEN, ROUTER, ACCOUNT, amount_to),
+            transfer(TO_TOKEN, ACCOUNT, SEQUENCER, 100),
+        ],
+    ));
+    assert_eq!(tracker(&app).poll().await.unwrap(), 1);
+    let (.., logged_from, logged_to) = log_row(&app.db.pool).await.unwrap();
+    assert_eq!(logged_from, Uint256::from(amount_from));
+    assert_eq!(logged_to, Uint256::from(amount_to));
+    assert_eq!(
+        logged_to.to_string(),
+        "680564733841876926926749214863536426412"
+    );
+}
+
+#[tokio::test]
+async fn test_swap_paid_to_the_funding_wallet_is_recorded() {
+    let app = TestApp::new().await;
+    let served = serve_receipt(&app);
+    let id = submitted_job(&app.db.pool).await;
+    sqlx::query("UPDATE swap_jobs SET pull_from_wallet = true WHERE id = $1")
+        .bind(id)
+        .execute(&app.db.pool)
+        .await
+        .unwrap();
+
+    // The wallet's tokens are pulled, swapped and the output sent back to it.
+    *served.lock().unwrap() = Some(receipt(
+        json!({ "execution_status": "SUCCEEDED" }),
+        "ACCEPTED_ON_L2",
+        vec![
+            transfer(FROM_TOKEN, WALLET, ACCOUNT, 1000),
+            transfer(FROM_TOKEN, ACCOUNT, ROUTER, 1000),
+            transfer(TO_TOKEN, ROUTER, ACCOUNT, 3500),
+            transfer(TO_TOKEN, ACCOUNT, WALLET, 3465),
+            transfer(TO_TOKEN, ACCOUNT, SEQUENCER, 100),
+        ],
+    ));
+    assert_eq!(tracker(&app).poll().await.unwrap(), 1);
+    let (.., amount_from, amount_to) = log_row(&app.db.pool).await.unwrap();
+    assert_eq!(amount_from, Uint256::from(1000u128));
+    assert_eq!(amount_to, Uint256::from(3500u128));
+}
+
+#[tokio::test]
+async fn test_swap_from_executor_account_is_recorded() {
+    let app = TestApp::new().await;
+    let served = serve_receipt(&app);
+    submitted_job(&app.db.pool).await;
+
+    // The account that paid the fee is the one that swapped.
+    *served.lock().unwrap() = Some(receipt(
+        json!({ "execution_status": "SUCCEEDED" }),
+        "ACCEPTED_ON_L2",
+        vec![
+            transfer(FROM_TOKEN, EXECUTOR, ROUTER, 1000),
+            transfer(TO_TOKEN, ROUTER, EXECUTOR, 3500),
+            transfer(TO_TOKEN, EXECUTOR, SEQUENCER, 100),
+        ],
+    ));
+    assert_eq!(
+        tracker_for(&app, &[ACCOUNT, EXECUTOR])
+            .poll()
+            .await
+            .unwrap(),
+        1
+    );
+
+    let (.., amount_from, amount_to) = log_row(&app.db.pool).await.unwrap();
+    assert_eq!(amount_from, Uint256::from(1000u128));
+    assert_eq!(amount_to, Uint256::from(3500u128));
+}
+
+#[tokio::test]
+async fn test_reverted_swap_is_recorded_and_retried() {
+    let app = TestApp::new().await;
+    let served = serve_receipt(&app);
+    let id = submitted_job(&app.db.pool).await;
+
+    *served.lock().unwrap() = Some(receipt(
+        json!({ "execution_status": "REVERTED", "revert_reason": "Insufficient output" }),
+        "ACCEPTED_ON_L2",
+        vec![transfer(TO_TOKEN, ACCOUNT, SEQUENCER, 100)],
+    ));
+    assert_eq!(tracker(&app).poll().await.unwrap(), 1);
+
+    let mut conn = app.db.pool.acquire().await.unwrap();
+    let job = find(&mut conn, id).await.unwrap().unwrap();
+    assert_eq!(job.status, SwapJobStatus::Failed);
+    assert_eq!(job.attempts, 1);
+    assert_eq!(job.last_error.as_deref(), Some("Insufficient output"));
+
+    let (_, status, _, revert_reason, _, amount_from, amount_to) =
+        log_row(&app.db.pool).await.unwrap();
+    assert_eq!(status.as_deref(), Some("reverted"));
+    assert_eq!(revert_reason.as_deref(), Some("Insufficient output"));
+    assert_eq!((amount_from, amount_to), (Uint256::zero(), Uint256::zero()));
+}
+
+#[tokio::test]
+async fn test_batched_swaps_are_split_per_job() {
+    let app = TestApp::new().await;
+    let served = serve_receipt(&app);
+    let small = submitted_job_of(&app.db.pool, 1000).await;
+    let large = submitted_job_of(&app.db.pool, 3000).await;
+
+    *served.lock().unwrap() = Some(receipt(
+        json!({ "execution_status": "SUCCEEDED" }),
+        "ACCEPTED_ON_L2",
+        vec![
+            transfer(FROM_TOKEN, ACCOUNT, ROUTER, 4000),
+            transfer(TO_TOKEN, ROUTER, ACCOUNT, 14001),
+            transfer(TO_TOKEN, ACCOUNT, SEQUENCER, 100),
+        ],
+    ));
+    assert_eq!(tracker(&app).poll().await.unwrap(), 2);
+    assert_eq!(
+        job_status(&app.db.pool, small).await,
+        SwapJobStatus::Confirmed
+    );
+    assert_eq!(
+        job_status(&app.db.pool, large).await,
+        SwapJobStatus::Confirmed
+    );
+
+    // Each job gets its own row, in proportion to its amount. The last job
+    // gets what rounding leaves over.
+    let rows = sqlx::query_as::<_, (Uuid, String, Option<String>, Uint256, Uint256)>(
+        r#"
+        SELECT swap_job_id, tx_hash, actual_fee::TEXT, amount_from, amount_to
+        FROM transactions_log
+        ORDER BY amount_from
+        "#,
+    )
+    .fetch_all(&app.db.pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            (
+                small,
+                TX_HASH.to_string(),
+                Some("25".to_string()),
+                Uint256::from(1000u128),
+                Uint256::from(3500u128)
+            ),
+            (
+                large,
+                TX_HASH.to_string(),
+                Some("75".to_string()),
+                Uint256::from(3000u128),
+                Uint256::from(10501u128)
+            ),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_split_outcome_adds_up() {
+    let outcome = TransactionOutcome {
+        tx_hash: TX_HASH.to_string(),
+        status: TransactionStatus::AcceptedOnL2,
+        block_number: 640,
+        revert_reason: None,
+        actual_fee: "100".to_string(),
+        fee_unit: "FRI".to_string(),
+        amount_from: U256::from(10u8),
+        amount_to: U256::from(7u8),
+    };
+    let amounts = [U256::from(1u128), U256::from(1u128), U256::from(1u128)];
+
+    let split = split_outcome(&outcome, &amounts);
+    let amounts_to: Vec<u128> = split
+        .iter()
+        .map(|outcome| outcome.amount_to.low())
+        .collect();
+    let fees: Vec<&str> = split
+        .iter()
+        .map(|outcome| outcome.actual_fee.as_str())
+        .collect();
+    assert_eq!(amounts_to, vec![2, 2, 3]);
+    assert_eq!(fees, vec!["33", "33", "34"]);
+    assert_eq!(
+        split
+            .iter()
+            .map(|outcome| outcome.amount_from.low())
+            .sum::<u128>(),
+        10
+    );
+    assert!(split.iter().all(|outcome| outcome.tx_hash == TX_HASH));
+}
+
+#[tokio::test]
+async fn test_swap_is_recorded_with_usd_values() {
+    let app = TestApp::new().await;
+    let served = serve_receipt(&app);
+    // ETH at $2500.5 and STRK at $0.5 on the router.
+    app.rpc.on("starknet_call", |params| {
+        let selector: Felt =
+            serde_json::from_value(params["request"]["entry_point_selector"].clone()).unwrap();
+        match selector {
+            s if s == selector!("get_eth_usd_price") => Ok(json!(["0x3a38243480", "0x8"])),
+            s if s == selector!("get_strk_usd_price") => Ok(json!(["0x2faf080", "0x8"])),
+            _ => Err(json!({ "code": 21, "message": "Invalid message selector" })),
+        }
+    });
+    for (token, feed) in [(FROM_TOKEN, "eth"), (TO_TOKEN, "strk")] {
+        register_token(&app.db.pool, token).await;
+        sqlx::query("UPDATE token SET price_feed = $2 WHERE contract_address = $1")
+            .bind(token)
+            .bind(feed)
+            .execute(&app.db.pool)
+            .await
+            .unwrap();
+    }
+    let eth = 1_000_000_000_000_000_000;
+    submitted_job_of(&app.db.pool, 2 * eth).await;
+
+    *served.lock().unwrap() = Some(receipt(
+        json!({ "execution_status": "SUCCEEDED" }),
+        "ACCEPTED_ON_L2",
+        vec![
+            transfer(FROM_TOKEN, ACCOUNT, ROUTER, 2 * eth),
+            transfer(TO_TOKEN, ROUTER, ACCOUNT, 9000 * eth),
+            transfer(TO_TOKEN, ACCOUNT, SEQUENCER, 100),
+        ],
+    ));
+    assert_eq!(tracker(&app).poll().await.unwrap(), 1);
+
+    let req = Request::get(format!("/log_retrieval?wallet_address={}", WALLET))
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.request(req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    let log = &json["data"][0];
+    assert_eq!(log["amount_from_usd"], "5001");
+    assert_eq!(log["amount_to_usd"], "4500");
+}
+
+#[tokio::test]
+async fn test_swap_is_recorded_when_oracle_is_unavailable() {
+    let app = TestApp::new().await;
+    let served = serve_receipt(&app);
+    app.rpc.on("starknet_call", |_| {
+        Err(json!({ "code": 20, "message": "Contract not found" }))
+    });
+    register_token(&app.db.pool, FROM_TOKEN).await;
+    sqlx::query("UPDATE token SET price_feed = 'eth' WHERE contract_address = $1")
+        .bind(FROM_TOKEN)
+        .execute(&app.db.pool)
+        .await
+        .unwrap();
+    let id = submitted_job(&app.db.pool).await;
+
+    *served.lock().unwrap() = Some(receipt(
+        json!({ "execution_status": "SUCCEEDED" }),
+        "ACCEPTED_ON_L2",
+        vec![
+            transfer(FROM_TOKEN, ACCOUNT, ROUTER, 1000),
+            transfer(TO_TOKEN, ROUTER, ACCOUNT, 3500),
+            transfer(TO_TOKEN, ACCOUNT, SEQUENCER, 100),
+        ],
+    ));
+    assert_eq!(tracker(&app).poll().await.unwrap(), 1);
+    assert_eq!(job_status(&app.db.pool, id).await, SwapJobStatus::Confirmed);
+
+    let values = sqlx::query_as::<_, (Option<String>, Option<String>)>(
+        "SELECT amount_from_usd::TEXT, amount_to_usd::TEXT FROM transactions_log",
+    )
+    .fetch_one(&app.db.pool)
+    .await
+    .unwrap();
+    assert_eq!(values, (None, None));
+}
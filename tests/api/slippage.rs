@@ -0,0 +1,94 @@
+use num_bigint::BigUint;
+use starknet::core::types::U256;
+
+use autoswappr_backend::{
+    service::{
+        quote::VenueQuote,
+        slippage::{min_amount_out, SlippageLimits, SwapAborted},
+    },
+    utils::{ekubo::sqrt_ratio_limit, uint256::Uint256},
+};
+
+fn quote(amount_out: u128, price_impact_bps: Option<u32>) -> VenueQuote {
+    VenueQuote {
+        venue: "ekubo".to_string(),
+        amount_out: amount_out.into(),
+        price_impact_bps,
+        gas_fee: 100u128.into(),
+        fee_unit: "FRI".to_string(),
+        pool_key: None,
+        routes: None,
+        reference_in: 1000u128.into(),
+        reference_out: 2000u128.into(),
+    }
+}
+
+fn to_biguint(value: U256) -> BigUint {
+    (BigUint::from(value.high()) << 128) | BigUint::from(value.low())
+}
+
+#[test]
+fn test_min_amount_out() {
+    assert_eq!(min_amount_out(10_000u128.into(), 50), 9_950u128.into());
+    assert_eq!(min_amount_out(999u128.into(), 100), 989u128.into());
+    assert_eq!(min_amount_out(999u128.into(), 0), 999u128.into());
+    assert_eq!(min_amount_out(999u128.into(), 10_000), Uint256::zero());
+}
+
+#[test]
+fn test_limits_from_quote() {
+    let limits = SlippageLimits::from_quote(&quote(1_990_000, Some(50)), 100).unwrap();
+    assert_eq!(limits.min_amount_out, 1_970_100u128.into());
+    assert_eq!(limits.max_slippage_bps, 100);
+}
+
+#[test]
+fn test_swap_beyond_slippage_is_aborted() {
+    assert_eq!(
+        SlippageLimits::from_quote(&quote(1_990_000, Some(150)), 100),
+        Err(SwapAborted(
+            "Quoted price impact of 150 bps exceeds the maximum slippage of 100 bps".to_string()
+        ))
+    );
+    assert!(SlippageLimits::from_quote(&quote(0, Some(0)), 100).is_err());
+    assert!(SlippageLimits::from_quote(&quote(1_990_000, None), 100).is_err());
+}
+
+#[test]
+fn test_sqrt_ratio_limit() {
+    let one = BigUint::from(1u8) << 128;
+    let limit = |amount_in: u128, amount_out: u128, slippage: u16, is_token1: bool| {
+        to_biguint(sqrt_ratio_limit(
+            amount_in.into(),
+            amount_out.into(),
+            slippage,
+            is_token1,
+        ))
+    };
+
+    // A 1:1 rate without slippage is a sqrt ratio of exactly 1.
+    assert_eq!(limit(1000, 1000, 0, false), one);
+    assert_eq!(limit(1000, 1000, 0, true), one);
+
+    // Selling token0 at 1:4 may lower the price to 4 * 0.99, a sqrt ratio of about 1.99.
+    let selling_token0 = limit(1000, 4000, 100, false);
+    assert!(selling_token0 < &one * 2u8);
+    assert_eq!(&selling_token0 * 100u8 / &one, BigUint::from(198u8));
+
+    // Selling token1 at 4:1 may raise the price to 4 / 0.99, a sqrt ratio of about 2.01.
+    let selling_token1 = limit(4000, 1000, 100, true);
+    assert!(selling_token1 > &one * 2u8);
+    assert_eq!(&selling_token1 * 10_000u16 / &one, BigUint::from(20_100u16));
+
+    // Limits never leave Ekubo's bounds.
+    assert_eq!(
+        limit(1000, 0, 50, false),
+        BigUint::from(18446748437148339063u128)
+    );
+    assert_eq!(
+        limit(1000, 1000, 10_000, true),
+        "6277100250585753475930931601400621808602321654880405518632"
+            .parse::<BigUint>()
+            .unwrap()
+    );
+}
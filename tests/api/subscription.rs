@@ -1,8 +1,8 @@
 use axum::{
-    body::Body,
+    body::{to_bytes, Body},
     http::{header::CONTENT_TYPE, Request, StatusCode},
 };
-use serde_json::json;
+use serde_json::{json, Value};
 
 use crate::helpers::*;
 
@@ -11,6 +11,13 @@ async fn test_subscribe_ok() {
     let app = TestApp::new().await;
 
     clean_database(&app.db.pool).await;
+    for token in [
+        "0xde3bc70e81af42a996a559a60f0fdf1cb371f012790f1b30de709efa637b9af5",
+        "0x07ab8059db97aab8ced83b37a1d60b8eef540f6cdc96acc153d583a59bedd125",
+        "0x40ca979f20ed76f960dc719457eaf0cef3b2c3932d58435b9192a58bc56c1e40",
+    ] {
+        register_token(&app.db.pool, token).await;
+    }
 
     let payload = json!({
         "wallet_address": "0xdbfcab49bd9bced4636b04319d71fbd0d84bde78a1d38e9e2fc391e83187c1c3",
@@ -22,12 +29,12 @@ async fn test_subscribe_ok() {
         "percentage": [60, 40]
     });
 
-    let req = Request::builder()
-        .method("POST")
-        .uri("/subscriptions")
-        .header(CONTENT_TYPE, "application/json")
-        .body(Body::from(serde_json::to_string(&payload).unwrap()))
-        .unwrap();
+    let req = signed_request(
+        "POST",
+        "/subscriptions",
+        payload["wallet_address"].as_str().unwrap(),
+        &payload,
+    );
 
     let resp = app.request(req).await;
     assert_eq!(resp.status(), StatusCode::OK);
@@ -46,6 +53,10 @@ async fn test_successful_subscription_creation() {
         "0x40ca979f20ed76f960dc719457eaf0cef3b2c3932d58435b9192a58bc56c1e40",
     ];
     let percentages = vec![60, 40];
+    register_token(&app.db.pool, to_token).await;
+    for token in &from_tokens {
+        register_token(&app.db.pool, token).await;
+    }
 
     let payload = json!({
         "wallet_address": wallet_address,
@@ -54,12 +65,12 @@ async fn test_successful_subscription_creation() {
         "percentage": percentages
     });
 
-    let req = Request::builder()
-        .method("POST")
-        .uri("/subscriptions")
-        .header(CONTENT_TYPE, "application/json")
-        .body(Body::from(serde_json::to_string(&payload).unwrap()))
-        .unwrap();
+    let req = signed_request(
+        "POST",
+        "/subscriptions",
+        payload["wallet_address"].as_str().unwrap(),
+        &payload,
+    );
 
     let resp = app.request(req).await;
 
@@ -117,6 +128,29 @@ async fn test_successful_subscription_creation() {
     );
 }
 
+#[tokio::test]
+async fn test_subscription_with_unregistered_token() {
+    let app = TestApp::new().await;
+    let to_token = "0xdbfcab49bd9bced4636b04319d71fbd0d84bde78a1d38e9e2fc391e83187c1c3";
+    register_token(&app.db.pool, to_token).await;
+
+    let payload = json!({
+        "wallet_address": "0x40ca979f20ed76f960dc719457eaf0cef3b2c3932d58435b9192a58bc56c1e40",
+        "to_token": to_token,
+        "from_token": ["0x07ab8059db97aab8ced83b37a1d60b8eef540f6cdc96acc153d583a59bedd125"],
+        "percentage": [100]
+    });
+    let req = signed_request(
+        "POST",
+        "/subscriptions",
+        payload["wallet_address"].as_str().unwrap(),
+        &payload,
+    );
+
+    let resp = app.request(req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
 #[tokio::test]
 async fn test_invalid_percentage_length() {
     let app = TestApp::new().await;
@@ -133,12 +167,12 @@ async fn test_invalid_percentage_length() {
         "percentage": [20]
     });
 
-    let req = Request::builder()
-        .method("POST")
-        .uri("/subscriptions")
-        .header(CONTENT_TYPE, "application/json")
-        .body(Body::from(serde_json::to_string(&payload).unwrap()))
-        .unwrap();
+    let req = signed_request(
+        "POST",
+        "/subscriptions",
+        payload["wallet_address"].as_str().unwrap(),
+        &payload,
+    );
 
     let resp = app.request(req).await;
     assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
@@ -160,12 +194,12 @@ async fn test_invalid_wallet_address() {
         "percentage": [20, 80]
     });
 
-    let req = Request::builder()
-        .method("POST")
-        .uri("/subscriptions")
-        .header(CONTENT_TYPE, "application/json")
-        .body(Body::from(serde_json::to_string(&payload).unwrap()))
-        .unwrap();
+    let req = signed_request(
+        "POST",
+        "/subscriptions",
+        payload["wallet_address"].as_str().unwrap(),
+        &payload,
+    );
 
     let resp = app.request(req).await;
     assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
@@ -187,12 +221,12 @@ async fn test_invalid_to_token_address() {
         "percentage": [20, 80]
     });
 
-    let req = Request::builder()
-        .method("POST")
-        .uri("/subscriptions")
-        .header(CONTENT_TYPE, "application/json")
-        .body(Body::from(serde_json::to_string(&payload).unwrap()))
-        .unwrap();
+    let req = signed_request(
+        "POST",
+        "/subscriptions",
+        payload["wallet_address"].as_str().unwrap(),
+        &payload,
+    );
 
     let resp = app.request(req).await;
     assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
@@ -211,3 +245,87 @@ async fn test_successful_subscription_retrieval() {
     let resp = app.request(req).await;
     assert_eq!(resp.status(), StatusCode::OK);
 }
+
+#[tokio::test]
+async fn test_subscription_max_slippage() {
+    let app = TestApp::new().await;
+    let wallet_address = "0x40ca979f20ed76f960dc719457eaf0cef3b2c3932d58435b9192a58bc56c1e40";
+    let to_token = "0xdbfcab49bd9bced4636b04319d71fbd0d84bde78a1d38e9e2fc391e83187c1c3";
+    let from_token = "0x07ab8059db97aab8ced83b37a1d60b8eef540f6cdc96acc153d583a59bedd125";
+    register_token(&app.db.pool, to_token).await;
+    register_token(&app.db.pool, from_token).await;
+
+    let subscribe = |max_slippage_bps: i64| {
+        let payload = json!({
+            "wallet_address": wallet_address,
+            "to_token": to_token,
+            "from_token": [from_token],
+            "percentage": [100],
+            "max_slippage_bps": max_slippage_bps
+        });
+        signed_request("POST", "/subscriptions", wallet_address, &payload)
+    };
+
+    for invalid in [-1, 10_001] {
+        let resp = app.request(subscribe(invalid)).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    let resp = app.request(subscribe(100)).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let req = Request::get(format!("/subscriptions?wallet_address={}", wallet_address))
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.request(req).await;
+    let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["data"][0]["max_slippage_bps"], 100);
+}
+
+#[tokio::test]
+async fn test_subscription_venue() {
+    let app = TestApp::new().await;
+    let wallet_address = "0x40ca979f20ed76f960dc719457eaf0cef3b2c3932d58435b9192a58bc56c1e40";
+    let to_token = "0xdbfcab49bd9bced4636b04319d71fbd0d84bde78a1d38e9e2fc391e83187c1c3";
+    let from_token = "0x07ab8059db97aab8ced83b37a1d60b8eef540f6cdc96acc153d583a59bedd125";
+    register_token(&app.db.pool, to_token).await;
+    register_token(&app.db.pool, from_token).await;
+
+    let subscribe = |venue: Option<&str>| {
+        let mut payload = json!({
+            "wallet_address": wallet_address,
+            "to_token": to_token,
+            "from_token": [from_token],
+            "percentage": [100]
+        });
+        if let Some(venue) = venue {
+            payload["venue"] = json!(venue);
+        }
+        signed_request("POST", "/subscriptions", wallet_address, &payload)
+    };
+    let venue = || async {
+        let req = Request::get(format!("/subscriptions?wallet_address={}", wallet_address))
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.request(req).await;
+        let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        json["data"][0]["venue"].clone()
+    };
+
+    let resp = app.request(subscribe(None)).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(venue().await, "best_price");
+
+    let resp = app.request(subscribe(Some("ekubo"))).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(venue().await, "ekubo");
+
+    // AVNU is not registered without an API URL.
+    for invalid in ["avnu", "uniswap", ""] {
+        let resp = app.request(subscribe(Some(invalid))).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+    assert_eq!(venue().await, "ekubo");
+}
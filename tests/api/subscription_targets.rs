@@ -0,0 +1,209 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use serde_json::{json, Value};
+
+use autoswappr_backend::service::auto_swap::TransferOutcome;
+
+use crate::helpers::*;
+
+const WALLET: &str = "0xdbfcab49bd9bced4636b04319d71fbd0d84bde78a1d38e9e2fc391e83187c1c3";
+const FROM_TOKEN: &str = "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
+const USDC: &str = "0x053c91253bc9682c04929ca02ed00b3e423f6710d2ee7e0d5ebb06f3ecf368a8";
+const STRK: &str = "0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d";
+const ONE: u128 = 1_000_000_000_000_000_000;
+
+async fn send(app: &TestApp, req: Request<Body>) -> (StatusCode, Value) {
+    let resp = app.request(req).await;
+    let status = resp.status();
+    let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+async fn get(app: &TestApp, uri: &str) -> (StatusCode, Value) {
+    send(app, Request::get(uri).body(Body::empty()).unwrap()).await
+}
+
+// Subscribe the wallet to swap `percentage` of FROM_TOKEN into `targets`.
+async fn subscribe(app: &TestApp, percentage: i16, targets: Value) -> StatusCode {
+    for token in [FROM_TOKEN, USDC, STRK] {
+        register_token(&app.db.pool, token).await;
+    }
+    let payload = json!({
+        "wallet_address": WALLET,
+        "from_token": [FROM_TOKEN],
+        "percentage": [percentage],
+        "targets": targets
+    });
+    send(
+        app,
+        signed_request("POST", "/subscriptions", WALLET, &payload),
+    )
+    .await
+    .0
+}
+
+#[tokio::test]
+async fn test_subscription_stores_weighted_targets() {
+    let app = TestApp::new().await;
+    let targets = json!([[
+        { "to_token": USDC, "weight": 5 },
+        { "to_token": STRK, "weight": 3 }
+    ]]);
+    assert_eq!(subscribe(&app, 80, targets).await, StatusCode::OK);
+
+    // The first target is the default `to_token` of the subscription.
+    let (status, json) = get(&app, &format!("/subscriptions?wallet_address={}", WALLET)).await;
+    assert_eq!(status, StatusCode::OK);
+    let data = json["data"].as_array().unwrap();
+    assert_eq!(data.len(), 1);
+    assert_eq!(data[0]["from_token"], FROM_TOKEN);
+    assert_eq!(data[0]["to_token"], USDC);
+    assert_eq!(data[0]["percentage"], 80);
+    let mut targets: Vec<(String, i64)> = data[0]["targets"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|target| {
+            (
+                target["to_token"].as_str().unwrap().to_string(),
+                target["weight"].as_i64().unwrap(),
+            )
+        })
+        .collect();
+    targets.sort();
+    let mut expected = vec![(USDC.to_string(), 5), (STRK.to_string(), 3)];
+    expected.sort();
+    assert_eq!(targets, expected);
+}
+
+#[tokio::test]
+async fn test_resubscribing_replaces_targets() {
+    let app = TestApp::new().await;
+    let targets = json!([[
+        { "to_token": USDC, "weight": 5 },
+        { "to_token": STRK, "weight": 3 }
+    ]]);
+    assert_eq!(subscribe(&app, 80, targets).await, StatusCode::OK);
+
+    let targets = json!([[{ "to_token": STRK, "weight": 2 }]]);
+    assert_eq!(subscribe(&app, 40, targets).await, StatusCode::OK);
+
+    let (_, json) = get(&app, &format!("/subscriptions?wallet_address={}", WALLET)).await;
+    let data = json["data"].as_array().unwrap();
+    assert_eq!(data.len(), 1);
+    assert_eq!(data[0]["percentage"], 40);
+    assert_eq!(data[0]["to_token"], STRK);
+    assert_eq!(
+        data[0]["targets"],
+        json!([{ "to_token": STRK, "weight": 2 }])
+    );
+}
+
+#[tokio::test]
+async fn test_subscription_without_targets_has_single_target() {
+    let app = TestApp::new().await;
+    for token in [FROM_TOKEN, USDC] {
+        register_token(&app.db.pool, token).await;
+    }
+    let payload = json!({
+        "wallet_address": WALLET,
+        "to_token": USDC,
+        "from_token": [FROM_TOKEN],
+        "percentage": [50]
+    });
+    let (status, _) = send(
+        &app,
+        signed_request("POST", "/subscriptions", WALLET, &payload),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, json) = get(&app, &format!("/subscriptions?wallet_address={}", WALLET)).await;
+    assert_eq!(
+        json["data"][0]["targets"],
+        json!([{ "to_token": USDC, "weight": 1 }])
+    );
+}
+
+#[tokio::test]
+async fn test_subscription_rejects_invalid_targets() {
+    let app = TestApp::new().await;
+    let unregistered = "0x0000000000000000000000000000000000000000000000000000000000000abc";
+    for targets in [
+        // Neither targets nor a to_token.
+        Value::Null,
+        // Not one list per from_token.
+        json!([]),
+        json!([[{ "to_token": USDC, "weight": 1 }], [{ "to_token": STRK, "weight": 1 }]]),
+        // No targets for the token.
+        json!([[]]),
+        json!([[{ "to_token": USDC, "weight": 0 }]]),
+        json!([[{ "to_token": USDC, "weight": 10001 }]]),
+        json!([[{ "to_token": USDC, "weight": 1 }, { "to_token": USDC, "weight": 2 }]]),
+        json!([[{ "to_token": FROM_TOKEN, "weight": 1 }]]),
+        json!([[{ "to_token": "0x123", "weight": 1 }]]),
+        json!([[{ "to_token": unregistered, "weight": 1 }]]),
+    ] {
+        assert_eq!(
+            subscribe(&app, 100, targets.clone()).await,
+            StatusCode::BAD_REQUEST,
+            "{}",
+            targets
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_received_transfer_queues_one_leg_per_target() {
+    let app = TestApp::new().await;
+    let targets = json!([[
+        { "to_token": USDC, "weight": 5 },
+        { "to_token": STRK, "weight": 3 }
+    ]]);
+    assert_eq!(subscribe(&app, 80, targets).await, StatusCode::OK);
+
+    let legs = receive_transfer(&app.db.pool, WALLET, FROM_TOKEN, 2 * ONE).await;
+    assert_eq!(legs.len(), 2);
+
+    // 80% of 2 tokens split 5:3 swaps 1 token into USDC and 0.6 into STRK.
+    let mut amounts = Vec::new();
+    for leg in legs {
+        let job_id = match leg.outcome {
+            TransferOutcome::Queued(job_id) => job_id,
+            TransferOutcome::Held(_) => panic!("Leg into {} was held", leg.to_token),
+        };
+        let (status, job) = get(&app, &format!("/swap_jobs/{}", job_id)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(job["to_token"], leg.to_token);
+        assert_eq!(job["percentage"], 80);
+        amounts.push((
+            job["to_token"].as_str().unwrap().to_string(),
+            job["amount"].as_str().unwrap().to_string(),
+        ));
+    }
+    amounts.sort();
+    let mut expected = vec![
+        (USDC.to_string(), "1000000000000000000".to_string()),
+        (STRK.to_string(), "600000000000000000".to_string()),
+    ];
+    expected.sort();
+    assert_eq!(amounts, expected);
+}
+
+#[tokio::test]
+async fn test_received_transfer_skips_empty_legs() {
+    let app = TestApp::new().await;
+    register_token_with_decimals(&app.db.pool, FROM_TOKEN, 0).await;
+    let targets = json!([[
+        { "to_token": USDC, "weight": 1 },
+        { "to_token": STRK, "weight": 1 }
+    ]]);
+    assert_eq!(subscribe(&app, 100, targets).await, StatusCode::OK);
+
+    // A single base unit cannot be split, so it all goes to the last target.
+    let legs = receive_transfer(&app.db.pool, WALLET, FROM_TOKEN, 1).await;
+    assert_eq!(legs.len(), 1);
+    assert_eq!(legs[0].to_token, STRK);
+}
@@ -0,0 +1,436 @@
+use anyhow::{anyhow, Result};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use starknet::core::types::{Felt, U256};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use uuid::Uuid;
+
+use autoswappr_backend::{
+    service::{
+        auto_swap::{TransferLeg, TransferOutcome},
+        slippage::SwapAborted,
+        swap_jobs::{enqueue, find, NewSwapJob, SwapJob, SwapJobStatus},
+        venues::BEST_PRICE,
+    },
+    utils::amount::Amount,
+    worker::{process_next_batch, process_next_job, BatchSettings, SwapExecutor},
+};
+
+use crate::helpers::*;
+
+const WALLET: &str = "0xdbfcab49bd9bced4636b04319d71fbd0d84bde78a1d38e9e2fc391e83187c1c3";
+const FROM_TOKEN: &str = "0x07ab8059db97aab8ced83b37a1d60b8eef540f6cdc96acc153d583a59bedd125";
+const TO_TOKEN: &str = "0x40ca979f20ed76f960dc719457eaf0cef3b2c3932d58435b9192a58bc56c1e40";
+const OTHER_TOKEN: &str = "0x053c91253bc9682c04929ca02ed00b3e423f6710d2ee7e0d5ebb06f3ecf368a8";
+
+struct FailingExecutor;
+
+impl SwapExecutor for FailingExecutor {
+    async fn execute(&self, _job: &SwapJob) -> Result<Felt> {
+        Err(anyhow!("RPC unavailable"))
+    }
+}
+
+struct AbortingExecutor;
+
+impl SwapExecutor for AbortingExecutor {
+    async fn execute(&self, _job: &SwapJob) -> Result<Felt> {
+        Err(SwapAborted("Price moved too much".to_string()).into())
+    }
+}
+
+// Records which jobs it executed.
+#[derive(Default)]
+struct RecordingExecutor {
+    executed: Mutex<Vec<Uuid>>,
+}
+
+impl SwapExecutor for RecordingExecutor {
+    async fn execute(&self, job: &SwapJob) -> Result<Felt> {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        self.executed.lock().unwrap().push(job.id);
+        Ok(Felt::from_hex("0xabc").unwrap())
+    }
+}
+
+// Records the jobs of every batch it sent as one transaction.
+#[derive(Default)]
+struct BatchingExecutor {
+    batches: Mutex<Vec<Vec<Uuid>>>,
+}
+
+impl SwapExecutor for BatchingExecutor {
+    async fn execute(&self, job: &SwapJob) -> Result<Felt> {
+        self.execute_batch(std::slice::from_ref(job))
+            .await
+            .remove(0)
+    }
+
+    async fn execute_batch(&self, jobs: &[SwapJob]) -> Vec<Result<Felt>> {
+        self.batches
+            .lock()
+            .unwrap()
+            .push(jobs.iter().map(|job| job.id).collect());
+        jobs.iter()
+            .map(|_| Ok(Felt::from_hex("0xabc").unwrap()))
+            .collect()
+    }
+}
+
+async fn subscribe(pool: &PgPool) {
+    register_token(pool, FROM_TOKEN).await;
+    sqlx::query("INSERT INTO swap_subscription (wallet_address, to_token) VALUES ($1, $2)")
+        .bind(WALLET)
+        .bind(TO_TOKEN)
+        .execute(pool)
+        .await
+        .unwrap();
+    sqlx::query(
+        "INSERT INTO swap_subscription_from_token (wallet_address, from_token, percentage) VALUES ($1, $2, 50)",
+    )
+    .bind(WALLET)
+    .bind(FROM_TOKEN)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+async fn new_job(pool: &PgPool, max_attempts: i32) -> Uuid {
+    new_pair_job(pool, TO_TOKEN, max_attempts).await
+}
+
+async fn new_pair_job(pool: &PgPool, to_token: &str, max_attempts: i32) -> Uuid {
+    let mut conn = pool.acquire().await.unwrap();
+    let job = NewSwapJob {
+        wallet_address: WALLET.to_string(),
+        from_token: FROM_TOKEN.to_string(),
+        to_token: to_token.to_string(),
+        percentage: 50,
+        amount: Amount::from_raw(U256::from(1000u128), 18),
+        max_slippage_bps: 50,
+        venue: BEST_PRICE.to_string(),
+        pull_from_wallet: false,
+        max_attempts,
+    };
+    enqueue(&mut conn, &job).await.unwrap()
+}
+
+async fn job(pool: &PgPool, id: Uuid) -> SwapJob {
+    let mut conn = pool.acquire().await.unwrap();
+    find(&mut conn, id).await.unwrap().unwrap()
+}
+
+// Id of the job queued for the only leg of a received transfer.
+fn queued_job(legs: &[TransferLeg]) -> Uuid {
+    match legs {
+        [TransferLeg {
+            outcome: TransferOutcome::Queued(id),
+            ..
+        }] => *id,
+        legs => panic!("Expected a single queued leg, got {:?}", legs),
+    }
+}
+
+#[tokio::test]
+async fn test_received_transfer_enqueues_job() {
+    let app = TestApp::new().await;
+    subscribe(&app.db.pool).await;
+
+    let legs = receive_transfer(&app.db.pool, WALLET, FROM_TOKEN, 2_000_000_000_000_000_000).await;
+    let job_id = queued_job(&legs);
+
+    let req = Request::get(format!("/swap_jobs/{}", job_id))
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.request(req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["status"], "pending");
+    assert_eq!(json["attempts"], 0);
+    assert_eq!(json["amount"], "1000000000000000000");
+    assert_eq!(json["max_slippage_bps"], 50);
+}
+
+#[tokio::test]
+async fn test_received_transfer_uses_token_decimals() {
+    let app = TestApp::new().await;
+    register_token_with_decimals(&app.db.pool, FROM_TOKEN, 6).await;
+    subscribe(&app.db.pool).await;
+
+    let legs = receive_transfer(&app.db.pool, WALLET, FROM_TOKEN, 1_500_000).await;
+    let job = job(&app.db.pool, queued_job(&legs)).await;
+    assert_eq!(job.amount, "750000");
+    assert_eq!(job.from_decimals, 6);
+}
+
+#[tokio::test]
+async fn test_received_transfer_too_small_to_swap() {
+    let app = TestApp::new().await;
+    register_token_with_decimals(&app.db.pool, FROM_TOKEN, 6).await;
+    subscribe(&app.db.pool).await;
+
+    for amount in [0, 1] {
+        let legs = receive_transfer(&app.db.pool, WALLET, FROM_TOKEN, amount).await;
+        assert!(legs.is_empty());
+    }
+}
+
+#[tokio::test]
+async fn test_received_transfer_without_subscription() {
+    let app = TestApp::new().await;
+    register_token(&app.db.pool, FROM_TOKEN).await;
+
+    let legs = receive_transfer(&app.db.pool, WALLET, FROM_TOKEN, 2_000_000_000_000_000_000).await;
+    assert!(legs.is_empty());
+}
+
+#[tokio::test]
+async fn test_auto_swap_endpoint_is_removed() {
+    let app = TestApp::new().await;
+    subscribe(&app.db.pool).await;
+
+    // Swaps are only queued for transfers found on chain, never for a claimed one.
+    let payload = json!({
+        "token_from": FROM_TOKEN,
+        "swap_recipient": WALLET,
+        "value_received": 2
+    });
+    let resp = app
+        .request(signed_request("POST", "/auto_swap", WALLET, &payload))
+        .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let (jobs,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM swap_jobs")
+        .fetch_one(&app.db.pool)
+        .await
+        .unwrap();
+    assert_eq!(jobs, 0);
+}
+
+#[tokio::test]
+async fn test_get_unknown_swap_job() {
+    let app = TestApp::new().await;
+
+    let req = Request::get(format!("/swap_jobs/{}", Uuid::now_v7()))
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.request(req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_successful_job_is_submitted() {
+    let app = TestApp::new().await;
+    let id = new_job(&app.db.pool, 3).await;
+
+    let executor = RecordingExecutor::default();
+    assert!(process_next_job(&app.db, &executor).await.unwrap());
+
+    let job = job(&app.db.pool, id).await;
+    assert_eq!(job.status, SwapJobStatus::Submitted);
+    assert_eq!(job.attempts, 1);
+    assert_eq!(job.transaction_hash.as_deref(), Some("0xabc"));
+    assert!(!process_next_job(&app.db, &executor).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_sent_job_is_not_sent_again_when_recording_fails() {
+    let app = TestApp::new().await;
+    let id = new_job(&app.db.pool, 3).await;
+
+    // The swap is sent, but marking the job submitted fails.
+    sqlx::query(
+        r#"
+        CREATE FUNCTION reject_submitted() RETURNS trigger AS $$
+        BEGIN
+            RAISE EXCEPTION 'database unavailable';
+        END
+        $$ LANGUAGE plpgsql
+        "#,
+    )
+    .execute(&app.db.pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        r#"
+        CREATE TRIGGER reject_submitted BEFORE UPDATE ON swap_jobs
+        FOR EACH ROW WHEN (NEW.status = 'submitted') EXECUTE FUNCTION reject_submitted()
+        "#,
+    )
+    .execute(&app.db.pool)
+    .await
+    .unwrap();
+
+    let executor = RecordingExecutor::default();
+    assert!(process_next_job(&app.db, &executor).await.is_err());
+    let sending = job(&app.db.pool, id).await;
+    assert_eq!(sending.status, SwapJobStatus::Sending);
+    // Its transaction is kept for the receipt tracker to reconcile it.
+    assert_eq!(sending.transaction_hash.as_deref(), Some("0xabc"));
+
+    // The claim was committed, so the job is never due again.
+    sqlx::query("UPDATE swap_jobs SET next_attempt_at = NOW() - INTERVAL '1 hour' WHERE id = $1")
+        .bind(id)
+        .execute(&app.db.pool)
+        .await
+        .unwrap();
+    assert!(!process_next_job(&app.db, &executor).await.unwrap());
+    assert_eq!(*executor.executed.lock().unwrap(), vec![id]);
+}
+
+#[tokio::test]
+async fn test_failed_job_is_retried_until_dead() {
+    let app = TestApp::new().await;
+    let id = new_job(&app.db.pool, 2).await;
+
+    assert!(process_next_job(&app.db, &FailingExecutor).await.unwrap());
+    let failed = job(&app.db.pool, id).await;
+    assert_eq!(failed.status, SwapJobStatus::Failed);
+    assert_eq!(failed.attempts, 1);
+    assert_eq!(failed.last_error.as_deref(), Some("RPC unavailable"));
+
+    // Not due again until the backoff has passed.
+    assert!(!process_next_job(&app.db, &FailingExecutor).await.unwrap());
+    sqlx::query("UPDATE swap_jobs SET next_attempt_at = NOW() WHERE id = $1")
+        .bind(id)
+        .execute(&app.db.pool)
+        .await
+        .unwrap();
+
+    assert!(process_next_job(&app.db, &FailingExecutor).await.unwrap());
+    let dead = job(&app.db.pool, id).await;
+    assert_eq!(dead.status, SwapJobStatus::Dead);
+    assert_eq!(dead.attempts, 2);
+
+    sqlx::query("UPDATE swap_jobs SET next_attempt_at = NOW() WHERE id = $1")
+        .bind(id)
+        .execute(&app.db.pool)
+        .await
+        .unwrap();
+    assert!(!process_next_job(&app.db, &FailingExecutor).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_concurrent_workers_execute_each_job_once() {
+    let app = TestApp::new().await;
+    let mut ids = Vec::new();
+    for _ in 0..10 {
+        ids.push(new_job(&app.db.pool, 3).await);
+    }
+
+    let executor = Arc::new(RecordingExecutor::default());
+    let workers: Vec<_> = (0..4)
+        .map(|_| {
+            let db = app.db.clone();
+            let executor = executor.clone();
+            tokio::spawn(
+                async move { while process_next_job(&db, executor.as_ref()).await.unwrap() {} },
+            )
+        })
+        .collect();
+    for worker in workers {
+        worker.await.unwrap();
+    }
+
+    let mut executed = executor.executed.lock().unwrap().clone();
+    executed.sort();
+    ids.sort();
+    assert_eq!(executed, ids);
+}
+
+#[tokio::test]
+async fn test_aborted_job_is_not_retried() {
+    let app = TestApp::new().await;
+    let id = new_job(&app.db.pool, 3).await;
+
+    assert!(process_next_job(&app.db, &AbortingExecutor).await.unwrap());
+    let aborted = job(&app.db.pool, id).await;
+    assert_eq!(aborted.status, SwapJobStatus::Aborted);
+    assert_eq!(aborted.attempts, 0);
+    assert_eq!(aborted.last_error.as_deref(), Some("Price moved too much"));
+
+    sqlx::query("UPDATE swap_jobs SET next_attempt_at = NOW() WHERE id = $1")
+        .bind(id)
+        .execute(&app.db.pool)
+        .await
+        .unwrap();
+    assert!(!process_next_job(&app.db, &AbortingExecutor).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_jobs_of_the_same_pair_are_batched() {
+    let app = TestApp::new().await;
+    let mut same_pair = Vec::new();
+    for _ in 0..3 {
+        same_pair.push(new_job(&app.db.pool, 3).await);
+    }
+    let other_pair = new_pair_job(&app.db.pool, OTHER_TOKEN, 3).await;
+    let settings = BatchSettings {
+        max_size: 2,
+        window: Duration::ZERO,
+    };
+
+    let executor = BatchingExecutor::default();
+    while process_next_batch(&app.db, &executor, &settings)
+        .await
+        .unwrap()
+    {}
+
+    let mut batches = executor.batches.lock().unwrap().clone();
+    batches.iter_mut().for_each(|batch| batch.sort());
+    batches.sort_by_key(|batch| batch.len());
+    // Batches never mix pairs and hold at most `max_size` jobs.
+    assert_eq!(batches.len(), 3);
+    assert!(batches.iter().all(|batch| batch.len() <= 2));
+    assert!(batches.contains(&vec![other_pair]));
+    let mut batched: Vec<Uuid> = batches
+        .into_iter()
+        .flatten()
+        .filter(|id| *id != other_pair)
+        .collect();
+    batched.sort();
+    same_pair.sort();
+    assert_eq!(batched, same_pair);
+
+    for id in same_pair {
+        let job = job(&app.db.pool, id).await;
+        assert_eq!(job.status, SwapJobStatus::Submitted);
+        assert_eq!(job.transaction_hash.as_deref(), Some("0xabc"));
+    }
+}
+
+#[tokio::test]
+async fn test_batch_waits_for_the_window() {
+    let app = TestApp::new().await;
+    let id = new_job(&app.db.pool, 3).await;
+    let settings = BatchSettings {
+        max_size: 10,
+        window: Duration::from_secs(60),
+    };
+
+    let executor = BatchingExecutor::default();
+    assert!(!process_next_batch(&app.db, &executor, &settings)
+        .await
+        .unwrap());
+
+    // Once the oldest job has waited out the window the batch is sent.
+    sqlx::query(
+        "UPDATE swap_jobs SET next_attempt_at = NOW() - INTERVAL '2 minutes' WHERE id = $1",
+    )
+    .bind(id)
+    .execute(&app.db.pool)
+    .await
+    .unwrap();
+    assert!(process_next_batch(&app.db, &executor, &settings)
+        .await
+        .unwrap());
+    assert_eq!(*executor.batches.lock().unwrap(), vec![vec![id]]);
+}
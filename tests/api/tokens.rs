@@ -0,0 +1,239 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{header::CONTENT_TYPE, Request, Response, StatusCode},
+};
+use serde_json::{json, Value};
+use starknet::{core::types::Felt, macros::selector};
+
+use autoswappr_backend::auth::ADMIN_KEY_HEADER;
+
+use crate::helpers::*;
+
+const TOKEN: &str = "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
+
+fn admin_request(method: &str, uri: &str, body: Option<Value>) -> Request<Body> {
+    let builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(ADMIN_KEY_HEADER, TEST_ADMIN_KEY);
+    match body {
+        Some(body) => builder
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap(),
+        None => builder.body(Body::empty()).unwrap(),
+    }
+}
+
+async fn json_body(resp: Response<Body>) -> Value {
+    let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+fn ether() -> Value {
+    json!({
+        "contract_address": TOKEN,
+        "token_name": "Ether",
+        "token_symbol": "ETH",
+        "token_decimals": 18
+    })
+}
+
+#[tokio::test]
+async fn test_admin_key_is_required() {
+    let app = TestApp::new().await;
+
+    let req = Request::get("/admin/tokens").body(Body::empty()).unwrap();
+    assert_eq!(app.request(req).await.status(), StatusCode::UNAUTHORIZED);
+
+    let req = Request::get("/admin/tokens")
+        .header(ADMIN_KEY_HEADER, "wrong-key")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(app.request(req).await.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_add_and_get_token() {
+    let app = TestApp::new().await;
+
+    let resp = app
+        .request(admin_request("POST", "/admin/tokens", Some(ether())))
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let token = json_body(resp).await;
+    assert_eq!(token["token_symbol"], "ETH");
+    assert_eq!(token["is_active"], true);
+
+    let resp = app
+        .request(admin_request(
+            "GET",
+            &format!("/admin/tokens/{}", TOKEN),
+            None,
+        ))
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(json_body(resp).await["token_name"], "Ether");
+
+    // A token can only be registered once.
+    let resp = app
+        .request(admin_request("POST", "/admin/tokens", Some(ether())))
+        .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_concurrent_token_registrations() {
+    let app = TestApp::new().await;
+
+    // Both requests may pass the registry check before either inserts.
+    let (a, b) = tokio::join!(
+        app.request(admin_request("POST", "/admin/tokens", Some(ether()))),
+        app.request(admin_request("POST", "/admin/tokens", Some(ether()))),
+    );
+    let mut statuses = vec![a.status().as_u16(), b.status().as_u16()];
+    statuses.sort();
+    assert_eq!(statuses, vec![200, 400]);
+    let rejected = match a.status() {
+        StatusCode::OK => b,
+        _ => a,
+    };
+    assert_eq!(
+        json_body(rejected).await["message"],
+        "Invalid request: Token is already registered"
+    );
+}
+
+#[tokio::test]
+async fn test_add_token_reads_metadata_from_chain() {
+    let app = TestApp::new().await;
+    app.rpc.on("starknet_call", |params| {
+        let selector: Felt =
+            serde_json::from_value(params["request"]["entry_point_selector"].clone()).unwrap();
+        match selector {
+            // ByteArray "Ether"
+            s if s == selector!("name") => Ok(json!(["0x0", "0x4574686572", "0x5"])),
+            // Short string "ETH"
+            s if s == selector!("symbol") => Ok(json!(["0x455448"])),
+            s if s == selector!("decimals") => Ok(json!(["0x12"])),
+            _ => Err(json!({ "code": 21, "message": "Invalid message selector" })),
+        }
+    });
+
+    let resp = app
+        .request(admin_request(
+            "POST",
+            "/admin/tokens",
+            Some(json!({ "contract_address": TOKEN })),
+        ))
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let token = json_body(resp).await;
+    assert_eq!(token["token_name"], "Ether");
+    assert_eq!(token["token_symbol"], "ETH");
+    assert_eq!(token["token_decimals"], 18);
+}
+
+#[tokio::test]
+async fn test_update_token() {
+    let app = TestApp::new().await;
+    app.request(admin_request("POST", "/admin/tokens", Some(ether())))
+        .await;
+
+    let uri = format!("/admin/tokens/{}", TOKEN);
+    let resp = app
+        .request(admin_request(
+            "PATCH",
+            &uri,
+            Some(json!({ "token_name": "Wrapped Ether", "is_stable": false })),
+        ))
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let token = json_body(resp).await;
+    assert_eq!(token["token_name"], "Wrapped Ether");
+    assert_eq!(token["token_symbol"], "ETH");
+
+    let resp = app
+        .request(admin_request(
+            "PATCH",
+            &uri,
+            Some(json!({ "token_decimals": 40 })),
+        ))
+        .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_disabled_token_is_hidden_and_rejected() {
+    let app = TestApp::new().await;
+    app.request(admin_request("POST", "/admin/tokens", Some(ether())))
+        .await;
+
+    let resp = app
+        .request(admin_request(
+            "DELETE",
+            &format!("/admin/tokens/{}", TOKEN),
+            None,
+        ))
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(json_body(resp).await["is_active"], false);
+
+    let resp = app
+        .request(admin_request("GET", "/admin/tokens", None))
+        .await;
+    assert_eq!(json_body(resp).await, json!([]));
+
+    let resp = app
+        .request(admin_request(
+            "GET",
+            "/admin/tokens?include_disabled=true",
+            None,
+        ))
+        .await;
+    assert_eq!(json_body(resp).await.as_array().unwrap().len(), 1);
+
+    let resp = app
+        .request(admin_request(
+            "GET",
+            "/admin/tokens/0x0000000000000000000000000000000000000000000000000000000000000abc",
+            None,
+        ))
+        .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_token_price_feed() {
+    let app = TestApp::new().await;
+    app.request(admin_request("POST", "/admin/tokens", Some(ether())))
+        .await;
+
+    let uri = format!("/admin/tokens/{}", TOKEN);
+    let resp = app.request(admin_request("GET", &uri, None)).await;
+    assert_eq!(json_body(resp).await["price_feed"], Value::Null);
+
+    for feed in ["eth", "strk", "pragma:ETH/USD"] {
+        let resp = app
+            .request(admin_request(
+                "PATCH",
+                &uri,
+                Some(json!({ "price_feed": feed })),
+            ))
+            .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(json_body(resp).await["price_feed"], feed);
+    }
+
+    for feed in ["usd", "pragma:", "chainlink:ETH/USD"] {
+        let resp = app
+            .request(admin_request(
+                "PATCH",
+                &uri,
+                Some(json!({ "price_feed": feed })),
+            ))
+            .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,10 +1,11 @@
-use crate::helpers::TestApp;
-use autoswappr_backend::service::transaction_logs::log_transaction;
+use crate::helpers::{register_token, TestApp};
+use autoswappr_backend::{service::transaction_logs::log_transaction, utils::amount::Amount};
 use axum::{
     body::{to_bytes, Body},
     http::{Request, StatusCode},
 };
 use serde_json::json;
+use starknet::core::types::U256;
 
 #[tokio::test]
 async fn test_transaction_log_service_with_valid_payload() {
@@ -12,10 +13,12 @@ async fn test_transaction_log_service_with_valid_payload() {
     let from_token = "0x07ab8059db97aab8ced83b37a1d60b8eef540f6cdc96acc153d583a59bedd125";
     let to_token = "0x40ca979f20ed76f960dc719457eaf0cef3b2c3932d58435b9192a58bc56c1e40";
     let percentage = 50;
-    let amount_from = 4000;
-    let amount_to = 2000;
+    let amount_from = U256::from(4000u128);
+    let amount_to = U256::from(2000u128);
 
     let app = TestApp::new().await;
+    register_token(&app.db.pool, from_token).await;
+    register_token(&app.db.pool, to_token).await;
     let tx = log_transaction(
         address,
         from_token,
@@ -31,8 +34,8 @@ async fn test_transaction_log_service_with_valid_payload() {
     assert_eq!(tx.from_token, from_token);
     assert_eq!(tx.to_token, to_token);
     assert_eq!(tx.percentage, percentage);
-    assert_eq!(tx.amount_from, amount_from);
-    assert_eq!(tx.amount_to, amount_to);
+    assert_eq!(tx.amount_from, Amount::from_raw(amount_from, 18));
+    assert_eq!(tx.amount_to, Amount::from_raw(amount_to, 18));
 }
 
 #[tokio::test]
@@ -41,8 +44,8 @@ async fn test_transaction_log_service_with_invalid_address() {
     let from_token = "0xF1d2eD1a7d9A2aE3c467Bc2Cojojoj5dF";
     let to_token = "0xF1d2eD1a7d9A2aE3c467Bc72C5iohhosdF";
     let percentage = 50;
-    let amount_from = 4000;
-    let amount_to = 2000;
+    let amount_from = U256::from(4000u128);
+    let amount_to = U256::from(2000u128);
 
     let app = TestApp::new().await;
     let result = log_transaction(
@@ -59,9 +62,44 @@ async fn test_transaction_log_service_with_invalid_address() {
     assert!(result.is_err())
 }
 
+#[tokio::test]
+async fn test_transaction_log_service_with_unregistered_token() {
+    let address = "0xdbfcab49bd9bced4636b04319d71fbd0d84bde78a1d38e9e2fc391e83187c1c3";
+    let from_token = "0x07ab8059db97aab8ced83b37a1d60b8eef540f6cdc96acc153d583a59bedd125";
+    let to_token = "0x40ca979f20ed76f960dc719457eaf0cef3b2c3932d58435b9192a58bc56c1e40";
+
+    let app = TestApp::new().await;
+    register_token(&app.db.pool, from_token).await;
+    let result = log_transaction(
+        address,
+        from_token,
+        to_token,
+        50,
+        U256::from(4000u128),
+        U256::from(2000u128),
+        &app.db.pool,
+    )
+    .await;
+
+    assert_eq!(
+        result.unwrap_err(),
+        format!("Unregistered token: {}", to_token)
+    );
+}
+
 #[tokio::test]
 async fn test_transaction_log_request_with_valid_payload() {
     let app = TestApp::new().await;
+    register_token(
+        &app.db.pool,
+        "0x07ab8059db97aab8ced83b37a1d60b8eef540f6cdc96acc153d583a59bedd125",
+    )
+    .await;
+    register_token(
+        &app.db.pool,
+        "0x40ca979f20ed76f960dc719457eaf0cef3b2c3932d58435b9192a58bc56c1e40",
+    )
+    .await;
     let response = app
         .request(
             Request::builder()
@@ -117,3 +155,59 @@ async fn test_transaction_log_request_with_invalid_payload() {
 
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 }
+
+#[tokio::test]
+async fn test_transaction_log_round_trips_max_u256() {
+    // 2^256 - 1
+    let max = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+    let wallet = "0xdbfcab49bd9bced4636b04319d71fbd0d84bde78a1d38e9e2fc391e83187c1c3";
+    let from_token = "0x07ab8059db97aab8ced83b37a1d60b8eef540f6cdc96acc153d583a59bedd125";
+    let to_token = "0x40ca979f20ed76f960dc719457eaf0cef3b2c3932d58435b9192a58bc56c1e40";
+
+    let app = TestApp::new().await;
+    register_token(&app.db.pool, from_token).await;
+    register_token(&app.db.pool, to_token).await;
+    let response = app
+        .request(
+            Request::builder()
+                .method("POST")
+                .uri("/log_transaction")
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    json!({
+                        "wallet_address": wallet,
+                        "from_token": from_token,
+                        "to_token": to_token,
+                        "percentage": 100,
+                        "amount_from": max,
+                        "amount_to": "1"
+                    })
+                    .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let stored = sqlx::query_scalar::<_, String>("SELECT amount_from::TEXT FROM transactions_log")
+        .fetch_one(&app.db.pool)
+        .await
+        .unwrap();
+    assert_eq!(stored, max);
+
+    let response = app
+        .request(
+            Request::get(format!(
+                "/log_retrieval?wallet_address={}&amount_to=1",
+                wallet
+            ))
+            .body(Body::empty())
+            .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), 1024 * 16).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["data"][0]["amount_from"], max);
+    assert_eq!(json["data"][0]["amount_to"], "1");
+}
@@ -0,0 +1,500 @@
+use anyhow::{anyhow, Result};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use serde_json::{json, Value};
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use uuid::Uuid;
+
+use autoswappr_backend::{
+    service::{
+        auto_swap::TransferOutcome,
+        pricing::{PriceSource, UsdPrice},
+        swap_jobs::find,
+        tokens::Token,
+        triggers::{evaluate, PriceTrigger, TriggerCondition, TriggerOutcome},
+    },
+    worker::TriggerEvaluator,
+};
+
+use crate::helpers::*;
+
+const WALLET: &str = "0xdbfcab49bd9bced4636b04319d71fbd0d84bde78a1d38e9e2fc391e83187c1c3";
+const FROM_TOKEN: &str = "0x053c91253bc9682c04929ca02ed00b3e423f6710d2ee7e0d5ebb06f3ecf368a8";
+const OTHER_TOKEN: &str = "0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d";
+const TO_TOKEN: &str = "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
+
+// Prices set by the test, by token address. Tokens set to `None` fail to be
+// priced, tokens that are not set have no price.
+#[derive(Default)]
+struct FakePrices {
+    prices: Mutex<HashMap<String, Option<UsdPrice>>>,
+}
+
+impl FakePrices {
+    fn set(&self, token: &str, price: Option<u128>) {
+        let price = price.map(|price| UsdPrice { price, decimals: 2 });
+        self.prices
+            .lock()
+            .unwrap()
+            .insert(token.to_lowercase(), price);
+    }
+}
+
+impl PriceSource for &FakePrices {
+    async fn usd_price(&self, token: &Token) -> Result<Option<UsdPrice>> {
+        match self
+            .prices
+            .lock()
+            .unwrap()
+            .get(&token.contract_address.to_lowercase())
+        {
+            Some(Some(price)) => Ok(Some(*price)),
+            Some(None) => Err(anyhow!("Oracle unavailable")),
+            None => Ok(None),
+        }
+    }
+}
+
+fn at(time: &str) -> OffsetDateTime {
+    OffsetDateTime::parse(time, &Rfc3339).unwrap()
+}
+
+// A price in cents.
+fn cents(price: u128) -> UsdPrice {
+    UsdPrice { price, decimals: 2 }
+}
+
+fn trigger(condition: TriggerCondition, price: &str, expires_at: Option<&str>) -> PriceTrigger {
+    PriceTrigger {
+        condition,
+        price: UsdPrice::parse(price).unwrap(),
+        expires_at: expires_at.map(at),
+    }
+}
+
+// Index of the first price of `prices` that fires `trigger`, evaluated a
+// minute apart from 10:00.
+fn first_fired(trigger: &PriceTrigger, prices: &[Option<u128>]) -> Option<usize> {
+    prices.iter().enumerate().position(|(i, price)| {
+        let now = at("2024-12-24T10:00:00Z") + time::Duration::minutes(i as i64);
+        evaluate(trigger, price.map(cents), now) == TriggerOutcome::Fired
+    })
+}
+
+#[test]
+fn test_above_trigger_fires_at_threshold() {
+    let trigger = trigger(TriggerCondition::Above, "0.55", None);
+    assert_eq!(
+        first_fired(&trigger, &[Some(50), Some(54), Some(55), Some(60)]),
+        Some(2)
+    );
+    assert_eq!(first_fired(&trigger, &[Some(40), Some(62)]), Some(1));
+    assert_eq!(first_fired(&trigger, &[Some(54), Some(30)]), None);
+}
+
+#[test]
+fn test_below_trigger_fires_at_threshold() {
+    let trigger = trigger(TriggerCondition::Below, "0.55", None);
+    assert_eq!(
+        first_fired(&trigger, &[Some(70), Some(56), Some(55), Some(50)]),
+        Some(2)
+    );
+    assert_eq!(first_fired(&trigger, &[Some(54)]), Some(0));
+    assert_eq!(first_fired(&trigger, &[Some(56), Some(90)]), None);
+}
+
+#[test]
+fn test_trigger_waits_without_price() {
+    let trigger = trigger(TriggerCondition::Above, "1", None);
+    let now = at("2024-12-24T10:00:00Z");
+    assert_eq!(evaluate(&trigger, None, now), TriggerOutcome::Unpriced);
+    assert_eq!(
+        evaluate(&trigger, Some(cents(99)), now),
+        TriggerOutcome::Waiting
+    );
+    assert_eq!(
+        first_fired(&trigger, &[None, Some(90), None, Some(100)]),
+        Some(3)
+    );
+}
+
+#[test]
+fn test_trigger_expires_before_firing() {
+    let trigger = trigger(
+        TriggerCondition::Above,
+        "0.55",
+        Some("2024-12-24T10:02:00Z"),
+    );
+    // Met at 10:03, once expired.
+    assert_eq!(
+        first_fired(&trigger, &[Some(50), Some(50), Some(50), Some(60)]),
+        None
+    );
+    assert_eq!(
+        evaluate(&trigger, Some(cents(60)), at("2024-12-24T10:02:00Z")),
+        TriggerOutcome::Expired
+    );
+    assert_eq!(
+        evaluate(&trigger, Some(cents(60)), at("2024-12-24T10:01:59Z")),
+        TriggerOutcome::Fired
+    );
+}
+
+#[test]
+fn test_trigger_compares_prices_across_decimals() {
+    let trigger = trigger(TriggerCondition::Above, "2500.5", None);
+    let now = at("2024-12-24T10:00:00Z");
+    let price = |price, decimals| Some(UsdPrice { price, decimals });
+    assert_eq!(
+        evaluate(&trigger, price(250_050_000_000, 8), now),
+        TriggerOutcome::Fired
+    );
+    assert_eq!(
+        evaluate(&trigger, price(250_049_999_999, 8), now),
+        TriggerOutcome::Waiting
+    );
+    assert_eq!(
+        evaluate(&trigger, price(2501, 0), now),
+        TriggerOutcome::Fired
+    );
+}
+
+fn evaluator<'a>(app: &TestApp, prices: &'a FakePrices) -> TriggerEvaluator<&'a FakePrices> {
+    TriggerEvaluator::new(prices, app.db.clone(), 5, Duration::from_secs(30))
+}
+
+async fn send(app: &TestApp, req: Request<Body>) -> (StatusCode, Value) {
+    let resp = app.request(req).await;
+    let status = resp.status();
+    let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+async fn get(app: &TestApp, uri: &str) -> (StatusCode, Value) {
+    send(app, Request::get(uri).body(Body::empty()).unwrap()).await
+}
+
+// Subscribe the wallet to swap all of FROM_TOKEN and OTHER_TOKEN with the given
+// triggers.
+async fn subscribe(app: &TestApp, triggers: Value) -> StatusCode {
+    for token in [FROM_TOKEN, OTHER_TOKEN, TO_TOKEN] {
+        register_token(&app.db.pool, token).await;
+    }
+    let payload = json!({
+        "wallet_address": WALLET,
+        "to_token": TO_TOKEN,
+        "from_token": [FROM_TOKEN, OTHER_TOKEN],
+        "percentage": [100, 100],
+        "triggers": triggers
+    });
+    send(
+        app,
+        signed_request("POST", "/subscriptions", WALLET, &payload),
+    )
+    .await
+    .0
+}
+
+// Receive 2 tokens of `token`, returning what became of the single leg.
+async fn receive(app: &TestApp, token: &str) -> TransferOutcome {
+    let legs = receive_transfer(&app.db.pool, WALLET, token, 2_000_000_000_000_000_000).await;
+    assert_eq!(legs.len(), 1);
+    legs[0].outcome
+}
+
+async fn triggered_swaps(app: &TestApp) -> Vec<Value> {
+    let (status, json) = get(app, &format!("/triggered_swaps?wallet_address={}", WALLET)).await;
+    assert_eq!(status, StatusCode::OK);
+    json["data"].as_array().unwrap().clone()
+}
+
+#[tokio::test]
+async fn test_subscription_trigger_is_stored() {
+    let app = TestApp::new().await;
+    let status = subscribe(
+        &app,
+        json!([
+            {"condition": "above", "price_usd": "0.55", "expires_at": "2024-12-31T12:00:00+01:00"},
+            null
+        ]),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, json) = get(&app, &format!("/subscriptions?wallet_address={}", WALLET)).await;
+    assert_eq!(status, StatusCode::OK);
+    let data = json["data"].as_array().unwrap();
+    let from = |token: &str| {
+        data.iter()
+            .find(|row| row["from_token"] == token)
+            .unwrap()
+            .clone()
+    };
+    let triggered = from(FROM_TOKEN);
+    assert_eq!(triggered["trigger_condition"], "above");
+    assert_eq!(triggered["trigger_price_usd"], "0.55");
+    assert_eq!(triggered["trigger_expires_at"], "2024-12-31T11:00:00Z");
+    let plain = from(OTHER_TOKEN);
+    assert_eq!(plain["trigger_condition"], Value::Null);
+    assert_eq!(plain["trigger_price_usd"], Value::Null);
+}
+
+#[tokio::test]
+async fn test_subscription_rejects_invalid_triggers() {
+    let app = TestApp::new().await;
+    for triggers in [
+        json!([{"condition": "above", "price_usd": "0.55"}]),
+        json!([{"condition": "above", "price_usd": "0"}, null]),
+        json!([{"condition": "above", "price_usd": "-1"}, null]),
+        json!([{"condition": "above", "price_usd": "abc"}, null]),
+        json!([{"condition": "sideways", "price_usd": "1"}, null]),
+        json!([{"condition": "below", "price_usd": "1", "expires_at": "tomorrow"}, null]),
+    ] {
+        assert_eq!(
+            subscribe(&app, triggers.clone()).await,
+            StatusCode::BAD_REQUEST,
+            "{}",
+            triggers
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_triggered_swap_fires_once_price_is_met() {
+    let app = TestApp::new().await;
+    let prices = FakePrices::default();
+    let evaluator = evaluator(&app, &prices);
+    subscribe(
+        &app,
+        json!([{"condition": "below", "price_usd": "0.50"}, null]),
+    )
+    .await;
+
+    // Transfers of a token without a trigger are queued right away.
+    assert!(matches!(
+        receive(&app, OTHER_TOKEN).await,
+        TransferOutcome::Queued(_)
+    ));
+
+    let id = match receive(&app, FROM_TOKEN).await {
+        TransferOutcome::Held(id) => id.to_string(),
+        outcome => panic!("Expected a held swap, got {:?}", outcome),
+    };
+
+    // Unpriced, then above the threshold, then failing to be priced.
+    assert_eq!(
+        evaluator.check(at("2024-12-24T10:00:00Z")).await.unwrap(),
+        0
+    );
+    prices.set(FROM_TOKEN, Some(51));
+    assert_eq!(
+        evaluator.check(at("2024-12-24T10:01:00Z")).await.unwrap(),
+        0
+    );
+    prices.set(FROM_TOKEN, None);
+    assert_eq!(
+        evaluator.check(at("2024-12-24T10:02:00Z")).await.unwrap(),
+        0
+    );
+    prices.set(FROM_TOKEN, Some(49));
+    assert_eq!(
+        evaluator.check(at("2024-12-24T10:03:00Z")).await.unwrap(),
+        1
+    );
+    // Fired swaps are no longer evaluated.
+    assert_eq!(
+        evaluator.check(at("2024-12-24T10:04:00Z")).await.unwrap(),
+        0
+    );
+
+    let swaps = triggered_swaps(&app).await;
+    assert_eq!(swaps.len(), 1);
+    assert_eq!(swaps[0]["id"], id.as_str());
+    assert_eq!(swaps[0]["status"], "fired");
+    assert_eq!(swaps[0]["amount"], "2000000000000000000");
+    let job_id = Uuid::parse_str(swaps[0]["swap_job_id"].as_str().unwrap()).unwrap();
+    let mut conn = app.db.pool.acquire().await.unwrap();
+    let job = find(&mut conn, job_id).await.unwrap().unwrap();
+    assert_eq!(job.from_token, FROM_TOKEN);
+    assert_eq!(job.amount, "2000000000000000000");
+
+    let (status, json) = get(&app, &format!("/triggered_swaps/{}/evaluations", id)).await;
+    assert_eq!(status, StatusCode::OK);
+    let evaluations: Vec<(Value, Value)> = json["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|evaluation| {
+            (
+                evaluation["outcome"].clone(),
+                evaluation["price_usd"].clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        evaluations,
+        vec![
+            (json!("unpriced"), Value::Null),
+            (json!("waiting"), json!("0.51")),
+            (json!("unpriced"), Value::Null),
+            (json!("fired"), json!("0.49")),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_only_changed_evaluations_are_logged() {
+    let app = TestApp::new().await;
+    let prices = FakePrices::default();
+    let evaluator = evaluator(&app, &prices);
+    subscribe(
+        &app,
+        json!([{"condition": "below", "price_usd": "0.50"}, null]),
+    )
+    .await;
+    let id = match receive(&app, FROM_TOKEN).await {
+        TransferOutcome::Held(id) => id.to_string(),
+        outcome => panic!("Expected a held swap, got {:?}", outcome),
+    };
+
+    // A swap that keeps waiting is logged once, whatever the price.
+    for (minute, price) in [(0, Some(60)), (1, Some(55)), (2, Some(70)), (3, None)] {
+        prices.set(FROM_TOKEN, price);
+        let now = at(&format!("2024-12-24T10:0{}:00Z", minute));
+        assert_eq!(evaluator.check(now).await.unwrap(), 0);
+    }
+    prices.set(FROM_TOKEN, Some(65));
+    assert_eq!(
+        evaluator.check(at("2024-12-24T10:04:00Z")).await.unwrap(),
+        0
+    );
+
+    let (_, json) = get(&app, &format!("/triggered_swaps/{}/evaluations", id)).await;
+    let evaluations: Vec<(Value, Value)> = json["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|evaluation| {
+            (
+                evaluation["outcome"].clone(),
+                evaluation["price_usd"].clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        evaluations,
+        vec![
+            (json!("waiting"), json!("0.6")),
+            (json!("unpriced"), Value::Null),
+            (json!("waiting"), json!("0.65")),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_every_waiting_swap_is_evaluated() {
+    let app = TestApp::new().await;
+    let prices = FakePrices::default();
+    let evaluator = evaluator(&app, &prices);
+    register_token(&app.db.pool, FROM_TOKEN).await;
+
+    // More swaps than one batch holds keep waiting in front of the newest one,
+    // whose trigger is met.
+    let hold = |condition: &'static str, price_usd: &'static str, count: i32| {
+        sqlx::query(
+            r#"
+            INSERT INTO triggered_swap
+            (wallet_address, from_token, to_token, percentage, amount, from_decimals,
+             max_slippage_bps, venue, condition, price_usd, created_at)
+            SELECT $1, $2, $3, 100, 1000, 18, 50, 'best_price', $4::trigger_condition,
+                   $5::NUMERIC, NOW() - MAKE_INTERVAL(secs => $6 - i)
+            FROM generate_series(1, $6) AS i
+            "#,
+        )
+        .bind(WALLET)
+        .bind(FROM_TOKEN)
+        .bind(TO_TOKEN)
+        .bind(condition)
+        .bind(price_usd)
+        .bind(count)
+        .execute(&app.db.pool)
+    };
+    hold("below", "0.40", 600).await.unwrap();
+    hold("above", "0.50", 1).await.unwrap();
+
+    prices.set(FROM_TOKEN, Some(55));
+    assert_eq!(
+        evaluator.check(at("2024-12-24T10:00:00Z")).await.unwrap(),
+        1
+    );
+    let evaluated: i64 =
+        sqlx::query_scalar("SELECT COUNT(DISTINCT triggered_swap_id) FROM trigger_evaluation")
+            .fetch_one(&app.db.pool)
+            .await
+            .unwrap();
+    assert_eq!(evaluated, 601);
+}
+
+#[tokio::test]
+async fn test_triggered_swap_expires() {
+    let app = TestApp::new().await;
+    let prices = FakePrices::default();
+    let evaluator = evaluator(&app, &prices);
+    subscribe(
+        &app,
+        json!([
+            {"condition": "above", "price_usd": "1", "expires_at": "2024-12-24T10:01:00Z"},
+            null
+        ]),
+    )
+    .await;
+    let id = match receive(&app, FROM_TOKEN).await {
+        TransferOutcome::Held(id) => id.to_string(),
+        outcome => panic!("Expected a held swap, got {:?}", outcome),
+    };
+
+    prices.set(FROM_TOKEN, Some(90));
+    assert_eq!(
+        evaluator.check(at("2024-12-24T10:00:00Z")).await.unwrap(),
+        0
+    );
+    // Met, but too late.
+    prices.set(FROM_TOKEN, Some(120));
+    assert_eq!(
+        evaluator.check(at("2024-12-24T10:01:00Z")).await.unwrap(),
+        0
+    );
+    assert_eq!(
+        evaluator.check(at("2024-12-24T10:02:00Z")).await.unwrap(),
+        0
+    );
+
+    let swaps = triggered_swaps(&app).await;
+    assert_eq!(swaps[0]["status"], "expired");
+    assert_eq!(swaps[0]["swap_job_id"], Value::Null);
+
+    let (_, json) = get(&app, &format!("/triggered_swaps/{}/evaluations", id)).await;
+    let outcomes: Vec<&str> = json["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|evaluation| evaluation["outcome"].as_str().unwrap())
+        .collect();
+    assert_eq!(outcomes, vec!["waiting", "expired"]);
+}
+
+#[tokio::test]
+async fn test_triggered_swap_endpoints_validate_input() {
+    let app = TestApp::new().await;
+    let (status, _) = get(&app, "/triggered_swaps?wallet_address=0x123").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get(
+        &app,
+        &format!("/triggered_swaps/{}/evaluations", Uuid::now_v7()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -1,8 +1,5 @@
-use crate::helpers::TestApp;
-use axum::{
-    body::{to_bytes, Body},
-    http::{Request, StatusCode},
-};
+use crate::helpers::{signed_request, TestApp};
+use axum::{body::to_bytes, http::StatusCode};
 use serde_json::json;
 
 #[tokio::test]
@@ -37,21 +34,17 @@ async fn test_unsubscribe_success() {
     .await
     .expect("Failed to insert test from_token");
 
+    let payload = json!({
+        "wallet_address": "0xdbfcab49bd9bced4636b04319d71fbd0d84bde78a1d38e9e2fc391e83187c1c3",
+        "from_token": "0x07ab8059db97aab8ced83b37a1d60b8eef540f6cdc96acc153d583a59bedd125"
+    });
     let response = app
-        .request(
-            Request::builder()
-                .method("POST")
-                .uri("/unsubscribe")
-                .header("Content-Type", "application/json")
-                .body(Body::from(
-                    json!({
-                        "wallet_address": "0xdbfcab49bd9bced4636b04319d71fbd0d84bde78a1d38e9e2fc391e83187c1c3",
-                        "from_token": "0x07ab8059db97aab8ced83b37a1d60b8eef540f6cdc96acc153d583a59bedd125"
-                    })
-                    .to_string(),
-                ))
-                .unwrap(),
-        )
+        .request(signed_request(
+            "POST",
+            "/unsubscribe",
+            payload["wallet_address"].as_str().unwrap(),
+            &payload,
+        ))
         .await;
 
     assert_eq!(response.status(), StatusCode::OK);
@@ -66,21 +59,17 @@ async fn test_unsubscribe_success() {
 async fn test_unsubscribe_invalid_wallet() {
     let app = TestApp::new().await;
 
+    let payload = json!({
+        "wallet_address": "invalid_wallet",
+        "from_token": "0x07ab8059db97aab8ced83b37a1d60b8eef540f6cdc96acc153d583a59bedd125"
+    });
     let response = app
-        .request(
-            Request::builder()
-                .method("POST")
-                .uri("/unsubscribe")
-                .header("Content-Type", "application/json")
-                .body(Body::from(
-                    json!({
-                        "wallet_address": "invalid_wallet",
-                        "from_token": "0x07ab8059db97aab8ced83b37a1d60b8eef540f6cdc96acc153d583a59bedd125"
-                    })
-                    .to_string(),
-                ))
-                .unwrap(),
-        )
+        .request(signed_request(
+            "POST",
+            "/unsubscribe",
+            payload["wallet_address"].as_str().unwrap(),
+            &payload,
+        ))
         .await;
 
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
@@ -90,21 +79,17 @@ async fn test_unsubscribe_invalid_wallet() {
 async fn test_unsubscribe_invalid_token() {
     let app = TestApp::new().await;
 
+    let payload = json!({
+        "wallet_address": "0x07ab8059db97aab8ced83b37a1d60b8eef540f6cdc96acc153d583a59bedd125",
+        "from_token": "invalid_token"
+    });
     let response = app
-        .request(
-            Request::builder()
-                .method("POST")
-                .uri("/unsubscribe")
-                .header("Content-Type", "application/json")
-                .body(Body::from(
-                    json!({
-                        "wallet_address": "0x07ab8059db97aab8ced83b37a1d60b8eef540f6cdc96acc153d583a59bedd125",
-                        "from_token": "invalid_token"
-                    })
-                    .to_string(),
-                ))
-                .unwrap(),
-        )
+        .request(signed_request(
+            "POST",
+            "/unsubscribe",
+            payload["wallet_address"].as_str().unwrap(),
+            &payload,
+        ))
         .await;
 
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
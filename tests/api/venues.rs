@@ -0,0 +1,239 @@
+use starknet::{
+    core::types::{Felt, U256},
+    macros::selector,
+};
+
+use autoswappr_backend::{
+    service::{
+        quote::{QuoteParams, VenueQuote},
+        slippage::SlippageLimits,
+        venues::{AvnuVenue, EkuboVenue, SwapVenue, VenueRegistry, BEST_PRICE},
+    },
+    utils::{
+        amount::Amount,
+        avnu::{AvnuClient, Route},
+        ekubo::{sqrt_ratio_limit, PoolKey, DEFAULT_POOL_TIERS},
+    },
+};
+
+use crate::helpers::*;
+
+const FROM_TOKEN: &str = "0x4718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d";
+const TO_TOKEN: &str = "0x53c91253bc9682c04929ca02ed00b3e423f6710d2ee7e0d5ebb06f3ecf368a8";
+const ROUTER: &str = "0x1234";
+const WALLET: &str = "0x5678";
+
+fn params() -> QuoteParams {
+    QuoteParams {
+        from_token: Felt::from_hex(FROM_TOKEN).unwrap(),
+        to_token: Felt::from_hex(TO_TOKEN).unwrap(),
+        amount: Amount::from_raw(U256::from(1_000_000u128), 6),
+        contract_address: Felt::from_hex(ROUTER).unwrap(),
+        funded_by: None,
+    }
+}
+
+fn quote(venue: &str, pool_key: Option<PoolKey>, routes: Option<Vec<Route>>) -> VenueQuote {
+    VenueQuote {
+        venue: venue.to_string(),
+        amount_out: 2_000_000u128.into(),
+        price_impact_bps: Some(10),
+        gas_fee: 100u128.into(),
+        fee_unit: "FRI".to_string(),
+        pool_key,
+        routes,
+        reference_in: 1000u128.into(),
+        reference_out: 2000u128.into(),
+    }
+}
+
+fn registry(app: &TestApp) -> VenueRegistry {
+    let mut venues = VenueRegistry::default();
+    venues.register(EkuboVenue::new(app.db.clone(), vec![]));
+    venues.register(AvnuVenue::new(AvnuClient::new("http://localhost")));
+    venues
+}
+
+fn names(venues: &[std::sync::Arc<dyn SwapVenue>]) -> Vec<&'static str> {
+    venues.iter().map(|venue| venue.name()).collect()
+}
+
+#[tokio::test]
+async fn test_registry_preference() {
+    let app = TestApp::new().await;
+    let venues = registry(&app);
+
+    assert_eq!(names(venues.all()), vec!["ekubo", "avnu"]);
+    assert!(venues.accepts(BEST_PRICE));
+    assert!(venues.accepts("avnu"));
+    assert!(!venues.accepts("uniswap"));
+
+    assert_eq!(names(&venues.preferred(BEST_PRICE)), vec!["ekubo", "avnu"]);
+    assert_eq!(names(&venues.preferred("avnu")), vec!["avnu"]);
+    assert!(venues.preferred("uniswap").is_empty());
+}
+
+#[tokio::test]
+async fn test_register_replaces_venue_of_same_name() {
+    let app = TestApp::new().await;
+    let mut venues = registry(&app);
+    venues.register(EkuboVenue::new(app.db.clone(), vec![]));
+
+    assert_eq!(names(venues.all()), vec!["avnu", "ekubo"]);
+}
+
+#[tokio::test]
+async fn test_ekubo_calls_are_bounded_by_slippage() {
+    let app = TestApp::new().await;
+    let venue = EkuboVenue::new(app.db.clone(), vec![]);
+    let params = params();
+    let pool_key = PoolKey::new(
+        params.from_token,
+        params.to_token,
+        &DEFAULT_POOL_TIERS.parse().unwrap(),
+    );
+    let is_token1 = pool_key.is_token1(params.from_token);
+    let avnu_quote = quote("avnu", None, Some(vec![]));
+    let quote = quote("ekubo", Some(pool_key), None);
+    let limits = SlippageLimits::from_quote(&quote, 100).unwrap();
+
+    let calls = venue
+        .build_calls(Felt::from_hex(WALLET).unwrap(), &params, &quote, &limits)
+        .unwrap();
+
+    let limit = sqrt_ratio_limit(quote.reference_in, quote.reference_out, 100, is_token1);
+    let swap = &calls[1].calldata;
+    assert_eq!(swap[2], Felt::from(u8::from(is_token1)));
+    assert_eq!(swap[3], Felt::from(limit.low()));
+    assert_eq!(swap[4], Felt::from(limit.high()));
+
+    // A quote from another venue has no pool to swap in.
+    let err = venue
+        .build_calls(
+            Felt::from_hex(WALLET).unwrap(),
+            &params,
+            &avnu_quote,
+            &limits,
+        )
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Quote has no Ekubo pool");
+}
+
+#[tokio::test]
+async fn test_avnu_calls_have_minimum_output() {
+    let venue = AvnuVenue::new(AvnuClient::new("http://localhost"));
+    let params = params();
+    let quote = quote("avnu", None, Some(vec![]));
+    let limits = SlippageLimits::from_quote(&quote, 100).unwrap();
+
+    let calls = venue
+        .build_calls(Felt::from_hex(WALLET).unwrap(), &params, &quote, &limits)
+        .unwrap();
+
+    let swap = &calls[1].calldata;
+    assert_eq!(swap[4], Felt::from(2_000_000u128));
+    assert_eq!(swap[6], Felt::from(1_980_000u128));
+}
+
+#[tokio::test]
+async fn test_funded_calls_spend_the_wallet_tokens() {
+    let app = TestApp::new().await;
+    let venue = EkuboVenue::new(app.db.clone(), vec![]);
+    let account = Felt::from_hex("0x9abc").unwrap();
+    let wallet = Felt::from_hex(WALLET).unwrap();
+    let params = QuoteParams {
+        funded_by: Some(wallet),
+        ..params()
+    };
+    let pool_key = PoolKey::new(
+        params.from_token,
+        params.to_token,
+        &DEFAULT_POOL_TIERS.parse().unwrap(),
+    );
+    let quote = quote("ekubo", Some(pool_key), None);
+    let limits = SlippageLimits::from_quote(&quote, 100).unwrap();
+
+    let calls = venue
+        .build_calls(account, &params, &quote, &limits)
+        .unwrap();
+    let calls = params.funded(account, params.amount, calls);
+
+    // The wallet's tokens are pulled into the account before it pays the router.
+    assert_eq!(calls.len(), 4);
+    assert_eq!(calls[0].to, params.from_token);
+    assert_eq!(calls[0].selector, selector!("transferFrom"));
+    assert_eq!(
+        calls[0].calldata,
+        vec![wallet, account, Felt::from(1_000_000u128), Felt::ZERO]
+    );
+    assert_eq!(calls[1].selector, selector!("transfer"));
+    assert_eq!(
+        calls[1].calldata,
+        vec![
+            params.contract_address,
+            Felt::from(1_000_000u128),
+            Felt::ZERO
+        ]
+    );
+
+    // Swaps of the account's own tokens pull nothing.
+    let own = QuoteParams {
+        funded_by: None,
+        ..params
+    };
+    assert!(own.funded(account, own.amount, vec![]).is_empty());
+}
+
+#[tokio::test]
+async fn test_funded_swaps_pay_the_wallet() {
+    let app = TestApp::new().await;
+    let account = Felt::from_hex("0x9abc").unwrap();
+    let wallet = Felt::from_hex(WALLET).unwrap();
+    let params = QuoteParams {
+        funded_by: Some(wallet),
+        ..params()
+    };
+
+    // AVNU pays the wallet directly.
+    let avnu = AvnuVenue::new(AvnuClient::new("http://localhost"));
+    let quote_avnu = quote("avnu", None, Some(vec![]));
+    let limits = SlippageLimits::from_quote(&quote_avnu, 100).unwrap();
+    let calls = avnu
+        .build_calls(account, &params, &quote_avnu, &limits)
+        .unwrap();
+    assert_eq!(calls[1].calldata[8], wallet);
+
+    // The Ekubo router pays the account, which sends the least output on.
+    let ekubo = EkuboVenue::new(app.db.clone(), vec![]);
+    let pool_key = PoolKey::new(
+        params.from_token,
+        params.to_token,
+        &DEFAULT_POOL_TIERS.parse().unwrap(),
+    );
+    let quote_ekubo = quote("ekubo", Some(pool_key), None);
+    let limits = SlippageLimits::from_quote(&quote_ekubo, 100).unwrap();
+    let calls = ekubo
+        .build_calls(account, &params, &quote_ekubo, &limits)
+        .unwrap();
+    let payout = calls.last().unwrap();
+    assert_eq!(payout.to, params.to_token);
+    assert_eq!(payout.selector, selector!("transfer"));
+    assert_eq!(
+        payout.calldata,
+        vec![wallet, Felt::from(1_980_000u128), Felt::ZERO]
+    );
+
+    // Swaps of the account's own tokens pay the account.
+    let own = QuoteParams {
+        funded_by: None,
+        ..params
+    };
+    let calls = avnu
+        .build_calls(account, &own, &quote_avnu, &limits)
+        .unwrap();
+    assert_eq!(calls[1].calldata[8], account);
+    let calls = ekubo
+        .build_calls(account, &own, &quote_ekubo, &limits)
+        .unwrap();
+    assert_eq!(calls.len(), 2);
+}
@@ -0,0 +1,232 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use autoswappr_backend::service::price_history::record;
+
+use crate::helpers::*;
+
+const WALLET: &str = "0x0000000000000000000000000000000000000000000000000000000000000a11";
+const OTHER_WALLET: &str = "0x0000000000000000000000000000000000000000000000000000000000000b22";
+const ETH: &str = "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
+const USDC: &str = "0x053c91253bc9682c04929ca02ed00b3e423f6710d2ee7e0d5ebb06f3ecf368a8";
+const UNPRICED: &str = "0x0000000000000000000000000000000000000000000000000000000000000fed";
+
+struct Swap<'a> {
+    wallet: &'a str,
+    from_token: &'a str,
+    to_token: &'a str,
+    amount_from: &'a str,
+    amount_to: &'a str,
+    usd: Option<(&'a str, &'a str)>,
+    status: Option<&'a str>,
+    created_at: &'a str,
+}
+
+async fn log_swap(pool: &PgPool, swap: Swap<'_>) {
+    sqlx::query(
+        r#"
+        INSERT INTO transactions_log
+        (wallet_address, from_token, to_token, percentage, amount_from, amount_to,
+         amount_from_usd, amount_to_usd, status, created_at)
+        VALUES ($1, $2, $3, 50, $4::NUMERIC, $5::NUMERIC, $6::NUMERIC, $7::NUMERIC,
+                $8::transaction_status, $9::TIMESTAMPTZ)
+        "#,
+    )
+    .bind(swap.wallet)
+    .bind(swap.from_token)
+    .bind(swap.to_token)
+    .bind(swap.amount_from)
+    .bind(swap.amount_to)
+    .bind(swap.usd.map(|(from, _)| from))
+    .bind(swap.usd.map(|(_, to)| to))
+    .bind(swap.status)
+    .bind(swap.created_at)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+async fn price(pool: &PgPool, token: &str, price_usd: &str, at: &str) {
+    let at = OffsetDateTime::parse(at, &Rfc3339).unwrap();
+    record(pool, token, price_usd, at).await.unwrap();
+}
+
+async fn seed(pool: &PgPool) {
+    register_token(pool, ETH).await;
+    register_token_with_decimals(pool, USDC, 6).await;
+    register_token(pool, UNPRICED).await;
+
+    price(pool, ETH, "2700", "2024-12-20T11:00:00Z").await;
+    price(pool, USDC, "1", "2024-12-20T11:00:00Z").await;
+    price(pool, ETH, "2000", "2024-12-21T00:00:00Z").await;
+
+    let swaps = [
+        // Valued when it executed.
+        Swap {
+            wallet: WALLET,
+            from_token: ETH,
+            to_token: USDC,
+            amount_from: "1000000000000000000",
+            amount_to: "2500000000",
+            usd: Some(("2500", "2500")),
+            status: Some("accepted_on_l2"),
+            created_at: "2024-12-20T10:00:00Z",
+        },
+        // Valued from the price history.
+        Swap {
+            wallet: WALLET,
+            from_token: ETH,
+            to_token: USDC,
+            amount_from: "2000000000000000000",
+            amount_to: "5400000000",
+            usd: None,
+            status: None,
+            created_at: "2024-12-20T12:00:00Z",
+        },
+        // Reverted swaps are left out.
+        Swap {
+            wallet: WALLET,
+            from_token: ETH,
+            to_token: USDC,
+            amount_from: "0",
+            amount_to: "0",
+            usd: None,
+            status: Some("reverted"),
+            created_at: "2024-12-20T13:00:00Z",
+        },
+        Swap {
+            wallet: WALLET,
+            from_token: UNPRICED,
+            to_token: USDC,
+            amount_from: "5000000000000000000",
+            amount_to: "10000000",
+            usd: None,
+            status: Some("accepted_on_l1"),
+            created_at: "2024-12-20T15:00:00Z",
+        },
+        Swap {
+            wallet: WALLET,
+            from_token: USDC,
+            to_token: ETH,
+            amount_from: "1000000000",
+            amount_to: "500000000000000000",
+            usd: None,
+            status: Some("accepted_on_l2"),
+            created_at: "2024-12-21T09:00:00Z",
+        },
+        Swap {
+            wallet: OTHER_WALLET,
+            from_token: ETH,
+            to_token: USDC,
+            amount_from: "1000000000000000000",
+            amount_to: "2500000000",
+            usd: None,
+            status: None,
+            created_at: "2024-12-21T10:00:00Z",
+        },
+    ];
+    for swap in swaps {
+        log_swap(pool, swap).await;
+    }
+}
+
+async fn get_stats(app: &TestApp, wallet: &str) -> (StatusCode, Value) {
+    let req = Request::get(format!("/wallets/{}/stats", wallet))
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.request(req).await;
+    let status = resp.status();
+    let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn test_wallet_stats_per_pair() {
+    let app = TestApp::new().await;
+    seed(&app.db.pool).await;
+
+    let (status, json) = get_stats(&app, WALLET).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["wallet_address"], WALLET);
+    assert_eq!(
+        json["pairs"],
+        json!([
+            {
+                "from_token": USDC,
+                "to_token": ETH,
+                "swap_count": 1,
+                "volume_in": "1000000000",
+                "volume_out": "500000000000000000",
+                "volume_in_usd": "1000",
+                "volume_out_usd": "1000",
+                "average_price": "0.0005",
+                "held_value_usd": "1000",
+                "swapped_value_usd": "1000",
+                "savings_usd": "0",
+                "first_swap_at": "2024-12-21T09:00:00Z",
+                "last_swap_at": "2024-12-21T09:00:00Z"
+            },
+            {
+                "from_token": UNPRICED,
+                "to_token": USDC,
+                "swap_count": 1,
+                "volume_in": "5000000000000000000",
+                "volume_out": "10000000",
+                "volume_in_usd": null,
+                "volume_out_usd": "10",
+                "average_price": "2",
+                "held_value_usd": null,
+                "swapped_value_usd": "10",
+                "savings_usd": null,
+                "first_swap_at": "2024-12-20T15:00:00Z",
+                "last_swap_at": "2024-12-20T15:00:00Z"
+            },
+            {
+                "from_token": ETH,
+                "to_token": USDC,
+                "swap_count": 2,
+                "volume_in": "3000000000000000000",
+                "volume_out": "7900000000",
+                "volume_in_usd": "7900",
+                "volume_out_usd": "7900",
+                "average_price": "2633.333333333333333333",
+                // 3 ETH at the latest $2000 against 7900 USDC.
+                "held_value_usd": "6000",
+                "swapped_value_usd": "7900",
+                "savings_usd": "1900",
+                "first_swap_at": "2024-12-20T10:00:00Z",
+                "last_swap_at": "2024-12-20T12:00:00Z"
+            }
+        ])
+    );
+}
+
+#[tokio::test]
+async fn test_wallet_stats_address() {
+    let app = TestApp::new().await;
+    seed(&app.db.pool).await;
+
+    // Addresses match whatever their case.
+    let (status, json) = get_stats(&app, &OTHER_WALLET.replace('b', "B")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["pairs"].as_array().unwrap().len(), 1);
+    assert_eq!(json["pairs"][0]["swap_count"], 1);
+    // The ETH price before the swap is the latest one.
+    assert_eq!(json["pairs"][0]["volume_in_usd"], "2000");
+
+    let (status, json) = get_stats(
+        &app,
+        "0x0000000000000000000000000000000000000000000000000000000000000c33",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["pairs"], json!([]));
+
+    let (status, _) = get_stats(&app, "0x123").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}